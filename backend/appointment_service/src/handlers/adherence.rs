@@ -0,0 +1,516 @@
+use std::collections::HashMap;
+
+use axum::{
+    http::{StatusCode, HeaderMap},
+    Json,
+    extract::Path,
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+
+use crate::config::Config;
+use super::{
+    authenticate, bad_request, database_error, ensure_plan_viewer, fetch_plan_items, fetch_plan_row,
+    forbidden, parse_date, user_timezone, weekday_code, ErrorResponse, TherapyPlan, TherapyPlanItem,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct CreateExerciseLogRequest {
+    pub plan_item_id: String,         // item_id iz današnjeg pregleda plana
+    pub performed_on: Option<String>, // YYYY-MM-DD, podrazumevano danas u zoni pacijenta
+    pub sets_done: i64,
+    pub reps_done: i64,
+    pub pain_level: i64,              // 0-10
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ExerciseLog {
+    pub id: String,
+    pub plan_id: String,
+    pub plan_item_id: Option<String>, // prazno ako je stavka kasnije uklonjena iz plana
+    pub patient_id: String,
+    pub exercise_id: i64,
+    pub performed_on: String,
+    pub sets_done: i64,
+    pub reps_done: i64,
+    pub pain_level: i64,
+    pub note: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExerciseLogsResponse {
+    pub logs: Vec<ExerciseLog>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WeeklyAdherence {
+    pub week_start: String, // ponedeljak
+    pub expected_sessions: i64,
+    pub completed_sessions: i64,
+    pub adherence_percent: f64,
+    pub missed_days: i64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct PainPoint {
+    pub date: String,
+    pub average_pain: f64,
+    pub max_pain: i64,
+    pub sessions: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AdherenceResponse {
+    pub plan_id: String,
+    pub patient_id: String,
+    // Poslednji dan koji ulazi u obračun: danas ili kraj plana
+    pub through: String,
+    pub expected_sessions: i64,
+    pub completed_sessions: i64,
+    pub adherence_percent: f64,
+    pub weeks: Vec<WeeklyAdherence>,
+    pub pain_trend: Vec<PainPoint>,
+    pub missed_days: Vec<String>,
+}
+
+const LOG_COLUMNS: &str =
+    "id, plan_id, plan_item_id, patient_id, exercise_id, performed_on, sets_done, reps_done, pain_level, note, created_at";
+
+fn percent(completed: i64, expected: i64) -> f64 {
+    if expected == 0 {
+        return 0.0;
+    }
+    (completed as f64 * 1000.0 / expected as f64).round() / 10.0
+}
+
+// Danas po kalendaru pacijenta
+async fn patient_today(conn: &mut SqliteConnection, config: &Config, patient_id: &str) -> NaiveDate {
+    let timezone = user_timezone(conn, config, patient_id).await;
+    Utc::now().with_timezone(&timezone).date_naive()
+}
+
+// Koliko puta je koja stavka plana propisana za dati dan
+fn expected_for_day(items: &[TherapyPlanItem], date: NaiveDate) -> HashMap<&str, i64> {
+    let weekday = weekday_code(date.weekday());
+    items
+        .iter()
+        .filter(|item| item.weekdays.split(',').any(|d| d == weekday))
+        .map(|item| (item.id.as_str(), item.times_per_day))
+        .collect()
+}
+
+// Pacijent beleži sesiju iz svog plana
+pub async fn create_exercise_log(
+    Path(plan_id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(config): Extension<Config>,
+    headers: HeaderMap,
+    Json(req): Json<CreateExerciseLogRequest>,
+) -> Result<(StatusCode, Json<ExerciseLog>), (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+
+    if !(0..=10).contains(&req.pain_level) {
+        return Err(bad_request("pain_level must be between 0 and 10"));
+    }
+    if req.sets_done < 0 || req.reps_done < 0 {
+        return Err(bad_request("sets_done and reps_done cannot be negative"));
+    }
+
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+    let plan = fetch_plan_row(&mut conn, &plan_id).await?;
+    if claims.sub != plan.patient_id {
+        return Err(forbidden());
+    }
+
+    let items = fetch_plan_items(&mut conn, &plan_id).await?;
+    let item = items
+        .iter()
+        .find(|item| item.id == req.plan_item_id)
+        .ok_or_else(|| bad_request("This exercise is not part of the therapy plan"))?;
+
+    let today = patient_today(&mut conn, &config, &plan.patient_id).await;
+    let performed_on = match &req.performed_on {
+        Some(date) => parse_date(date, "performed_on")?,
+        None => today,
+    };
+    if performed_on > today {
+        return Err(bad_request("Sessions cannot be logged in advance"));
+    }
+    let performed_on = performed_on.format("%Y-%m-%d").to_string();
+    if performed_on < plan.start_date || performed_on > plan.end_date {
+        return Err(bad_request("performed_on is outside the therapy plan period"));
+    }
+
+    let id = uuid::Uuid::new_v4().to_string();
+    sqlx::query(
+        r#"
+        INSERT INTO exercise_logs (
+            id, plan_id, plan_item_id, patient_id, exercise_id, performed_on,
+            sets_done, reps_done, pain_level, note, created_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&id)
+    .bind(&plan_id)
+    .bind(&item.id)
+    .bind(&plan.patient_id)
+    .bind(item.exercise_id)
+    .bind(&performed_on)
+    .bind(req.sets_done)
+    .bind(req.reps_done)
+    .bind(req.pain_level)
+    .bind(&req.note)
+    .bind(Utc::now().to_rfc3339())
+    .execute(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    let log = sqlx::query_as::<_, ExerciseLog>(&format!("SELECT {} FROM exercise_logs WHERE id = ?", LOG_COLUMNS))
+        .bind(&id)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(database_error)?;
+
+    Ok((StatusCode::CREATED, Json(log)))
+}
+
+pub async fn get_exercise_logs(
+    Path(plan_id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+) -> Result<Json<ExerciseLogsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+    let plan = fetch_plan_row(&mut conn, &plan_id).await?;
+    ensure_plan_viewer(&claims, &plan)?;
+
+    let logs = sqlx::query_as::<_, ExerciseLog>(
+        &format!(
+            "SELECT {} FROM exercise_logs WHERE plan_id = ? ORDER BY performed_on DESC, created_at DESC",
+            LOG_COLUMNS
+        )
+    )
+    .bind(&plan_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    Ok(Json(ExerciseLogsResponse { logs }))
+}
+
+// Nedeljni procenat, bol po danima i propušteni dani, od početka plana do danas
+async fn compute_adherence(
+    conn: &mut SqliteConnection,
+    plan: &TherapyPlan,
+    through: NaiveDate,
+) -> Result<AdherenceResponse, (StatusCode, Json<ErrorResponse>)> {
+    let start = parse_date(&plan.start_date, "start_date")?;
+    let end = parse_date(&plan.end_date, "end_date")?.min(through);
+    let items = fetch_plan_items(conn, &plan.id).await?;
+
+    // Broj sesija po stavci plana i danu
+    let done_rows: Vec<(String, String, i64)> = sqlx::query_as(
+        r#"
+        SELECT plan_item_id, performed_on, COUNT(*)
+        FROM exercise_logs
+        WHERE plan_id = ? AND plan_item_id IS NOT NULL AND performed_on BETWEEN ? AND ?
+        GROUP BY plan_item_id, performed_on
+        "#
+    )
+    .bind(&plan.id)
+    .bind(&plan.start_date)
+    .bind(end.format("%Y-%m-%d").to_string())
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    let done: HashMap<(String, String), i64> = done_rows
+        .into_iter()
+        .map(|(plan_item_id, date, count)| ((plan_item_id, date), count))
+        .collect();
+
+    let pain_trend = sqlx::query_as::<_, PainPoint>(
+        r#"
+        SELECT performed_on AS date,
+               ROUND(AVG(pain_level), 1) AS average_pain,
+               MAX(pain_level) AS max_pain,
+               COUNT(*) AS sessions
+        FROM exercise_logs
+        WHERE plan_id = ?
+        GROUP BY performed_on
+        ORDER BY performed_on ASC
+        "#
+    )
+    .bind(&plan.id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    let mut weeks: Vec<WeeklyAdherence> = Vec::new();
+    let mut missed_days = Vec::new();
+    let mut date = start;
+    while date <= end {
+        let date_str = date.format("%Y-%m-%d").to_string();
+        let week_start = (date - Duration::days(date.weekday().num_days_from_monday() as i64))
+            .format("%Y-%m-%d")
+            .to_string();
+        if weeks.last().is_none_or(|w| w.week_start != week_start) {
+            weeks.push(WeeklyAdherence {
+                week_start,
+                expected_sessions: 0,
+                completed_sessions: 0,
+                adherence_percent: 0.0,
+                missed_days: 0,
+            });
+        }
+        let week = weeks.last_mut().expect("week was just pushed");
+
+        let expected = expected_for_day(&items, date);
+        if !expected.is_empty() {
+            let mut completed = 0;
+            for (plan_item_id, times) in &expected {
+                // Dodatne sesije iste stavke ne nadoknađuju propuštene
+                let key = (plan_item_id.to_string(), date_str.clone());
+                completed += done.get(&key).copied().unwrap_or(0).min(*times);
+            }
+            week.expected_sessions += expected.values().sum::<i64>();
+            week.completed_sessions += completed;
+            if completed == 0 {
+                week.missed_days += 1;
+                missed_days.push(date_str);
+            }
+        }
+
+        date += Duration::days(1);
+    }
+
+    for week in &mut weeks {
+        week.adherence_percent = percent(week.completed_sessions, week.expected_sessions);
+    }
+    let expected_sessions = weeks.iter().map(|w| w.expected_sessions).sum();
+    let completed_sessions = weeks.iter().map(|w| w.completed_sessions).sum();
+
+    Ok(AdherenceResponse {
+        plan_id: plan.id.clone(),
+        patient_id: plan.patient_id.clone(),
+        through: end.format("%Y-%m-%d").to_string(),
+        expected_sessions,
+        completed_sessions,
+        adherence_percent: percent(completed_sessions, expected_sessions),
+        weeks,
+        pain_trend,
+        missed_days,
+    })
+}
+
+pub async fn get_plan_adherence(
+    Path(plan_id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(config): Extension<Config>,
+    headers: HeaderMap,
+) -> Result<Json<AdherenceResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+    let plan = fetch_plan_row(&mut conn, &plan_id).await?;
+    ensure_plan_viewer(&claims, &plan)?;
+
+    let today = patient_today(&mut conn, &config, &plan.patient_id).await;
+    Ok(Json(compute_adherence(&mut conn, &plan, today).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::{create_therapy_plan, update_therapy_plan, TherapyPlanItemRequest, TherapyPlanRequest};
+    use crate::test_support::{auth_headers, test_config, test_pool};
+
+    async fn log(pool: &SqlitePool, plan_id: &str, plan_item_id: &str, date: &str, pain_level: i64) {
+        let (status, _) = create_exercise_log(
+            Path(plan_id.to_string()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-001", "patient"),
+            Json(CreateExerciseLogRequest {
+                plan_item_id: plan_item_id.to_string(),
+                performed_on: Some(date.to_string()),
+                sets_done: 3,
+                reps_done: 10,
+                pain_level,
+                note: None,
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn adherence_counts_sessions_per_week() {
+        let pool = test_pool().await;
+
+        // Dve nedelje, vežba 1 ponedeljkom i petkom dva puta dnevno, vežba 2 sredom
+        let (_, Json(plan)) = create_therapy_plan(
+            Extension(pool.clone()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(TherapyPlanRequest {
+                patient_id: "patient-001".to_string(),
+                physiotherapist_id: None,
+                title: "Lumbalni deo".to_string(),
+                goals: None,
+                start_date: "2025-01-06".to_string(),
+                end_date: "2025-01-19".to_string(),
+                items: vec![
+                    TherapyPlanItemRequest {
+                        id: None,
+                        exercise_id: 1,
+                        weekdays: vec!["mon".to_string(), "fri".to_string()],
+                        sets: 3,
+                        reps: 10,
+                        hold_seconds: None,
+                        times_per_day: Some(2),
+                        notes: None,
+                    },
+                    TherapyPlanItemRequest {
+                        id: None,
+                        exercise_id: 2,
+                        weekdays: vec!["wed".to_string()],
+                        sets: 2,
+                        reps: 15,
+                        hold_seconds: None,
+                        times_per_day: None,
+                        notes: None,
+                    },
+                ],
+            }),
+        )
+        .await
+        .unwrap();
+        let plan_id = plan.plan.id;
+        let (first, second) = (plan.items[0].id.as_str(), plan.items[1].id.as_str());
+
+        // Prva nedelja: ponedeljak tri puta (računa se dva), sreda jednom, petak propušten
+        for pain in [6, 4, 5] {
+            log(&pool, &plan_id, first, "2025-01-06", pain).await;
+        }
+        log(&pool, &plan_id, second, "2025-01-08", 3).await;
+        // Druga nedelja: samo petak jednom
+        log(&pool, &plan_id, first, "2025-01-17", 2).await;
+
+        let Json(adherence) = get_plan_adherence(
+            Path(plan_id.clone()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("physio-001", "physiotherapist"),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(adherence.through, "2025-01-19");
+        assert_eq!(adherence.weeks.len(), 2);
+        assert_eq!(adherence.weeks[0].expected_sessions, 5);
+        assert_eq!(adherence.weeks[0].completed_sessions, 3);
+        assert_eq!(adherence.weeks[0].adherence_percent, 60.0);
+        assert_eq!(adherence.weeks[1].completed_sessions, 1);
+        assert_eq!(adherence.adherence_percent, 40.0);
+        assert_eq!(adherence.missed_days, vec!["2025-01-10", "2025-01-13", "2025-01-15"]);
+
+        assert_eq!(adherence.pain_trend[0].date, "2025-01-06");
+        assert_eq!(adherence.pain_trend[0].average_pain, 5.0);
+        assert_eq!(adherence.pain_trend[0].max_pain, 6);
+        assert_eq!(adherence.pain_trend.len(), 3);
+
+        // Vežba van plana se ne može zabeležiti
+        let err = create_exercise_log(
+            Path(plan_id.clone()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-001", "patient"),
+            Json(CreateExerciseLogRequest {
+                plan_item_id: "not-in-plan".to_string(),
+                performed_on: Some("2025-01-07".to_string()),
+                sets_done: 1,
+                reps_done: 1,
+                pain_level: 0,
+                note: None,
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn logs_follow_plan_items_through_edits() {
+        let pool = test_pool().await;
+
+        // Ista vežba dva puta: ponedeljkom lakša, sredom teža varijanta
+        let item = |id: Option<String>, weekday: &str, sets: i64| TherapyPlanItemRequest {
+            id,
+            exercise_id: 1,
+            weekdays: vec![weekday.to_string()],
+            sets,
+            reps: 10,
+            hold_seconds: None,
+            times_per_day: None,
+            notes: None,
+        };
+        let request = |items| TherapyPlanRequest {
+            patient_id: "patient-001".to_string(),
+            physiotherapist_id: None,
+            title: "Rame".to_string(),
+            goals: None,
+            start_date: "2025-01-06".to_string(),
+            end_date: "2025-01-12".to_string(),
+            items,
+        };
+        let (_, Json(plan)) = create_therapy_plan(
+            Extension(pool.clone()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(request(vec![item(None, "mon", 2), item(None, "wed", 4)])),
+        )
+        .await
+        .unwrap();
+        let plan_id = plan.plan.id.clone();
+        let (monday, wednesday) = (plan.items[0].id.clone(), plan.items[1].id.clone());
+
+        // Zabeležena je stavka za sredu, ne ona za ponedeljak
+        log(&pool, &plan_id, &wednesday, "2025-01-08", 2).await;
+
+        // Izmena sa istim id-jevima menja redosled i seriju, a log ostaje vezan za stavku
+        let Json(updated) = update_therapy_plan(
+            Path(plan_id.clone()),
+            Extension(pool.clone()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(request(vec![item(Some(wednesday.clone()), "wed", 5), item(Some(monday), "mon", 2)])),
+        )
+        .await
+        .unwrap();
+        assert_eq!(updated.items[0].id, wednesday);
+
+        let Json(adherence) = get_plan_adherence(
+            Path(plan_id.clone()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("physio-001", "physiotherapist"),
+        )
+        .await
+        .unwrap();
+        assert_eq!((adherence.expected_sessions, adherence.completed_sessions), (2, 1));
+        assert_eq!(adherence.missed_days, vec!["2025-01-06"]);
+
+        let Json(logs) = get_exercise_logs(
+            Path(plan_id),
+            Extension(pool.clone()),
+            auth_headers("patient-001", "patient"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(logs.logs[0].plan_item_id.as_deref(), Some(wednesday.as_str()));
+    }
+}
@@ -0,0 +1,329 @@
+use axum::{
+    http::{StatusCode, HeaderMap},
+    response::Response,
+    Json,
+    extract::Query,
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use chrono::Duration;
+
+use crate::config::Config;
+use super::{
+    attachment, authenticate, bad_request, clinic_now, csv_field, database_error, ensure_admin,
+    parse_date, ErrorResponse, WORK_END_HOUR, WORK_START_HOUR,
+};
+
+// Podrazumevani izveštaj pokriva poslednje četiri nedelje
+const DEFAULT_RANGE_DAYS: i64 = 27;
+const MAX_RANGE_DAYS: i64 = 366;
+
+#[derive(Debug, Deserialize)]
+pub struct AnalyticsQuery {
+    pub from: Option<String>, // YYYY-MM-DD, uključivo
+    pub to: Option<String>,
+    pub physiotherapist_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct PhysiotherapistWeek {
+    pub physiotherapist_id: String,
+    pub physiotherapist_name: String,
+    // Ponedeljak nedelje (ISO nedelja)
+    pub week_start: String,
+    pub available_minutes: i64,
+    pub booked_minutes: i64,
+    pub utilization_percent: f64,
+    pub appointments: i64,
+    pub cancellations: i64,
+    pub no_shows: i64,
+    pub cancellation_rate_percent: f64,
+    pub no_show_rate_percent: f64,
+    pub patients: i64,
+    pub new_patients: i64,
+    pub returning_patients: i64,
+    // Prosečno vreme od zakazivanja do termina
+    pub avg_lead_time_hours: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnalyticsResponse {
+    pub from: String,
+    pub to: String,
+    pub weeks: Vec<PhysiotherapistWeek>,
+}
+
+// Ponedeljak nedelje kojoj pripada datum iz kolone
+fn week_start_sql(column: &str) -> String {
+    format!(
+        "date({0}, '-' || ((CAST(strftime('%w', {0}) AS INTEGER) + 6) % 7) || ' days')",
+        column
+    )
+}
+
+// Slotovi se nude svakog dana od WORK_START_HOUR do WORK_END_HOUR, pa je to i
+// raspoloživo vreme. Zauzet je termin koji nije otkazan (i nedolazak drži slot).
+// Pacijent je nov u nedelji u kojoj je imao prvi neotkazan termin u klinici.
+fn analytics_sql() -> String {
+    format!(
+        r#"
+        WITH RECURSIVE days(day) AS (
+            SELECT date(?1)
+            UNION ALL
+            SELECT date(day, '+1 day') FROM days WHERE day < date(?2)
+        ),
+        capacity AS (
+            SELECT u.id AS physiotherapist_id,
+                   u.first_name || ' ' || u.last_name AS physiotherapist_name,
+                   {day_week} AS week_start,
+                   COUNT(*) * ?4 AS available_minutes
+            FROM users u
+            CROSS JOIN days d
+            WHERE u.role = 'physiotherapist'
+            AND (?3 IS NULL OR u.id = ?3)
+            GROUP BY u.id, week_start
+        ),
+        first_visits AS (
+            SELECT patient_id, MIN(appointment_date) AS first_date
+            FROM appointments
+            WHERE status IN ('scheduled', 'confirmed', 'completed')
+            GROUP BY patient_id
+        ),
+        booked AS (
+            SELECT a.physiotherapist_id,
+                   {appointment_week} AS week_start,
+                   COUNT(*) AS appointments,
+                   SUM(CASE WHEN a.status NOT IN ('cancelled', 'late_cancellation') THEN a.duration_minutes ELSE 0 END) AS booked_minutes,
+                   SUM(CASE WHEN a.status IN ('cancelled', 'late_cancellation') THEN 1 ELSE 0 END) AS cancellations,
+                   SUM(CASE WHEN a.status = 'no_show' THEN 1 ELSE 0 END) AS no_shows,
+                   COUNT(DISTINCT CASE WHEN a.status IN ('scheduled', 'confirmed', 'completed') THEN a.patient_id END) AS patients,
+                   COUNT(DISTINCT CASE WHEN a.status IN ('scheduled', 'confirmed', 'completed')
+                                        AND f.first_date >= {appointment_week} THEN a.patient_id END) AS new_patients,
+                   AVG(CASE WHEN a.status NOT IN ('cancelled', 'late_cancellation')
+                            THEN (julianday(a.starts_at) - julianday(a.created_at)) * 24 END) AS lead_time_hours
+            FROM appointments a
+            LEFT JOIN first_visits f ON f.patient_id = a.patient_id
+            WHERE a.appointment_date BETWEEN ?1 AND ?2
+            AND (?3 IS NULL OR a.physiotherapist_id = ?3)
+            GROUP BY a.physiotherapist_id, week_start
+        )
+        SELECT c.physiotherapist_id, c.physiotherapist_name, c.week_start, c.available_minutes,
+               COALESCE(b.booked_minutes, 0) AS booked_minutes,
+               ROUND(100.0 * COALESCE(b.booked_minutes, 0) / c.available_minutes, 1) AS utilization_percent,
+               COALESCE(b.appointments, 0) AS appointments,
+               COALESCE(b.cancellations, 0) AS cancellations,
+               COALESCE(b.no_shows, 0) AS no_shows,
+               COALESCE(ROUND(100.0 * b.cancellations / b.appointments, 1), 0.0) AS cancellation_rate_percent,
+               COALESCE(ROUND(100.0 * b.no_shows / b.appointments, 1), 0.0) AS no_show_rate_percent,
+               COALESCE(b.patients, 0) AS patients,
+               COALESCE(b.new_patients, 0) AS new_patients,
+               COALESCE(b.patients - b.new_patients, 0) AS returning_patients,
+               ROUND(b.lead_time_hours, 1) AS avg_lead_time_hours
+        FROM capacity c
+        LEFT JOIN booked b ON b.physiotherapist_id = c.physiotherapist_id AND b.week_start = c.week_start
+        ORDER BY c.physiotherapist_name ASC, c.physiotherapist_id ASC, c.week_start ASC
+        "#,
+        day_week = week_start_sql("d.day"),
+        appointment_week = week_start_sql("a.appointment_date"),
+    )
+}
+
+async fn compute_analytics(
+    pool: &SqlitePool,
+    config: &Config,
+    query: &AnalyticsQuery,
+) -> Result<AnalyticsResponse, (StatusCode, Json<ErrorResponse>)> {
+    let to = match &query.to {
+        Some(to) => parse_date(to, "to")?,
+        None => clinic_now(config).date(),
+    };
+    let from = match &query.from {
+        Some(from) => parse_date(from, "from")?,
+        None => to - Duration::days(DEFAULT_RANGE_DAYS),
+    };
+    if from > to {
+        return Err(bad_request("from must not be after to"));
+    }
+    if (to - from).num_days() >= MAX_RANGE_DAYS {
+        return Err(bad_request(&format!("Date range cannot exceed {} days", MAX_RANGE_DAYS)));
+    }
+
+    let from = from.format("%Y-%m-%d").to_string();
+    let to = to.format("%Y-%m-%d").to_string();
+    let working_minutes = (WORK_END_HOUR - WORK_START_HOUR) as i64 * 60;
+
+    let weeks = sqlx::query_as::<_, PhysiotherapistWeek>(&analytics_sql())
+        .bind(&from)
+        .bind(&to)
+        .bind(&query.physiotherapist_id)
+        .bind(working_minutes)
+        .fetch_all(pool)
+        .await
+        .map_err(database_error)?;
+
+    Ok(AnalyticsResponse { from, to, weeks })
+}
+
+// Iskorišćenost fizioterapeuta i pokazatelji klinike po nedeljama (samo admin)
+pub async fn get_analytics(
+    Query(query): Query<AnalyticsQuery>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(config): Extension<Config>,
+    headers: HeaderMap,
+) -> Result<Json<AnalyticsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+    ensure_admin(&claims)?;
+
+    Ok(Json(compute_analytics(&pool, &config, &query).await?))
+}
+
+fn render_analytics_csv(weeks: &[PhysiotherapistWeek]) -> String {
+    let mut body = String::from(
+        "physiotherapist_id,physiotherapist_name,week_start,available_minutes,booked_minutes,\
+         utilization_percent,appointments,cancellations,no_shows,cancellation_rate_percent,\
+         no_show_rate_percent,patients,new_patients,returning_patients,avg_lead_time_hours\r\n"
+    );
+    for week in weeks {
+        let row = [
+            csv_field(&week.physiotherapist_id),
+            csv_field(&week.physiotherapist_name),
+            week.week_start.clone(),
+            week.available_minutes.to_string(),
+            week.booked_minutes.to_string(),
+            week.utilization_percent.to_string(),
+            week.appointments.to_string(),
+            week.cancellations.to_string(),
+            week.no_shows.to_string(),
+            week.cancellation_rate_percent.to_string(),
+            week.no_show_rate_percent.to_string(),
+            week.patients.to_string(),
+            week.new_patients.to_string(),
+            week.returning_patients.to_string(),
+            week.avg_lead_time_hours.map(|h| h.to_string()).unwrap_or_default(),
+        ];
+        body.push_str(&row.join(","));
+        body.push_str("\r\n");
+    }
+    body
+}
+
+pub async fn export_analytics_csv(
+    Query(query): Query<AnalyticsQuery>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(config): Extension<Config>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+    ensure_admin(&claims)?;
+
+    let report = compute_analytics(&pool, &config, &query).await?;
+    let filename = format!("analytics-{}-{}.csv", report.from, report.to);
+    Ok(attachment("text/csv; charset=utf-8", &filename, render_analytics_csv(&report.weeks)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{auth_headers, insert_patient, test_config, test_pool};
+
+    // Termin sa zadatim statusom i trenutkom zakazivanja
+    async fn insert_visit(
+        pool: &SqlitePool,
+        patient_id: &str,
+        date: &str,
+        (start, end, minutes): (&str, &str, i64),
+        status: &str,
+        created_at: &str,
+        starts_at: &str,
+    ) {
+        sqlx::query(
+            r#"
+            INSERT INTO appointments (id, patient_id, physiotherapist_id, appointment_date, start_time, end_time,
+                                      duration_minutes, status, starts_at, created_at, updated_at)
+            VALUES (?, ?, 'physio-001', ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(patient_id)
+        .bind(date)
+        .bind(start)
+        .bind(end)
+        .bind(minutes)
+        .bind(status)
+        .bind(starts_at)
+        .bind(created_at)
+        .bind(created_at)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    fn week_of_jan_14() -> AnalyticsQuery {
+        AnalyticsQuery {
+            from: Some("2030-01-14".to_string()),
+            to: Some("2030-01-20".to_string()),
+            physiotherapist_id: Some("physio-001".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn weekly_utilization_rates_and_patients() {
+        let pool = test_pool().await;
+        insert_patient(&pool, "patient-002").await;
+        insert_patient(&pool, "patient-003").await;
+
+        // patient-001 je već dolazio prethodne nedelje
+        insert_visit(&pool, "patient-001", "2030-01-08", ("09:00", "09:20", 20), "completed",
+                     "2030-01-01T08:00:00Z", "2030-01-08T08:00:00Z").await;
+        insert_visit(&pool, "patient-001", "2030-01-15", ("10:00", "10:40", 40), "completed",
+                     "2030-01-13T09:00:00Z", "2030-01-15T09:00:00Z").await;
+        insert_visit(&pool, "patient-002", "2030-01-16", ("10:00", "10:20", 20), "no_show",
+                     "2030-01-15T09:00:00Z", "2030-01-16T09:00:00Z").await;
+        insert_visit(&pool, "patient-002", "2030-01-17", ("10:00", "10:20", 20), "cancelled",
+                     "2030-01-15T09:00:00Z", "2030-01-17T09:00:00Z").await;
+        insert_visit(&pool, "patient-003", "2030-01-18", ("10:00", "10:20", 20), "scheduled",
+                     "2030-01-18T06:00:00Z", "2030-01-18T09:00:00Z").await;
+
+        let Json(report) = get_analytics(
+            Query(week_of_jan_14()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("admin-001", "admin"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.weeks.len(), 1);
+        let week = &report.weeks[0];
+        assert_eq!(week.week_start, "2030-01-14");
+        assert_eq!((week.available_minutes, week.booked_minutes), (7 * 480, 80));
+        assert_eq!(week.utilization_percent, 2.4);
+        assert_eq!((week.appointments, week.cancellations, week.no_shows), (4, 1, 1));
+        assert_eq!((week.cancellation_rate_percent, week.no_show_rate_percent), (25.0, 25.0));
+        assert_eq!((week.patients, week.new_patients, week.returning_patients), (2, 1, 1));
+        assert_eq!(week.avg_lead_time_hours, Some(25.0));
+
+        let csv = render_analytics_csv(&report.weeks);
+        assert!(csv.lines().nth(1).unwrap().starts_with("physio-001,"));
+
+        let err = get_analytics(
+            Query(AnalyticsQuery { from: Some("2030-02-01".to_string()), ..week_of_jan_14() }),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("admin-001", "admin"),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+
+        let err = get_analytics(
+            Query(week_of_jan_14()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("physio-001", "physiotherapist"),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+    }
+}
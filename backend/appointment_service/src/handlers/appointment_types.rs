@@ -0,0 +1,519 @@
+use axum::{
+    http::{StatusCode, HeaderMap},
+    Json,
+    extract::{Path, Query},
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use chrono::Utc;
+
+use crate::utils::Claims;
+use super::{
+    authenticate, bad_request, database_error, ensure_admin, ErrorResponse, SLOT_MINUTES,
+    WORK_END_HOUR, WORK_START_HOUR,
+};
+
+// Najduža pauza posle termina
+const MAX_BUFFER_MINUTES: i64 = 60;
+
+fn default_duration() -> i64 {
+    SLOT_MINUTES
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AppointmentTypeRequest {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default = "default_duration")]
+    pub duration_minutes: i64,
+    #[serde(default)]
+    pub buffer_minutes: i64,
+    #[serde(default)]
+    pub price_cents: i64,
+    pub currency: Option<String>, // podrazumevano RSD
+    #[serde(default = "default_true")]
+    pub online_bookable: bool,
+    #[serde(default = "default_true")]
+    pub active: bool,
+    // Fizioterapeuti koji nude ovu vrstu; prazna lista znači svi
+    #[serde(default)]
+    pub physiotherapist_ids: Vec<String>,
+    // Resursi koje svaki termin ove vrste zauzima
+    #[serde(default)]
+    pub resource_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AppointmentTypesQuery {
+    pub physiotherapist_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AppointmentType {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub duration_minutes: i64,
+    pub buffer_minutes: i64,
+    pub price_cents: i64,
+    pub currency: String,
+    pub online_bookable: bool,
+    pub active: bool,
+    pub created_at: String,
+    pub updated_at: String,
+    #[sqlx(skip)]
+    pub physiotherapist_ids: Vec<String>,
+    #[sqlx(skip)]
+    pub resource_ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AppointmentTypesResponse {
+    pub appointment_types: Vec<AppointmentType>,
+}
+
+const TYPE_COLUMNS: &str = "id, name, description, duration_minutes, buffer_minutes, price_cents, \
+    currency, online_bookable, active, created_at, updated_at";
+
+impl AppointmentType {
+    // Da li fizioterapeut nudi ovu vrstu termina
+    pub fn offered_by(&self, physiotherapist_id: &str) -> bool {
+        self.physiotherapist_ids.is_empty()
+            || self.physiotherapist_ids.iter().any(|id| id == physiotherapist_id)
+    }
+}
+
+fn validate_type(req: &AppointmentTypeRequest) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if req.name.trim().is_empty() {
+        return Err(bad_request("Name is required"));
+    }
+
+    // Termin mora da stane u radni dan i da počinje na mreži slotova
+    let working_minutes = (WORK_END_HOUR - WORK_START_HOUR) as i64 * 60;
+    if req.duration_minutes <= 0
+        || req.duration_minutes % SLOT_MINUTES != 0
+        || req.duration_minutes > working_minutes
+    {
+        return Err(bad_request(&format!(
+            "duration_minutes must be a multiple of {} within the working day",
+            SLOT_MINUTES
+        )));
+    }
+    if !(0..=MAX_BUFFER_MINUTES).contains(&req.buffer_minutes) {
+        return Err(bad_request(&format!("buffer_minutes must be between 0 and {}", MAX_BUFFER_MINUTES)));
+    }
+    if req.price_cents < 0 {
+        return Err(bad_request("price_cents cannot be negative"));
+    }
+    Ok(())
+}
+
+async fn with_links(
+    conn: &mut SqliteConnection,
+    mut appointment_type: AppointmentType,
+) -> Result<AppointmentType, (StatusCode, Json<ErrorResponse>)> {
+    appointment_type.resource_ids = sqlx::query_scalar(
+        "SELECT resource_id FROM appointment_type_resources WHERE appointment_type_id = ? ORDER BY resource_id ASC"
+    )
+    .bind(&appointment_type.id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    appointment_type.physiotherapist_ids = sqlx::query_scalar(
+        r#"
+        SELECT physiotherapist_id FROM appointment_type_physiotherapists
+        WHERE appointment_type_id = ?
+        ORDER BY physiotherapist_id ASC
+        "#
+    )
+    .bind(&appointment_type.id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    Ok(appointment_type)
+}
+
+pub(crate) async fn fetch_appointment_type(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<AppointmentType, (StatusCode, Json<ErrorResponse>)> {
+    let appointment_type = sqlx::query_as::<_, AppointmentType>(
+        &format!("SELECT {} FROM appointment_types WHERE id = ?", TYPE_COLUMNS)
+    )
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse { error: "Appointment type not found".to_string() })
+    ))?;
+
+    with_links(conn, appointment_type).await
+}
+
+// Vrsta termina koja se zakazuje kod datog fizioterapeuta. Pacijenti mogu sami
+// da zakažu samo vrste koje su dostupne online.
+pub(crate) async fn bookable_type(
+    conn: &mut SqliteConnection,
+    claims: &Claims,
+    appointment_type_id: &str,
+    physiotherapist_id: &str,
+) -> Result<AppointmentType, (StatusCode, Json<ErrorResponse>)> {
+    let appointment_type = fetch_appointment_type(conn, appointment_type_id).await?;
+
+    if !appointment_type.active {
+        return Err(bad_request("This appointment type is no longer offered"));
+    }
+    if !appointment_type.offered_by(physiotherapist_id) {
+        return Err(bad_request("This physiotherapist does not offer this appointment type"));
+    }
+    if claims.role == "patient" && !appointment_type.online_bookable {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse { error: "This appointment type can only be booked by the clinic".to_string() })
+        ));
+    }
+
+    Ok(appointment_type)
+}
+
+// Zameni listu resursa koje vrsta termina traži
+async fn set_type_resources(
+    conn: &mut SqliteConnection,
+    appointment_type_id: &str,
+    resource_ids: &[String],
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    sqlx::query("DELETE FROM appointment_type_resources WHERE appointment_type_id = ?")
+        .bind(appointment_type_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(database_error)?;
+
+    for resource_id in resource_ids {
+        let exists: Option<String> = sqlx::query_scalar("SELECT id FROM resources WHERE id = ?")
+            .bind(resource_id)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(database_error)?;
+        if exists.is_none() {
+            return Err(bad_request(&format!("Unknown resource: {}", resource_id)));
+        }
+
+        sqlx::query(
+            "INSERT OR IGNORE INTO appointment_type_resources (appointment_type_id, resource_id) VALUES (?, ?)"
+        )
+        .bind(appointment_type_id)
+        .bind(resource_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(database_error)?;
+    }
+
+    Ok(())
+}
+
+// Zameni listu fizioterapeuta koji nude vrstu termina
+async fn set_type_physiotherapists(
+    conn: &mut SqliteConnection,
+    appointment_type_id: &str,
+    physiotherapist_ids: &[String],
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    sqlx::query("DELETE FROM appointment_type_physiotherapists WHERE appointment_type_id = ?")
+        .bind(appointment_type_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(database_error)?;
+
+    for physiotherapist_id in physiotherapist_ids {
+        let exists: Option<String> =
+            sqlx::query_scalar("SELECT id FROM users WHERE id = ? AND role = 'physiotherapist'")
+                .bind(physiotherapist_id)
+                .fetch_optional(&mut *conn)
+                .await
+                .map_err(database_error)?;
+        if exists.is_none() {
+            return Err(bad_request(&format!("Unknown physiotherapist: {}", physiotherapist_id)));
+        }
+
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO appointment_type_physiotherapists (appointment_type_id, physiotherapist_id)
+            VALUES (?, ?)
+            "#
+        )
+        .bind(appointment_type_id)
+        .bind(physiotherapist_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(database_error)?;
+    }
+
+    Ok(())
+}
+
+pub async fn create_appointment_type(
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+    Json(req): Json<AppointmentTypeRequest>,
+) -> Result<(StatusCode, Json<AppointmentType>), (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+    ensure_admin(&claims)?;
+    validate_type(&req)?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = Utc::now().to_rfc3339();
+    let mut tx = pool.begin().await.map_err(database_error)?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO appointment_types (
+            id, name, description, duration_minutes, buffer_minutes, price_cents,
+            currency, online_bookable, active, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&id)
+    .bind(req.name.trim())
+    .bind(&req.description)
+    .bind(req.duration_minutes)
+    .bind(req.buffer_minutes)
+    .bind(req.price_cents)
+    .bind(req.currency.as_deref().unwrap_or("RSD"))
+    .bind(req.online_bookable)
+    .bind(req.active)
+    .bind(&now)
+    .bind(&now)
+    .execute(&mut *tx)
+    .await
+    .map_err(database_error)?;
+    set_type_resources(&mut tx, &id, &req.resource_ids).await?;
+    set_type_physiotherapists(&mut tx, &id, &req.physiotherapist_ids).await?;
+
+    let appointment_type = fetch_appointment_type(&mut tx, &id).await?;
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!("Appointment type {} created", appointment_type.name);
+
+    Ok((StatusCode::CREATED, Json(appointment_type)))
+}
+
+// Pacijenti i posetioci vide samo aktivne vrste koje se zakazuju online
+pub async fn get_appointment_types(
+    Extension(pool): Extension<SqlitePool>,
+    Query(query): Query<AppointmentTypesQuery>,
+    headers: HeaderMap,
+) -> Result<Json<AppointmentTypesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let staff = authenticate(&headers).is_ok_and(|c| c.role != "patient");
+
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+    let rows = sqlx::query_as::<_, AppointmentType>(
+        &format!(
+            r#"
+            SELECT {} FROM appointment_types
+            WHERE (? OR (active = 1 AND online_bookable = 1))
+            ORDER BY name ASC
+            "#,
+            TYPE_COLUMNS
+        )
+    )
+    .bind(staff)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    let mut appointment_types = Vec::with_capacity(rows.len());
+    for appointment_type in rows {
+        let appointment_type = with_links(&mut conn, appointment_type).await?;
+        if query.physiotherapist_id.as_deref().is_none_or(|id| appointment_type.offered_by(id)) {
+            appointment_types.push(appointment_type);
+        }
+    }
+
+    Ok(Json(AppointmentTypesResponse { appointment_types }))
+}
+
+// Izmena ne menja trajanje ni pauzu već zakazanih termina
+pub async fn update_appointment_type(
+    Path(id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+    Json(req): Json<AppointmentTypeRequest>,
+) -> Result<Json<AppointmentType>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+    ensure_admin(&claims)?;
+    validate_type(&req)?;
+
+    let mut tx = pool.begin().await.map_err(database_error)?;
+    fetch_appointment_type(&mut tx, &id).await?;
+
+    sqlx::query(
+        r#"
+        UPDATE appointment_types
+        SET name = ?, description = ?, duration_minutes = ?, buffer_minutes = ?, price_cents = ?,
+            currency = ?, online_bookable = ?, active = ?, updated_at = ?
+        WHERE id = ?
+        "#
+    )
+    .bind(req.name.trim())
+    .bind(&req.description)
+    .bind(req.duration_minutes)
+    .bind(req.buffer_minutes)
+    .bind(req.price_cents)
+    .bind(req.currency.as_deref().unwrap_or("RSD"))
+    .bind(req.online_bookable)
+    .bind(req.active)
+    .bind(Utc::now().to_rfc3339())
+    .bind(&id)
+    .execute(&mut *tx)
+    .await
+    .map_err(database_error)?;
+    // Već zakazani termini zadržavaju resurse koje su zauzeli
+    set_type_resources(&mut tx, &id, &req.resource_ids).await?;
+    set_type_physiotherapists(&mut tx, &id, &req.physiotherapist_ids).await?;
+
+    let appointment_type = fetch_appointment_type(&mut tx, &id).await?;
+    tx.commit().await.map_err(database_error)?;
+
+    Ok(Json(appointment_type))
+}
+
+// Vrsta sa zakazanim terminima se samo isključuje, da istorija ostane čitljiva
+pub async fn delete_appointment_type(
+    Path(id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+    ensure_admin(&claims)?;
+
+    let mut tx = pool.begin().await.map_err(database_error)?;
+    fetch_appointment_type(&mut tx, &id).await?;
+
+    let used: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM appointments WHERE appointment_type_id = ?")
+        .bind(&id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(database_error)?;
+
+    if used > 0 {
+        sqlx::query("UPDATE appointment_types SET active = 0, updated_at = ? WHERE id = ?")
+            .bind(Utc::now().to_rfc3339())
+            .bind(&id)
+            .execute(&mut *tx)
+            .await
+            .map_err(database_error)?;
+    } else {
+        sqlx::query("DELETE FROM appointment_types WHERE id = ?")
+            .bind(&id)
+            .execute(&mut *tx)
+            .await
+            .map_err(database_error)?;
+    }
+    tx.commit().await.map_err(database_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::{create_appointment, get_available_slots, AvailableSlotsQuery, CreateAppointmentRequest};
+    use crate::test_support::{auth_headers, test_config, test_pool};
+
+    fn booking(start_time: &str, appointment_type_id: &str) -> CreateAppointmentRequest {
+        CreateAppointmentRequest {
+            patient_id: "patient-001".to_string(),
+            physiotherapist_id: "physio-001".to_string(),
+            appointment_date: "2030-01-15".to_string(),
+            start_time: start_time.to_string(),
+            appointment_type_id: Some(appointment_type_id.to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn duration_and_buffer_shape_bookings_and_slots() {
+        let pool = test_pool().await;
+
+        let (_, Json(massage)) = create_appointment_type(
+            Extension(pool.clone()),
+            auth_headers("admin-001", "admin"),
+            Json(serde_json::from_value(serde_json::json!({
+                "name": "Sportska masaža",
+                "duration_minutes": 40,
+                "buffer_minutes": 20,
+                "price_cents": 350000,
+                "physiotherapist_ids": ["physio-001"],
+            })).unwrap()),
+        )
+        .await
+        .unwrap();
+
+        let Json(booked) = create_appointment(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-001", "patient"),
+            Json(booking("10:00", &massage.id)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(booked.end_time, "10:40");
+        assert_eq!(booked.duration_minutes, 40);
+        assert_eq!(booked.buffer_minutes, 20);
+
+        // 10:40 pada u pauzu, a 09:40 bi trajao do 10:20
+        let err = create_appointment(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-001", "patient"),
+            Json(booking("10:40", &massage.id)),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+
+        let Json(slots) = get_available_slots(
+            Path("physio-001".to_string()),
+            Query(AvailableSlotsQuery { date: "2030-01-15".to_string(), appointment_type_id: Some(massage.id.clone()) }),
+            Extension(pool.clone()),
+            Extension(test_config()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(slots.duration_minutes, 40);
+        let available = |time: &str| slots.slots.iter().find(|s| s.time == time).unwrap().available;
+        assert!(available("09:00"));
+        assert!(!available("09:40"));
+        assert!(!available("10:40"));
+        assert!(available("11:00"));
+        assert!(!available("15:40"));
+
+        // Prvi pregled se ne zakazuje online
+        let err = create_appointment(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-001", "patient"),
+            Json(booking("13:00", "type-initial-assessment")),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+
+        let Json(assessment) = create_appointment(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(booking("13:00", "type-initial-assessment")),
+        )
+        .await
+        .unwrap();
+        assert_eq!(assessment.end_time, "14:00");
+    }
+}
@@ -0,0 +1,434 @@
+use axum::{
+    http::{header, StatusCode, HeaderMap},
+    response::{IntoResponse, Response},
+    Json,
+    extract::Path,
+    Extension,
+};
+use serde::Serialize;
+use sqlx::{SqliteConnection, SqlitePool};
+use chrono::{DateTime, Utc};
+
+use crate::config::Config;
+use super::{authenticate, clinic_instant, clinic_now, database_error, forbidden, ErrorResponse};
+
+// Domen u UID-u događaja; UID mora ostati isti da bi kalendari prepoznali izmene
+const UID_DOMAIN: &str = "fisionet";
+
+#[derive(Debug, Serialize)]
+pub struct CalendarTokenResponse {
+    pub token: String,
+    pub feed_path: String,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct CalendarEvent {
+    id: String,
+    patient_id: String,
+    physiotherapist_id: String,
+    appointment_date: String,
+    start_time: String,
+    end_time: String,
+    status: String,
+    starts_at: Option<String>,
+    ends_at: Option<String>,
+    updated_at: String,
+    patient_name: String,
+    physiotherapist_name: String,
+    // Broj pomeranja termina, za SEQUENCE
+    changes: i64,
+}
+
+const EVENT_SELECT: &str = r#"
+    SELECT a.id, a.patient_id, a.physiotherapist_id, a.appointment_date,
+           a.start_time, a.end_time, a.status, a.starts_at, a.ends_at, a.updated_at,
+           COALESCE(pu.first_name || ' ' || pu.last_name, '') AS patient_name,
+           COALESCE(fu.first_name || ' ' || fu.last_name, '') AS physiotherapist_name,
+           (SELECT COUNT(*) FROM appointment_history h WHERE h.appointment_id = a.id) AS changes
+    FROM appointments a
+    LEFT JOIN users pu ON pu.id = a.patient_id
+    LEFT JOIN users fu ON fu.id = a.physiotherapist_id
+"#;
+
+fn not_found() -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse { error: "Calendar not found".to_string() })
+    )
+}
+
+// Putanje su oblika /appointments/:id.ics i /calendar/:user_id.:token.ics; axum ne
+// razlikuje rutu po sufiksu, pa se putanja bez .ics odbija ovde
+fn strip_ics(file: &str) -> Result<&str, (StatusCode, Json<ErrorResponse>)> {
+    file.strip_suffix(".ics")
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "Expected a .ics file name".to_string() })
+        ))
+}
+
+fn feed_path(user_id: &str, token: &str) -> String {
+    format!("/calendar/{}.{}.ics", user_id, token)
+}
+
+// Poređenje čije trajanje ne zavisi od toga gde se tokeni razlikuju
+fn tokens_match(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected.bytes().zip(given.bytes()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+// Escape za TEXT vrednosti (RFC 5545, 3.3.11)
+fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+// Linije duže od 75 okteta se prelamaju sa CRLF + razmak (RFC 5545, 3.1)
+fn fold_line(line: &str) -> String {
+    let mut folded = String::new();
+    let mut width = 0;
+    for ch in line.chars() {
+        if width + ch.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(ch);
+        width += ch.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+fn ical_utc(at: DateTime<Utc>) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+// Sačuvani UTC trenutak, ili zidno vreme klinike za starije redove
+fn event_instant(config: &Config, stored: Option<&str>, date: &str, time: &str) -> Option<String> {
+    let at = match stored.and_then(|s| DateTime::parse_from_rfc3339(s).ok()) {
+        Some(at) => at.with_timezone(&Utc),
+        None => clinic_instant(config, date, time).ok()?,
+    };
+    Some(ical_utc(at))
+}
+
+fn render_event(event: &CalendarEvent, viewer_id: &str, config: &Config, now: DateTime<Utc>) -> String {
+    let (start, end) = match (
+        event_instant(config, event.starts_at.as_deref(), &event.appointment_date, &event.start_time),
+        event_instant(config, event.ends_at.as_deref(), &event.appointment_date, &event.end_time),
+    ) {
+        (Some(start), Some(end)) => (start, end),
+        _ => return String::new(),
+    };
+
+    let cancelled = event.status == "cancelled" || event.status == "late_cancellation";
+    let summary = if viewer_id == event.physiotherapist_id {
+        format!("Appointment: {}", event.patient_name)
+    } else {
+        format!("Physiotherapy: {}", event.physiotherapist_name)
+    };
+    let description = format!(
+        "Physiotherapist: {}\nPatient: {}\nStatus: {}",
+        event.physiotherapist_name, event.patient_name, event.status
+    );
+    let last_modified = DateTime::parse_from_rfc3339(&event.updated_at)
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or(now);
+
+    let lines = [
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}@{}", event.id, UID_DOMAIN),
+        format!("SEQUENCE:{}", event.changes + if cancelled { 1 } else { 0 }),
+        format!("DTSTAMP:{}", ical_utc(now)),
+        format!("LAST-MODIFIED:{}", ical_utc(last_modified)),
+        format!("DTSTART:{}", start),
+        format!("DTEND:{}", end),
+        format!("SUMMARY:{}", escape_text(&summary)),
+        format!("DESCRIPTION:{}", escape_text(&description)),
+        format!("LOCATION:{}", escape_text(&config.clinic_location)),
+        format!("STATUS:{}", if cancelled { "CANCELLED" } else { "CONFIRMED" }),
+        "END:VEVENT".to_string(),
+    ];
+
+    lines.iter().map(|line| fold_line(line)).collect()
+}
+
+fn render_calendar(events: &[CalendarEvent], viewer_id: &str, name: &str, config: &Config) -> String {
+    let now = Utc::now();
+    let mut body = String::new();
+    for line in [
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//FisioNet//Appointments//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        format!("X-WR-CALNAME:{}", escape_text(name)),
+    ] {
+        body.push_str(&fold_line(&line));
+    }
+    for event in events {
+        body.push_str(&render_event(event, viewer_id, config, now));
+    }
+    body.push_str(&fold_line("END:VCALENDAR"));
+    body
+}
+
+fn calendar_response(body: String, filename: Option<&str>) -> Response {
+    let mut response = ([(header::CONTENT_TYPE, "text/calendar; charset=utf-8")], body).into_response();
+    if let Some(filename) = filename {
+        if let Ok(value) = format!("attachment; filename=\"{}\"", filename).parse() {
+            response.headers_mut().insert(header::CONTENT_DISPOSITION, value);
+        }
+    }
+    response
+}
+
+fn new_token() -> String {
+    format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+}
+
+// Upiši novi token za korisnika; stari feed prestaje da radi
+async fn store_token(
+    conn: &mut SqliteConnection,
+    user_id: &str,
+) -> Result<CalendarTokenResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token = new_token();
+
+    sqlx::query(
+        r#"
+        INSERT INTO calendar_tokens (user_id, token, created_at) VALUES (?, ?, ?)
+        ON CONFLICT (user_id) DO UPDATE SET token = excluded.token, created_at = excluded.created_at
+        "#
+    )
+    .bind(user_id)
+    .bind(&token)
+    .bind(chrono::Utc::now().to_rfc3339())
+    .execute(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    Ok(CalendarTokenResponse { feed_path: feed_path(user_id, &token), token })
+}
+
+// Jedan termin kao .ics fajl
+pub async fn get_appointment_ics(
+    Path(file): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(config): Extension<Config>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let id = strip_ics(&file)?;
+    let claims = authenticate(&headers)?;
+
+    let event = sqlx::query_as::<_, CalendarEvent>(&format!("{} WHERE a.id = ?", EVENT_SELECT))
+        .bind(id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: "Appointment not found".to_string() })
+        ))?;
+
+    if claims.role != "admin" && claims.sub != event.patient_id && claims.sub != event.physiotherapist_id {
+        return Err(forbidden());
+    }
+
+    let body = render_calendar(std::slice::from_ref(&event), &claims.sub, "FisioNet", &config);
+    Ok(calendar_response(body, Some(&format!("appointment-{}.ics", event.id))))
+}
+
+// Token za pretplatu na kalendar; pravi se pri prvom pozivu
+pub async fn get_calendar_token(
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+) -> Result<Json<CalendarTokenResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+
+    let existing: Option<String> = sqlx::query_scalar("SELECT token FROM calendar_tokens WHERE user_id = ?")
+        .bind(&claims.sub)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(database_error)?;
+
+    let response = match existing {
+        Some(token) => CalendarTokenResponse { feed_path: feed_path(&claims.sub, &token), token },
+        None => store_token(&mut conn, &claims.sub).await?,
+    };
+
+    Ok(Json(response))
+}
+
+pub async fn regenerate_calendar_token(
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+) -> Result<Json<CalendarTokenResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+
+    Ok(Json(store_token(&mut conn, &claims.sub).await?))
+}
+
+// Javni feed sa predstojećim terminima korisnika; token je jedina autorizacija.
+// Otkazani termini ostaju u feed-u sa STATUS:CANCELLED da bi se uklonili iz kalendara.
+pub async fn get_calendar_feed(
+    Path(file): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(config): Extension<Config>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    // Token se ne traži u SQL-u, već se poredi sa tokenom korisnika u konstantnom vremenu
+    let (user_id, token) = strip_ics(&file)?.rsplit_once('.').ok_or_else(not_found)?;
+
+    let stored: Option<String> = sqlx::query_scalar("SELECT token FROM calendar_tokens WHERE user_id = ?")
+        .bind(user_id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(database_error)?;
+    if !stored.is_some_and(|stored| tokens_match(&stored, token)) {
+        return Err(not_found());
+    }
+
+    let events = sqlx::query_as::<_, CalendarEvent>(
+        &format!(
+            r#"
+            {}
+            WHERE (a.patient_id = ? OR a.physiotherapist_id = ?)
+            AND a.appointment_date >= ?
+            ORDER BY a.appointment_date ASC, a.start_time ASC
+            "#,
+            EVENT_SELECT
+        )
+    )
+    .bind(user_id)
+    .bind(user_id)
+    .bind(clinic_now(&config).format("%Y-%m-%d").to_string())
+    .fetch_all(&pool)
+    .await
+    .map_err(database_error)?;
+
+    let body = render_calendar(&events, user_id, "FisioNet appointments", &config);
+    Ok(calendar_response(body, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::{cancel_appointment, create_appointment, CreateAppointmentRequest};
+    use crate::test_support::{auth_headers, test_config, test_pool};
+
+    async fn body_text(response: Response) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn text_is_escaped_and_long_lines_are_folded() {
+        assert_eq!(escape_text("a,b;c\\d\ne"), "a\\,b\\;c\\\\d\\ne");
+
+        let folded = fold_line(&format!("DESCRIPTION:{}", "š".repeat(60)));
+        let lines: Vec<&str> = folded.trim_end_matches("\r\n").split("\r\n").collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| line.len() <= 75));
+        assert!(lines[1].starts_with(' '));
+    }
+
+    #[tokio::test]
+    async fn feed_lists_upcoming_appointments_and_marks_cancellations() {
+        let pool = test_pool().await;
+        let booked = create_appointment(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-001", "patient"),
+            Json(CreateAppointmentRequest {
+                patient_id: "patient-001".to_string(),
+                physiotherapist_id: "physio-001".to_string(),
+                appointment_date: "2030-01-15".to_string(),
+                start_time: "10:00".to_string(),
+                appointment_type_id: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        let err = get_appointment_ics(
+            Path(format!("{}.ics", booked.id)),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("someone-else", "patient"),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+
+        let err = get_appointment_ics(
+            Path(booked.id.clone()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-001", "patient"),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+
+        let Json(token) = get_calendar_token(Extension(pool.clone()), auth_headers("patient-001", "patient"))
+            .await
+            .unwrap();
+        assert_eq!(token.feed_path, format!("/calendar/patient-001.{}.ics", token.token));
+        let feed_file = token.feed_path.strip_prefix("/calendar/").unwrap().to_string();
+
+        // Tuđi korisnik ili izmenjen token ne otvaraju feed
+        for wrong in [format!("physio-001.{}.ics", token.token), format!("patient-001.{}0.ics", token.token)] {
+            let err = get_calendar_feed(Path(wrong), Extension(pool.clone()), Extension(test_config()))
+                .await
+                .unwrap_err();
+            assert_eq!(err.0, StatusCode::NOT_FOUND);
+        }
+
+        let feed = body_text(
+            get_calendar_feed(Path(feed_file.clone()), Extension(pool.clone()), Extension(test_config()))
+                .await
+                .unwrap(),
+        )
+        .await;
+        assert!(feed.contains(&format!("UID:{}@fisionet\r\n", booked.id)));
+        assert!(feed.contains("STATUS:CONFIRMED\r\n"));
+        assert!(feed.contains("LOCATION:FisioNet\r\n"));
+
+        let Json(cancelled) = cancel_appointment(
+            Path(booked.id.clone()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-001", "patient"),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(cancelled.status, "cancelled");
+
+        let feed = body_text(
+            get_calendar_feed(Path(feed_file.clone()), Extension(pool.clone()), Extension(test_config()))
+                .await
+                .unwrap(),
+        )
+        .await;
+        assert!(feed.contains(&format!("UID:{}@fisionet\r\n", booked.id)));
+        assert!(feed.contains("STATUS:CANCELLED\r\n"));
+        assert!(feed.contains("SEQUENCE:1\r\n"));
+
+        // Novi token poništava stari link
+        let Json(regenerated) = regenerate_calendar_token(Extension(pool.clone()), auth_headers("patient-001", "patient"))
+            .await
+            .unwrap();
+        assert_ne!(regenerated.token, token.token);
+
+        let err = get_calendar_feed(Path(feed_file), Extension(pool.clone()), Extension(test_config()))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,388 @@
+use axum::{
+    http::{StatusCode, HeaderMap},
+    Json,
+    extract::Path,
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+
+use crate::config::Config;
+use crate::utils::Claims;
+use super::{
+    appointment_start, authenticate, clinic_now, database_error, ensure_participant,
+    fetch_appointment, forbidden, localize_for, notify_hold, offer_freed_slot,
+    series_occurrences_in_scope, AppointmentResponse, ErrorResponse, SeriesScope, WaitlistHold,
+};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CancelAppointmentRequest {
+    pub reason: Option<String>,
+    // "following" otkazuje i sve naredne termine iz iste serije, "all" sve buduće
+    #[serde(default)]
+    pub scope: SeriesScope,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct PatientCancellationStats {
+    pub patient_id: String,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub email: Option<String>,
+    pub late_cancellations: i64,
+    pub no_shows: i64,
+    #[sqlx(skip)]
+    pub restricted: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CancellationReportResponse {
+    pub cancellation_notice_hours: i64,
+    pub late_cancellation_threshold: i64,
+    pub patients: Vec<PatientCancellationStats>,
+}
+
+// Broj kasnih otkazivanja i nedolazaka koji se računaju pacijentu
+pub(crate) async fn patient_strikes(
+    conn: &mut SqliteConnection,
+    patient_id: &str,
+) -> Result<i64, (StatusCode, Json<ErrorResponse>)> {
+    sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*) FROM appointments
+        WHERE patient_id = ?
+        AND status IN ('late_cancellation', 'no_show')
+        "#
+    )
+    .bind(patient_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(database_error)
+}
+
+// Da li je pacijent prešao prag kasnih otkazivanja i nedolazaka
+pub(crate) async fn is_restricted(
+    conn: &mut SqliteConnection,
+    config: &Config,
+    patient_id: &str,
+) -> Result<bool, (StatusCode, Json<ErrorResponse>)> {
+    if config.late_cancellation_threshold <= 0 {
+        return Ok(false);
+    }
+
+    Ok(patient_strikes(conn, patient_id).await? >= config.late_cancellation_threshold)
+}
+
+// Pacijent preko praga sme da ima najviše restricted_max_future_bookings budućih termina
+// Termini iz `moving` se ne broje, jer se pomeraju a ne dodaju.
+pub(crate) async fn ensure_booking_allowed(
+    conn: &mut SqliteConnection,
+    config: &Config,
+    patient_id: &str,
+    moving: &[&str],
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if !is_restricted(conn, config, patient_id).await? {
+        return Ok(());
+    }
+
+    let sql = format!(
+        r#"
+        SELECT COUNT(*) FROM appointments
+        WHERE patient_id = ?
+        AND status IN ('scheduled', 'confirmed')
+        AND appointment_date || ' ' || start_time >= ?
+        AND id NOT IN ({})
+        "#,
+        vec!["?"; moving.len()].join(", ")
+    );
+    let mut query = sqlx::query_scalar::<_, i64>(&sql)
+        .bind(patient_id)
+        .bind(clinic_now(config).format("%Y-%m-%d %H:%M").to_string());
+    for id in moving {
+        query = query.bind(*id);
+    }
+    let upcoming = query
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(database_error)?;
+
+    if upcoming >= config.restricted_max_future_bookings {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse {
+                error: format!(
+                    "Too many late cancellations or no-shows: only {} future booking(s) allowed at a time",
+                    config.restricted_max_future_bookings
+                ),
+            })
+        ));
+    }
+
+    Ok(())
+}
+
+// Otkaži termin; pacijent koji otkazuje unutar roka dobija status late_cancellation.
+// Vraća status koji je upisan.
+pub(crate) async fn cancel_appointment_in_tx(
+    conn: &mut SqliteConnection,
+    config: &Config,
+    claims: &Claims,
+    appointment: &AppointmentResponse,
+    reason: Option<&str>,
+) -> Result<String, (StatusCode, Json<ErrorResponse>)> {
+    ensure_participant(claims, appointment)?;
+
+    if appointment.status != "scheduled" && appointment.status != "confirmed" {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse { error: "Only scheduled or confirmed appointments can be cancelled".to_string() })
+        ));
+    }
+
+    // Rok se računa između UTC trenutaka, da pomeranje sata ne bi menjalo njegovu dužinu
+    let minutes_left = (appointment_start(config, appointment)? - chrono::Utc::now()).num_minutes();
+    let is_late = claims.sub == appointment.patient_id
+        && minutes_left < config.cancellation_notice_hours * 60;
+    let new_status = if is_late { "late_cancellation" } else { "cancelled" };
+
+    let now = chrono::Utc::now().to_rfc3339();
+
+    sqlx::query(
+        r#"
+        UPDATE appointments
+        SET status = ?, cancelled_at = ?, cancelled_by = ?, cancellation_reason = ?, updated_at = ?
+        WHERE id = ?
+        "#
+    )
+    .bind(new_status)
+    .bind(&now)
+    .bind(&claims.sub)
+    .bind(reason)
+    .bind(&now)
+    .bind(&appointment.id)
+    .execute(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    tracing::info!("Appointment {} {} by {}", appointment.id, new_status, claims.sub);
+
+    Ok(new_status.to_string())
+}
+
+// Otkaži termin i ostale termine serije iz opsega, pa ponudi oslobođene slotove
+// pacijentima sa liste čekanja. Obaveštenja se šalju tek posle commit-a.
+pub(crate) async fn cancel_in_scope(
+    conn: &mut SqliteConnection,
+    config: &Config,
+    claims: &Claims,
+    appointment: &AppointmentResponse,
+    reason: Option<&str>,
+    scope: SeriesScope,
+) -> Result<Vec<WaitlistHold>, (StatusCode, Json<ErrorResponse>)> {
+    cancel_appointment_in_tx(conn, config, claims, appointment, reason).await?;
+
+    let others = series_occurrences_in_scope(conn, config, appointment, scope).await?;
+    for other in &others {
+        cancel_appointment_in_tx(conn, config, claims, other, reason).await?;
+    }
+
+    let mut holds = Vec::new();
+    for freed in std::iter::once(appointment).chain(&others) {
+        if let Some(hold) = offer_freed_slot(conn, config, freed).await? {
+            holds.push(hold);
+        }
+    }
+
+    Ok(holds)
+}
+
+pub async fn cancel_appointment(
+    Path(id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(config): Extension<Config>,
+    headers: HeaderMap,
+    req: Option<Json<CancelAppointmentRequest>>,
+) -> Result<Json<AppointmentResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+    let req = req.map(|Json(r)| r).unwrap_or_default();
+
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE")
+        .await
+        .map_err(database_error)?;
+
+    let appointment = fetch_appointment(&mut tx, &id).await?;
+    let holds = cancel_in_scope(
+        &mut tx,
+        &config,
+        &claims,
+        &appointment,
+        req.reason.as_deref(),
+        req.scope,
+    ).await?;
+
+    let mut updated = fetch_appointment(&mut tx, &id).await?;
+    localize_for(&mut tx, &config, &claims.sub, std::slice::from_mut(&mut updated)).await;
+    tx.commit().await.map_err(database_error)?;
+
+    for hold in &holds {
+        notify_hold(&config, hold);
+    }
+
+    Ok(Json(updated))
+}
+
+// Izveštaj o kasnim otkazivanjima i nedolascima po pacijentu (samo admin)
+pub async fn get_cancellation_report(
+    Extension(pool): Extension<SqlitePool>,
+    Extension(config): Extension<Config>,
+    headers: HeaderMap,
+) -> Result<Json<CancellationReportResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+    if claims.role != "admin" {
+        return Err(forbidden());
+    }
+
+    let mut patients = sqlx::query_as::<_, PatientCancellationStats>(
+        r#"
+        SELECT a.patient_id, u.first_name, u.last_name, u.email,
+               SUM(CASE WHEN a.status = 'late_cancellation' THEN 1 ELSE 0 END) AS late_cancellations,
+               SUM(CASE WHEN a.status = 'no_show' THEN 1 ELSE 0 END) AS no_shows
+        FROM appointments a
+        LEFT JOIN users u ON u.id = a.patient_id
+        GROUP BY a.patient_id
+        ORDER BY late_cancellations + no_shows DESC, u.last_name ASC
+        "#
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(database_error)?;
+
+    for patient in patients.iter_mut() {
+        patient.restricted = config.late_cancellation_threshold > 0
+            && patient.late_cancellations + patient.no_shows >= config.late_cancellation_threshold;
+    }
+
+    Ok(Json(CancellationReportResponse {
+        cancellation_notice_hours: config.cancellation_notice_hours,
+        late_cancellation_threshold: config.late_cancellation_threshold,
+        patients,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+    use crate::handlers::{create_appointment, CreateAppointmentRequest};
+    use crate::test_support::{auth_headers, test_config, test_pool};
+
+    // Upis direktno u bazu, da bi termin mogao biti blizu trenutnog vremena
+    async fn insert_appointment(pool: &SqlitePool, id: &str, starts_at: NaiveDateTime, status: &str) {
+        let ends_at = starts_at + chrono::Duration::minutes(20);
+        sqlx::query(
+            "INSERT INTO appointments (id, patient_id, physiotherapist_id, appointment_date, \
+             start_time, end_time, duration_minutes, status, created_at, updated_at) \
+             VALUES (?, 'patient-001', 'physio-001', ?, ?, ?, 20, ?, '', '')"
+        )
+        .bind(id)
+        .bind(starts_at.format("%Y-%m-%d").to_string())
+        .bind(starts_at.format("%H:%M").to_string())
+        .bind(ends_at.format("%H:%M").to_string())
+        .bind(status)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn cancel(pool: &SqlitePool, id: &str, user_id: &str, role: &str) -> AppointmentResponse {
+        cancel_appointment(
+            Path(id.to_string()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers(user_id, role),
+            None,
+        )
+        .await
+        .unwrap()
+        .0
+    }
+
+    #[tokio::test]
+    async fn patient_cancelling_inside_notice_window_is_late() {
+        let pool = test_pool().await;
+        let soon = clinic_now(&test_config()) + chrono::Duration::hours(3);
+        let later = clinic_now(&test_config()) + chrono::Duration::days(3);
+        insert_appointment(&pool, "soon", soon, "scheduled").await;
+        insert_appointment(&pool, "later", later, "scheduled").await;
+
+        assert_eq!(cancel(&pool, "soon", "patient-001", "patient").await.status, "late_cancellation");
+        assert_eq!(cancel(&pool, "later", "patient-001", "patient").await.status, "cancelled");
+
+        let err = cancel_appointment(
+            Path("soon".to_string()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-001", "patient"),
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn physiotherapist_cancelling_late_is_not_counted() {
+        let pool = test_pool().await;
+        insert_appointment(&pool, "soon", clinic_now(&test_config()) + chrono::Duration::hours(3), "scheduled").await;
+
+        assert_eq!(cancel(&pool, "soon", "physio-001", "physiotherapist").await.status, "cancelled");
+    }
+
+    #[tokio::test]
+    async fn patient_over_threshold_is_limited_to_one_future_booking() {
+        let pool = test_pool().await;
+        let past = clinic_now(&test_config()) - chrono::Duration::days(10);
+        insert_appointment(&pool, "s1", past, "no_show").await;
+        insert_appointment(&pool, "s2", past + chrono::Duration::days(1), "late_cancellation").await;
+        insert_appointment(&pool, "s3", past + chrono::Duration::days(2), "no_show").await;
+
+        let request = |start_time: &str| Json(CreateAppointmentRequest {
+            patient_id: "patient-001".to_string(),
+            physiotherapist_id: "physio-001".to_string(),
+            appointment_date: "2030-01-15".to_string(),
+            start_time: start_time.to_string(),
+            appointment_type_id: None,
+        });
+
+        let first = create_appointment(Extension(pool.clone()), Extension(test_config()), auth_headers("patient-001", "patient"), request("10:00"))
+            .await
+            .unwrap();
+        assert_eq!(first.status, "scheduled");
+
+        let err = create_appointment(Extension(pool.clone()), Extension(test_config()), auth_headers("patient-001", "patient"), request("11:00"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+
+        let Json(report) = get_cancellation_report(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("admin-001", "admin"),
+        )
+        .await
+        .unwrap();
+        let stats = report.patients.iter().find(|p| p.patient_id == "patient-001").unwrap();
+        assert_eq!(stats.late_cancellations, 1);
+        assert_eq!(stats.no_shows, 2);
+        assert!(stats.restricted);
+
+        let err = get_cancellation_report(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-001", "patient"),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+    }
+}
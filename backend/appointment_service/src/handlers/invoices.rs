@@ -0,0 +1,927 @@
+use axum::{
+    http::{header, StatusCode, HeaderMap},
+    response::{IntoResponse, Response},
+    Json,
+    extract::{Path, Query},
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use chrono::{DateTime, Datelike, Utc};
+
+use crate::config::Config;
+use crate::utils::Claims;
+use super::{
+    authenticate, bad_request, clinic_now, database_error, ensure_admin, forbidden, utc_timestamp,
+    AppointmentResponse, ErrorResponse,
+};
+
+const PAYMENT_METHODS: [&str; 3] = ["cash", "card", "insurance"];
+
+// Opis stavke za termine bez vrste termina
+const DEFAULT_LINE_DESCRIPTION: &str = "Physiotherapy session";
+
+#[derive(Debug, Deserialize)]
+pub struct InvoicesQuery {
+    pub patient_id: Option<String>,
+    pub status: Option<String>, // open, paid, void
+    // Opseg datuma izdavanja (YYYY-MM-DD), uključivo
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RecordPaymentRequest {
+    pub amount_cents: i64,
+    pub method: String,            // cash, card, insurance
+    pub reference: Option<String>, // broj slipa ili zahteva osiguranju
+    pub paid_at: Option<String>,   // RFC 3339, podrazumevano sada
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct InvoiceLine {
+    pub id: String,
+    pub appointment_id: String,
+    pub description: String,
+    pub quantity: i64,
+    pub unit_price_cents: i64,
+    pub amount_cents: i64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Payment {
+    pub id: String,
+    pub amount_cents: i64,
+    pub method: String,
+    pub reference: Option<String>,
+    pub recorded_by: String,
+    pub paid_at: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Invoice {
+    pub id: String,
+    pub number: String,
+    pub patient_id: String,
+    pub patient_name: String,
+    pub status: String,
+    pub currency: String,
+    pub total_cents: i64,
+    pub paid_cents: i64,
+    pub balance_cents: i64,
+    pub issue_date: String,
+    pub issued_at: String,
+    #[sqlx(skip)]
+    pub lines: Vec<InvoiceLine>,
+    #[sqlx(skip)]
+    pub payments: Vec<Payment>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InvoicesResponse {
+    pub invoices: Vec<Invoice>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct PatientBalance {
+    pub patient_id: String,
+    pub patient_name: String,
+    pub currency: String,
+    pub open_invoices: i64,
+    pub invoiced_cents: i64,
+    pub paid_cents: i64,
+    pub outstanding_cents: i64,
+    // Najstariji neplaćeni račun
+    pub oldest_issue_date: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BalancesResponse {
+    pub balances: Vec<PatientBalance>,
+}
+
+// Uplate se sabiraju u podupitu da bi stanje bilo tačno i bez stavki
+const INVOICE_SELECT: &str = r#"
+    SELECT i.id, i.number, i.patient_id,
+           COALESCE(u.first_name || ' ' || u.last_name, '') AS patient_name,
+           i.status, i.currency, i.total_cents,
+           COALESCE(p.paid_cents, 0) AS paid_cents,
+           i.total_cents - COALESCE(p.paid_cents, 0) AS balance_cents,
+           i.issue_date, i.issued_at
+    FROM invoices i
+    LEFT JOIN users u ON u.id = i.patient_id
+    LEFT JOIN (
+        SELECT invoice_id, SUM(amount_cents) AS paid_cents FROM payments GROUP BY invoice_id
+    ) p ON p.invoice_id = i.id
+"#;
+
+fn invoice_not_found() -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse { error: "Invoice not found".to_string() })
+    )
+}
+
+// Račun vidi admin i pacijent na koga glasi
+fn ensure_invoice_viewer(claims: &Claims, invoice: &Invoice) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if claims.role == "admin" || claims.sub == invoice.patient_id {
+        Ok(())
+    } else {
+        Err(forbidden())
+    }
+}
+
+// Iznos u para kao "4000.00"
+fn format_amount(cents: i64) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    format!("{}{}.{:02}", sign, cents.abs() / 100, cents.abs() % 100)
+}
+
+// Sledeći broj za prefiks i godinu; poziva se u BEGIN IMMEDIATE transakciji pa nema rupa ni duplikata
+async fn next_invoice_number(
+    conn: &mut SqliteConnection,
+    prefix: &str,
+    year: i32,
+) -> Result<String, (StatusCode, Json<ErrorResponse>)> {
+    let number: i64 = sqlx::query_scalar(
+        r#"
+        INSERT INTO invoice_sequences (prefix, year, last_number) VALUES (?, ?, 1)
+        ON CONFLICT (prefix, year) DO UPDATE SET last_number = last_number + 1
+        RETURNING last_number
+        "#
+    )
+    .bind(prefix)
+    .bind(year)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    Ok(format!("{}-{}-{:05}", prefix, year, number))
+}
+
+// Račun na kom je termin, sa statusom i uplaćenim iznosom
+async fn appointment_invoice(
+    conn: &mut SqliteConnection,
+    appointment_id: &str,
+) -> Result<Option<(String, String, i64)>, (StatusCode, Json<ErrorResponse>)> {
+    sqlx::query_as(
+        r#"
+        SELECT i.id, i.status, COALESCE((SELECT SUM(amount_cents) FROM payments WHERE invoice_id = i.id), 0)
+        FROM invoice_lines l
+        JOIN invoices i ON i.id = l.invoice_id
+        WHERE l.appointment_id = ?
+        "#
+    )
+    .bind(appointment_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(database_error)
+}
+
+async fn set_invoice_status(
+    conn: &mut SqliteConnection,
+    invoice_id: &str,
+    status: &str,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    sqlx::query("UPDATE invoices SET status = ?, updated_at = ? WHERE id = ?")
+        .bind(status)
+        .bind(utc_timestamp(Utc::now()))
+        .bind(invoice_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(database_error)?;
+    Ok(())
+}
+
+// Izdaje račun za završen termin po ceni njegove vrste. Termin se naplaćuje
+// samo jednom: ponovno označavanje kao završen ne pravi novi račun, nego
+// ponovo otvara poništeni.
+pub(crate) async fn issue_invoice(
+    conn: &mut SqliteConnection,
+    config: &Config,
+    appointment: &AppointmentResponse,
+) -> Result<Option<String>, (StatusCode, Json<ErrorResponse>)> {
+    if let Some((invoice_id, status, _)) = appointment_invoice(conn, &appointment.id).await? {
+        if status == "void" {
+            let total: i64 = sqlx::query_scalar("SELECT total_cents FROM invoices WHERE id = ?")
+                .bind(&invoice_id)
+                .fetch_one(&mut *conn)
+                .await
+                .map_err(database_error)?;
+            set_invoice_status(conn, &invoice_id, if total == 0 { "paid" } else { "open" }).await?;
+        }
+        return Ok(None);
+    }
+
+    let priced: Option<(String, i64, String)> = match &appointment.appointment_type_id {
+        Some(type_id) => sqlx::query_as("SELECT name, price_cents, currency FROM appointment_types WHERE id = ?")
+            .bind(type_id)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(database_error)?,
+        None => None,
+    };
+    let (name, price_cents, currency) = priced.unwrap_or_else(|| (
+        DEFAULT_LINE_DESCRIPTION.to_string(),
+        config.default_price_cents,
+        "RSD".to_string(),
+    ));
+
+    let today = clinic_now(config).date();
+    let number = next_invoice_number(conn, &config.invoice_prefix, today.year()).await?;
+    let invoice_id = uuid::Uuid::new_v4().to_string();
+    let now = utc_timestamp(Utc::now());
+    let status = if price_cents == 0 { "paid" } else { "open" };
+
+    sqlx::query(
+        r#"
+        INSERT INTO invoices (id, number, patient_id, status, currency, total_cents, issued_at, issue_date, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&invoice_id)
+    .bind(&number)
+    .bind(&appointment.patient_id)
+    .bind(status)
+    .bind(&currency)
+    .bind(price_cents)
+    .bind(&now)
+    .bind(today.format("%Y-%m-%d").to_string())
+    .bind(&now)
+    .execute(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO invoice_lines (id, invoice_id, appointment_id, description, quantity, unit_price_cents, amount_cents, position)
+        VALUES (?, ?, ?, ?, 1, ?, ?, 0)
+        "#
+    )
+    .bind(uuid::Uuid::new_v4().to_string())
+    .bind(&invoice_id)
+    .bind(&appointment.id)
+    .bind(format!("{} ({} {})", name, appointment.appointment_date, appointment.start_time))
+    .bind(price_cents)
+    .bind(price_cents)
+    .execute(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    Ok(Some(invoice_id))
+}
+
+// Termin koji više nije završen ne sme da ostane naplaćen: račun bez uplata se
+// poništava, a termin čiji je račun (delimično) plaćen ne može da promeni status
+pub(crate) async fn void_invoice(
+    conn: &mut SqliteConnection,
+    appointment: &AppointmentResponse,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    match appointment_invoice(conn, &appointment.id).await? {
+        Some((_, _, paid_cents)) if paid_cents > 0 => Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse {
+                error: "This appointment's invoice has payments; it can no longer leave completed".to_string(),
+            })
+        )),
+        Some((invoice_id, status, _)) if status != "void" => set_invoice_status(conn, &invoice_id, "void").await,
+        _ => Ok(()),
+    }
+}
+
+async fn fetch_invoice(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<Invoice, (StatusCode, Json<ErrorResponse>)> {
+    let mut invoice = sqlx::query_as::<_, Invoice>(&format!("{} WHERE i.id = ?", INVOICE_SELECT))
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(invoice_not_found)?;
+
+    invoice.lines = sqlx::query_as::<_, InvoiceLine>(
+        r#"
+        SELECT id, appointment_id, description, quantity, unit_price_cents, amount_cents
+        FROM invoice_lines
+        WHERE invoice_id = ?
+        ORDER BY position ASC
+        "#
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    invoice.payments = sqlx::query_as::<_, Payment>(
+        r#"
+        SELECT id, amount_cents, method, reference, recorded_by, paid_at, created_at
+        FROM payments
+        WHERE invoice_id = ?
+        ORDER BY paid_at ASC, created_at ASC
+        "#
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    Ok(invoice)
+}
+
+// Računi po filterima; pacijent uvek dobija samo svoje
+async fn find_invoices(
+    pool: &SqlitePool,
+    claims: &Claims,
+    query: &InvoicesQuery,
+) -> Result<Vec<Invoice>, (StatusCode, Json<ErrorResponse>)> {
+    let patient_id = match claims.role.as_str() {
+        "admin" => query.patient_id.clone(),
+        "patient" => Some(claims.sub.clone()),
+        _ => return Err(forbidden()),
+    };
+    if let Some(status) = &query.status {
+        if !["open", "paid", "void"].contains(&status.as_str()) {
+            return Err(bad_request("status must be open, paid or void"));
+        }
+    }
+
+    sqlx::query_as::<_, Invoice>(&format!(
+        r#"{}
+        WHERE (?1 IS NULL OR i.patient_id = ?1)
+          AND (?2 IS NULL OR i.status = ?2)
+          AND (?3 IS NULL OR i.issue_date >= ?3)
+          AND (?4 IS NULL OR i.issue_date <= ?4)
+        ORDER BY i.issued_at DESC, i.number DESC
+        "#,
+        INVOICE_SELECT
+    ))
+    .bind(patient_id)
+    .bind(&query.status)
+    .bind(&query.from)
+    .bind(&query.to)
+    .fetch_all(pool)
+    .await
+    .map_err(database_error)
+}
+
+pub async fn get_invoices(
+    Query(query): Query<InvoicesQuery>,
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+) -> Result<Json<InvoicesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+    let invoices = find_invoices(&pool, &claims, &query).await?;
+
+    Ok(Json(InvoicesResponse { invoices }))
+}
+
+pub async fn get_invoice(
+    Path(id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+) -> Result<Json<Invoice>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+    let invoice = fetch_invoice(&mut conn, &id).await?;
+    ensure_invoice_viewer(&claims, &invoice)?;
+
+    Ok(Json(invoice))
+}
+
+// Uplata na račun; račun prelazi u paid kada je stanje nula
+pub async fn record_payment(
+    Path(id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+    Json(req): Json<RecordPaymentRequest>,
+) -> Result<(StatusCode, Json<Invoice>), (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+    ensure_admin(&claims)?;
+
+    if !PAYMENT_METHODS.contains(&req.method.as_str()) {
+        return Err(bad_request("method must be cash, card or insurance"));
+    }
+    if req.amount_cents <= 0 {
+        return Err(bad_request("amount_cents must be positive"));
+    }
+    let paid_at = match &req.paid_at {
+        Some(paid_at) => DateTime::parse_from_rfc3339(paid_at)
+            .map(|at| utc_timestamp(at.with_timezone(&Utc)))
+            .map_err(|_| bad_request("paid_at must be an RFC 3339 timestamp"))?,
+        None => utc_timestamp(Utc::now()),
+    };
+
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE")
+        .await
+        .map_err(database_error)?;
+
+    let invoice = fetch_invoice(&mut tx, &id).await?;
+    if invoice.status == "void" {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse { error: "Invoice is void".to_string() })
+        ));
+    }
+    if invoice.balance_cents <= 0 {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse { error: "Invoice is already paid".to_string() })
+        ));
+    }
+    if req.amount_cents > invoice.balance_cents {
+        return Err(bad_request(&format!(
+            "Payment exceeds the outstanding balance of {} {}",
+            format_amount(invoice.balance_cents),
+            invoice.currency
+        )));
+    }
+
+    let now = utc_timestamp(Utc::now());
+    sqlx::query(
+        r#"
+        INSERT INTO payments (id, invoice_id, amount_cents, method, reference, recorded_by, paid_at, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(uuid::Uuid::new_v4().to_string())
+    .bind(&id)
+    .bind(req.amount_cents)
+    .bind(&req.method)
+    .bind(req.reference.as_deref().map(str::trim).filter(|r| !r.is_empty()))
+    .bind(&claims.sub)
+    .bind(&paid_at)
+    .bind(&now)
+    .execute(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    if req.amount_cents == invoice.balance_cents {
+        sqlx::query("UPDATE invoices SET status = 'paid', updated_at = ? WHERE id = ?")
+            .bind(&now)
+            .bind(&id)
+            .execute(&mut *tx)
+            .await
+            .map_err(database_error)?;
+    }
+
+    let invoice = fetch_invoice(&mut tx, &id).await?;
+    tx.commit().await.map_err(database_error)?;
+
+    Ok((StatusCode::CREATED, Json(invoice)))
+}
+
+// Neizmirena dugovanja po pacijentu, najveća prva
+pub async fn get_outstanding_balances(
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+) -> Result<Json<BalancesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+    ensure_admin(&claims)?;
+
+    let balances = sqlx::query_as::<_, PatientBalance>(&format!(
+        r#"
+        SELECT patient_id, patient_name, currency,
+               COUNT(*) AS open_invoices,
+               SUM(total_cents) AS invoiced_cents,
+               SUM(paid_cents) AS paid_cents,
+               SUM(balance_cents) AS outstanding_cents,
+               MIN(issue_date) AS oldest_issue_date
+        FROM ({}) invoices
+        WHERE status = 'open'
+        GROUP BY patient_id, patient_name, currency
+        ORDER BY outstanding_cents DESC, patient_id ASC
+        "#,
+        INVOICE_SELECT
+    ))
+    .fetch_all(&pool)
+    .await
+    .map_err(database_error)?;
+
+    Ok(Json(BalancesResponse { balances }))
+}
+
+// Polje za CSV (RFC 4180). Tekst koji počinje sa =, +, - ili @ dobija apostrof
+// da ga tabelarni programi ne bi izvršili kao formulu.
+pub(crate) fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+fn render_invoices_csv(invoices: &[Invoice]) -> String {
+    let mut body = String::from("number,issue_date,patient_id,patient_name,status,currency,total,paid,balance\r\n");
+    for invoice in invoices {
+        let row = [
+            csv_field(&invoice.number),
+            invoice.issue_date.clone(),
+            csv_field(&invoice.patient_id),
+            csv_field(&invoice.patient_name),
+            invoice.status.clone(),
+            csv_field(&invoice.currency),
+            format_amount(invoice.total_cents),
+            format_amount(invoice.paid_cents),
+            format_amount(invoice.balance_cents),
+        ];
+        body.push_str(&row.join(","));
+        body.push_str("\r\n");
+    }
+    body
+}
+
+pub(crate) fn attachment(content_type: &str, filename: &str, body: impl IntoResponse) -> Response {
+    let mut response = ([(header::CONTENT_TYPE, content_type.to_string())], body).into_response();
+    if let Ok(value) = format!("attachment; filename=\"{}\"", filename).parse() {
+        response.headers_mut().insert(header::CONTENT_DISPOSITION, value);
+    }
+    response
+}
+
+// Računi po istim filterima kao GET /invoices, kao CSV
+pub async fn export_invoices_csv(
+    Query(query): Query<InvoicesQuery>,
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+    let invoices = find_invoices(&pool, &claims, &query).await?;
+
+    Ok(attachment("text/csv; charset=utf-8", "invoices.csv", render_invoices_csv(&invoices)))
+}
+
+// Tekst za PDF string u WinAnsiEncoding. Š i Ž postoje u kodnoj strani,
+// ostala slova sa kvačicama se pišu bez njih.
+fn pdf_text(text: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '(' | ')' | '\\' => {
+                bytes.push(b'\\');
+                bytes.push(ch as u8);
+            }
+            'Š' => bytes.push(0x8A),
+            'š' => bytes.push(0x9A),
+            'Ž' => bytes.push(0x8E),
+            'ž' => bytes.push(0x9E),
+            'Č' | 'Ć' => bytes.push(b'C'),
+            'č' | 'ć' => bytes.push(b'c'),
+            'Đ' => bytes.extend_from_slice(b"Dj"),
+            'đ' => bytes.extend_from_slice(b"dj"),
+            ' '..='~' => bytes.push(ch as u8),
+            '\u{A0}'..='\u{FF}' => bytes.push(ch as u32 as u8),
+            _ => bytes.push(b'?'),
+        }
+    }
+    bytes
+}
+
+// A4 strana u tačkama
+const PAGE_WIDTH: i32 = 595;
+const PAGE_HEIGHT: i32 = 842;
+const MARGIN: i32 = 50;
+
+// Jednostavan PDF sa Helvetica fontom, bez spoljnih biblioteka
+struct PdfWriter {
+    pages: Vec<Vec<u8>>,
+    y: i32,
+}
+
+impl PdfWriter {
+    fn new() -> Self {
+        Self { pages: vec![Vec::new()], y: PAGE_HEIGHT - MARGIN }
+    }
+
+    // Red teksta; kolone su parovi (x, tekst)
+    fn row(&mut self, size: i32, columns: &[(i32, &str)]) {
+        if self.y - size < MARGIN {
+            self.pages.push(Vec::new());
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+        self.y -= size;
+        let page = self.pages.last_mut().expect("writer always has a page");
+        for (x, text) in columns {
+            page.extend_from_slice(format!("BT /F1 {} Tf {} {} Td (", size, x, self.y).as_bytes());
+            page.extend_from_slice(&pdf_text(text));
+            page.extend_from_slice(b") Tj ET\n");
+        }
+        self.y -= size / 2;
+    }
+
+    fn gap(&mut self, points: i32) {
+        self.y -= points;
+    }
+
+    fn finish(self) -> Vec<u8> {
+        // 1 katalog, 2 stablo strana, 3 font, zatim po dva objekta za svaku stranu
+        let page_count = self.pages.len();
+        let kids: Vec<String> = (0..page_count).map(|i| format!("{} 0 R", 4 + i * 2)).collect();
+        let mut objects: Vec<Vec<u8>> = vec![
+            b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+            format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), page_count).into_bytes(),
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec(),
+        ];
+        for (i, content) in self.pages.into_iter().enumerate() {
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH, PAGE_HEIGHT, 5 + i * 2
+            ).into_bytes());
+            let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+            stream.extend_from_slice(&content);
+            stream.extend_from_slice(b"\nendstream");
+            objects.push(stream);
+        }
+
+        let mut pdf = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+            pdf.extend_from_slice(object);
+            pdf.extend_from_slice(b"\nendobj\n");
+        }
+
+        let xref = pdf.len();
+        pdf.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+        for offset in offsets {
+            pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+        pdf.extend_from_slice(format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        ).as_bytes());
+        pdf
+    }
+}
+
+fn render_invoice_pdf(invoice: &Invoice, config: &Config) -> Vec<u8> {
+    let money = |cents: i64| format!("{} {}", format_amount(cents), invoice.currency);
+    let mut pdf = PdfWriter::new();
+
+    pdf.row(18, &[(MARGIN, &config.clinic_location)]);
+    pdf.row(14, &[(MARGIN, &format!("Invoice {}", invoice.number))]);
+    pdf.row(10, &[(MARGIN, &format!("Issue date: {}", invoice.issue_date))]);
+    pdf.row(10, &[(MARGIN, &format!("Patient: {}", invoice.patient_name))]);
+    pdf.gap(16);
+
+    pdf.row(10, &[(MARGIN, "Description"), (360, "Qty"), (400, "Unit price"), (490, "Amount")]);
+    for line in &invoice.lines {
+        pdf.row(10, &[
+            (MARGIN, &line.description),
+            (360, &line.quantity.to_string()),
+            (400, &format_amount(line.unit_price_cents)),
+            (490, &format_amount(line.amount_cents)),
+        ]);
+    }
+    pdf.gap(10);
+    pdf.row(10, &[(400, "Total"), (490, &money(invoice.total_cents))]);
+    pdf.row(10, &[(400, "Paid"), (490, &money(invoice.paid_cents))]);
+    pdf.row(12, &[(400, "Balance due"), (490, &money(invoice.balance_cents))]);
+
+    if !invoice.payments.is_empty() {
+        pdf.gap(16);
+        pdf.row(10, &[(MARGIN, "Payments")]);
+        for payment in &invoice.payments {
+            pdf.row(10, &[
+                (MARGIN, payment.paid_at.get(..10).unwrap_or(&payment.paid_at)),
+                (150, &payment.method),
+                (250, payment.reference.as_deref().unwrap_or("")),
+                (490, &money(payment.amount_cents)),
+            ]);
+        }
+    }
+
+    pdf.finish()
+}
+
+pub async fn get_invoice_pdf(
+    Path(id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(config): Extension<Config>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+    let invoice = fetch_invoice(&mut conn, &id).await?;
+    ensure_invoice_viewer(&claims, &invoice)?;
+
+    let filename = format!("{}.pdf", invoice.number);
+    Ok(attachment("application/pdf", &filename, render_invoice_pdf(&invoice, &config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::{create_appointment, update_appointment_status, CreateAppointmentRequest};
+    use crate::test_support::{auth_headers, test_config, test_pool};
+
+    async fn completed_appointment(pool: &SqlitePool, start_time: &str, appointment_type_id: Option<&str>) -> String {
+        let Json(appointment) = create_appointment(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(CreateAppointmentRequest {
+                patient_id: "patient-001".to_string(),
+                physiotherapist_id: "physio-001".to_string(),
+                appointment_date: "2030-01-15".to_string(),
+                start_time: start_time.to_string(),
+                appointment_type_id: appointment_type_id.map(str::to_string),
+            }),
+        )
+        .await
+        .unwrap();
+        set_status(pool, &appointment.id, "completed").await;
+        appointment.id
+    }
+
+    async fn set_status(pool: &SqlitePool, id: &str, status: &str) {
+        let status = update_appointment_status(
+            Path(id.to_string()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(serde_json::json!({ "status": status })),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    fn payment(amount_cents: i64, method: &str) -> RecordPaymentRequest {
+        RecordPaymentRequest {
+            amount_cents,
+            method: method.to_string(),
+            reference: None,
+            paid_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn completed_appointments_are_invoiced_once_with_sequential_numbers() {
+        let pool = test_pool().await;
+        let massage = completed_appointment(&pool, "09:00", Some("type-massage")).await;
+        completed_appointment(&pool, "11:00", None).await;
+
+        // Ponovno završavanje ne naplaćuje termin dva puta
+        set_status(&pool, &massage, "confirmed").await;
+        set_status(&pool, &massage, "completed").await;
+
+        let Json(list) = get_invoices(
+            Query(InvoicesQuery { patient_id: None, status: None, from: None, to: None }),
+            Extension(pool.clone()),
+            auth_headers("patient-001", "patient"),
+        )
+        .await
+        .unwrap();
+        let year = clinic_now(&test_config()).year();
+        let mut numbers: Vec<(&str, i64)> = list.invoices.iter().map(|i| (i.number.as_str(), i.total_cents)).collect();
+        numbers.sort();
+        assert_eq!(numbers, vec![
+            (format!("FN-{}-00001", year).as_str(), 300000),
+            (format!("FN-{}-00002", year).as_str(), 200000),
+        ]);
+
+        let err = get_invoices(
+            Query(InvoicesQuery { patient_id: None, status: None, from: None, to: None }),
+            Extension(pool.clone()),
+            auth_headers("physio-001", "physiotherapist"),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn payments_settle_the_balance() {
+        let pool = test_pool().await;
+        completed_appointment(&pool, "09:00", Some("type-massage")).await;
+        let invoice_id: String = sqlx::query_scalar("SELECT id FROM invoices").fetch_one(&pool).await.unwrap();
+
+        let (status, Json(partial)) = record_payment(
+            Path(invoice_id.clone()),
+            Extension(pool.clone()),
+            auth_headers("admin-001", "admin"),
+            Json(payment(100000, "cash")),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!((partial.status.as_str(), partial.balance_cents), ("open", 200000));
+
+        let Json(balances) = get_outstanding_balances(Extension(pool.clone()), auth_headers("admin-001", "admin"))
+            .await
+            .unwrap();
+        assert_eq!(balances.balances.len(), 1);
+        assert_eq!(balances.balances[0].outstanding_cents, 200000);
+
+        let err = record_payment(
+            Path(invoice_id.clone()),
+            Extension(pool.clone()),
+            auth_headers("admin-001", "admin"),
+            Json(payment(250000, "card")),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+
+        let (_, Json(paid)) = record_payment(
+            Path(invoice_id.clone()),
+            Extension(pool.clone()),
+            auth_headers("admin-001", "admin"),
+            Json(RecordPaymentRequest { reference: Some("POL-42".to_string()), ..payment(200000, "insurance") }),
+        )
+        .await
+        .unwrap();
+        assert_eq!((paid.status.as_str(), paid.balance_cents), ("paid", 0));
+        assert_eq!(paid.payments.len(), 2);
+
+        let err = record_payment(
+            Path(invoice_id.clone()),
+            Extension(pool.clone()),
+            auth_headers("admin-001", "admin"),
+            Json(payment(100, "cash")),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+
+        let Json(balances) = get_outstanding_balances(Extension(pool.clone()), auth_headers("admin-001", "admin"))
+            .await
+            .unwrap();
+        assert!(balances.balances.is_empty());
+
+        let csv = render_invoices_csv(std::slice::from_ref(&paid));
+        assert!(csv.lines().nth(1).unwrap().ends_with(",paid,RSD,3000.00,3000.00,0.00"));
+
+        let pdf = render_invoice_pdf(&paid, &test_config());
+        assert!(pdf.starts_with(b"%PDF-1.4"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+    }
+
+    #[tokio::test]
+    async fn leaving_completed_voids_an_unpaid_invoice() {
+        let pool = test_pool().await;
+        let massage = completed_appointment(&pool, "09:00", Some("type-massage")).await;
+        let invoice_status = || async {
+            sqlx::query_as::<_, (String, String)>("SELECT number, status FROM invoices")
+                .fetch_all(&pool)
+                .await
+                .unwrap()
+        };
+        let (number, _) = invoice_status().await.remove(0);
+
+        // Greškom označen kao završen: račun se poništava i ne plaća se
+        set_status(&pool, &massage, "no_show").await;
+        assert_eq!(invoice_status().await, vec![(number.clone(), "void".to_string())]);
+        let invoice_id: String = sqlx::query_scalar("SELECT id FROM invoices").fetch_one(&pool).await.unwrap();
+        let err = record_payment(
+            Path(invoice_id.clone()),
+            Extension(pool.clone()),
+            auth_headers("admin-001", "admin"),
+            Json(payment(100, "cash")),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+
+        // Ponovo završen: isti račun se ponovo otvara
+        set_status(&pool, &massage, "completed").await;
+        assert_eq!(invoice_status().await, vec![(number.clone(), "open".to_string())]);
+
+        // Posle uplate termin više ne može da napusti status završen
+        let (_, _) = record_payment(
+            Path(invoice_id),
+            Extension(pool.clone()),
+            auth_headers("admin-001", "admin"),
+            Json(payment(100, "cash")),
+        )
+        .await
+        .unwrap();
+        let err = update_appointment_status(
+            Path(massage.clone()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(serde_json::json!({ "status": "scheduled" })),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+        let status: String = sqlx::query_scalar("SELECT status FROM appointments WHERE id = ?")
+            .bind(&massage)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(status, "completed");
+    }
+}
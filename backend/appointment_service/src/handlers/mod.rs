@@ -1,14 +1,46 @@
 ﻿use axum::{
-    http::StatusCode,
+    http::{StatusCode, HeaderMap},
     Json,
     extract::{Path, Query},
     Extension,
 };
 use serde::{Deserialize, Serialize};
-use sqlx::SqlitePool;
-use chrono::{NaiveTime, Duration};
+use sqlx::{SqliteConnection, SqlitePool};
+use chrono::{DateTime, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, Duration, SecondsFormat, TimeZone, Utc};
+use chrono_tz::Tz;
 
-#[derive(Serialize)]
+use crate::config::Config;
+use crate::utils::{verify_jwt_token, Claims};
+
+mod adherence;
+mod analytics;
+mod appointment_types;
+mod calendar;
+mod cancellation;
+mod invoices;
+mod reminders;
+mod reschedule;
+mod resources;
+mod series;
+mod session_notes;
+mod therapy_plans;
+mod waitlist;
+
+pub use adherence::*;
+pub use analytics::*;
+pub use appointment_types::*;
+pub use calendar::*;
+pub use cancellation::*;
+pub use invoices::*;
+pub use reminders::*;
+pub use reschedule::*;
+pub use resources::*;
+pub use series::*;
+pub use session_notes::*;
+pub use therapy_plans::*;
+pub use waitlist::*;
+
+#[derive(Debug, Serialize)]
 pub struct ErrorResponse {
     pub error: String,
 }
@@ -16,6 +48,8 @@ pub struct ErrorResponse {
 #[derive(Deserialize)]
 pub struct AvailableSlotsQuery {
     pub date: String,
+    // Sa vrstom termina slot mora imati i slobodne resurse koje ona traži
+    pub appointment_type_id: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -28,6 +62,10 @@ pub struct TimeSlot {
 #[derive(Serialize)]
 pub struct AvailableSlotsResponse {
     pub date: String,
+    // Zona u kojoj su date vremena slotova (zona klinike)
+    pub timezone: String,
+    // Trajanje termina koji bi počeo u slobodnom slotu
+    pub duration_minutes: i64,
     pub slots: Vec<TimeSlot>,
 }
 
@@ -37,9 +75,10 @@ pub struct CreateAppointmentRequest {
     pub physiotherapist_id: String,
     pub appointment_date: String,
     pub start_time: String,
+    pub appointment_type_id: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Debug, Serialize, sqlx::FromRow)]
 pub struct AppointmentResponse {
     pub id: String,
     pub patient_id: String,
@@ -48,14 +87,471 @@ pub struct AppointmentResponse {
     pub start_time: String,
     pub end_time: String,
     pub duration_minutes: i32,
+    // Pauza posle termina u kojoj fizioterapeut nije slobodan
+    pub buffer_minutes: i32,
     pub status: String,
+    pub series_id: Option<String>,
+    pub appointment_type_id: Option<String>,
+    // UTC trenuci početka i kraja (RFC 3339)
+    pub starts_at: Option<String>,
+    pub ends_at: Option<String>,
+    // Vreme termina u zoni korisnika koji ga gleda
+    #[sqlx(skip)]
+    pub local: Option<LocalTimes>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalTimes {
+    pub timezone: String,
+    pub date: String,
+    pub start_time: String,
+    pub end_time: String,
 }
 
+// Kolone koje se čitaju u AppointmentResponse
+pub(crate) const APPOINTMENT_COLUMNS: &str = "id, patient_id, physiotherapist_id, appointment_date, \
+    start_time, end_time, duration_minutes, buffer_minutes, status, series_id, appointment_type_id, \
+    starts_at, ends_at";
+
 #[derive(Serialize)]
 pub struct AppointmentsListResponse {
     pub appointments: Vec<AppointmentResponse>,
 }
 
+// Radno vreme i trajanje jednog slota
+pub(crate) const WORK_START_HOUR: u32 = 8;
+pub(crate) const WORK_END_HOUR: u32 = 16;
+pub(crate) const SLOT_MINUTES: i64 = 20;
+
+// Trenutno vreme u zidnom vremenu klinike, u kome se čuvaju termini
+pub(crate) fn clinic_now(config: &Config) -> NaiveDateTime {
+    Utc::now().with_timezone(&config.clinic_timezone).naive_local()
+}
+
+// UTC vreme u formatu koji se može porediti kao string (starts_at, expires_at)
+pub(crate) fn utc_timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+// Zidno vreme klinike u UTC trenutak. Kod pomeranja sata unazad uzima se prvo
+// pojavljivanje; vreme koje ne postoji (pomeranje unapred) je greška.
+pub(crate) fn clinic_instant(
+    config: &Config,
+    date: &str,
+    time: &str,
+) -> Result<DateTime<Utc>, (StatusCode, Json<ErrorResponse>)> {
+    let naive = NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M")
+        .map_err(|_| (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "Invalid date or time format".to_string() })
+        ))?;
+
+    match config.clinic_timezone.from_local_datetime(&naive) {
+        LocalResult::Single(at) | LocalResult::Ambiguous(at, _) => Ok(at.with_timezone(&Utc)),
+        LocalResult::None => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "This time does not exist in the clinic time zone".to_string() })
+        )),
+    }
+}
+
+// Početak termina kao UTC trenutak; stariji redovi bez starts_at se računaju iz zidnog vremena
+pub(crate) fn appointment_start(
+    config: &Config,
+    appointment: &AppointmentResponse,
+) -> Result<DateTime<Utc>, (StatusCode, Json<ErrorResponse>)> {
+    if let Some(at) = appointment.starts_at.as_deref().and_then(|s| DateTime::parse_from_rfc3339(s).ok()) {
+        return Ok(at.with_timezone(&Utc));
+    }
+    clinic_instant(config, &appointment.appointment_date, &appointment.start_time)
+}
+
+// Zona korisnika iz users.timezone, ili zona klinike
+pub(crate) async fn user_timezone(
+    conn: &mut SqliteConnection,
+    config: &Config,
+    user_id: &str,
+) -> Tz {
+    let timezone: Option<String> = sqlx::query_scalar("SELECT timezone FROM users WHERE id = ?")
+        .bind(user_id)
+        .fetch_optional(&mut *conn)
+        .await
+        .ok()
+        .flatten()
+        .flatten();
+
+    timezone
+        .and_then(|tz| tz.parse().ok())
+        .unwrap_or(config.clinic_timezone)
+}
+
+// Popuni local za korisnika koji gleda termine
+pub(crate) async fn localize_for(
+    conn: &mut SqliteConnection,
+    config: &Config,
+    viewer_id: &str,
+    appointments: &mut [AppointmentResponse],
+) {
+    let timezone = user_timezone(conn, config, viewer_id).await;
+    for appointment in appointments.iter_mut() {
+        localize(config, appointment, timezone);
+    }
+}
+
+pub(crate) fn localize(config: &Config, appointment: &mut AppointmentResponse, timezone: Tz) {
+    let start = match appointment_start(config, appointment) {
+        Ok(start) => start,
+        Err(_) => return,
+    };
+    let end = start + Duration::minutes(appointment.duration_minutes as i64);
+    let local_start = start.with_timezone(&timezone);
+
+    appointment.local = Some(LocalTimes {
+        timezone: timezone.name().to_string(),
+        date: local_start.format("%Y-%m-%d").to_string(),
+        start_time: local_start.format("%H:%M").to_string(),
+        end_time: end.with_timezone(&timezone).format("%H:%M").to_string(),
+    });
+}
+
+// UTC početak i kraj za zidno vreme klinike, u obliku koji se čuva u bazi
+pub(crate) fn clinic_instants(
+    config: &Config,
+    appointment_date: &str,
+    start_time: &str,
+    end_time: &str,
+) -> Result<(String, String), (StatusCode, Json<ErrorResponse>)> {
+    Ok((
+        utc_timestamp(clinic_instant(config, appointment_date, start_time)?),
+        utc_timestamp(clinic_instant(config, appointment_date, end_time)?),
+    ))
+}
+
+// Popuni starts_at/ends_at za termine upisane pre uvođenja vremenskih zona (migracija 017).
+// SQLite nema bazu vremenskih zona, pa se to radi ovde, u zoni klinike iz CLINIC_TIMEZONE.
+pub async fn backfill_appointment_instants(pool: &SqlitePool, config: &Config) -> Result<usize, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let rows: Vec<(String, String, String, String)> = sqlx::query_as(
+        "SELECT id, appointment_date, start_time, end_time FROM appointments WHERE starts_at IS NULL"
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut converted = 0;
+    for (id, date, start_time, end_time) in rows {
+        let Ok((starts_at, ends_at)) = clinic_instants(config, &date, &start_time, &end_time) else {
+            tracing::warn!("Appointment {} has a date or time that does not exist in {}, skipping", id, config.clinic_timezone);
+            continue;
+        };
+
+        sqlx::query("UPDATE appointments SET starts_at = ?, ends_at = ? WHERE id = ?")
+            .bind(&starts_at)
+            .bind(&ends_at)
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+        converted += 1;
+    }
+    tx.commit().await?;
+
+    Ok(converted)
+}
+
+pub(crate) fn slot_conflict() -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::CONFLICT,
+        Json(ErrorResponse { error: "Time slot already booked".to_string() })
+    )
+}
+
+// Greška koju vraća unique indeks ili trigger za preklapanje termina (migracija 011)
+pub(crate) fn is_slot_conflict(err: &sqlx::Error) -> bool {
+    match err {
+        sqlx::Error::Database(db_err) => {
+            db_err.is_unique_violation() || db_err.message().contains("appointment_overlap")
+        }
+        _ => false,
+    }
+}
+
+pub(crate) fn database_error(e: sqlx::Error) -> (StatusCode, Json<ErrorResponse>) {
+    if is_slot_conflict(&e) {
+        return slot_conflict();
+    }
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse { error: format!("Database error: {}", e) })
+    )
+}
+
+// Termin mora biti u radnom vremenu i poravnat sa 20-minutnim slotovima.
+// Vraća normalizovane (datum, početak, kraj) stringove kakvi se čuvaju u bazi.
+pub(crate) fn validate_slot(
+    appointment_date: &str,
+    start_time: &str,
+    duration_minutes: i64,
+) -> Result<(String, String, String), (StatusCode, Json<ErrorResponse>)> {
+    let date = NaiveDate::parse_from_str(appointment_date, "%Y-%m-%d")
+        .map_err(|_| (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "Invalid date format".to_string() })
+        ))?;
+
+    let start = NaiveTime::parse_from_str(start_time, "%H:%M")
+        .map_err(|_| (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "Invalid time format".to_string() })
+        ))?;
+
+    let day_start = NaiveTime::from_hms_opt(WORK_START_HOUR, 0, 0).unwrap();
+    let day_end = NaiveTime::from_hms_opt(WORK_END_HOUR, 0, 0).unwrap();
+    let end = start + Duration::minutes(duration_minutes);
+
+    let minutes_from_open = (start - day_start).num_minutes();
+    if start < day_start || end > day_end || end <= start || minutes_from_open % SLOT_MINUTES != 0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "Time is outside of working hours or not aligned to a slot".to_string() })
+        ));
+    }
+
+    Ok((
+        date.format("%Y-%m-%d").to_string(),
+        start.format("%H:%M").to_string(),
+        end.format("%H:%M").to_string(),
+    ))
+}
+
+// Termin koji je već počeo ne može da se zakaže niti da se na njega pomeri drugi
+pub(crate) fn ensure_future_start(
+    config: &Config,
+    appointment_date: &str,
+    start_time: &str,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    let starts_at = format!("{} {}", appointment_date, start_time);
+    if starts_at <= clinic_now(config).format("%Y-%m-%d %H:%M").to_string() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "Appointment time is in the past".to_string() })
+        ));
+    }
+    Ok(())
+}
+
+// Kraj termina zajedno sa pauzom posle njega, "HH:MM"
+pub(crate) fn blocked_until(end_time: &str, buffer_minutes: i64) -> String {
+    match NaiveTime::parse_from_str(end_time, "%H:%M") {
+        Ok(end) => (end + Duration::minutes(buffer_minutes)).format("%H:%M").to_string(),
+        Err(_) => end_time.to_string(),
+    }
+}
+
+// end_time je kraj termina koji se proverava, uključujući njegovu pauzu;
+// pauze postojećih termina se dodaju ovde
+pub(crate) async fn slot_is_free(
+    conn: &mut SqliteConnection,
+    physiotherapist_id: &str,
+    appointment_date: &str,
+    start_time: &str,
+    end_time: &str,
+    exclude_id: Option<&str>,
+) -> Result<bool, (StatusCode, Json<ErrorResponse>)> {
+    let count: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*) FROM appointments
+        WHERE physiotherapist_id = ?
+        AND appointment_date = ?
+        AND start_time < ?
+        AND strftime('%H:%M', end_time, '+' || buffer_minutes || ' minutes') > ?
+        AND status NOT IN ('cancelled', 'late_cancellation')
+        AND id != COALESCE(?, '')
+        "#
+    )
+    .bind(physiotherapist_id)
+    .bind(appointment_date)
+    .bind(end_time)
+    .bind(start_time)
+    .bind(exclude_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    if count > 0 {
+        return Ok(false);
+    }
+
+    // Slot koji je ponuđen nekome sa liste čekanja nije slobodan dok ponuda važi
+    Ok(!slot_is_held(conn, physiotherapist_id, appointment_date, start_time, end_time).await?)
+}
+
+// Proveri da fizioterapeut nema aktivan termin koji se preklapa sa [start, end).
+// Mora se pozvati unutar BEGIN IMMEDIATE transakcije.
+pub(crate) async fn ensure_slot_free(
+    conn: &mut SqliteConnection,
+    physiotherapist_id: &str,
+    appointment_date: &str,
+    start_time: &str,
+    end_time: &str,
+    exclude_id: Option<&str>,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if !slot_is_free(conn, physiotherapist_id, appointment_date, start_time, end_time, exclude_id).await? {
+        return Err(slot_conflict());
+    }
+
+    Ok(())
+}
+
+// Upiši novi zakazan termin i vrati ga
+pub(crate) async fn insert_appointment(
+    conn: &mut SqliteConnection,
+    config: &Config,
+    patient_id: &str,
+    physiotherapist_id: &str,
+    appointment_date: &str,
+    start_time: &str,
+    end_time: &str,
+) -> Result<AppointmentResponse, (StatusCode, Json<ErrorResponse>)> {
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+    let (starts_at, ends_at) = clinic_instants(config, appointment_date, start_time, end_time)?;
+    let duration_minutes = match (
+        NaiveTime::parse_from_str(start_time, "%H:%M"),
+        NaiveTime::parse_from_str(end_time, "%H:%M"),
+    ) {
+        (Ok(start), Ok(end)) => (end - start).num_minutes(),
+        _ => SLOT_MINUTES,
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO appointments (
+            id, patient_id, physiotherapist_id, appointment_date,
+            start_time, end_time, duration_minutes, status,
+            starts_at, ends_at, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&id)
+    .bind(patient_id)
+    .bind(physiotherapist_id)
+    .bind(appointment_date)
+    .bind(start_time)
+    .bind(end_time)
+    .bind(duration_minutes)
+    .bind("scheduled")
+    .bind(&starts_at)
+    .bind(&ends_at)
+    .bind(&now)
+    .bind(&now)
+    .execute(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    Ok(AppointmentResponse {
+        id,
+        patient_id: patient_id.to_string(),
+        physiotherapist_id: physiotherapist_id.to_string(),
+        appointment_date: appointment_date.to_string(),
+        start_time: start_time.to_string(),
+        end_time: end_time.to_string(),
+        duration_minutes: duration_minutes as i32,
+        buffer_minutes: 0,
+        status: "scheduled".to_string(),
+        series_id: None,
+        appointment_type_id: None,
+        starts_at: Some(starts_at),
+        ends_at: Some(ends_at),
+        local: None,
+    })
+}
+
+// Poveži termin sa vrstom, upiši pauzu i zauzmi resurse koje vrsta traži
+pub(crate) async fn assign_appointment_type(
+    conn: &mut SqliteConnection,
+    appointment: &mut AppointmentResponse,
+    appointment_type_id: &str,
+    buffer_minutes: i64,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    sqlx::query("UPDATE appointments SET appointment_type_id = ?, buffer_minutes = ? WHERE id = ?")
+        .bind(appointment_type_id)
+        .bind(buffer_minutes)
+        .bind(&appointment.id)
+        .execute(&mut *conn)
+        .await
+        .map_err(database_error)?;
+    attach_resources(conn, &appointment.id, appointment_type_id).await?;
+
+    appointment.appointment_type_id = Some(appointment_type_id.to_string());
+    appointment.buffer_minutes = buffer_minutes as i32;
+    Ok(())
+}
+
+pub(crate) async fn fetch_appointment(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<AppointmentResponse, (StatusCode, Json<ErrorResponse>)> {
+    sqlx::query_as::<_, AppointmentResponse>(
+        &format!("SELECT {} FROM appointments WHERE id = ?", APPOINTMENT_COLUMNS)
+    )
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse { error: "Appointment not found".to_string() })
+    ))
+}
+
+pub(crate) fn forbidden() -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::FORBIDDEN,
+        Json(ErrorResponse { error: "Insufficient permissions".to_string() })
+    )
+}
+
+pub(crate) fn ensure_admin(claims: &Claims) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if claims.role != "admin" {
+        return Err(forbidden());
+    }
+    Ok(())
+}
+
+// Pacijent, fizioterapeut sa termina ili admin
+pub(crate) fn ensure_participant(claims: &Claims, appointment: &AppointmentResponse) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if claims.role == "admin"
+        || claims.sub == appointment.patient_id
+        || claims.sub == appointment.physiotherapist_id
+    {
+        return Ok(());
+    }
+    Err(forbidden())
+}
+
+// Pacijent zakazuje samo za sebe; fizioterapeut i admin mogu u ime pacijenta
+pub(crate) fn ensure_can_book_for(claims: &Claims, patient_id: &str) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if claims.role == "patient" && claims.sub != patient_id {
+        return Err(forbidden());
+    }
+    Ok(())
+}
+
+// Izvuci korisnika iz Bearer tokena koji izdaje auth_service
+pub(crate) fn authenticate(headers: &HeaderMap) -> Result<Claims, (StatusCode, Json<ErrorResponse>)> {
+    let token = headers.get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or_else(|| (
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse { error: "Missing or invalid authorization header".to_string() })
+        ))?;
+
+    verify_jwt_token(token).map_err(|_| (
+        StatusCode::UNAUTHORIZED,
+        Json(ErrorResponse { error: "Invalid or expired token".to_string() })
+    ))
+}
+
 pub async fn health_check() -> &'static str {
     "Appointment Service is running!"
 }
@@ -64,38 +560,84 @@ pub async fn get_available_slots(
     Path(physiotherapist_id): Path<String>,
     Query(query): Query<AvailableSlotsQuery>,
     Extension(pool): Extension<SqlitePool>,
+    Extension(config): Extension<Config>,
 ) -> Result<Json<AvailableSlotsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+
+    // Trajanje i pauza iz vrste termina; bez nje važi jedan slot bez pauze
+    let (duration_minutes, buffer_minutes) = match &query.appointment_type_id {
+        Some(type_id) => {
+            let appointment_type = fetch_appointment_type(&mut conn, type_id).await?;
+            if !appointment_type.offered_by(&physiotherapist_id) {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(ErrorResponse { error: "This physiotherapist does not offer this appointment type".to_string() })
+                ));
+            }
+            (appointment_type.duration_minutes, appointment_type.buffer_minutes)
+        }
+        None => (SLOT_MINUTES, 0),
+    };
+    let day_end = NaiveTime::from_hms_opt(WORK_END_HOUR, 0, 0).unwrap();
+
     // Generiši sve slotove od 8:00 do 16:00 (20-minutni intervali)
     let mut slots = Vec::new();
-    let start_hour = 8;
-    let end_hour = 16;
     
-    for hour in start_hour..end_hour {
+    for hour in WORK_START_HOUR..WORK_END_HOUR {
         for minute in [0, 20, 40] {
-            let time_str = format!("{:02}:{:02}", hour, minute);
+            let start = NaiveTime::from_hms_opt(hour, minute, 0).unwrap();
+            let time_str = start.format("%H:%M").to_string();
+            let cell_end = (start + Duration::minutes(SLOT_MINUTES)).format("%H:%M").to_string();
             
-            // Proveri da li je slot zauzet u bazi
+            // Proveri da li neki termin pokriva ovaj slot
             let count: i64 = sqlx::query_scalar(
                 r#"
                 SELECT COUNT(*) FROM appointments
                 WHERE physiotherapist_id = ?
                 AND appointment_date = ?
-                AND start_time = ?
-                AND status != 'cancelled'
+                AND start_time < ?
+                AND end_time > ?
+                AND status NOT IN ('cancelled', 'late_cancellation')
                 "#
             )
             .bind(&physiotherapist_id)
             .bind(&query.date)
+            .bind(&cell_end)
             .bind(&time_str)
-            .fetch_one(&pool)
+            .fetch_one(&mut *conn)
             .await
             .unwrap_or(0);
             
             let is_booked = count > 0;
+
+            // Termin koji počinje ovde mora da stane do kraja radnog vremena i da
+            // se, sa pauzom, ne preklapa sa drugim terminima ni ponudama sa liste čekanja
+            let end = start + Duration::minutes(duration_minutes);
+            let end_time = end.format("%H:%M").to_string();
+            let fits = end <= day_end && end > start;
+            let is_free = fits && slot_is_free(
+                &mut conn,
+                &physiotherapist_id,
+                &query.date,
+                &time_str,
+                &blocked_until(&end_time, buffer_minutes),
+                None,
+            )
+            .await
+            .unwrap_or(false);
+
+            // Fizioterapeut je slobodan, ali soba ili aparat možda nisu
+            let resources_free = match &query.appointment_type_id {
+                Some(type_id) if is_free => busy_resource(&mut conn, type_id, &query.date, &time_str, &end_time, None)
+                    .await
+                    .map(|busy| busy.is_none())
+                    .unwrap_or(false),
+                _ => true,
+            };
             
             slots.push(TimeSlot {
                 time: time_str,
-                available: !is_booked,
+                available: !is_booked && is_free && resources_free,
                 booked: is_booked,
             });
         }
@@ -103,106 +645,91 @@ pub async fn get_available_slots(
 
     Ok(Json(AvailableSlotsResponse {
         date: query.date,
+        timezone: config.clinic_timezone.name().to_string(),
+        duration_minutes,
         slots,
     }))
 }
 
 pub async fn create_appointment(
     Extension(pool): Extension<SqlitePool>,
+    Extension(config): Extension<Config>,
+    headers: HeaderMap,
     Json(req): Json<CreateAppointmentRequest>,
 ) -> Result<Json<AppointmentResponse>, (StatusCode, Json<ErrorResponse>)> {
-    // Generiši UUID
-    let id = uuid::Uuid::new_v4().to_string();
-    
-    // Izračunaj end_time (start_time + 20 minuta)
-    let start_time = NaiveTime::parse_from_str(&req.start_time, "%H:%M")
-        .map_err(|_| (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse { error: "Invalid time format".to_string() })
-        ))?;
-    
-    let end_time = start_time + Duration::minutes(20);
-    let end_time_str = end_time.format("%H:%M").to_string();
-    
-    // Proveri da li je slot već zauzet
-    let count: i64 = sqlx::query_scalar(
-        r#"
-        SELECT COUNT(*) FROM appointments
-        WHERE physiotherapist_id = ?
-        AND appointment_date = ?
-        AND start_time = ?
-        AND status != 'cancelled'
-        "#
-    )
-    .bind(&req.physiotherapist_id)
-    .bind(&req.appointment_date)
-    .bind(&req.start_time)
-    .fetch_one(&pool)
-    .await
-    .map_err(|e| (
-        StatusCode::INTERNAL_SERVER_ERROR,
-        Json(ErrorResponse { error: format!("Database error: {}", e) })
-    ))?;
-    
-    if count > 0 {
-        return Err((
-            StatusCode::CONFLICT,
-            Json(ErrorResponse { error: "Time slot already booked".to_string() })
-        ));
+    let claims = authenticate(&headers)?;
+    ensure_can_book_for(&claims, &req.patient_id)?;
+
+    // Zaključaj bazu za upis pre provere, da dva istovremena zahteva
+    // ne bi oba videla slobodan slot
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE")
+        .await
+        .map_err(database_error)?;
+
+    let appointment_type = match &req.appointment_type_id {
+        Some(type_id) => Some(bookable_type(&mut tx, &claims, type_id, &req.physiotherapist_id).await?),
+        None => None,
+    };
+
+    // Izračunaj end_time iz trajanja vrste termina (bez vrste 20 minuta)
+    let (duration_minutes, buffer_minutes) = appointment_type.as_ref()
+        .map(|t| (t.duration_minutes, t.buffer_minutes))
+        .unwrap_or((SLOT_MINUTES, 0));
+    let (appointment_date, start_time_str, end_time_str) =
+        validate_slot(&req.appointment_date, &req.start_time, duration_minutes)?;
+    ensure_future_start(&config, &appointment_date, &start_time_str)?;
+
+    ensure_slot_free(
+        &mut tx,
+        &req.physiotherapist_id,
+        &appointment_date,
+        &start_time_str,
+        &blocked_until(&end_time_str, buffer_minutes),
+        None,
+    ).await?;
+
+    ensure_resources_free(
+        &mut tx,
+        req.appointment_type_id.as_deref(),
+        &appointment_date,
+        &start_time_str,
+        &end_time_str,
+        None,
+    ).await?;
+
+    ensure_booking_allowed(&mut tx, &config, &req.patient_id, &[]).await?;
+
+    let mut appointment = insert_appointment(
+        &mut tx,
+        &config,
+        &req.patient_id,
+        &req.physiotherapist_id,
+        &appointment_date,
+        &start_time_str,
+        &end_time_str,
+    ).await?;
+    if let Some(appointment_type) = &appointment_type {
+        assign_appointment_type(&mut tx, &mut appointment, &appointment_type.id, appointment_type.buffer_minutes).await?;
     }
+
+    // Zakazuje se u ime pacijenta, pa se vreme prikazuje u njegovoj zoni
+    localize_for(&mut tx, &config, &req.patient_id, std::slice::from_mut(&mut appointment)).await;
+    tx.commit().await.map_err(database_error)?;
     
-    // Kreiraj appointment
-    let now = chrono::Utc::now().to_rfc3339();
-    
-    sqlx::query(
-        r#"
-        INSERT INTO appointments (
-            id, patient_id, physiotherapist_id, appointment_date,
-            start_time, end_time, duration_minutes, status,
-            created_at, updated_at
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-        "#
-    )
-    .bind(&id)
-    .bind(&req.patient_id)
-    .bind(&req.physiotherapist_id)
-    .bind(&req.appointment_date)
-    .bind(&req.start_time)
-    .bind(&end_time_str)
-    .bind(20)
-    .bind("scheduled")
-    .bind(&now)
-    .bind(&now)
-    .execute(&pool)
-    .await
-    .map_err(|e| (
-        StatusCode::INTERNAL_SERVER_ERROR,
-        Json(ErrorResponse { error: format!("Failed to create appointment: {}", e) })
-    ))?;
-    
-    Ok(Json(AppointmentResponse {
-        id,
-        patient_id: req.patient_id,
-        physiotherapist_id: req.physiotherapist_id,
-        appointment_date: req.appointment_date,
-        start_time: req.start_time,
-        end_time: end_time_str,
-        duration_minutes: 20,
-        status: "scheduled".to_string(),
-    }))
+    Ok(Json(appointment))
 }
 
 pub async fn get_user_appointments(
     Extension(pool): Extension<SqlitePool>,
+    Extension(config): Extension<Config>,
+    headers: HeaderMap,
 ) -> Result<Json<AppointmentsListResponse>, (StatusCode, Json<ErrorResponse>)> {
     // Za sada vraćam sve appointments
-    let rows = sqlx::query_as::<_, (String, String, String, String, String, String, i32, String)>(
-        r#"
-        SELECT id, patient_id, physiotherapist_id, appointment_date,
-               start_time, end_time, duration_minutes, status
-        FROM appointments
-        ORDER BY appointment_date DESC, start_time DESC
-        "#
+    let mut appointments = sqlx::query_as::<_, AppointmentResponse>(
+        &format!(
+            "SELECT {} FROM appointments ORDER BY appointment_date DESC, start_time DESC",
+            APPOINTMENT_COLUMNS
+        )
     )
     .fetch_all(&pool)
     .await
@@ -210,17 +737,19 @@ pub async fn get_user_appointments(
         StatusCode::INTERNAL_SERVER_ERROR,
         Json(ErrorResponse { error: format!("Database error: {}", e) })
     ))?;
-    
-    let appointments = rows.into_iter().map(|row| AppointmentResponse {
-        id: row.0,
-        patient_id: row.1,
-        physiotherapist_id: row.2,
-        appointment_date: row.3,
-        start_time: row.4,
-        end_time: row.5,
-        duration_minutes: row.6,
-        status: row.7,
-    }).collect();
+
+    // Prijavljen korisnik vidi vremena u svojoj zoni, ostali u zoni klinike
+    match authenticate(&headers) {
+        Ok(claims) => {
+            let mut conn = pool.acquire().await.map_err(database_error)?;
+            localize_for(&mut conn, &config, &claims.sub, &mut appointments).await;
+        }
+        Err(_) => {
+            for appointment in appointments.iter_mut() {
+                localize(&config, appointment, config.clinic_timezone);
+            }
+        }
+    }
     
     Ok(Json(AppointmentsListResponse { appointments }))
 }
@@ -228,18 +757,58 @@ pub async fn get_user_appointments(
 pub async fn update_appointment_status(
     Path(id): Path<String>,
     Extension(pool): Extension<SqlitePool>,
+    Extension(config): Extension<Config>,
+    headers: HeaderMap,
     Json(status): Json<serde_json::Value>,
 ) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+
     let status_str = status.get("status")
         .and_then(|s| s.as_str())
         .ok_or((
             StatusCode::BAD_REQUEST,
             Json(ErrorResponse { error: "Missing status field".to_string() })
         ))?;
+
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE")
+        .await
+        .map_err(database_error)?;
+
+    let appointment = fetch_appointment(&mut tx, &id).await?;
+
+    // Otkazivanje ide kroz pravila o roku za otkazivanje i poštuje opseg serije
+    if status_str == "cancelled" {
+        let reason = status.get("reason").and_then(|r| r.as_str());
+        let scope = match status.get("scope") {
+            Some(scope) => serde_json::from_value::<SeriesScope>(scope.clone()).map_err(|_| (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse { error: "Invalid scope: expected this, following or all".to_string() })
+            ))?,
+            None => SeriesScope::This,
+        };
+        let holds = cancel_in_scope(&mut tx, &config, &claims, &appointment, reason, scope).await?;
+        tx.commit().await.map_err(database_error)?;
+        for hold in &holds {
+            notify_hold(&config, hold);
+        }
+        return Ok(StatusCode::OK);
+    }
+
+    // Ostale statuse menja samo fizioterapeut sa termina ili admin
+    if claims.role != "admin" && claims.sub != appointment.physiotherapist_id {
+        return Err(forbidden());
+    }
+
+    if !["scheduled", "confirmed", "completed", "no_show"].contains(&status_str) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: format!("Invalid appointment status: {}", status_str) })
+        ));
+    }
     
     let now = chrono::Utc::now().to_rfc3339();
     
-    let result = sqlx::query(
+    sqlx::query(
         r#"
         UPDATE appointments
         SET status = ?, updated_at = ?
@@ -249,19 +818,313 @@ pub async fn update_appointment_status(
     .bind(status_str)
     .bind(&now)
     .bind(&id)
-    .execute(&pool)
+    .execute(&mut *tx)
     .await
-    .map_err(|e| (
-        StatusCode::INTERNAL_SERVER_ERROR,
-        Json(ErrorResponse { error: format!("Database error: {}", e) })
-    ))?;
-    
-    if result.rows_affected() == 0 {
-        return Err((
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse { error: "Appointment not found".to_string() })
-        ));
+    .map_err(database_error)?;
+
+    // Završen termin se naplaćuje po ceni vrste termina; ako to prestane da bude, račun se poništava
+    if status_str == "completed" {
+        issue_invoice(&mut tx, &config, &appointment).await?;
+    } else if appointment.status == "completed" {
+        void_invoice(&mut tx, &appointment).await?;
     }
+
+    tx.commit().await.map_err(database_error)?;
     
     Ok(StatusCode::OK)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{auth_headers, run_migrations, test_config, test_pool, test_pool_before};
+    use chrono::Datelike;
+
+    fn booking(patient_id: &str, start_time: &str) -> CreateAppointmentRequest {
+        CreateAppointmentRequest {
+            patient_id: patient_id.to_string(),
+            physiotherapist_id: "physio-001".to_string(),
+            appointment_date: "2030-01-15".to_string(),
+            start_time: start_time.to_string(),
+            appointment_type_id: None,
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_bookings_for_same_slot_yield_one_conflict() {
+        let pool = test_pool().await;
+
+        for _ in 0..10 {
+            sqlx::query("DELETE FROM appointments").execute(&pool).await.unwrap();
+
+            let first = tokio::spawn(create_appointment(
+                Extension(pool.clone()),
+                Extension(test_config()),
+                auth_headers("patient-001", "patient"),
+                Json(booking("patient-001", "10:00")),
+            ));
+            let second = tokio::spawn(create_appointment(
+                Extension(pool.clone()),
+                Extension(test_config()),
+                auth_headers("physio-001", "physiotherapist"),
+                Json(booking("admin-001", "10:00")),
+            ));
+            let results = [first.await.unwrap(), second.await.unwrap()];
+
+            let successes = results.iter().filter(|r| r.is_ok()).count();
+            let conflicts = results
+                .iter()
+                .filter(|r| matches!(r, Err((StatusCode::CONFLICT, _))))
+                .count();
+            assert_eq!(successes, 1);
+            assert_eq!(conflicts, 1);
+
+            let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM appointments")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+            assert_eq!(stored, 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn overlapping_booking_is_rejected() {
+        let pool = test_pool().await;
+
+        let booked = create_appointment(Extension(pool.clone()), Extension(test_config()), auth_headers("patient-001", "patient"), Json(booking("patient-001", "10:00")))
+            .await
+            .unwrap();
+        assert_eq!(booked.end_time, "10:20");
+
+        let err = create_appointment(Extension(pool.clone()), Extension(test_config()), auth_headers("physio-001", "physiotherapist"), Json(booking("admin-001", "10:00")))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+
+        // Termin van 20-minutne mreže bi se preklapao sa dva slota
+        let err = create_appointment(Extension(pool.clone()), Extension(test_config()), auth_headers("physio-001", "physiotherapist"), Json(booking("admin-001", "10:10")))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+
+        let adjacent = create_appointment(Extension(pool.clone()), Extension(test_config()), auth_headers("physio-001", "physiotherapist"), Json(booking("admin-001", "10:20")))
+            .await
+            .unwrap();
+        assert_eq!(adjacent.status, "scheduled");
+    }
+
+    #[tokio::test]
+    async fn patients_book_only_for_themselves() {
+        let pool = test_pool().await;
+
+        let err = create_appointment(Extension(pool.clone()), Extension(test_config()), HeaderMap::new(), Json(booking("patient-001", "10:00")))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::UNAUTHORIZED);
+
+        let err = create_appointment(Extension(pool.clone()), Extension(test_config()), auth_headers("admin-001", "patient"), Json(booking("patient-001", "10:00")))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+
+        let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM appointments").fetch_one(&pool).await.unwrap();
+        assert_eq!(stored, 0);
+    }
+
+    #[tokio::test]
+    async fn schema_rejects_overlap_that_bypasses_handler() {
+        let pool = test_pool().await;
+        let insert = "INSERT INTO appointments (id, patient_id, physiotherapist_id, appointment_date, \
+                      start_time, end_time, duration_minutes, status, created_at, updated_at) \
+                      VALUES (?, 'patient-001', 'physio-001', '2030-01-15', ?, ?, ?, 'scheduled', '', '')";
+
+        sqlx::query(insert).bind("a").bind("09:00").bind("09:40").bind(40)
+            .execute(&pool).await.unwrap();
+
+        let err = sqlx::query(insert).bind("b").bind("09:20").bind("09:40").bind(20)
+            .execute(&pool).await.unwrap_err();
+        assert!(is_slot_conflict(&err));
+
+        // Otkazani termin ne zauzima slot
+        sqlx::query("UPDATE appointments SET status = 'cancelled' WHERE id = 'a'")
+            .execute(&pool).await.unwrap();
+        sqlx::query(insert).bind("b").bind("09:20").bind("09:40").bind(20)
+            .execute(&pool).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn schema_keeps_buffers_free() {
+        let pool = test_pool().await;
+        let insert = "INSERT INTO appointments (id, patient_id, physiotherapist_id, appointment_date, \
+                      start_time, end_time, duration_minutes, buffer_minutes, status, created_at, updated_at) \
+                      VALUES (?, 'patient-001', 'physio-001', '2030-01-15', ?, ?, 20, ?, 'scheduled', '', '')";
+
+        // 09:00-09:20 sa pauzom od 20 minuta drži fizioterapeuta do 09:40
+        sqlx::query(insert).bind("a").bind("09:00").bind("09:20").bind(20)
+            .execute(&pool).await.unwrap();
+        let err = sqlx::query(insert).bind("b").bind("09:30").bind("09:50").bind(0)
+            .execute(&pool).await.unwrap_err();
+        assert!(is_slot_conflict(&err));
+
+        // Pauza novog termina ne sme da pređe u sledeći
+        let err = sqlx::query(insert).bind("b").bind("08:30").bind("08:50").bind(15)
+            .execute(&pool).await.unwrap_err();
+        assert!(is_slot_conflict(&err));
+
+        sqlx::query(insert).bind("b").bind("09:40").bind("10:00").bind(0)
+            .execute(&pool).await.unwrap();
+
+        // Ni produžena pauza postojećeg termina
+        let err = sqlx::query("UPDATE appointments SET buffer_minutes = 30 WHERE id = 'a'")
+            .execute(&pool).await.unwrap_err();
+        assert!(is_slot_conflict(&err));
+        sqlx::query("UPDATE appointments SET buffer_minutes = 10 WHERE id = 'a'")
+            .execute(&pool).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn legacy_overlaps_are_recorded_and_keep_their_status_changes() {
+        let pool = test_pool_before(11).await;
+        let insert = "INSERT INTO appointments (id, patient_id, physiotherapist_id, appointment_date, \
+                      start_time, end_time, duration_minutes, status, created_at, updated_at) \
+                      VALUES (?, 'patient-001', 'physio-001', '2030-01-15', ?, ?, 20, 'scheduled', '', '')";
+        for (id, start, end) in [("a", "09:00", "09:20"), ("b", "09:00", "09:20"), ("c", "09:10", "09:30"), ("d", "10:00", "10:20")] {
+            sqlx::query(insert).bind(id).bind(start).bind(end).execute(&pool).await.unwrap();
+        }
+        run_migrations(&pool).await;
+
+        // Duplikat istog slota je otkazan, delimično preklapanje ostaje; oba su zapisana
+        let conflicts: Vec<(String, String, String, i64)> = sqlx::query_as(
+            "SELECT appointment_id, conflicts_with, previous_status, cancelled_by_migration \
+             FROM appointment_overlap_conflicts ORDER BY appointment_id, conflicts_with"
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(conflicts, vec![
+            ("b".to_string(), "a".to_string(), "scheduled".to_string(), 1),
+            ("c".to_string(), "a".to_string(), "scheduled".to_string(), 0),
+            ("c".to_string(), "b".to_string(), "scheduled".to_string(), 0),
+        ]);
+        let status = |id: &'static str| {
+            let pool = pool.clone();
+            async move {
+                sqlx::query_scalar::<_, String>("SELECT status FROM appointments WHERE id = ?")
+                    .bind(id)
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap()
+            }
+        };
+        assert_eq!((status("a").await, status("b").await, status("c").await), ("scheduled".to_string(), "cancelled".to_string(), "scheduled".to_string()));
+
+        // Promena statusa ne pomera termin, pa je dozvoljena i za preklopljene redove
+        sqlx::query("UPDATE appointments SET status = 'completed' WHERE id = 'c'").execute(&pool).await.unwrap();
+        sqlx::query("UPDATE appointments SET status = 'cancelled' WHERE id = 'c'").execute(&pool).await.unwrap();
+
+        // Vraćanje otkazanog ili pomeranje u zauzet slot i dalje nije
+        let err = sqlx::query("UPDATE appointments SET status = 'scheduled' WHERE id = 'b'").execute(&pool).await.unwrap_err();
+        assert!(is_slot_conflict(&err));
+        let err = sqlx::query("UPDATE appointments SET start_time = '09:00', end_time = '09:20' WHERE id = 'd'").execute(&pool).await.unwrap_err();
+        assert!(is_slot_conflict(&err));
+    }
+
+    #[tokio::test]
+    async fn appointments_store_utc_instants_across_dst() {
+        let pool = test_pool().await;
+
+        // Beograd je UTC+1 pre 31.03.2030. i UTC+2 posle
+        let winter = create_appointment(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-001", "patient"),
+            Json(CreateAppointmentRequest { appointment_date: "2030-03-29".to_string(), ..booking("patient-001", "09:00") }),
+        )
+        .await
+        .unwrap();
+        let summer = create_appointment(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-001", "patient"),
+            Json(CreateAppointmentRequest { appointment_date: "2030-04-01".to_string(), ..booking("patient-001", "09:00") }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(winter.starts_at.as_deref(), Some("2030-03-29T08:00:00Z"));
+        assert_eq!(summer.starts_at.as_deref(), Some("2030-04-01T07:00:00Z"));
+
+        // Pacijent u drugoj zoni vidi isti trenutak u svom lokalnom vremenu
+        sqlx::query("UPDATE users SET timezone = 'America/New_York' WHERE id = 'patient-001'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let Json(list) = get_user_appointments(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            crate::test_support::auth_headers("patient-001", "patient"),
+        )
+        .await
+        .unwrap();
+        let local = list.appointments.iter().find(|a| a.id == summer.id).unwrap().local.clone().unwrap();
+        assert_eq!(local.timezone, "America/New_York");
+        assert_eq!((local.date.as_str(), local.start_time.as_str()), ("2030-04-01", "03:00"));
+    }
+
+    #[tokio::test]
+    async fn legacy_appointments_get_utc_instants_in_the_clinic_zone() {
+        let pool = test_pool().await;
+        let insert = "INSERT INTO appointments (id, patient_id, physiotherapist_id, appointment_date, \
+                      start_time, end_time, duration_minutes, status, created_at, updated_at) \
+                      VALUES (?, 'patient-001', 'physio-001', ?, ?, ?, 20, 'scheduled', '', '')";
+
+        // Svaki dan 2030. u 09:00, plus sati oko pomeranja sata u Evropi i Americi
+        let mut rows = Vec::new();
+        let mut date = NaiveDate::from_ymd_opt(2030, 1, 1).unwrap();
+        while date.year() == 2030 {
+            rows.push((date.format("%Y-%m-%d").to_string(), "09:00", "09:20"));
+            date += Duration::days(1);
+        }
+        for date in ["2030-03-10", "2030-03-31", "2030-10-27", "2030-11-03"] {
+            for (start, end) in [("01:30", "01:50"), ("02:30", "02:50"), ("03:30", "03:50")] {
+                rows.push((date.to_string(), start, end));
+            }
+        }
+        for (i, (date, start, end)) in rows.iter().enumerate() {
+            sqlx::query(insert).bind(i.to_string()).bind(date).bind(start).bind(end)
+                .execute(&pool).await.unwrap();
+        }
+
+        // datum, početak, kraj, starts_at, ends_at
+        type Stored = (String, String, String, Option<String>, Option<String>);
+        for zone in [chrono_tz::Europe::Belgrade, chrono_tz::America::New_York] {
+            let config = Config { clinic_timezone: zone, ..test_config() };
+            sqlx::query("UPDATE appointments SET starts_at = NULL, ends_at = NULL").execute(&pool).await.unwrap();
+            backfill_appointment_instants(&pool, &config).await.unwrap();
+
+            let stored: Vec<Stored> = sqlx::query_as(
+                "SELECT appointment_date, start_time, end_time, starts_at, ends_at FROM appointments"
+            )
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+            assert_eq!(stored.len(), rows.len());
+            for (date, start, end, starts_at, ends_at) in stored {
+                // Vreme koje u zoni ne postoji (02:30 na dan prelaska na letnje) ostaje bez trenutka
+                let Ok((expected_start, expected_end)) = clinic_instants(&config, &date, &start, &end) else {
+                    assert_eq!(starts_at, None, "{} {} {}", zone, date, start);
+                    continue;
+                };
+                assert_eq!(starts_at.as_deref(), Some(expected_start.as_str()), "{} {} {}", zone, date, start);
+                assert_eq!(ends_at.as_deref(), Some(expected_end.as_str()), "{} {} {}", zone, date, end);
+            }
+        }
+
+        let starts_at: String = sqlx::query_scalar(
+            "SELECT starts_at FROM appointments WHERE appointment_date = '2030-07-01' AND start_time = '09:00'"
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(starts_at, "2030-07-01T13:00:00Z");
+    }
+}
@@ -0,0 +1,370 @@
+use axum::{
+    http::StatusCode,
+    Json,
+    extract::Path,
+    Extension,
+};
+use sqlx::SqlitePool;
+use chrono::{DateTime, Duration, Utc};
+
+use crate::config::Config;
+use crate::notifications::{send_email, deliver_notification};
+use super::{
+    appointment_start, database_error, fetch_appointment, user_timezone, utc_timestamp,
+    AppointmentResponse, ErrorResponse,
+};
+
+// Koliko često se traže termini za podsetnik
+const REMINDER_CHECK_SECONDS: u64 = 60;
+
+#[derive(Debug, sqlx::FromRow)]
+struct UpcomingAppointment {
+    id: String,
+    patient_id: String,
+    starts_at: String,
+    created_at: String,
+    patient_email: Option<String>,
+    physiotherapist_name: String,
+}
+
+// Podsetnik koji je poslat
+#[derive(Debug)]
+pub(crate) struct SentReminder {
+    pub appointment_id: String,
+    pub offset_minutes: i64,
+}
+
+fn new_token() -> String {
+    format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+}
+
+// Podsetnik koji je sada na redu: najmanji pomak koji je već prošao. Ako je
+// servis bio ugašen, stariji podsetnici se preskaču umesto da stignu zajedno.
+// Pomak se preskače i kada je termin zakazan tek posle njega.
+fn due_offset(offsets: &[i64], now: DateTime<Utc>, starts_at: DateTime<Utc>, booked_at: DateTime<Utc>) -> Option<i64> {
+    let offset = offsets
+        .iter()
+        .copied()
+        .filter(|offset| now >= starts_at - Duration::minutes(*offset))
+        .min()?;
+    (booked_at <= starts_at - Duration::minutes(offset)).then_some(offset)
+}
+
+// Lepo čitljiv opis pomaka za tekst podsetnika
+fn describe_offset(minutes: i64) -> String {
+    match minutes {
+        1440 => "tomorrow".to_string(),
+        m if m % 1440 == 0 => format!("in {} days", m / 1440),
+        60 => "in an hour".to_string(),
+        m if m % 60 == 0 => format!("in {} hours", m / 60),
+        m => format!("in {} minutes", m),
+    }
+}
+
+// Pošalji podsetnike koji su na redu u trenutku now. Red se upisuje tek kada
+// podsetnik stigne bar jednim kanalom; neuspeli se ponavlja u sledećem prolazu.
+pub(crate) async fn send_due_reminders(
+    pool: &SqlitePool,
+    config: &Config,
+    now: DateTime<Utc>,
+) -> Result<Vec<SentReminder>, (StatusCode, Json<ErrorResponse>)> {
+    let Some(&largest) = config.reminder_offsets_minutes.iter().max() else {
+        return Ok(Vec::new());
+    };
+
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+    let upcoming = sqlx::query_as::<_, UpcomingAppointment>(
+        r#"
+        SELECT a.id, a.patient_id, a.starts_at, a.created_at,
+               pu.email AS patient_email,
+               COALESCE(fu.first_name || ' ' || fu.last_name, '') AS physiotherapist_name
+        FROM appointments a
+        LEFT JOIN users pu ON pu.id = a.patient_id
+        LEFT JOIN users fu ON fu.id = a.physiotherapist_id
+        WHERE a.status IN ('scheduled', 'confirmed')
+        AND a.starts_at > ?
+        AND a.starts_at <= ?
+        ORDER BY a.starts_at ASC
+        "#
+    )
+    .bind(utc_timestamp(now))
+    .bind(utc_timestamp(now + Duration::minutes(largest)))
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    let mut sent = Vec::new();
+    for appointment in upcoming {
+        let (Ok(starts_at), Ok(booked_at)) = (
+            DateTime::parse_from_rfc3339(&appointment.starts_at),
+            DateTime::parse_from_rfc3339(&appointment.created_at),
+        ) else {
+            continue;
+        };
+        let starts_at = starts_at.with_timezone(&Utc);
+        let Some(offset) = due_offset(&config.reminder_offsets_minutes, now, starts_at, booked_at.with_timezone(&Utc)) else {
+            continue;
+        };
+
+        let already_sent: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM appointment_reminders WHERE appointment_id = ? AND offset_minutes = ? AND starts_at = ?"
+        )
+        .bind(&appointment.id)
+        .bind(offset)
+        .bind(&appointment.starts_at)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(database_error)?;
+        if already_sent > 0 {
+            continue;
+        }
+
+        // Vreme u zoni pacijenta
+        let local = starts_at.with_timezone(&user_timezone(&mut conn, config, &appointment.patient_id).await);
+        let token = new_token();
+        let title = format!("Appointment {}", describe_offset(offset));
+        let body = format!(
+            "Your appointment with {} is on {} at {}. Please confirm that you are coming.",
+            appointment.physiotherapist_name,
+            local.format("%Y-%m-%d"),
+            local.format("%H:%M")
+        );
+
+        // Kanali koji nisu podešeni se preskaču
+        let mut attempted = false;
+        let mut channels = Vec::new();
+        if !config.chat_service_url.is_empty() {
+            attempted = true;
+            let data = serde_json::json!({
+                "appointment_id": appointment.id,
+                "starts_at": appointment.starts_at,
+                "offset_minutes": offset,
+                "confirm_path": format!("/reminders/{}/confirm", token),
+            });
+            match deliver_notification(config, &appointment.patient_id, "appointment_reminder", &title, &body, data).await {
+                Ok(()) => channels.push("chat"),
+                Err(e) => tracing::warn!("Failed to send reminder for appointment {}: {}", appointment.id, e),
+            }
+        }
+        if let Some(email) = appointment.patient_email.as_deref().filter(|_| !config.smtp_url.is_empty()) {
+            attempted = true;
+            let confirm_url = format!("{}/{}", config.reminder_confirm_url.trim_end_matches('/'), token);
+            match send_email(config, email, &title, &format!("{}\n\nConfirm: {}\n", body, confirm_url)).await {
+                Ok(()) => channels.push("email"),
+                Err(e) => tracing::warn!("Failed to email reminder for appointment {}: {}", appointment.id, e),
+            }
+        }
+        if attempted && channels.is_empty() {
+            continue;
+        }
+
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO appointment_reminders (id, appointment_id, offset_minutes, starts_at, token, channels, sent_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(&appointment.id)
+        .bind(offset)
+        .bind(&appointment.starts_at)
+        .bind(&token)
+        .bind(channels.join(","))
+        .bind(utc_timestamp(now))
+        .execute(&mut *conn)
+        .await
+        .map_err(database_error)?;
+
+        sent.push(SentReminder { appointment_id: appointment.id, offset_minutes: offset });
+    }
+
+    Ok(sent)
+}
+
+// Pozadinski posao koji se pokreće iz main-a
+pub async fn run_reminder_scheduler(pool: SqlitePool, config: Config) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(REMINDER_CHECK_SECONDS));
+
+    loop {
+        interval.tick().await;
+        match send_due_reminders(&pool, &config, Utc::now()).await {
+            Ok(sent) => {
+                for reminder in &sent {
+                    tracing::info!(
+                        "Sent reminder {} minutes before appointment {}",
+                        reminder.offset_minutes, reminder.appointment_id
+                    );
+                }
+            }
+            Err((_, Json(e))) => tracing::error!("Failed to send appointment reminders: {}", e.error),
+        }
+    }
+}
+
+// Potvrda dolaska iz podsetnika. Token iz podsetnika zamenjuje prijavu, ali
+// važi samo za vreme termina za koje je podsetnik poslat.
+pub async fn confirm_from_reminder(
+    Path(token): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(config): Extension<Config>,
+) -> Result<Json<AppointmentResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE")
+        .await
+        .map_err(database_error)?;
+
+    let (reminder_id, appointment_id, reminded_start): (String, String, String) = sqlx::query_as(
+        "SELECT id, appointment_id, starts_at FROM appointment_reminders WHERE token = ?"
+    )
+    .bind(&token)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse { error: "Reminder not found".to_string() })
+    ))?;
+
+    let mut appointment = fetch_appointment(&mut tx, &appointment_id).await?;
+    let gone = |message: &str| (StatusCode::GONE, Json(ErrorResponse { error: message.to_string() }));
+    if appointment.starts_at.as_deref() != Some(reminded_start.as_str()) {
+        return Err(gone("The appointment was rescheduled after this reminder"));
+    }
+    if appointment_start(&config, &appointment)? <= Utc::now() {
+        return Err(gone("The appointment has already started"));
+    }
+    match appointment.status.as_str() {
+        "scheduled" | "confirmed" => {}
+        status => return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse { error: format!("Appointment is {} and cannot be confirmed", status) })
+        )),
+    }
+
+    let now = utc_timestamp(Utc::now());
+    if appointment.status == "scheduled" {
+        sqlx::query("UPDATE appointments SET status = 'confirmed', updated_at = ? WHERE id = ?")
+            .bind(&now)
+            .bind(&appointment.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(database_error)?;
+        appointment.status = "confirmed".to_string();
+    }
+    sqlx::query("UPDATE appointment_reminders SET confirmed_at = COALESCE(confirmed_at, ?) WHERE id = ?")
+        .bind(&now)
+        .bind(&reminder_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(database_error)?;
+
+    tx.commit().await.map_err(database_error)?;
+
+    Ok(Json(appointment))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::{create_appointment, CreateAppointmentRequest};
+    use crate::test_support::{auth_headers, test_config, test_pool};
+
+    async fn reminder_token(pool: &SqlitePool, appointment_id: &str, offset_minutes: i64) -> String {
+        sqlx::query_scalar("SELECT token FROM appointment_reminders WHERE appointment_id = ? AND offset_minutes = ?")
+            .bind(appointment_id)
+            .bind(offset_minutes)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    fn at(timestamp: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(timestamp).unwrap().with_timezone(&Utc)
+    }
+
+    async fn book(pool: &SqlitePool, start_time: &str) -> AppointmentResponse {
+        let Json(appointment) = create_appointment(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-001", "patient"),
+            Json(CreateAppointmentRequest {
+                patient_id: "patient-001".to_string(),
+                physiotherapist_id: "physio-001".to_string(),
+                appointment_date: "2030-01-15".to_string(),
+                start_time: start_time.to_string(),
+                appointment_type_id: None,
+            }),
+        )
+        .await
+        .unwrap();
+        appointment
+    }
+
+    #[tokio::test]
+    async fn each_reminder_goes_out_once_and_confirms_the_visit() {
+        let pool = test_pool().await;
+        // 10:00 u Beogradu je 09:00 UTC
+        let early = book(&pool, "10:00").await;
+        // Zakazan kada je do termina ostalo manje od dana
+        let late = book(&pool, "11:00").await;
+        sqlx::query("UPDATE appointments SET created_at = '2030-01-14T20:00:00Z' WHERE id = ?")
+            .bind(&late.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let day_before = send_due_reminders(&pool, &test_config(), at("2030-01-14T09:30:00Z")).await.unwrap();
+        assert_eq!(day_before.len(), 1);
+        assert_eq!((day_before[0].appointment_id.as_str(), day_before[0].offset_minutes), (early.id.as_str(), 1440));
+
+        // Drugi prolaz (ili restart) ne šalje ponovo
+        let again = send_due_reminders(&pool, &test_config(), at("2030-01-14T09:31:00Z")).await.unwrap();
+        assert!(again.is_empty());
+
+        let hour_before = send_due_reminders(&pool, &test_config(), at("2030-01-15T08:10:00Z")).await.unwrap();
+        assert_eq!(hour_before.len(), 1);
+        assert_eq!((hour_before[0].appointment_id.as_str(), hour_before[0].offset_minutes), (early.id.as_str(), 60));
+
+        let Json(confirmed) = confirm_from_reminder(
+            Path(reminder_token(&pool, &early.id, 1440).await),
+            Extension(pool.clone()),
+            Extension(test_config()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(confirmed.status, "confirmed");
+
+        let err = confirm_from_reminder(Path("unknown".to_string()), Extension(pool.clone()), Extension(test_config()))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::NOT_FOUND);
+
+        // Pomeren termin dobija nove podsetnike, a stari token više ne važi
+        let hour_token = reminder_token(&pool, &early.id, 60).await;
+        sqlx::query("UPDATE appointments SET start_time = '12:00', end_time = '12:20', starts_at = '2030-01-15T11:00:00Z' WHERE id = ?")
+            .bind(&early.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let moved = send_due_reminders(&pool, &test_config(), at("2030-01-15T10:05:00Z")).await.unwrap();
+        assert!(moved.iter().any(|r| r.appointment_id == early.id && r.offset_minutes == 60));
+        let err = confirm_from_reminder(Path(hour_token), Extension(pool.clone()), Extension(test_config()))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::GONE);
+    }
+
+    #[tokio::test]
+    async fn failed_reminder_is_retried() {
+        let pool = test_pool().await;
+        let appointment = book(&pool, "10:00").await;
+
+        // Chat servis ne odgovara, pa se podsetnik ne beleži
+        let unreachable = Config { chat_service_url: "http://127.0.0.1:9".to_string(), ..test_config() };
+        let failed = send_due_reminders(&pool, &unreachable, at("2030-01-14T09:30:00Z")).await.unwrap();
+        assert!(failed.is_empty());
+
+        let retried = send_due_reminders(&pool, &test_config(), at("2030-01-14T09:31:00Z")).await.unwrap();
+        assert_eq!(retried.len(), 1);
+        assert_eq!(retried[0].appointment_id, appointment.id);
+    }
+}
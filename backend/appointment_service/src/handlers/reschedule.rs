@@ -0,0 +1,765 @@
+use axum::{
+    http::{StatusCode, HeaderMap},
+    Json,
+    extract::Path,
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use chrono::NaiveDate;
+
+use crate::config::Config;
+use crate::utils::Claims;
+use super::{
+    authenticate, blocked_until, bookable_type, clinic_instants, database_error,
+    ensure_booking_allowed, ensure_future_start, ensure_participant, ensure_resources_free,
+    ensure_slot_free, fetch_appointment, forbidden, localize_for, notify_hold, offer_freed_slot,
+    series_occurrences_in_scope, validate_slot, AppointmentResponse, ErrorResponse, SeriesScope,
+    WaitlistHold,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct RescheduleAppointmentRequest {
+    pub appointment_date: String,
+    pub start_time: String,
+    // "following" pomera i sve naredne termine iz serije za isti broj dana, na isto vreme;
+    // "all" isto radi za sve termine serije koji još nisu počeli
+    #[serde(default)]
+    pub scope: SeriesScope,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct RescheduleRequestResponse {
+    pub id: String,
+    pub appointment_id: String,
+    pub requested_by: String,
+    pub new_date: String,
+    pub new_start_time: String,
+    pub new_end_time: String,
+    pub status: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RescheduleResponse {
+    pub appointment: AppointmentResponse,
+    // Popunjeno kada pomeranje čeka odobrenje fizioterapeuta
+    pub pending_request: Option<RescheduleRequestResponse>,
+    // Naredni termini iz serije pomereni zajedno sa ovim
+    pub following: Vec<AppointmentResponse>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AppointmentHistoryEntry {
+    pub id: String,
+    pub action: String,
+    pub old_date: Option<String>,
+    pub old_start_time: Option<String>,
+    pub old_end_time: Option<String>,
+    pub new_date: Option<String>,
+    pub new_start_time: Option<String>,
+    pub new_end_time: Option<String>,
+    pub changed_by: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AppointmentHistoryResponse {
+    pub history: Vec<AppointmentHistoryEntry>,
+}
+
+fn ensure_reschedulable(appointment: &AppointmentResponse) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if appointment.status != "scheduled" && appointment.status != "confirmed" {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse { error: "Only scheduled or confirmed appointments can be rescheduled".to_string() })
+        ));
+    }
+    Ok(())
+}
+
+// Premesti termin na novo vreme i upiši staro i novo vreme u istoriju
+async fn move_appointment(
+    conn: &mut SqliteConnection,
+    config: &Config,
+    appointment: &AppointmentResponse,
+    new_date: &str,
+    new_start_time: &str,
+    new_end_time: &str,
+    changed_by: &str,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let (starts_at, ends_at) = clinic_instants(config, new_date, new_start_time, new_end_time)?;
+
+    sqlx::query(
+        r#"
+        UPDATE appointments
+        SET appointment_date = ?, start_time = ?, end_time = ?, starts_at = ?, ends_at = ?, updated_at = ?
+        WHERE id = ?
+        "#
+    )
+    .bind(new_date)
+    .bind(new_start_time)
+    .bind(new_end_time)
+    .bind(&starts_at)
+    .bind(&ends_at)
+    .bind(&now)
+    .bind(&appointment.id)
+    .execute(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO appointment_history (
+            id, appointment_id, action,
+            old_date, old_start_time, old_end_time,
+            new_date, new_start_time, new_end_time,
+            changed_by, created_at
+        ) VALUES (?, ?, 'rescheduled', ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(uuid::Uuid::new_v4().to_string())
+    .bind(&appointment.id)
+    .bind(&appointment.appointment_date)
+    .bind(&appointment.start_time)
+    .bind(&appointment.end_time)
+    .bind(new_date)
+    .bind(new_start_time)
+    .bind(new_end_time)
+    .bind(changed_by)
+    .bind(&now)
+    .execute(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    Ok(())
+}
+
+// Iste provere kao pri zakazivanju: vrsta termina mora biti dostupna pozivaocu,
+// a pacijent preko praga kasnih otkazivanja ne sme da dobije više budućih termina
+async fn ensure_can_move(
+    conn: &mut SqliteConnection,
+    config: &Config,
+    claims: &Claims,
+    appointment: &AppointmentResponse,
+    moving: &[&AppointmentResponse],
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if let Some(type_id) = &appointment.appointment_type_id {
+        bookable_type(conn, claims, type_id, &appointment.physiotherapist_id).await?;
+    }
+    let moving_ids: Vec<&str> = moving.iter().map(|a| a.id.as_str()).collect();
+    ensure_booking_allowed(conn, config, &appointment.patient_id, &moving_ids).await
+}
+
+// Ponudi slotove koje su pomereni termini oslobodili listi čekanja
+async fn offer_vacated_slots(
+    conn: &mut SqliteConnection,
+    config: &Config,
+    vacated: &[&AppointmentResponse],
+) -> Result<Vec<WaitlistHold>, (StatusCode, Json<ErrorResponse>)> {
+    let mut holds = Vec::new();
+    for appointment in vacated {
+        if let Some(hold) = offer_freed_slot(conn, config, appointment).await? {
+            holds.push(hold);
+        }
+    }
+    Ok(holds)
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, (StatusCode, Json<ErrorResponse>)> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse { error: "Stored appointment date is invalid".to_string() })
+    ))
+}
+
+async fn fetch_pending_request(
+    conn: &mut SqliteConnection,
+    appointment_id: &str,
+) -> Result<RescheduleRequestResponse, (StatusCode, Json<ErrorResponse>)> {
+    sqlx::query_as::<_, RescheduleRequestResponse>(
+        r#"
+        SELECT id, appointment_id, requested_by, new_date, new_start_time,
+               new_end_time, status, created_at
+        FROM reschedule_requests
+        WHERE appointment_id = ? AND status = 'pending'
+        "#
+    )
+    .bind(appointment_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse { error: "No pending reschedule request".to_string() })
+    ))
+}
+
+pub async fn reschedule_appointment(
+    Path(id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(config): Extension<Config>,
+    headers: HeaderMap,
+    Json(req): Json<RescheduleAppointmentRequest>,
+) -> Result<(StatusCode, Json<RescheduleResponse>), (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+
+    // Provera i zamena slota u jednoj transakciji, tako da pacijent
+    // ne može da izgubi termin između otkazivanja i novog zakazivanja
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE")
+        .await
+        .map_err(database_error)?;
+
+    let appointment = fetch_appointment(&mut tx, &id).await?;
+    ensure_participant(&claims, &appointment)?;
+    ensure_reschedulable(&appointment)?;
+
+    let (new_date, new_start_time, new_end_time) = validate_slot(
+        &req.appointment_date,
+        &req.start_time,
+        appointment.duration_minutes as i64,
+    )?;
+    ensure_future_start(&config, &new_date, &new_start_time)?;
+
+    if new_date == appointment.appointment_date && new_start_time == appointment.start_time {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "Appointment is already at this time".to_string() })
+        ));
+    }
+
+    let following = series_occurrences_in_scope(&mut tx, &config, &appointment, req.scope).await?;
+    let needs_approval = config.reschedule_requires_approval && claims.role == "patient";
+
+    let moved: Vec<&AppointmentResponse> = std::iter::once(&appointment).chain(&following).collect();
+    ensure_can_move(&mut tx, &config, &claims, &appointment, &moved).await?;
+
+    if needs_approval && !following.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "A series can only be rescheduled by the physiotherapist".to_string() })
+        ));
+    }
+
+    // Pacijent čeka odobrenje ako je tako podešeno; stari termin ostaje njegov do tada
+    if needs_approval {
+        ensure_slot_free(
+            &mut tx,
+            &appointment.physiotherapist_id,
+            &new_date,
+            &new_start_time,
+            &blocked_until(&new_end_time, appointment.buffer_minutes as i64),
+            Some(&appointment.id),
+        ).await?;
+        ensure_resources_free(
+            &mut tx,
+            appointment.appointment_type_id.as_deref(),
+            &new_date,
+            &new_start_time,
+            &new_end_time,
+            Some(&appointment.id),
+        ).await?;
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let request_id = uuid::Uuid::new_v4().to_string();
+
+        sqlx::query(
+            r#"
+            INSERT INTO reschedule_requests (
+                id, appointment_id, requested_by, new_date,
+                new_start_time, new_end_time, status, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, 'pending', ?)
+            "#
+        )
+        .bind(&request_id)
+        .bind(&appointment.id)
+        .bind(&claims.sub)
+        .bind(&new_date)
+        .bind(&new_start_time)
+        .bind(&new_end_time)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| match &e {
+            sqlx::Error::Database(db_err) if db_err.is_unique_violation() => (
+                StatusCode::CONFLICT,
+                Json(ErrorResponse { error: "A reschedule request is already pending".to_string() })
+            ),
+            _ => database_error(e),
+        })?;
+
+        let pending = fetch_pending_request(&mut tx, &appointment.id).await?;
+        tx.commit().await.map_err(database_error)?;
+
+        return Ok((
+            StatusCode::ACCEPTED,
+            Json(RescheduleResponse { appointment, pending_request: Some(pending), following: Vec::new() }),
+        ));
+    }
+
+    // Naredni termini se pomeraju za isti broj dana kao ovaj i dobijaju isto vreme
+    let day_shift = parse_date(&new_date)? - parse_date(&appointment.appointment_date)?;
+    let mut moves = vec![(&appointment, new_date.clone(), new_start_time.clone(), new_end_time.clone())];
+    for next in &following {
+        let shifted = (parse_date(&next.appointment_date)? + day_shift).format("%Y-%m-%d").to_string();
+        let (date, start_time, end_time) =
+            validate_slot(&shifted, &new_start_time, next.duration_minutes as i64)?;
+        ensure_future_start(&config, &date, &start_time)?;
+        moves.push((next, date, start_time, end_time));
+    }
+
+    // Pri pomeranju unapred kreće se od poslednjeg termina da se serija ne sudari sama sa sobom;
+    // kod "all" ovaj termin ne mora biti prvi u seriji, pa se redosled određuje po datumu
+    moves.sort_by(|a, b| {
+        (&a.0.appointment_date, &a.0.start_time).cmp(&(&b.0.appointment_date, &b.0.start_time))
+    });
+    if day_shift.num_days() > 0 {
+        moves.reverse();
+    }
+
+    for (moving, date, start_time, end_time) in &moves {
+        ensure_slot_free(
+            &mut tx,
+            &moving.physiotherapist_id,
+            date,
+            start_time,
+            &blocked_until(end_time, moving.buffer_minutes as i64),
+            Some(&moving.id),
+        ).await?;
+        ensure_resources_free(
+            &mut tx,
+            moving.appointment_type_id.as_deref(),
+            date,
+            start_time,
+            end_time,
+            Some(&moving.id),
+        ).await?;
+        move_appointment(&mut tx, &config, moving, date, start_time, end_time, &claims.sub).await?;
+    }
+    let holds = offer_vacated_slots(&mut tx, &config, &moved).await?;
+
+    let mut updated = fetch_appointment(&mut tx, &id).await?;
+    let mut moved_following = Vec::new();
+    for next in &following {
+        moved_following.push(fetch_appointment(&mut tx, &next.id).await?);
+    }
+    localize_for(&mut tx, &config, &claims.sub, std::slice::from_mut(&mut updated)).await;
+    localize_for(&mut tx, &config, &claims.sub, &mut moved_following).await;
+    tx.commit().await.map_err(database_error)?;
+
+    for hold in &holds {
+        notify_hold(&config, hold);
+    }
+
+    tracing::info!(
+        "Appointment {} rescheduled to {} {} ({} following)",
+        id, new_date, new_start_time, moved_following.len()
+    );
+
+    Ok((
+        StatusCode::OK,
+        Json(RescheduleResponse { appointment: updated, pending_request: None, following: moved_following }),
+    ))
+}
+
+pub async fn approve_reschedule(
+    Path(id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(config): Extension<Config>,
+    headers: HeaderMap,
+) -> Result<Json<AppointmentResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE")
+        .await
+        .map_err(database_error)?;
+
+    let appointment = fetch_appointment(&mut tx, &id).await?;
+    if claims.role != "admin" && claims.sub != appointment.physiotherapist_id {
+        return Err(forbidden());
+    }
+    ensure_reschedulable(&appointment)?;
+
+    let request = fetch_pending_request(&mut tx, &id).await?;
+    ensure_future_start(&config, &request.new_date, &request.new_start_time)?;
+
+    // Slot je možda zauzet od trenutka kada je zahtev poslat
+    ensure_slot_free(
+        &mut tx,
+        &appointment.physiotherapist_id,
+        &request.new_date,
+        &request.new_start_time,
+        &blocked_until(&request.new_end_time, appointment.buffer_minutes as i64),
+        Some(&appointment.id),
+    ).await?;
+    ensure_resources_free(
+        &mut tx,
+        appointment.appointment_type_id.as_deref(),
+        &request.new_date,
+        &request.new_start_time,
+        &request.new_end_time,
+        Some(&appointment.id),
+    ).await?;
+
+    move_appointment(
+        &mut tx,
+        &config,
+        &appointment,
+        &request.new_date,
+        &request.new_start_time,
+        &request.new_end_time,
+        &claims.sub,
+    ).await?;
+
+    sqlx::query("UPDATE reschedule_requests SET status = 'approved', decided_at = ? WHERE id = ?")
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(&request.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(database_error)?;
+    let holds = offer_vacated_slots(&mut tx, &config, &[&appointment]).await?;
+
+    let mut updated = fetch_appointment(&mut tx, &id).await?;
+    localize_for(&mut tx, &config, &claims.sub, std::slice::from_mut(&mut updated)).await;
+    tx.commit().await.map_err(database_error)?;
+
+    for hold in &holds {
+        notify_hold(&config, hold);
+    }
+
+    Ok(Json(updated))
+}
+
+pub async fn reject_reschedule(
+    Path(id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE")
+        .await
+        .map_err(database_error)?;
+
+    let appointment = fetch_appointment(&mut tx, &id).await?;
+    if claims.role != "admin" && claims.sub != appointment.physiotherapist_id {
+        return Err(forbidden());
+    }
+
+    let request = fetch_pending_request(&mut tx, &id).await?;
+
+    sqlx::query("UPDATE reschedule_requests SET status = 'rejected', decided_at = ? WHERE id = ?")
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(&request.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(database_error)?;
+    tx.commit().await.map_err(database_error)?;
+
+    Ok(StatusCode::OK)
+}
+
+pub async fn get_appointment_history(
+    Path(id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+) -> Result<Json<AppointmentHistoryResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+    let appointment = fetch_appointment(&mut conn, &id).await?;
+    ensure_participant(&claims, &appointment)?;
+
+    let history = sqlx::query_as::<_, AppointmentHistoryEntry>(
+        r#"
+        SELECT id, action, old_date, old_start_time, old_end_time,
+               new_date, new_start_time, new_end_time, changed_by, created_at
+        FROM appointment_history
+        WHERE appointment_id = ?
+        ORDER BY created_at ASC
+        "#
+    )
+    .bind(&id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    Ok(Json(AppointmentHistoryResponse { history }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::{
+        create_appointment, create_appointment_type, get_waitlist, join_waitlist, CreateAppointmentRequest,
+        JoinWaitlistRequest,
+    };
+    use crate::test_support::{auth_headers, insert_patient, test_config, test_pool};
+
+    fn config(reschedule_requires_approval: bool) -> Config {
+        Config {
+            reschedule_requires_approval,
+            ..test_config()
+        }
+    }
+
+    async fn book(pool: &SqlitePool, patient_id: &str, start_time: &str) -> AppointmentResponse {
+        create_appointment(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(CreateAppointmentRequest {
+                patient_id: patient_id.to_string(),
+                physiotherapist_id: "physio-001".to_string(),
+                appointment_date: "2030-01-15".to_string(),
+                start_time: start_time.to_string(),
+                appointment_type_id: None,
+            }),
+        )
+        .await
+        .unwrap()
+        .0
+    }
+
+    fn move_to(start_time: &str) -> Json<RescheduleAppointmentRequest> {
+        Json(RescheduleAppointmentRequest {
+            appointment_date: "2030-01-16".to_string(),
+            start_time: start_time.to_string(),
+            scope: SeriesScope::This,
+        })
+    }
+
+    #[tokio::test]
+    async fn reschedule_moves_appointment_and_records_history() {
+        let pool = test_pool().await;
+        let booked = book(&pool, "patient-001", "10:00").await;
+
+        let (status, Json(resp)) = reschedule_appointment(
+            Path(booked.id.clone()),
+            Extension(pool.clone()),
+            Extension(config(false)),
+            auth_headers("patient-001", "patient"),
+            move_to("11:00"),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(resp.appointment.id, booked.id);
+        assert_eq!(resp.appointment.appointment_date, "2030-01-16");
+        assert_eq!(resp.appointment.start_time, "11:00");
+        assert_eq!(resp.appointment.end_time, "11:20");
+
+        let Json(history) = get_appointment_history(
+            Path(booked.id.clone()),
+            Extension(pool.clone()),
+            auth_headers("physio-001", "physiotherapist"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(history.history.len(), 1);
+        assert_eq!(history.history[0].old_start_time.as_deref(), Some("10:00"));
+        assert_eq!(history.history[0].new_start_time.as_deref(), Some("11:00"));
+    }
+
+    #[tokio::test]
+    async fn reschedule_into_taken_slot_conflicts_and_keeps_original() {
+        let pool = test_pool().await;
+        let booked = book(&pool, "patient-001", "10:00").await;
+        let other = create_appointment(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(CreateAppointmentRequest {
+                patient_id: "admin-001".to_string(),
+                physiotherapist_id: "physio-001".to_string(),
+                appointment_date: "2030-01-16".to_string(),
+                start_time: "11:00".to_string(),
+                appointment_type_id: None,
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(other.start_time, "11:00");
+
+        let err = reschedule_appointment(
+            Path(booked.id.clone()),
+            Extension(pool.clone()),
+            Extension(config(false)),
+            auth_headers("patient-001", "patient"),
+            move_to("11:00"),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+
+        let mut conn = pool.acquire().await.unwrap();
+        let unchanged = fetch_appointment(&mut conn, &booked.id).await.unwrap();
+        assert_eq!(unchanged.start_time, "10:00");
+
+        let err = reschedule_appointment(
+            Path(booked.id.clone()),
+            Extension(pool.clone()),
+            Extension(config(false)),
+            auth_headers("patient-002", "patient"),
+            move_to("12:00"),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn patient_reschedule_waits_for_physiotherapist_approval() {
+        let pool = test_pool().await;
+        let booked = book(&pool, "patient-001", "10:00").await;
+
+        let (status, Json(resp)) = reschedule_appointment(
+            Path(booked.id.clone()),
+            Extension(pool.clone()),
+            Extension(config(true)),
+            auth_headers("patient-001", "patient"),
+            move_to("11:00"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(resp.appointment.start_time, "10:00");
+        assert_eq!(resp.pending_request.unwrap().new_start_time, "11:00");
+
+        let err = approve_reschedule(
+            Path(booked.id.clone()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-001", "patient"),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+
+        let Json(approved) = approve_reschedule(
+            Path(booked.id.clone()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("physio-001", "physiotherapist"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(approved.id, booked.id);
+        assert_eq!(approved.appointment_date, "2030-01-16");
+        assert_eq!(approved.start_time, "11:00");
+    }
+
+    #[tokio::test]
+    async fn reschedule_follows_the_booking_rules() {
+        let pool = test_pool().await;
+        let booked = book(&pool, "patient-001", "10:00").await;
+
+        let err = reschedule_appointment(
+            Path(booked.id.clone()),
+            Extension(pool.clone()),
+            Extension(config(false)),
+            auth_headers("patient-001", "patient"),
+            Json(RescheduleAppointmentRequest {
+                appointment_date: "2020-01-16".to_string(),
+                start_time: "11:00".to_string(),
+                scope: SeriesScope::This,
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+
+        // Vrstu koju zakazuje samo klinika pacijent ne može ni sam da pomeri
+        let (_, Json(clinic_only)) = create_appointment_type(
+            Extension(pool.clone()),
+            auth_headers("admin-001", "admin"),
+            Json(serde_json::from_value(serde_json::json!({
+                "name": "Procena",
+                "duration_minutes": 20,
+                "online_bookable": false,
+                "physiotherapist_ids": ["physio-001"],
+            })).unwrap()),
+        )
+        .await
+        .unwrap();
+        let Json(assessment) = create_appointment(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(CreateAppointmentRequest {
+                patient_id: "patient-001".to_string(),
+                physiotherapist_id: "physio-001".to_string(),
+                appointment_date: "2030-01-15".to_string(),
+                start_time: "12:00".to_string(),
+                appointment_type_id: Some(clinic_only.id.clone()),
+            }),
+        )
+        .await
+        .unwrap();
+
+        let err = reschedule_appointment(
+            Path(assessment.id.clone()),
+            Extension(pool.clone()),
+            Extension(config(false)),
+            auth_headers("patient-001", "patient"),
+            move_to("12:00"),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+
+        let (status, _) = reschedule_appointment(
+            Path(assessment.id.clone()),
+            Extension(pool.clone()),
+            Extension(config(false)),
+            auth_headers("physio-001", "physiotherapist"),
+            move_to("12:00"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn vacated_slot_is_offered_to_the_waitlist() {
+        let pool = test_pool().await;
+        let booked = book(&pool, "patient-001", "10:00").await;
+        insert_patient(&pool, "patient-002").await;
+        let (_, Json(entry)) = join_waitlist(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-002", "patient"),
+            Json(JoinWaitlistRequest {
+                patient_id: "patient-002".to_string(),
+                physiotherapist_id: "physio-001".to_string(),
+                date_from: "2030-01-15".to_string(),
+                date_to: "2030-01-15".to_string(),
+                preferred_start: None,
+                preferred_end: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        let (status, _) = reschedule_appointment(
+            Path(booked.id.clone()),
+            Extension(pool.clone()),
+            Extension(config(false)),
+            auth_headers("patient-001", "patient"),
+            move_to("11:00"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::OK);
+
+        let Json(waitlist) = get_waitlist(Extension(pool.clone()), auth_headers("patient-002", "patient"))
+            .await
+            .unwrap();
+        assert_eq!(waitlist.holds.len(), 1);
+        assert_eq!(waitlist.holds[0].entry_id, entry.id);
+        assert_eq!(waitlist.holds[0].appointment_date, "2030-01-15");
+        assert_eq!(waitlist.holds[0].start_time, "10:00");
+    }
+}
@@ -0,0 +1,540 @@
+use axum::{
+    http::{StatusCode, HeaderMap},
+    Json,
+    extract::{Path, Query},
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use chrono::{Duration, NaiveTime, Utc};
+
+use super::{
+    authenticate, bad_request, database_error, ensure_admin, utc_timestamp, ErrorResponse, SLOT_MINUTES,
+    WORK_END_HOUR, WORK_START_HOUR,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct CreateResourceRequest {
+    pub name: String,
+    pub kind: String,           // room, equipment
+    pub quantity: Option<i64>,  // podrazumevano 1
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateResourceRequest {
+    pub name: Option<String>,
+    pub quantity: Option<i64>,
+    pub active: Option<bool>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Resource {
+    pub id: String,
+    pub name: String,
+    pub kind: String,
+    pub quantity: i64,
+    pub active: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResourcesResponse {
+    pub resources: Vec<Resource>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OccupancyQuery {
+    pub date: String,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ResourceBooking {
+    pub appointment_id: String,
+    pub physiotherapist_id: String,
+    pub start_time: String,
+    pub end_time: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SlotUsage {
+    pub time: String,
+    pub in_use: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResourceOccupancy {
+    pub resource: Resource,
+    pub bookings: Vec<ResourceBooking>,
+    pub slots: Vec<SlotUsage>,
+    // Udeo zauzetih jedinica kroz radni dan, u procentima
+    pub utilization_percent: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OccupancyResponse {
+    pub date: String,
+    pub resources: Vec<ResourceOccupancy>,
+}
+
+const RESOURCE_COLUMNS: &str = "id, name, kind, quantity, active, created_at, updated_at";
+
+fn validate_quantity(quantity: i64) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if quantity < 1 {
+        return Err(bad_request("quantity must be at least 1"));
+    }
+    Ok(())
+}
+
+async fn fetch_resource(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<Resource, (StatusCode, Json<ErrorResponse>)> {
+    sqlx::query_as::<_, Resource>(&format!("SELECT {} FROM resources WHERE id = ?", RESOURCE_COLUMNS))
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: "Resource not found".to_string() })
+        ))
+}
+
+// Koliko jedinica resursa drže aktivni termini koji se preklapaju sa [start, end)
+async fn resource_in_use(
+    conn: &mut SqliteConnection,
+    resource_id: &str,
+    appointment_date: &str,
+    start_time: &str,
+    end_time: &str,
+    exclude_id: Option<&str>,
+) -> Result<i64, (StatusCode, Json<ErrorResponse>)> {
+    sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*) FROM appointment_resources ar
+        JOIN appointments a ON a.id = ar.appointment_id
+        WHERE ar.resource_id = ?
+        AND a.appointment_date = ?
+        AND a.start_time < ?
+        AND a.end_time > ?
+        AND a.status NOT IN ('cancelled', 'late_cancellation')
+        AND a.id != COALESCE(?, '')
+        "#
+    )
+    .bind(resource_id)
+    .bind(appointment_date)
+    .bind(end_time)
+    .bind(start_time)
+    .bind(exclude_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(database_error)
+}
+
+// Najviše jedinica resursa koje budući termini drže u isto vreme. Vrhunac je uvek
+// na početku nekog termina, pa se za svaki broje termini koji su tada u toku.
+async fn peak_future_use(
+    conn: &mut SqliteConnection,
+    resource_id: &str,
+) -> Result<i64, (StatusCode, Json<ErrorResponse>)> {
+    sqlx::query_scalar(
+        r#"
+        WITH booked AS (
+            SELECT a.appointment_date, a.start_time, a.end_time
+            FROM appointment_resources ar
+            JOIN appointments a ON a.id = ar.appointment_id
+            WHERE ar.resource_id = ?
+            AND a.ends_at > ?
+            AND a.status NOT IN ('cancelled', 'late_cancellation')
+        )
+        SELECT COALESCE(MAX((
+            SELECT COUNT(*) FROM booked other
+            WHERE other.appointment_date = b.appointment_date
+            AND other.start_time <= b.start_time
+            AND other.end_time > b.start_time
+        )), 0)
+        FROM booked b
+        "#
+    )
+    .bind(resource_id)
+    .bind(utc_timestamp(Utc::now()))
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(database_error)
+}
+
+// Prvi resurs potreban za vrstu termina koji je u tom periodu potpuno zauzet
+pub(crate) async fn busy_resource(
+    conn: &mut SqliteConnection,
+    appointment_type_id: &str,
+    appointment_date: &str,
+    start_time: &str,
+    end_time: &str,
+    exclude_id: Option<&str>,
+) -> Result<Option<Resource>, (StatusCode, Json<ErrorResponse>)> {
+    let required = sqlx::query_as::<_, Resource>(
+        r#"
+        SELECT r.id, r.name, r.kind, r.quantity, r.active, r.created_at, r.updated_at
+        FROM appointment_type_resources tr
+        JOIN resources r ON r.id = tr.resource_id
+        WHERE tr.appointment_type_id = ?
+        ORDER BY r.name ASC
+        "#
+    )
+    .bind(appointment_type_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    for resource in required {
+        // Isključen resurs se ne može rezervisati
+        if !resource.active {
+            return Ok(Some(resource));
+        }
+        let in_use = resource_in_use(conn, &resource.id, appointment_date, start_time, end_time, exclude_id).await?;
+        if in_use >= resource.quantity {
+            return Ok(Some(resource));
+        }
+    }
+
+    Ok(None)
+}
+
+// Mora se pozvati unutar BEGIN IMMEDIATE transakcije, kao ensure_slot_free
+pub(crate) async fn ensure_resources_free(
+    conn: &mut SqliteConnection,
+    appointment_type_id: Option<&str>,
+    appointment_date: &str,
+    start_time: &str,
+    end_time: &str,
+    exclude_id: Option<&str>,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    let Some(appointment_type_id) = appointment_type_id else {
+        return Ok(());
+    };
+
+    match busy_resource(conn, appointment_type_id, appointment_date, start_time, end_time, exclude_id).await? {
+        Some(resource) => Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse { error: format!("{} is not available at this time", resource.name) })
+        )),
+        None => Ok(()),
+    }
+}
+
+// Zauzmi resurse koje vrsta termina traži
+pub(crate) async fn attach_resources(
+    conn: &mut SqliteConnection,
+    appointment_id: &str,
+    appointment_type_id: &str,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    sqlx::query(
+        r#"
+        INSERT INTO appointment_resources (appointment_id, resource_id)
+        SELECT ?, resource_id FROM appointment_type_resources WHERE appointment_type_id = ?
+        "#
+    )
+    .bind(appointment_id)
+    .bind(appointment_type_id)
+    .execute(&mut *conn)
+    .await
+    .map_err(database_error)?;
+    Ok(())
+}
+
+pub async fn create_resource(
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+    Json(req): Json<CreateResourceRequest>,
+) -> Result<(StatusCode, Json<Resource>), (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+    ensure_admin(&claims)?;
+
+    if req.name.trim().is_empty() {
+        return Err(bad_request("Name is required"));
+    }
+    if req.kind != "room" && req.kind != "equipment" {
+        return Err(bad_request("kind must be room or equipment"));
+    }
+    let quantity = req.quantity.unwrap_or(1);
+    validate_quantity(quantity)?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = Utc::now().to_rfc3339();
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO resources (id, name, kind, quantity, active, created_at, updated_at)
+        VALUES (?, ?, ?, ?, 1, ?, ?)
+        "#
+    )
+    .bind(&id)
+    .bind(req.name.trim())
+    .bind(&req.kind)
+    .bind(quantity)
+    .bind(&now)
+    .bind(&now)
+    .execute(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    let resource = fetch_resource(&mut conn, &id).await?;
+    Ok((StatusCode::CREATED, Json(resource)))
+}
+
+pub async fn get_resources(
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+) -> Result<Json<ResourcesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    authenticate(&headers)?;
+
+    let resources = sqlx::query_as::<_, Resource>(
+        &format!("SELECT {} FROM resources ORDER BY kind ASC, name ASC", RESOURCE_COLUMNS)
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(database_error)?;
+
+    Ok(Json(ResourcesResponse { resources }))
+}
+
+pub async fn update_resource(
+    Path(id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+    Json(req): Json<UpdateResourceRequest>,
+) -> Result<Json<Resource>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+    ensure_admin(&claims)?;
+    if let Some(quantity) = req.quantity {
+        validate_quantity(quantity)?;
+    }
+
+    // BEGIN IMMEDIATE da se između provere i izmene ne upiše nova rezervacija
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE")
+        .await
+        .map_err(database_error)?;
+    fetch_resource(&mut tx, &id).await?;
+
+    if let Some(quantity) = req.quantity {
+        let peak = peak_future_use(&mut tx, &id).await?;
+        if peak > quantity {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(ErrorResponse {
+                    error: format!("Upcoming appointments use {} units of this resource at the same time", peak),
+                })
+            ));
+        }
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE resources
+        SET name = COALESCE(?, name),
+            quantity = COALESCE(?, quantity),
+            active = COALESCE(?, active),
+            updated_at = ?
+        WHERE id = ?
+        "#
+    )
+    .bind(req.name.as_deref().map(str::trim))
+    .bind(req.quantity)
+    .bind(req.active)
+    .bind(Utc::now().to_rfc3339())
+    .bind(&id)
+    .execute(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    let resource = fetch_resource(&mut tx, &id).await?;
+    tx.commit().await.map_err(database_error)?;
+
+    Ok(Json(resource))
+}
+
+// Dnevna zauzetost svih resursa, po slotovima
+pub async fn get_resource_occupancy(
+    Extension(pool): Extension<SqlitePool>,
+    Query(query): Query<OccupancyQuery>,
+    headers: HeaderMap,
+) -> Result<Json<OccupancyResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+    ensure_admin(&claims)?;
+
+    let date = chrono::NaiveDate::parse_from_str(&query.date, "%Y-%m-%d")
+        .map_err(|_| bad_request("Invalid date format"))?
+        .format("%Y-%m-%d")
+        .to_string();
+
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+    let resources = sqlx::query_as::<_, Resource>(
+        &format!("SELECT {} FROM resources ORDER BY kind ASC, name ASC", RESOURCE_COLUMNS)
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    let day_start = NaiveTime::from_hms_opt(WORK_START_HOUR, 0, 0).unwrap();
+    let day_end = NaiveTime::from_hms_opt(WORK_END_HOUR, 0, 0).unwrap();
+
+    let mut occupancy = Vec::with_capacity(resources.len());
+    for resource in resources {
+        let bookings = sqlx::query_as::<_, ResourceBooking>(
+            r#"
+            SELECT a.id AS appointment_id, a.physiotherapist_id, a.start_time, a.end_time
+            FROM appointment_resources ar
+            JOIN appointments a ON a.id = ar.appointment_id
+            WHERE ar.resource_id = ?
+            AND a.appointment_date = ?
+            AND a.status NOT IN ('cancelled', 'late_cancellation')
+            ORDER BY a.start_time ASC
+            "#
+        )
+        .bind(&resource.id)
+        .bind(&date)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(database_error)?;
+
+        let mut slots = Vec::new();
+        let mut time = day_start;
+        while time < day_end {
+            let start = time.format("%H:%M").to_string();
+            let end = (time + Duration::minutes(SLOT_MINUTES)).format("%H:%M").to_string();
+            let in_use = bookings.iter()
+                .filter(|b| b.start_time < end && b.end_time > start)
+                .count() as i64;
+            slots.push(SlotUsage { time: start, in_use });
+            time += Duration::minutes(SLOT_MINUTES);
+        }
+
+        let capacity = resource.quantity * slots.len() as i64;
+        let used: i64 = slots.iter().map(|s| s.in_use.min(resource.quantity)).sum();
+        let utilization_percent = if capacity == 0 {
+            0.0
+        } else {
+            (used as f64 * 1000.0 / capacity as f64).round() / 10.0
+        };
+
+        occupancy.push(ResourceOccupancy { resource, bookings, slots, utilization_percent });
+    }
+
+    Ok(Json(OccupancyResponse { date, resources: occupancy }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::{
+        create_appointment, create_appointment_type, get_available_slots, AppointmentTypeRequest,
+        AvailableSlotsQuery, CreateAppointmentRequest,
+    };
+    use crate::test_support::{auth_headers, insert_user, test_config, test_pool};
+
+    fn booking(physiotherapist_id: &str, appointment_type_id: &str) -> CreateAppointmentRequest {
+        CreateAppointmentRequest {
+            patient_id: "patient-001".to_string(),
+            physiotherapist_id: physiotherapist_id.to_string(),
+            appointment_date: "2030-01-15".to_string(),
+            start_time: "10:00".to_string(),
+            appointment_type_id: Some(appointment_type_id.to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn booking_needs_a_free_resource() {
+        let pool = test_pool().await;
+        insert_user(&pool, "physio-002", "physiotherapist").await;
+
+        let admin = || auth_headers("admin-001", "admin");
+        let (_, Json(shockwave)) = create_resource(
+            Extension(pool.clone()),
+            admin(),
+            Json(CreateResourceRequest { name: "Shockwave".to_string(), kind: "equipment".to_string(), quantity: None }),
+        )
+        .await
+        .unwrap();
+        let (_, Json(therapy)) = create_appointment_type(
+            Extension(pool.clone()),
+            admin(),
+            Json(serde_json::from_value::<AppointmentTypeRequest>(serde_json::json!({
+                "name": "Udarni talas",
+                "resource_ids": [shockwave.id],
+            })).unwrap()),
+        )
+        .await
+        .unwrap();
+
+        let Json(booked) = create_appointment(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(booking("physio-001", &therapy.id)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(booked.appointment_type_id.as_deref(), Some(therapy.id.as_str()));
+
+        // Drugi fizioterapeut je slobodan, ali jedini aparat nije
+        let err = create_appointment(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(booking("physio-002", &therapy.id)),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+
+        let Json(slots) = get_available_slots(
+            Path("physio-002".to_string()),
+            Query(AvailableSlotsQuery { date: "2030-01-15".to_string(), appointment_type_id: Some(therapy.id.clone()) }),
+            Extension(pool.clone()),
+            Extension(test_config()),
+        )
+        .await
+        .unwrap();
+        let slot = |time: &str| slots.slots.iter().find(|s| s.time == time).unwrap().available;
+        assert!(!slot("10:00"));
+        assert!(slot("10:20"));
+
+        let Json(occupancy) = get_resource_occupancy(
+            Extension(pool.clone()),
+            Query(OccupancyQuery { date: "2030-01-15".to_string() }),
+            admin(),
+        )
+        .await
+        .unwrap();
+        let usage = &occupancy.resources[0];
+        assert_eq!(usage.bookings.len(), 1);
+        assert_eq!(usage.slots.iter().find(|s| s.time == "10:00").unwrap().in_use, 1);
+        assert_eq!(usage.utilization_percent, 4.2);
+
+        // Sa drugim aparatom staju oba termina, a tada se količina više ne može spustiti
+        let set_quantity = |quantity| {
+            update_resource(
+                Path(shockwave.id.clone()),
+                Extension(pool.clone()),
+                admin(),
+                Json(UpdateResourceRequest { name: None, quantity: Some(quantity), active: None }),
+            )
+        };
+        let Json(raised) = set_quantity(2).await.unwrap();
+        assert_eq!(raised.quantity, 2);
+        let Json(second) = create_appointment(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(booking("physio-002", &therapy.id)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(second.start_time, booked.start_time);
+
+        let err = set_quantity(1).await.unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+    }
+}
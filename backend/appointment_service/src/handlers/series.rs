@@ -0,0 +1,779 @@
+use axum::{
+    http::{StatusCode, HeaderMap},
+    Json,
+    extract::Path,
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+use crate::config::Config;
+use crate::utils::Claims;
+use super::{
+    assign_appointment_type, authenticate, blocked_until, bookable_type, busy_resource, clinic_now,
+    database_error, ensure_can_book_for, forbidden, insert_appointment, is_restricted, localize_for,
+    slot_is_free, validate_slot, AppointmentResponse, AppointmentType, ErrorResponse,
+    APPOINTMENT_COLUMNS, SLOT_MINUTES,
+};
+
+// Najviše termina u jednoj seriji i najduži period koji pravilo pokriva
+const MAX_SERIES_OCCURRENCES: usize = 52;
+const MAX_SERIES_DAYS: i64 = 366;
+
+// Da li se izmena odnosi samo na ovaj termin, i na sve naredne iz serije,
+// ili na sve termine serije koji još nisu počeli
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SeriesScope {
+    #[default]
+    This,
+    Following,
+    All,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateSeriesRequest {
+    pub patient_id: String,
+    pub physiotherapist_id: String,
+    pub start_date: String,       // YYYY-MM-DD
+    pub start_time: String,       // HH:MM
+    pub weekdays: Vec<String>,    // "mon", "thursday", ...
+    pub occurrences: Option<u32>,
+    pub end_date: Option<String>, // YYYY-MM-DD, inclusive
+    // Trajanje, pauza i resursi svakog termina, kao kod pojedinačnog zakazivanja
+    pub appointment_type_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeriesOccurrence {
+    pub appointment_date: String,
+    pub start_time: String,
+    pub end_time: String,
+    pub available: bool,
+    pub conflict: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeriesPreviewResponse {
+    pub occurrences: Vec<SeriesOccurrence>,
+    pub available_count: usize,
+    pub conflict_count: usize,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AppointmentSeries {
+    pub id: String,
+    pub patient_id: String,
+    pub physiotherapist_id: String,
+    pub start_date: String,
+    pub start_time: String,
+    pub duration_minutes: i32,
+    pub weekdays: String,
+    pub occurrences: Option<i64>,
+    pub end_date: Option<String>,
+    pub created_by: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeriesResponse {
+    pub series: AppointmentSeries,
+    pub appointments: Vec<AppointmentResponse>,
+    // Termini iz pravila koji nisu zakazani jer su zauzeti
+    pub conflicts: Vec<SeriesOccurrence>,
+}
+
+pub(crate) fn bad_request(message: &str) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse { error: message.to_string() })
+    )
+}
+
+pub(crate) fn weekday_code(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "mon",
+        Weekday::Tue => "tue",
+        Weekday::Wed => "wed",
+        Weekday::Thu => "thu",
+        Weekday::Fri => "fri",
+        Weekday::Sat => "sat",
+        Weekday::Sun => "sun",
+    }
+}
+
+// Razvij pravilo ponavljanja u listu datuma
+fn expand_rule(req: &CreateSeriesRequest) -> Result<Vec<NaiveDate>, (StatusCode, Json<ErrorResponse>)> {
+    let start = NaiveDate::parse_from_str(&req.start_date, "%Y-%m-%d")
+        .map_err(|_| bad_request("Invalid start_date format"))?;
+
+    let mut weekdays = Vec::new();
+    for day in &req.weekdays {
+        let weekday = day.parse::<Weekday>()
+            .map_err(|_| bad_request(&format!("Invalid weekday: {}", day)))?;
+        if !weekdays.contains(&weekday) {
+            weekdays.push(weekday);
+        }
+    }
+    if weekdays.is_empty() {
+        return Err(bad_request("At least one weekday is required"));
+    }
+
+    let end = match (&req.occurrences, &req.end_date) {
+        (Some(_), None) => None,
+        (None, Some(end_date)) => Some(
+            NaiveDate::parse_from_str(end_date, "%Y-%m-%d")
+                .map_err(|_| bad_request("Invalid end_date format"))?,
+        ),
+        _ => return Err(bad_request("Exactly one of occurrences or end_date is required")),
+    };
+
+    if let Some(occurrences) = req.occurrences {
+        if occurrences == 0 || occurrences as usize > MAX_SERIES_OCCURRENCES {
+            return Err(bad_request(&format!("occurrences must be between 1 and {}", MAX_SERIES_OCCURRENCES)));
+        }
+    }
+    if let Some(end) = end {
+        if end < start || (end - start).num_days() >= MAX_SERIES_DAYS {
+            return Err(bad_request("end_date must be after start_date and within a year"));
+        }
+    }
+
+    // Sa end_date skupi jedan datum više od maksimuma da bi se prekoračenje primetilo
+    let limit = req.occurrences.map(|n| n as usize).unwrap_or(MAX_SERIES_OCCURRENCES + 1);
+    let mut dates = Vec::new();
+    let mut date = start;
+    while dates.len() < limit
+        && (date - start).num_days() < MAX_SERIES_DAYS
+        && end.is_none_or(|end| date <= end)
+    {
+        if weekdays.contains(&date.weekday()) {
+            dates.push(date);
+        }
+        date += Duration::days(1);
+    }
+
+    if dates.len() > MAX_SERIES_OCCURRENCES {
+        return Err(bad_request(&format!("A series can have at most {} occurrences", MAX_SERIES_OCCURRENCES)));
+    }
+    if dates.is_empty() {
+        return Err(bad_request("The recurrence rule does not produce any dates"));
+    }
+
+    Ok(dates)
+}
+
+// Vrsta termina iz zahteva, ako je navedena i ako je pozivalac sme da zakaže
+async fn series_type(
+    conn: &mut SqliteConnection,
+    claims: &Claims,
+    req: &CreateSeriesRequest,
+) -> Result<Option<AppointmentType>, (StatusCode, Json<ErrorResponse>)> {
+    match &req.appointment_type_id {
+        Some(type_id) => Ok(Some(bookable_type(conn, claims, type_id, &req.physiotherapist_id).await?)),
+        None => Ok(None),
+    }
+}
+
+// Proveri svaki termin iz pravila; ništa se ne upisuje
+async fn check_occurrences(
+    conn: &mut SqliteConnection,
+    req: &CreateSeriesRequest,
+    appointment_type: Option<&AppointmentType>,
+) -> Result<Vec<SeriesOccurrence>, (StatusCode, Json<ErrorResponse>)> {
+    let (duration_minutes, buffer_minutes) = appointment_type
+        .map(|t| (t.duration_minutes, t.buffer_minutes))
+        .unwrap_or((SLOT_MINUTES, 0));
+    let mut occurrences = Vec::new();
+
+    for date in expand_rule(req)? {
+        let (date, start_time, end_time) =
+            validate_slot(&date.format("%Y-%m-%d").to_string(), &req.start_time, duration_minutes)?;
+
+        let blocked = blocked_until(&end_time, buffer_minutes);
+        let conflict = if !slot_is_free(conn, &req.physiotherapist_id, &date, &start_time, &blocked, None).await? {
+            Some("Time slot already booked".to_string())
+        } else if let Some(appointment_type) = appointment_type {
+            busy_resource(conn, &appointment_type.id, &date, &start_time, &end_time, None)
+                .await?
+                .map(|resource| format!("{} is not available at this time", resource.name))
+        } else {
+            None
+        };
+
+        occurrences.push(SeriesOccurrence {
+            appointment_date: date,
+            start_time,
+            end_time,
+            available: conflict.is_none(),
+            conflict,
+        });
+    }
+
+    Ok(occurrences)
+}
+
+// Ostali aktivni termini iz iste serije na koje se izmena odnosi, po redu
+pub(crate) async fn series_occurrences_in_scope(
+    conn: &mut SqliteConnection,
+    config: &Config,
+    appointment: &AppointmentResponse,
+    scope: SeriesScope,
+) -> Result<Vec<AppointmentResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let series_id = match (&appointment.series_id, scope) {
+        (Some(series_id), SeriesScope::Following | SeriesScope::All) => series_id,
+        _ => return Ok(Vec::new()),
+    };
+    // "following" kreće posle ovog termina, "all" od sada
+    let after = match scope {
+        SeriesScope::All => clinic_now(config).format("%Y-%m-%d %H:%M").to_string(),
+        _ => format!("{} {}", appointment.appointment_date, appointment.start_time),
+    };
+
+    sqlx::query_as::<_, AppointmentResponse>(
+        &format!(
+            r#"
+            SELECT {} FROM appointments
+            WHERE series_id = ?
+            AND id != ?
+            AND status IN ('scheduled', 'confirmed')
+            AND appointment_date || ' ' || start_time > ?
+            ORDER BY appointment_date ASC, start_time ASC
+            "#,
+            APPOINTMENT_COLUMNS
+        )
+    )
+    .bind(series_id)
+    .bind(&appointment.id)
+    .bind(after)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(database_error)
+}
+
+async fn assign_series(
+    conn: &mut SqliteConnection,
+    appointment: &mut AppointmentResponse,
+    series_id: &str,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    sqlx::query("UPDATE appointments SET series_id = ? WHERE id = ?")
+        .bind(series_id)
+        .bind(&appointment.id)
+        .execute(&mut *conn)
+        .await
+        .map_err(database_error)?;
+
+    appointment.series_id = Some(series_id.to_string());
+    Ok(())
+}
+
+async fn fetch_series(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<SeriesResponse, (StatusCode, Json<ErrorResponse>)> {
+    let series = sqlx::query_as::<_, AppointmentSeries>(
+        r#"
+        SELECT id, patient_id, physiotherapist_id, start_date, start_time, duration_minutes,
+               weekdays, occurrences, end_date, created_by, created_at
+        FROM appointment_series
+        WHERE id = ?
+        "#
+    )
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse { error: "Appointment series not found".to_string() })
+    ))?;
+
+    let appointments = sqlx::query_as::<_, AppointmentResponse>(
+        &format!(
+            "SELECT {} FROM appointments WHERE series_id = ? ORDER BY appointment_date ASC, start_time ASC",
+            APPOINTMENT_COLUMNS
+        )
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    Ok(SeriesResponse { series, appointments, conflicts: Vec::new() })
+}
+
+// Izveštaj o konfliktima pre zakazivanja serije
+pub async fn preview_appointment_series(
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+    Json(req): Json<CreateSeriesRequest>,
+) -> Result<Json<SeriesPreviewResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+    ensure_can_book_for(&claims, &req.patient_id)?;
+
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+    let appointment_type = series_type(&mut conn, &claims, &req).await?;
+    let occurrences = check_occurrences(&mut conn, &req, appointment_type.as_ref()).await?;
+    let available_count = occurrences.iter().filter(|o| o.available).count();
+
+    Ok(Json(SeriesPreviewResponse {
+        conflict_count: occurrences.len() - available_count,
+        available_count,
+        occurrences,
+    }))
+}
+
+// Zakaži sve slobodne termine iz pravila u jednoj transakciji
+pub async fn create_appointment_series(
+    Extension(pool): Extension<SqlitePool>,
+    Extension(config): Extension<Config>,
+    headers: HeaderMap,
+    Json(req): Json<CreateSeriesRequest>,
+) -> Result<(StatusCode, Json<SeriesResponse>), (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+    ensure_can_book_for(&claims, &req.patient_id)?;
+
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE")
+        .await
+        .map_err(database_error)?;
+
+    // Pacijent sa ograničenjem zbog kasnih otkazivanja ne može da zakaže seriju
+    if is_restricted(&mut tx, &config, &req.patient_id).await? {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse { error: "Too many late cancellations or no-shows to book a series".to_string() })
+        ));
+    }
+
+    let appointment_type = series_type(&mut tx, &claims, &req).await?;
+    let occurrences = check_occurrences(&mut tx, &req, appointment_type.as_ref()).await?;
+    if !occurrences.iter().any(|o| o.available) {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse { error: "None of the series occurrences are available".to_string() })
+        ));
+    }
+
+    let series_id = uuid::Uuid::new_v4().to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+    let weekdays = req.weekdays.iter()
+        .filter_map(|d| d.parse::<Weekday>().ok())
+        .map(weekday_code)
+        .collect::<Vec<_>>()
+        .join(",");
+    let first = &occurrences[0];
+
+    sqlx::query(
+        r#"
+        INSERT INTO appointment_series (
+            id, patient_id, physiotherapist_id, start_date, start_time, duration_minutes,
+            weekdays, occurrences, end_date, created_by, created_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&series_id)
+    .bind(&req.patient_id)
+    .bind(&req.physiotherapist_id)
+    .bind(&req.start_date)
+    .bind(&first.start_time)
+    .bind(appointment_type.as_ref().map(|t| t.duration_minutes).unwrap_or(SLOT_MINUTES))
+    .bind(&weekdays)
+    .bind(req.occurrences.map(|n| n as i64))
+    .bind(&req.end_date)
+    .bind(&claims.sub)
+    .bind(&now)
+    .execute(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    let mut conflicts = Vec::new();
+    for occurrence in occurrences {
+        if !occurrence.available {
+            conflicts.push(occurrence);
+            continue;
+        }
+
+        let mut appointment = insert_appointment(
+            &mut tx,
+            &config,
+            &req.patient_id,
+            &req.physiotherapist_id,
+            &occurrence.appointment_date,
+            &occurrence.start_time,
+            &occurrence.end_time,
+        ).await?;
+        assign_series(&mut tx, &mut appointment, &series_id).await?;
+        if let Some(appointment_type) = &appointment_type {
+            assign_appointment_type(&mut tx, &mut appointment, &appointment_type.id, appointment_type.buffer_minutes).await?;
+        }
+    }
+
+    let mut response = fetch_series(&mut tx, &series_id).await?;
+    localize_for(&mut tx, &config, &claims.sub, &mut response.appointments).await;
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!(
+        "Series {} booked with {} appointments, {} conflicts",
+        series_id,
+        response.appointments.len(),
+        conflicts.len()
+    );
+
+    response.conflicts = conflicts;
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+pub async fn get_appointment_series(
+    Path(id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(config): Extension<Config>,
+    headers: HeaderMap,
+) -> Result<Json<SeriesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+    let mut response = fetch_series(&mut conn, &id).await?;
+
+    if claims.role != "admin"
+        && claims.sub != response.series.patient_id
+        && claims.sub != response.series.physiotherapist_id
+    {
+        return Err(forbidden());
+    }
+
+    localize_for(&mut conn, &config, &claims.sub, &mut response.appointments).await;
+    Ok(Json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::{
+        cancel_appointment, create_appointment, create_appointment_type, reschedule_appointment,
+        update_appointment_status, CancelAppointmentRequest, CreateAppointmentRequest,
+        RescheduleAppointmentRequest,
+    };
+    use crate::test_support::{auth_headers, test_config, test_pool};
+
+    // Ponedeljkom i četvrtkom, šest puta, počevši od ponedeljka 14.01.2030.
+    fn twice_a_week() -> CreateSeriesRequest {
+        CreateSeriesRequest {
+            patient_id: "patient-001".to_string(),
+            physiotherapist_id: "physio-001".to_string(),
+            start_date: "2030-01-14".to_string(),
+            start_time: "09:00".to_string(),
+            weekdays: vec!["mon".to_string(), "thursday".to_string()],
+            occurrences: Some(6),
+            end_date: None,
+            appointment_type_id: None,
+        }
+    }
+
+    #[test]
+    fn rule_expands_to_requested_weekdays() {
+        let dates = expand_rule(&twice_a_week()).unwrap();
+        let formatted: Vec<String> = dates.iter().map(|d| d.format("%Y-%m-%d").to_string()).collect();
+        assert_eq!(
+            formatted,
+            ["2030-01-14", "2030-01-17", "2030-01-21", "2030-01-24", "2030-01-28", "2030-01-31"]
+        );
+
+        let until_end_date = CreateSeriesRequest {
+            occurrences: None,
+            end_date: Some("2030-01-21".to_string()),
+            ..twice_a_week()
+        };
+        assert_eq!(expand_rule(&until_end_date).unwrap().len(), 3);
+
+        let both = CreateSeriesRequest { end_date: Some("2030-01-21".to_string()), ..twice_a_week() };
+        assert_eq!(expand_rule(&both).unwrap_err().0, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn series_books_free_occurrences_and_reports_clashes() {
+        let pool = test_pool().await;
+
+        // Drugi pacijent već ima termin u četvrtak 17.01. u 09:00
+        let taken = create_appointment(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(CreateAppointmentRequest {
+                patient_id: "admin-001".to_string(),
+                physiotherapist_id: "physio-001".to_string(),
+                appointment_date: "2030-01-17".to_string(),
+                start_time: "09:00".to_string(),
+                appointment_type_id: None,
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(taken.status, "scheduled");
+
+        let Json(preview) = preview_appointment_series(
+            Extension(pool.clone()),
+            auth_headers("patient-001", "patient"),
+            Json(twice_a_week()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(preview.available_count, 5);
+        assert_eq!(preview.conflict_count, 1);
+        assert_eq!(preview.occurrences[1].appointment_date, "2030-01-17");
+        assert!(!preview.occurrences[1].available);
+
+        let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM appointments")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(stored, 1);
+
+        let (status, Json(series)) = create_appointment_series(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-001", "patient"),
+            Json(twice_a_week()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(series.appointments.len(), 5);
+        assert_eq!(series.conflicts.len(), 1);
+        assert_eq!(series.conflicts[0].appointment_date, "2030-01-17");
+        assert!(series.appointments.iter().all(|a| a.series_id.as_deref() == Some(series.series.id.as_str())));
+    }
+
+    #[tokio::test]
+    async fn series_occurrences_take_the_appointment_type() {
+        let pool = test_pool().await;
+
+        let (_, Json(massage)) = create_appointment_type(
+            Extension(pool.clone()),
+            auth_headers("admin-001", "admin"),
+            Json(serde_json::from_value(serde_json::json!({
+                "name": "Sportska masaža",
+                "duration_minutes": 40,
+                "buffer_minutes": 30,
+                "physiotherapist_ids": ["physio-001"],
+            })).unwrap()),
+        )
+        .await
+        .unwrap();
+
+        // Termin u 10:00 u četvrtak 17.01. pada u pauzu posle masaže koja traje do 09:40
+        let taken = create_appointment(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(CreateAppointmentRequest {
+                patient_id: "admin-001".to_string(),
+                physiotherapist_id: "physio-001".to_string(),
+                appointment_date: "2030-01-17".to_string(),
+                start_time: "10:00".to_string(),
+                appointment_type_id: None,
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(taken.status, "scheduled");
+
+        let (_, Json(series)) = create_appointment_series(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-001", "patient"),
+            Json(CreateSeriesRequest { appointment_type_id: Some(massage.id.clone()), ..twice_a_week() }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(series.series.duration_minutes, 40);
+        assert_eq!(series.conflicts.len(), 1);
+        assert_eq!(series.conflicts[0].appointment_date, "2030-01-17");
+        assert_eq!(series.appointments.len(), 5);
+        assert!(series.appointments.iter().all(|a| {
+            a.appointment_type_id.as_deref() == Some(massage.id.as_str())
+                && a.end_time == "09:40"
+                && a.buffer_minutes == 30
+        }));
+    }
+
+    #[tokio::test]
+    async fn status_cancellation_respects_series_scope() {
+        let pool = test_pool().await;
+        let (_, Json(series)) = create_appointment_series(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-001", "patient"),
+            Json(twice_a_week()),
+        )
+        .await
+        .unwrap();
+
+        let statuses = || async {
+            let Json(after) = get_appointment_series(
+                Path(series.series.id.clone()),
+                Extension(pool.clone()),
+                Extension(test_config()),
+                auth_headers("physio-001", "physiotherapist"),
+            )
+            .await
+            .unwrap();
+            after.appointments.into_iter().map(|a| a.status).collect::<Vec<_>>()
+        };
+
+        let err = update_appointment_status(
+            Path(series.appointments[4].id.clone()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(serde_json::json!({ "status": "cancelled", "scope": "everything" })),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+
+        update_appointment_status(
+            Path(series.appointments[4].id.clone()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(serde_json::json!({ "status": "cancelled", "scope": "following" })),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            statuses().await,
+            ["scheduled", "scheduled", "scheduled", "scheduled", "cancelled", "cancelled"]
+        );
+
+        // "all" otkazuje i termine pre izabranog
+        update_appointment_status(
+            Path(series.appointments[2].id.clone()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(serde_json::json!({ "status": "cancelled", "scope": "all" })),
+        )
+        .await
+        .unwrap();
+        assert!(statuses().await.iter().all(|s| s == "cancelled"));
+    }
+
+    #[tokio::test]
+    async fn cancel_this_and_following_leaves_earlier_occurrences() {
+        let pool = test_pool().await;
+        let (_, Json(series)) = create_appointment_series(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-001", "patient"),
+            Json(twice_a_week()),
+        )
+        .await
+        .unwrap();
+
+        let third = &series.appointments[2];
+        let Json(cancelled) = cancel_appointment(
+            Path(third.id.clone()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-001", "patient"),
+            Some(Json(CancelAppointmentRequest { reason: None, scope: SeriesScope::Following })),
+        )
+        .await
+        .unwrap();
+        assert_eq!(cancelled.status, "cancelled");
+
+        let Json(after) = get_appointment_series(
+            Path(series.series.id.clone()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("physio-001", "physiotherapist"),
+        )
+        .await
+        .unwrap();
+        let statuses: Vec<&str> = after.appointments.iter().map(|a| a.status.as_str()).collect();
+        assert_eq!(statuses, ["scheduled", "scheduled", "cancelled", "cancelled", "cancelled", "cancelled"]);
+    }
+
+    #[tokio::test]
+    async fn reschedule_this_and_following_shifts_later_occurrences() {
+        let pool = test_pool().await;
+        let (_, Json(series)) = create_appointment_series(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-001", "patient"),
+            Json(twice_a_week()),
+        )
+        .await
+        .unwrap();
+
+        // Ponedeljak 21.01. 09:00 -> utorak 22.01. 10:00, i svi naredni dan kasnije u 10:00
+        let third = &series.appointments[2];
+        let (_, Json(resp)) = reschedule_appointment(
+            Path(third.id.clone()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-001", "patient"),
+            Json(RescheduleAppointmentRequest {
+                appointment_date: "2030-01-22".to_string(),
+                start_time: "10:00".to_string(),
+                scope: SeriesScope::Following,
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.following.len(), 3);
+
+        let Json(after) = get_appointment_series(
+            Path(series.series.id.clone()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-001", "patient"),
+        )
+        .await
+        .unwrap();
+        let times: Vec<String> = after.appointments.iter()
+            .map(|a| format!("{} {}", a.appointment_date, a.start_time))
+            .collect();
+        assert_eq!(
+            times,
+            [
+                "2030-01-14 09:00", "2030-01-17 09:00", "2030-01-22 10:00",
+                "2030-01-25 10:00", "2030-01-29 10:00", "2030-02-01 10:00",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn series_is_private_to_participants() {
+        let pool = test_pool().await;
+
+        let err = preview_appointment_series(
+            Extension(pool.clone()),
+            auth_headers("someone-else", "patient"),
+            Json(twice_a_week()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+
+        let (_, Json(series)) = create_appointment_series(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(twice_a_week()),
+        )
+        .await
+        .unwrap();
+
+        let err = get_appointment_series(
+            Path(series.series.id.clone()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("someone-else", "patient"),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+    }
+}
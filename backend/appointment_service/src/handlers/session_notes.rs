@@ -0,0 +1,711 @@
+use axum::{
+    http::{StatusCode, HeaderMap},
+    Json,
+    extract::Path,
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use chrono::Utc;
+
+use crate::utils::Claims;
+use super::{
+    authenticate, bad_request, database_error, fetch_appointment, forbidden, AppointmentResponse,
+    ErrorResponse,
+};
+
+const MAX_NOTE_ENTRIES: usize = 100;
+
+#[derive(Debug, Clone, Deserialize, Serialize, sqlx::FromRow)]
+pub struct SessionNoteEntry {
+    pub body_region: String,
+    pub side: Option<String>,        // left, right, bilateral
+    pub measurement: Option<String>, // npr. flexion
+    pub value: Option<f64>,
+    pub unit: Option<String>,        // npr. deg
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateSessionNoteRequest {
+    pub subjective: Option<String>,
+    pub objective: Option<String>,
+    pub assessment: Option<String>,
+    pub plan: Option<String>,
+    #[serde(default)]
+    pub entries: Vec<SessionNoteEntry>,
+    pub patient_summary: Option<String>,
+    #[serde(default)]
+    pub publish_summary: bool,
+}
+
+// Dopuna: izostavljena polja se preuzimaju iz poslednje verzije, a polja navedena
+// u clear se u novoj verziji brišu
+#[derive(Debug, Deserialize)]
+pub struct AmendSessionNoteRequest {
+    pub subjective: Option<String>,
+    pub objective: Option<String>,
+    pub assessment: Option<String>,
+    pub plan: Option<String>,
+    pub entries: Option<Vec<SessionNoteEntry>>,
+    pub patient_summary: Option<String>,
+    pub publish_summary: Option<bool>,
+    pub amendment_reason: Option<String>,
+    #[serde(default)]
+    pub clear: Vec<String>, // subjective, objective, assessment, plan, patient_summary
+}
+
+const CLEARABLE_FIELDS: [&str; 5] = ["subjective", "objective", "assessment", "plan", "patient_summary"];
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SessionNote {
+    pub id: String,
+    pub appointment_id: String,
+    pub version: i64,
+    pub author_id: String,
+    pub subjective: Option<String>,
+    pub objective: Option<String>,
+    pub assessment: Option<String>,
+    pub plan: Option<String>,
+    pub amendment_reason: Option<String>,
+    pub patient_summary: Option<String>,
+    pub summary_published: bool,
+    pub created_at: String,
+    #[sqlx(skip)]
+    pub entries: Vec<SessionNoteEntry>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SessionNoteVersion {
+    pub version: i64,
+    pub author_id: String,
+    pub amendment_reason: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionNoteResponse {
+    pub note: SessionNote,
+    pub versions: Vec<SessionNoteVersion>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PatientSummaryResponse {
+    pub appointment_id: String,
+    pub appointment_date: String,
+    pub physiotherapist_id: String,
+    pub summary: String,
+    pub published_at: String,
+}
+
+const NOTE_COLUMNS: &str = r#"
+    id, appointment_id, version, author_id, subjective, objective, assessment, plan,
+    amendment_reason, patient_summary, summary_published, created_at
+"#;
+
+// Beleške čitaju i pišu samo fizioterapeut sa termina i admin
+fn ensure_treating(claims: &Claims, appointment: &AppointmentResponse) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if claims.role == "admin" || claims.sub == appointment.physiotherapist_id {
+        return Ok(());
+    }
+    Err(forbidden())
+}
+
+// Vrednost polja u dopuni: nova, obrisana ili preuzeta iz poslednje verzije
+fn amended(
+    clear: &[String],
+    field: &str,
+    new: Option<String>,
+    latest: Option<String>,
+) -> Result<Option<String>, (StatusCode, Json<ErrorResponse>)> {
+    if !clear.iter().any(|f| f == field) {
+        return Ok(new.or(latest));
+    }
+    if new.is_some() {
+        return Err(bad_request(&format!("{} cannot be both set and cleared", field)));
+    }
+    Ok(None)
+}
+
+fn validate_entries(entries: &[SessionNoteEntry]) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if entries.len() > MAX_NOTE_ENTRIES {
+        return Err(bad_request(&format!("A note can have at most {} entries", MAX_NOTE_ENTRIES)));
+    }
+    for entry in entries {
+        if entry.body_region.trim().is_empty() {
+            return Err(bad_request("body_region is required"));
+        }
+        if let Some(side) = &entry.side {
+            if !["left", "right", "bilateral"].contains(&side.as_str()) {
+                return Err(bad_request("side must be left, right or bilateral"));
+            }
+        }
+        if entry.value.is_some() && entry.measurement.is_none() {
+            return Err(bad_request("A value needs a measurement name"));
+        }
+    }
+    Ok(())
+}
+
+async fn latest_note(
+    conn: &mut SqliteConnection,
+    appointment_id: &str,
+) -> Result<Option<SessionNote>, (StatusCode, Json<ErrorResponse>)> {
+    let note = sqlx::query_as::<_, SessionNote>(
+        &format!(
+            "SELECT {} FROM session_notes WHERE appointment_id = ? ORDER BY version DESC LIMIT 1",
+            NOTE_COLUMNS
+        )
+    )
+    .bind(appointment_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    match note {
+        Some(note) => Ok(Some(with_entries(conn, note).await?)),
+        None => Ok(None),
+    }
+}
+
+async fn with_entries(
+    conn: &mut SqliteConnection,
+    mut note: SessionNote,
+) -> Result<SessionNote, (StatusCode, Json<ErrorResponse>)> {
+    note.entries = sqlx::query_as::<_, SessionNoteEntry>(
+        r#"
+        SELECT body_region, side, measurement, value, unit, comment
+        FROM session_note_entries
+        WHERE note_id = ?
+        ORDER BY position ASC
+        "#
+    )
+    .bind(&note.id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(database_error)?;
+    Ok(note)
+}
+
+async fn note_versions(
+    conn: &mut SqliteConnection,
+    appointment_id: &str,
+) -> Result<Vec<SessionNoteVersion>, (StatusCode, Json<ErrorResponse>)> {
+    sqlx::query_as::<_, SessionNoteVersion>(
+        r#"
+        SELECT version, author_id, amendment_reason, created_at
+        FROM session_notes
+        WHERE appointment_id = ?
+        ORDER BY version ASC
+        "#
+    )
+    .bind(appointment_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(database_error)
+}
+
+// Upiši novu verziju; stare verzije se nikad ne menjaju
+#[allow(clippy::too_many_arguments)]
+async fn insert_note_version(
+    conn: &mut SqliteConnection,
+    appointment_id: &str,
+    version: i64,
+    author_id: &str,
+    soap: [Option<String>; 4],
+    entries: &[SessionNoteEntry],
+    patient_summary: Option<String>,
+    summary_published: bool,
+    amendment_reason: Option<String>,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    let [subjective, objective, assessment, plan] = soap;
+    let note_id = uuid::Uuid::new_v4().to_string();
+
+    sqlx::query(
+        r#"
+        INSERT INTO session_notes (
+            id, appointment_id, version, author_id, subjective, objective, assessment, plan,
+            amendment_reason, patient_summary, summary_published, created_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&note_id)
+    .bind(appointment_id)
+    .bind(version)
+    .bind(author_id)
+    .bind(subjective)
+    .bind(objective)
+    .bind(assessment)
+    .bind(plan)
+    .bind(amendment_reason)
+    .bind(patient_summary)
+    .bind(summary_published)
+    .bind(Utc::now().to_rfc3339())
+    .execute(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    for (position, entry) in entries.iter().enumerate() {
+        sqlx::query(
+            r#"
+            INSERT INTO session_note_entries (
+                id, note_id, body_region, side, measurement, value, unit, comment, position
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(&note_id)
+        .bind(entry.body_region.trim())
+        .bind(&entry.side)
+        .bind(&entry.measurement)
+        .bind(entry.value)
+        .bind(&entry.unit)
+        .bind(&entry.comment)
+        .bind(position as i64)
+        .execute(&mut *conn)
+        .await
+        .map_err(database_error)?;
+    }
+
+    Ok(())
+}
+
+async fn note_response(
+    conn: &mut SqliteConnection,
+    appointment_id: &str,
+) -> Result<SessionNoteResponse, (StatusCode, Json<ErrorResponse>)> {
+    let note = latest_note(conn, appointment_id).await?.ok_or_else(note_not_found)?;
+    let versions = note_versions(conn, appointment_id).await?;
+    Ok(SessionNoteResponse { note, versions })
+}
+
+fn note_not_found() -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse { error: "Session note not found".to_string() })
+    )
+}
+
+// Prva verzija beleške za obavljen termin
+pub async fn create_session_note(
+    Path(appointment_id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+    Json(req): Json<CreateSessionNoteRequest>,
+) -> Result<(StatusCode, Json<SessionNoteResponse>), (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+    validate_entries(&req.entries)?;
+    if req.publish_summary && req.patient_summary.is_none() {
+        return Err(bad_request("patient_summary is required to publish a summary"));
+    }
+
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE")
+        .await
+        .map_err(database_error)?;
+
+    let appointment = fetch_appointment(&mut tx, &appointment_id).await?;
+    ensure_treating(&claims, &appointment)?;
+    if appointment.status != "completed" {
+        return Err(bad_request("Notes can only be written for completed appointments"));
+    }
+    if latest_note(&mut tx, &appointment_id).await?.is_some() {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse { error: "A note already exists; add an amendment instead".to_string() })
+        ));
+    }
+
+    insert_note_version(
+        &mut tx,
+        &appointment_id,
+        1,
+        &claims.sub,
+        [req.subjective, req.objective, req.assessment, req.plan],
+        &req.entries,
+        req.patient_summary,
+        req.publish_summary,
+        None,
+    )
+    .await?;
+
+    let response = note_response(&mut tx, &appointment_id).await?;
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!("Session note created for appointment {}", appointment_id);
+
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+// Dopuna dodaje novu verziju na osnovu poslednje
+pub async fn amend_session_note(
+    Path(appointment_id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+    Json(req): Json<AmendSessionNoteRequest>,
+) -> Result<(StatusCode, Json<SessionNoteResponse>), (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+    if let Some(entries) = &req.entries {
+        validate_entries(entries)?;
+    }
+    if let Some(field) = req.clear.iter().find(|f| !CLEARABLE_FIELDS.contains(&f.as_str())) {
+        return Err(bad_request(&format!("{} cannot be cleared", field)));
+    }
+
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE")
+        .await
+        .map_err(database_error)?;
+
+    let appointment = fetch_appointment(&mut tx, &appointment_id).await?;
+    ensure_treating(&claims, &appointment)?;
+    let latest = latest_note(&mut tx, &appointment_id).await?.ok_or_else(note_not_found)?;
+
+    let patient_summary = amended(&req.clear, "patient_summary", req.patient_summary, latest.patient_summary)?;
+    let summary_published = req.publish_summary.unwrap_or(latest.summary_published);
+    if summary_published && patient_summary.is_none() {
+        return Err(bad_request("patient_summary is required to publish a summary"));
+    }
+
+    insert_note_version(
+        &mut tx,
+        &appointment_id,
+        latest.version + 1,
+        &claims.sub,
+        [
+            amended(&req.clear, "subjective", req.subjective, latest.subjective)?,
+            amended(&req.clear, "objective", req.objective, latest.objective)?,
+            amended(&req.clear, "assessment", req.assessment, latest.assessment)?,
+            amended(&req.clear, "plan", req.plan, latest.plan)?,
+        ],
+        req.entries.as_deref().unwrap_or(&latest.entries),
+        patient_summary,
+        summary_published,
+        req.amendment_reason,
+    )
+    .await?;
+
+    let response = note_response(&mut tx, &appointment_id).await?;
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!(
+        "Session note for appointment {} amended to version {}",
+        appointment_id,
+        response.note.version
+    );
+
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+pub async fn get_session_note(
+    Path(appointment_id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+) -> Result<Json<SessionNoteResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+    let appointment = fetch_appointment(&mut conn, &appointment_id).await?;
+    ensure_treating(&claims, &appointment)?;
+
+    Ok(Json(note_response(&mut conn, &appointment_id).await?))
+}
+
+pub async fn get_session_note_version(
+    Path((appointment_id, version)): Path<(String, i64)>,
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+) -> Result<Json<SessionNote>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+    let appointment = fetch_appointment(&mut conn, &appointment_id).await?;
+    ensure_treating(&claims, &appointment)?;
+
+    let note = sqlx::query_as::<_, SessionNote>(
+        &format!("SELECT {} FROM session_notes WHERE appointment_id = ? AND version = ?", NOTE_COLUMNS)
+    )
+    .bind(&appointment_id)
+    .bind(version)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(note_not_found)?;
+
+    Ok(Json(with_entries(&mut conn, note).await?))
+}
+
+// Pacijent vidi samo objavljeni sažetak iz poslednje verzije
+pub async fn get_session_summary(
+    Path(appointment_id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+) -> Result<Json<PatientSummaryResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+    let appointment = fetch_appointment(&mut conn, &appointment_id).await?;
+    if claims.sub != appointment.patient_id {
+        ensure_treating(&claims, &appointment)?;
+    }
+
+    let note = latest_note(&mut conn, &appointment_id).await?;
+    match note {
+        Some(SessionNote { patient_summary: Some(summary), summary_published: true, created_at, .. }) => {
+            Ok(Json(PatientSummaryResponse {
+                appointment_id,
+                appointment_date: appointment.appointment_date,
+                physiotherapist_id: appointment.physiotherapist_id,
+                summary,
+                published_at: created_at,
+            }))
+        }
+        _ => Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: "No published summary for this appointment".to_string() })
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::{create_appointment, CreateAppointmentRequest};
+    use crate::test_support::{auth_headers, test_config, test_pool};
+
+    async fn completed_appointment(pool: &SqlitePool) -> String {
+        let Json(appointment) = create_appointment(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-001", "patient"),
+            Json(CreateAppointmentRequest {
+                patient_id: "patient-001".to_string(),
+                physiotherapist_id: "physio-001".to_string(),
+                appointment_date: "2030-01-14".to_string(),
+                start_time: "10:00".to_string(),
+                appointment_type_id: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        sqlx::query("UPDATE appointments SET status = 'completed' WHERE id = ?")
+            .bind(&appointment.id)
+            .execute(pool)
+            .await
+            .unwrap();
+        appointment.id
+    }
+
+    fn knee_flexion(value: f64) -> SessionNoteEntry {
+        SessionNoteEntry {
+            body_region: "knee".to_string(),
+            side: Some("left".to_string()),
+            measurement: Some("flexion".to_string()),
+            value: Some(value),
+            unit: Some("deg".to_string()),
+            comment: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn amendments_keep_earlier_versions() {
+        let pool = test_pool().await;
+        let id = completed_appointment(&pool).await;
+
+        let (status, _) = create_session_note(
+            Path(id.clone()),
+            Extension(pool.clone()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(CreateSessionNoteRequest {
+                subjective: Some("Bol pri penjanju uz stepenice".to_string()),
+                objective: Some("Otok oko patele".to_string()),
+                assessment: None,
+                plan: Some("Vežbe snage kvadricepsa".to_string()),
+                entries: vec![knee_flexion(95.0)],
+                patient_summary: None,
+                publish_summary: false,
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+
+        let (_, Json(amended)) = amend_session_note(
+            Path(id.clone()),
+            Extension(pool.clone()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(AmendSessionNoteRequest {
+                subjective: None,
+                objective: None,
+                assessment: Some("Napredak".to_string()),
+                plan: None,
+                entries: Some(vec![knee_flexion(110.0)]),
+                patient_summary: Some("Nastavite sa vežbama kod kuće".to_string()),
+                publish_summary: Some(true),
+                amendment_reason: Some("Ispravljeno merenje".to_string()),
+                clear: Vec::new(),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(amended.note.version, 2);
+        assert_eq!(amended.note.plan.as_deref(), Some("Vežbe snage kvadricepsa"));
+        assert_eq!(amended.note.entries[0].value, Some(110.0));
+        assert_eq!(amended.versions.len(), 2);
+
+        let Json(original) = get_session_note_version(
+            Path((id.clone(), 1)),
+            Extension(pool.clone()),
+            auth_headers("physio-001", "physiotherapist"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(original.entries[0].value, Some(95.0));
+        assert!(original.assessment.is_none());
+
+        // Baza ne dozvoljava izmenu ni brisanje postojeće verzije, ni brisanje termina sa beleškom
+        let overwrite = sqlx::query("UPDATE session_notes SET plan = 'x' WHERE version = 1")
+            .execute(&pool)
+            .await;
+        assert!(overwrite.is_err());
+        for delete in [
+            "DELETE FROM session_note_entries",
+            "DELETE FROM session_notes WHERE version = 1",
+            "DELETE FROM appointments",
+        ] {
+            assert!(sqlx::query(delete).execute(&pool).await.is_err(), "{}", delete);
+        }
+        let versions: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM session_notes")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(versions, 2);
+    }
+
+    #[tokio::test]
+    async fn amendment_can_clear_fields() {
+        let pool = test_pool().await;
+        let id = completed_appointment(&pool).await;
+
+        let (_, _) = create_session_note(
+            Path(id.clone()),
+            Extension(pool.clone()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(CreateSessionNoteRequest {
+                subjective: Some("Bol u ramenu".to_string()),
+                objective: None,
+                assessment: Some("Pogrešan pacijent".to_string()),
+                plan: Some("Istezanje".to_string()),
+                entries: Vec::new(),
+                patient_summary: None,
+                publish_summary: false,
+            }),
+        )
+        .await
+        .unwrap();
+
+        let amendment = |clear: &[&str], assessment: Option<&str>| AmendSessionNoteRequest {
+            subjective: None,
+            objective: None,
+            assessment: assessment.map(str::to_string),
+            plan: None,
+            entries: None,
+            patient_summary: None,
+            publish_summary: None,
+            amendment_reason: Some("Procena uneta greškom".to_string()),
+            clear: clear.iter().map(|f| f.to_string()).collect(),
+        };
+
+        for (clear, assessment) in [(&["assessment"][..], Some("Nova")), (&["author_id"][..], None)] {
+            let err = amend_session_note(
+                Path(id.clone()),
+                Extension(pool.clone()),
+                auth_headers("physio-001", "physiotherapist"),
+                Json(amendment(clear, assessment)),
+            )
+            .await
+            .unwrap_err();
+            assert_eq!(err.0, StatusCode::BAD_REQUEST);
+        }
+
+        let (_, Json(amended)) = amend_session_note(
+            Path(id.clone()),
+            Extension(pool.clone()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(amendment(&["assessment"], None)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(amended.note.version, 2);
+        assert!(amended.note.assessment.is_none());
+        assert_eq!(amended.note.plan.as_deref(), Some("Istezanje"));
+    }
+
+    #[tokio::test]
+    async fn patient_sees_only_the_published_summary() {
+        let pool = test_pool().await;
+        let id = completed_appointment(&pool).await;
+
+        let (_, _) = create_session_note(
+            Path(id.clone()),
+            Extension(pool.clone()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(CreateSessionNoteRequest {
+                subjective: None,
+                objective: None,
+                assessment: Some("Interna procena".to_string()),
+                plan: None,
+                entries: Vec::new(),
+                patient_summary: Some("Dobar napredak".to_string()),
+                publish_summary: false,
+            }),
+        )
+        .await
+        .unwrap();
+
+        let err = get_session_note(
+            Path(id.clone()),
+            Extension(pool.clone()),
+            auth_headers("patient-001", "patient"),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+
+        let err = get_session_summary(
+            Path(id.clone()),
+            Extension(pool.clone()),
+            auth_headers("patient-001", "patient"),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::NOT_FOUND);
+
+        let (_, _) = amend_session_note(
+            Path(id.clone()),
+            Extension(pool.clone()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(AmendSessionNoteRequest {
+                subjective: None,
+                objective: None,
+                assessment: None,
+                plan: None,
+                entries: None,
+                patient_summary: None,
+                publish_summary: Some(true),
+                amendment_reason: None,
+                clear: Vec::new(),
+            }),
+        )
+        .await
+        .unwrap();
+
+        let Json(summary) = get_session_summary(
+            Path(id.clone()),
+            Extension(pool.clone()),
+            auth_headers("patient-001", "patient"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(summary.summary, "Dobar napredak");
+    }
+}
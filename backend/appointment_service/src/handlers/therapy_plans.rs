@@ -0,0 +1,881 @@
+use axum::{
+    http::{StatusCode, HeaderMap},
+    Json,
+    extract::{Path, Query},
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use chrono::{Datelike, NaiveDate, Utc, Weekday};
+
+use crate::config::Config;
+use crate::utils::Claims;
+use super::{
+    authenticate, bad_request, database_error, forbidden, user_timezone, weekday_code, ErrorResponse,
+};
+
+// Najduži plan i najviše vežbi u jednom planu
+const MAX_PLAN_DAYS: i64 = 366;
+const MAX_PLAN_ITEMS: usize = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct TherapyPlanItemRequest {
+    // Postojeća stavka koja se menja; bez id-ja se dodaje nova
+    pub id: Option<String>,
+    pub exercise_id: i64,
+    pub weekdays: Vec<String>,       // "mon", "thursday", ...
+    pub sets: i64,
+    pub reps: i64,
+    pub hold_seconds: Option<i64>,   // podrazumevano 0
+    pub times_per_day: Option<i64>,  // podrazumevano 1
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TherapyPlanRequest {
+    pub patient_id: String,
+    // Fizioterapeut propisuje u svoje ime; admin mora da navede fizioterapeuta
+    pub physiotherapist_id: Option<String>,
+    pub title: String,
+    pub goals: Option<String>,
+    pub start_date: String, // YYYY-MM-DD
+    pub end_date: String,   // YYYY-MM-DD, inclusive
+    pub items: Vec<TherapyPlanItemRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TherapyPlansQuery {
+    pub patient_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TherapyPlanTodayQuery {
+    pub patient_id: Option<String>,
+    pub date: Option<String>, // YYYY-MM-DD, podrazumevano danas u zoni pacijenta
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TherapyPlan {
+    pub id: String,
+    pub patient_id: String,
+    pub physiotherapist_id: String,
+    pub title: String,
+    pub goals: Option<String>,
+    pub start_date: String,
+    pub end_date: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TherapyPlanItem {
+    pub id: String,
+    pub plan_id: String,
+    pub exercise_id: i64,
+    pub weekdays: String,
+    pub sets: i64,
+    pub reps: i64,
+    pub hold_seconds: i64,
+    pub times_per_day: i64,
+    pub notes: Option<String>,
+    pub position: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TherapyPlanResponse {
+    pub plan: TherapyPlan,
+    pub items: Vec<TherapyPlanItem>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TherapyPlansResponse {
+    pub plans: Vec<TherapyPlanResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TodayExercise {
+    pub plan_id: String,
+    pub plan_title: String,
+    pub item_id: String,
+    pub exercise_id: i64,
+    pub sets: i64,
+    pub reps: i64,
+    pub hold_seconds: i64,
+    pub times_per_day: i64,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TherapyPlanTodayResponse {
+    pub patient_id: String,
+    pub date: String,
+    pub weekday: String,
+    pub exercises: Vec<TodayExercise>,
+}
+
+const PLAN_COLUMNS: &str =
+    "id, patient_id, physiotherapist_id, title, goals, start_date, end_date, created_at, updated_at";
+
+const PLAN_ITEM_COLUMNS: &str =
+    "id, plan_id, exercise_id, weekdays, sets, reps, hold_seconds, times_per_day, notes, position";
+
+pub(crate) fn parse_date(value: &str, field: &str) -> Result<NaiveDate, (StatusCode, Json<ErrorResponse>)> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| bad_request(&format!("Invalid {} format", field)))
+}
+
+// Dani u nedelji iz zahteva u obliku koji se čuva: "mon,wed,fri"
+fn normalize_weekdays(days: &[String]) -> Result<String, (StatusCode, Json<ErrorResponse>)> {
+    let mut weekdays = Vec::new();
+    for day in days {
+        let weekday = day.parse::<Weekday>()
+            .map_err(|_| bad_request(&format!("Invalid weekday: {}", day)))?;
+        if !weekdays.contains(&weekday) {
+            weekdays.push(weekday);
+        }
+    }
+    if weekdays.is_empty() {
+        return Err(bad_request("Each plan item needs at least one weekday"));
+    }
+
+    weekdays.sort_by_key(|d| d.num_days_from_monday());
+    Ok(weekdays.into_iter().map(weekday_code).collect::<Vec<_>>().join(","))
+}
+
+fn validate_plan(req: &TherapyPlanRequest) -> Result<Vec<String>, (StatusCode, Json<ErrorResponse>)> {
+    if req.title.trim().is_empty() {
+        return Err(bad_request("Title is required"));
+    }
+
+    let start = parse_date(&req.start_date, "start_date")?;
+    let end = parse_date(&req.end_date, "end_date")?;
+    if end < start || (end - start).num_days() >= MAX_PLAN_DAYS {
+        return Err(bad_request("end_date must be after start_date and within a year"));
+    }
+
+    if req.items.is_empty() || req.items.len() > MAX_PLAN_ITEMS {
+        return Err(bad_request(&format!("A plan must have between 1 and {} items", MAX_PLAN_ITEMS)));
+    }
+
+    let mut weekdays = Vec::new();
+    for item in &req.items {
+        if item.sets < 1 || item.reps < 1 {
+            return Err(bad_request("sets and reps must be at least 1"));
+        }
+        if item.hold_seconds.is_some_and(|s| s < 0) {
+            return Err(bad_request("hold_seconds cannot be negative"));
+        }
+        if item.times_per_day.is_some_and(|n| n < 1) {
+            return Err(bad_request("times_per_day must be at least 1"));
+        }
+        weekdays.push(normalize_weekdays(&item.weekdays)?);
+    }
+
+    Ok(weekdays)
+}
+
+// Samo fizioterapeut i admin propisuju planove
+fn ensure_prescriber(claims: &Claims) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if claims.role != "physiotherapist" && claims.role != "admin" {
+        return Err(forbidden());
+    }
+    Ok(())
+}
+
+// Fizioterapeut menja samo svoje planove; admin sve
+fn ensure_plan_owner(claims: &Claims, plan: &TherapyPlan) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    ensure_prescriber(claims)?;
+    if claims.role != "admin" && claims.sub != plan.physiotherapist_id {
+        return Err(forbidden());
+    }
+    Ok(())
+}
+
+pub(crate) fn ensure_plan_viewer(claims: &Claims, plan: &TherapyPlan) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if claims.role == "admin"
+        || claims.sub == plan.patient_id
+        || claims.sub == plan.physiotherapist_id
+    {
+        return Ok(());
+    }
+    Err(forbidden())
+}
+
+async fn ensure_user_role(
+    conn: &mut SqliteConnection,
+    user_id: &str,
+    role: &str,
+    field: &str,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    let exists: Option<String> = sqlx::query_scalar("SELECT id FROM users WHERE id = ? AND role = ?")
+        .bind(user_id)
+        .bind(role)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(database_error)?;
+
+    if exists.is_none() {
+        return Err(bad_request(&format!("{} does not belong to a {}", field, role)));
+    }
+    Ok(())
+}
+
+// Pacijent, fizioterapeut i vežbe iz plana moraju postojati; tabela exercises je
+// u istoj bazi, a vodi je exercise_service
+async fn ensure_plan_references(
+    conn: &mut SqliteConnection,
+    req: &TherapyPlanRequest,
+    physiotherapist_id: &str,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    ensure_user_role(conn, &req.patient_id, "patient", "patient_id").await?;
+    ensure_user_role(conn, physiotherapist_id, "physiotherapist", "physiotherapist_id").await?;
+
+    for item in &req.items {
+        let exists: Option<i64> = sqlx::query_scalar("SELECT id FROM exercises WHERE id = ?")
+            .bind(item.exercise_id)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(database_error)?;
+        if exists.is_none() {
+            return Err(bad_request(&format!("Unknown exercise: {}", item.exercise_id)));
+        }
+    }
+    Ok(())
+}
+
+pub(crate) async fn fetch_plan_row(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<TherapyPlan, (StatusCode, Json<ErrorResponse>)> {
+    sqlx::query_as::<_, TherapyPlan>(&format!("SELECT {} FROM therapy_plans WHERE id = ?", PLAN_COLUMNS))
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: "Therapy plan not found".to_string() })
+        ))
+}
+
+pub(crate) async fn fetch_plan_items(
+    conn: &mut SqliteConnection,
+    plan_id: &str,
+) -> Result<Vec<TherapyPlanItem>, (StatusCode, Json<ErrorResponse>)> {
+    sqlx::query_as::<_, TherapyPlanItem>(
+        &format!("SELECT {} FROM therapy_plan_items WHERE plan_id = ? ORDER BY position ASC", PLAN_ITEM_COLUMNS)
+    )
+    .bind(plan_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(database_error)
+}
+
+async fn fetch_plan(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<TherapyPlanResponse, (StatusCode, Json<ErrorResponse>)> {
+    let plan = fetch_plan_row(conn, id).await?;
+    let items = fetch_plan_items(conn, id).await?;
+    Ok(TherapyPlanResponse { plan, items })
+}
+
+// Upiši stavke plana redom. Stavke sa id-jem se menjaju na mestu i zadržavaju id,
+// stavke bez id-ja se dodaju, a stavke plana koje nisu navedene se brišu.
+async fn save_plan_items(
+    conn: &mut SqliteConnection,
+    plan_id: &str,
+    items: &[TherapyPlanItemRequest],
+    weekdays: &[String],
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    let existing: Vec<String> = sqlx::query_scalar("SELECT id FROM therapy_plan_items WHERE plan_id = ?")
+        .bind(plan_id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(database_error)?;
+
+    let mut kept: Vec<&str> = Vec::new();
+    for id in items.iter().filter_map(|item| item.id.as_deref()) {
+        if !existing.iter().any(|e| e == id) {
+            return Err(bad_request(&format!("Unknown plan item: {}", id)));
+        }
+        if kept.contains(&id) {
+            return Err(bad_request(&format!("Plan item listed twice: {}", id)));
+        }
+        kept.push(id);
+    }
+
+    for id in existing.iter().filter(|id| !kept.contains(&id.as_str())) {
+        sqlx::query("DELETE FROM therapy_plan_items WHERE id = ?")
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .map_err(database_error)?;
+    }
+
+    for (position, (item, weekdays)) in items.iter().zip(weekdays).enumerate() {
+        let query = match &item.id {
+            Some(_) => r#"
+                UPDATE therapy_plan_items
+                SET exercise_id = ?, weekdays = ?, sets = ?, reps = ?,
+                    hold_seconds = ?, times_per_day = ?, notes = ?, position = ?
+                WHERE id = ? AND plan_id = ?
+                "#,
+            None => r#"
+                INSERT INTO therapy_plan_items (
+                    exercise_id, weekdays, sets, reps,
+                    hold_seconds, times_per_day, notes, position, id, plan_id
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+        };
+
+        sqlx::query(query)
+            .bind(item.exercise_id)
+            .bind(weekdays)
+            .bind(item.sets)
+            .bind(item.reps)
+            .bind(item.hold_seconds.unwrap_or(0))
+            .bind(item.times_per_day.unwrap_or(1))
+            .bind(&item.notes)
+            .bind(position as i64)
+            .bind(item.id.clone().unwrap_or_else(|| uuid::Uuid::new_v4().to_string()))
+            .bind(plan_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(database_error)?;
+    }
+    Ok(())
+}
+
+// Fizioterapeut iz zahteva: svoj id, a admin ga navodi
+fn prescribing_physiotherapist(
+    claims: &Claims,
+    req: &TherapyPlanRequest,
+) -> Result<String, (StatusCode, Json<ErrorResponse>)> {
+    match (claims.role.as_str(), &req.physiotherapist_id) {
+        ("admin", Some(physiotherapist_id)) => Ok(physiotherapist_id.clone()),
+        ("admin", None) => Err(bad_request("physiotherapist_id is required")),
+        (_, Some(physiotherapist_id)) if physiotherapist_id != &claims.sub => Err(forbidden()),
+        _ => Ok(claims.sub.clone()),
+    }
+}
+
+pub async fn create_therapy_plan(
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+    Json(req): Json<TherapyPlanRequest>,
+) -> Result<(StatusCode, Json<TherapyPlanResponse>), (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+    ensure_prescriber(&claims)?;
+    let physiotherapist_id = prescribing_physiotherapist(&claims, &req)?;
+    let weekdays = validate_plan(&req)?;
+
+    let mut tx = pool.begin().await.map_err(database_error)?;
+    ensure_plan_references(&mut tx, &req, &physiotherapist_id).await?;
+
+    let plan_id = uuid::Uuid::new_v4().to_string();
+    let now = Utc::now().to_rfc3339();
+
+    sqlx::query(
+        r#"
+        INSERT INTO therapy_plans (
+            id, patient_id, physiotherapist_id, title, goals,
+            start_date, end_date, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&plan_id)
+    .bind(&req.patient_id)
+    .bind(&physiotherapist_id)
+    .bind(req.title.trim())
+    .bind(&req.goals)
+    .bind(&req.start_date)
+    .bind(&req.end_date)
+    .bind(&now)
+    .bind(&now)
+    .execute(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    save_plan_items(&mut tx, &plan_id, &req.items, &weekdays).await?;
+
+    let response = fetch_plan(&mut tx, &plan_id).await?;
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!("Therapy plan {} created for patient {}", plan_id, req.patient_id);
+
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+// Pacijent vidi svoje planove, fizioterapeut one koje je propisao, admin sve
+pub async fn get_therapy_plans(
+    Extension(pool): Extension<SqlitePool>,
+    Query(query): Query<TherapyPlansQuery>,
+    headers: HeaderMap,
+) -> Result<Json<TherapyPlansResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+    let rows = sqlx::query_as::<_, TherapyPlan>(
+        &format!(
+            r#"
+            SELECT {} FROM therapy_plans
+            WHERE (? = 'admin' OR patient_id = ? OR physiotherapist_id = ?)
+            AND (? IS NULL OR patient_id = ?)
+            ORDER BY start_date DESC, created_at DESC
+            "#,
+            PLAN_COLUMNS
+        )
+    )
+    .bind(&claims.role)
+    .bind(&claims.sub)
+    .bind(&claims.sub)
+    .bind(&query.patient_id)
+    .bind(&query.patient_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    let mut plans = Vec::with_capacity(rows.len());
+    for plan in rows {
+        let items = fetch_plan_items(&mut conn, &plan.id).await?;
+        plans.push(TherapyPlanResponse { plan, items });
+    }
+
+    Ok(Json(TherapyPlansResponse { plans }))
+}
+
+pub async fn get_therapy_plan(
+    Path(id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+) -> Result<Json<TherapyPlanResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+    let response = fetch_plan(&mut conn, &id).await?;
+    ensure_plan_viewer(&claims, &response.plan)?;
+
+    Ok(Json(response))
+}
+
+// Izmena zamenjuje ceo plan; stavke se usklađuju po id-ju, pa navedene zadržavaju svoj id
+pub async fn update_therapy_plan(
+    Path(id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+    Json(req): Json<TherapyPlanRequest>,
+) -> Result<Json<TherapyPlanResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+    let weekdays = validate_plan(&req)?;
+
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE")
+        .await
+        .map_err(database_error)?;
+
+    let plan = fetch_plan_row(&mut tx, &id).await?;
+    ensure_plan_owner(&claims, &plan)?;
+    // Admin bez physiotherapist_id zadržava postojećeg fizioterapeuta
+    let physiotherapist_id = match (claims.role.as_str(), &req.physiotherapist_id) {
+        ("admin", None) => plan.physiotherapist_id.clone(),
+        _ => prescribing_physiotherapist(&claims, &req)?,
+    };
+    ensure_plan_references(&mut tx, &req, &physiotherapist_id).await?;
+
+    // Vežbe koje je pacijent već upisao ostaju vezane za njega
+    if req.patient_id != plan.patient_id {
+        let logged: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM exercise_logs WHERE plan_id = ?")
+            .bind(&id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(database_error)?;
+        if logged > 0 {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(ErrorResponse { error: "The patient cannot change once exercises are logged".to_string() })
+            ));
+        }
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE therapy_plans
+        SET patient_id = ?, physiotherapist_id = ?, title = ?, goals = ?,
+            start_date = ?, end_date = ?, updated_at = ?
+        WHERE id = ?
+        "#
+    )
+    .bind(&req.patient_id)
+    .bind(&physiotherapist_id)
+    .bind(req.title.trim())
+    .bind(&req.goals)
+    .bind(&req.start_date)
+    .bind(&req.end_date)
+    .bind(Utc::now().to_rfc3339())
+    .bind(&id)
+    .execute(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    save_plan_items(&mut tx, &id, &req.items, &weekdays).await?;
+
+    let response = fetch_plan(&mut tx, &id).await?;
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!("Therapy plan {} updated by {}", id, claims.sub);
+
+    Ok(Json(response))
+}
+
+pub async fn delete_therapy_plan(
+    Path(id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+
+    let mut tx = pool.begin().await.map_err(database_error)?;
+    let plan = fetch_plan_row(&mut tx, &id).await?;
+    ensure_plan_owner(&claims, &plan)?;
+
+    sqlx::query("DELETE FROM therapy_plans WHERE id = ?")
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(database_error)?;
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!("Therapy plan {} deleted by {}", id, claims.sub);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Šta pacijent radi danas: stavke aktivnih planova za taj dan u nedelji
+pub async fn get_therapy_plan_today(
+    Extension(pool): Extension<SqlitePool>,
+    Extension(config): Extension<Config>,
+    Query(query): Query<TherapyPlanTodayQuery>,
+    headers: HeaderMap,
+) -> Result<Json<TherapyPlanTodayResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+
+    let patient_id = match (claims.role.as_str(), query.patient_id) {
+        ("patient", Some(patient_id)) if patient_id != claims.sub => return Err(forbidden()),
+        ("patient", _) => claims.sub.clone(),
+        (_, Some(patient_id)) => patient_id,
+        (_, None) => return Err(bad_request("patient_id is required")),
+    };
+
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+
+    // "Danas" je po kalendaru pacijenta, ne klinike
+    let date = match &query.date {
+        Some(date) => parse_date(date, "date")?,
+        None => {
+            let timezone = user_timezone(&mut conn, &config, &patient_id).await;
+            Utc::now().with_timezone(&timezone).date_naive()
+        }
+    };
+    let date_str = date.format("%Y-%m-%d").to_string();
+    let weekday = weekday_code(date.weekday());
+
+    let plans = sqlx::query_as::<_, TherapyPlan>(
+        &format!(
+            r#"
+            SELECT {} FROM therapy_plans
+            WHERE patient_id = ?
+            AND start_date <= ? AND end_date >= ?
+            AND (? = 'admin' OR ? = patient_id OR ? = physiotherapist_id)
+            ORDER BY start_date ASC, created_at ASC
+            "#,
+            PLAN_COLUMNS
+        )
+    )
+    .bind(&patient_id)
+    .bind(&date_str)
+    .bind(&date_str)
+    .bind(&claims.role)
+    .bind(&claims.sub)
+    .bind(&claims.sub)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    let mut exercises = Vec::new();
+    for plan in plans {
+        for item in fetch_plan_items(&mut conn, &plan.id).await? {
+            if !item.weekdays.split(',').any(|d| d == weekday) {
+                continue;
+            }
+            exercises.push(TodayExercise {
+                plan_id: plan.id.clone(),
+                plan_title: plan.title.clone(),
+                item_id: item.id,
+                exercise_id: item.exercise_id,
+                sets: item.sets,
+                reps: item.reps,
+                hold_seconds: item.hold_seconds,
+                times_per_day: item.times_per_day,
+                notes: item.notes,
+            });
+        }
+    }
+
+    Ok(Json(TherapyPlanTodayResponse {
+        patient_id,
+        date: date_str,
+        weekday: weekday.to_string(),
+        exercises,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{auth_headers, insert_patient, insert_user, test_config, test_pool};
+
+    fn item(exercise_id: i64, weekdays: &[&str]) -> TherapyPlanItemRequest {
+        TherapyPlanItemRequest {
+            id: None,
+            exercise_id,
+            weekdays: weekdays.iter().map(|d| d.to_string()).collect(),
+            sets: 3,
+            reps: 10,
+            hold_seconds: Some(5),
+            times_per_day: Some(2),
+            notes: None,
+        }
+    }
+
+    fn plan_request(items: Vec<TherapyPlanItemRequest>) -> TherapyPlanRequest {
+        TherapyPlanRequest {
+            patient_id: "patient-001".to_string(),
+            physiotherapist_id: None,
+            title: "Rehabilitacija kolena".to_string(),
+            goals: Some("Pun opseg pokreta".to_string()),
+            start_date: "2030-01-14".to_string(),
+            end_date: "2030-02-24".to_string(),
+            items,
+        }
+    }
+
+    async fn today(pool: &SqlitePool, date: &str) -> TherapyPlanTodayResponse {
+        let Json(response) = get_therapy_plan_today(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            Query(TherapyPlanTodayQuery { patient_id: None, date: Some(date.to_string()) }),
+            auth_headers("patient-001", "patient"),
+        )
+        .await
+        .unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn today_expands_items_for_the_weekday() {
+        let pool = test_pool().await;
+
+        let (status, Json(created)) = create_therapy_plan(
+            Extension(pool.clone()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(plan_request(vec![item(1, &["mon", "wed"]), item(2, &["wednesday", "fri"])])),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created.plan.physiotherapist_id, "physio-001");
+        assert_eq!(created.items[1].weekdays, "wed,fri");
+
+        // Sreda 16.01.2030: obe vežbe
+        let wednesday = today(&pool, "2030-01-16").await;
+        assert_eq!(wednesday.weekday, "wed");
+        assert_eq!(
+            wednesday.exercises.iter().map(|e| e.exercise_id).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+
+        // Utorak nema ništa, a posle end_date plan više ne važi
+        assert!(today(&pool, "2030-01-15").await.exercises.is_empty());
+        assert!(today(&pool, "2030-02-25").await.exercises.is_empty());
+    }
+
+    #[tokio::test]
+    async fn only_the_prescribing_physio_can_change_a_plan() {
+        let pool = test_pool().await;
+
+        let err = create_therapy_plan(
+            Extension(pool.clone()),
+            auth_headers("patient-001", "patient"),
+            Json(plan_request(vec![item(1, &["mon"])])),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+
+        let (_, Json(created)) = create_therapy_plan(
+            Extension(pool.clone()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(plan_request(vec![item(1, &["mon"])])),
+        )
+        .await
+        .unwrap();
+
+        let err = update_therapy_plan(
+            Path(created.plan.id.clone()),
+            Extension(pool.clone()),
+            auth_headers("physio-002", "physiotherapist"),
+            Json(plan_request(vec![item(3, &["tue"])])),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+
+        let Json(updated) = update_therapy_plan(
+            Path(created.plan.id.clone()),
+            Extension(pool.clone()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(plan_request(vec![item(3, &["tue"]), item(4, &["tue"])])),
+        )
+        .await
+        .unwrap();
+        assert_eq!(updated.items.len(), 2);
+        assert_eq!(updated.items[0].exercise_id, 3);
+
+        let status = delete_therapy_plan(
+            Path(created.plan.id.clone()),
+            Extension(pool.clone()),
+            auth_headers("physio-001", "physiotherapist"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let items: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM therapy_plan_items")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(items, 0);
+    }
+
+    #[tokio::test]
+    async fn update_keeps_ids_of_listed_items() {
+        let pool = test_pool().await;
+
+        let (_, Json(created)) = create_therapy_plan(
+            Extension(pool.clone()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(plan_request(vec![item(1, &["mon"]), item(2, &["tue"]), item(3, &["wed"])])),
+        )
+        .await
+        .unwrap();
+        let ids: Vec<String> = created.items.iter().map(|i| i.id.clone()).collect();
+
+        // Druga stavka se menja i ide na početak, treća se briše, a dodaje se nova
+        let Json(updated) = update_therapy_plan(
+            Path(created.plan.id.clone()),
+            Extension(pool.clone()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(plan_request(vec![
+                TherapyPlanItemRequest { id: Some(ids[1].clone()), sets: 5, ..item(2, &["thu"]) },
+                TherapyPlanItemRequest { id: Some(ids[0].clone()), ..item(1, &["mon"]) },
+                item(4, &["fri"]),
+            ])),
+        )
+        .await
+        .unwrap();
+        assert_eq!(updated.items.len(), 3);
+        assert_eq!(updated.items[0].id, ids[1]);
+        assert_eq!((updated.items[0].sets, updated.items[0].weekdays.as_str()), (5, "thu"));
+        assert_eq!(updated.items[1].id, ids[0]);
+        assert!(!ids.contains(&updated.items[2].id));
+        assert_eq!(updated.items[2].exercise_id, 4);
+
+        // Stavka iz drugog plana ili obrisana stavka se ne može navesti
+        let err = update_therapy_plan(
+            Path(created.plan.id.clone()),
+            Extension(pool.clone()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(plan_request(vec![TherapyPlanItemRequest { id: Some(ids[2].clone()), ..item(3, &["wed"]) }])),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn update_follows_the_create_rules() {
+        let pool = test_pool().await;
+        insert_user(&pool, "physio-002", "physiotherapist").await;
+        insert_patient(&pool, "patient-002").await;
+
+        let err = create_therapy_plan(
+            Extension(pool.clone()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(plan_request(vec![item(99, &["mon"])])),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+
+        let (_, Json(created)) = create_therapy_plan(
+            Extension(pool.clone()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(plan_request(vec![item(1, &["mon"])])),
+        )
+        .await
+        .unwrap();
+        let update = |headers, req| {
+            update_therapy_plan(Path(created.plan.id.clone()), Extension(pool.clone()), headers, Json(req))
+        };
+
+        // Fizioterapeut ne predaje plan drugome, a admin samo fizioterapeutu
+        let err = update(
+            auth_headers("physio-001", "physiotherapist"),
+            TherapyPlanRequest { physiotherapist_id: Some("physio-002".to_string()), ..plan_request(vec![item(1, &["mon"])]) },
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+
+        let err = update(
+            auth_headers("admin-001", "admin"),
+            TherapyPlanRequest { physiotherapist_id: Some("patient-002".to_string()), ..plan_request(vec![item(1, &["mon"])]) },
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+
+        let err = update(auth_headers("physio-001", "physiotherapist"), plan_request(vec![item(99, &["mon"])]))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+
+        // Pacijent se menja dok nema upisanih vežbi
+        let Json(moved) = update(
+            auth_headers("physio-001", "physiotherapist"),
+            TherapyPlanRequest { patient_id: "patient-002".to_string(), ..plan_request(vec![item(1, &["mon"])]) },
+        )
+        .await
+        .unwrap();
+        assert_eq!(moved.plan.patient_id, "patient-002");
+
+        sqlx::query(
+            r#"
+            INSERT INTO exercise_logs (
+                id, plan_id, plan_item_id, patient_id, exercise_id, performed_on,
+                sets_done, reps_done, pain_level, created_at
+            ) VALUES ('log-1', ?, ?, 'patient-002', 1, '2030-01-14', 3, 10, 2, '2030-01-14T10:00:00Z')
+            "#
+        )
+        .bind(&created.plan.id)
+        .bind(&moved.items[0].id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let err = update(auth_headers("admin-001", "admin"), plan_request(vec![item(1, &["mon"])]))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+    }
+}
@@ -0,0 +1,862 @@
+use axum::{
+    http::{StatusCode, HeaderMap},
+    Json,
+    extract::Path,
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use chrono::{Duration, NaiveDate, NaiveTime, Utc};
+
+use crate::config::Config;
+use crate::notifications::notify;
+use super::{
+    assign_appointment_type, authenticate, blocked_until, clinic_now, database_error,
+    ensure_booking_allowed, ensure_can_book_for, ensure_resources_free, ensure_slot_free, forbidden,
+    insert_appointment, localize_for, slot_is_free, utc_timestamp, AppointmentResponse,
+    ErrorResponse, WORK_END_HOUR, WORK_START_HOUR,
+};
+
+// Koliko često se proveravaju istekle ponude
+const HOLD_EXPIRY_CHECK_SECONDS: u64 = 60;
+
+#[derive(Debug, Deserialize)]
+pub struct JoinWaitlistRequest {
+    pub patient_id: String,
+    pub physiotherapist_id: String,
+    pub date_from: String,               // YYYY-MM-DD
+    pub date_to: String,                 // YYYY-MM-DD, inclusive
+    pub preferred_start: Option<String>, // HH:MM, podrazumevano početak radnog vremena
+    pub preferred_end: Option<String>,   // HH:MM, podrazumevano kraj radnog vremena
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct WaitlistEntry {
+    pub id: String,
+    pub patient_id: String,
+    pub physiotherapist_id: String,
+    pub date_from: String,
+    pub date_to: String,
+    pub preferred_start: String,
+    pub preferred_end: String,
+    pub status: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct WaitlistHold {
+    pub id: String,
+    pub entry_id: String,
+    pub patient_id: String,
+    pub physiotherapist_id: String,
+    pub appointment_date: String,
+    pub start_time: String,
+    pub end_time: String,
+    // Vrsta i pauza oslobođenog termina, koje dobija i preuzeti termin
+    pub appointment_type_id: Option<String>,
+    pub buffer_minutes: i64,
+    pub status: String,
+    pub expires_at: String,
+    pub created_at: String,
+}
+
+// Oslobođeni slot koji se nudi listi čekanja
+pub(crate) struct FreedSlot<'a> {
+    pub physiotherapist_id: &'a str,
+    pub appointment_date: &'a str,
+    pub start_time: &'a str,
+    pub end_time: &'a str,
+    pub appointment_type_id: Option<&'a str>,
+    pub buffer_minutes: i64,
+}
+
+impl<'a> From<&'a AppointmentResponse> for FreedSlot<'a> {
+    fn from(appointment: &'a AppointmentResponse) -> Self {
+        FreedSlot {
+            physiotherapist_id: &appointment.physiotherapist_id,
+            appointment_date: &appointment.appointment_date,
+            start_time: &appointment.start_time,
+            end_time: &appointment.end_time,
+            appointment_type_id: appointment.appointment_type_id.as_deref(),
+            buffer_minutes: appointment.buffer_minutes as i64,
+        }
+    }
+}
+
+impl<'a> From<&'a WaitlistHold> for FreedSlot<'a> {
+    fn from(hold: &'a WaitlistHold) -> Self {
+        FreedSlot {
+            physiotherapist_id: &hold.physiotherapist_id,
+            appointment_date: &hold.appointment_date,
+            start_time: &hold.start_time,
+            end_time: &hold.end_time,
+            appointment_type_id: hold.appointment_type_id.as_deref(),
+            buffer_minutes: hold.buffer_minutes,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct WaitlistResponse {
+    pub entries: Vec<WaitlistEntry>,
+    // Ponude koje još važe
+    pub holds: Vec<WaitlistHold>,
+}
+
+const ENTRY_COLUMNS: &str = "id, patient_id, physiotherapist_id, date_from, date_to, \
+    preferred_start, preferred_end, status, created_at";
+
+const HOLD_COLUMNS: &str = "id, entry_id, patient_id, physiotherapist_id, appointment_date, \
+    start_time, end_time, appointment_type_id, buffer_minutes, status, expires_at, created_at";
+
+fn bad_request(message: &str) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse { error: message.to_string() })
+    )
+}
+
+// Da li je slot trenutno ponuđen nekome sa liste čekanja
+pub(crate) async fn slot_is_held(
+    conn: &mut SqliteConnection,
+    physiotherapist_id: &str,
+    appointment_date: &str,
+    start_time: &str,
+    end_time: &str,
+) -> Result<bool, (StatusCode, Json<ErrorResponse>)> {
+    let count: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*) FROM waitlist_holds
+        WHERE physiotherapist_id = ?
+        AND appointment_date = ?
+        AND start_time < ?
+        AND end_time > ?
+        AND status = 'active'
+        AND expires_at > ?
+        "#
+    )
+    .bind(physiotherapist_id)
+    .bind(appointment_date)
+    .bind(end_time)
+    .bind(start_time)
+    .bind(utc_timestamp(Utc::now()))
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    Ok(count > 0)
+}
+
+async fn fetch_entry(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<WaitlistEntry, (StatusCode, Json<ErrorResponse>)> {
+    sqlx::query_as::<_, WaitlistEntry>(
+        &format!("SELECT {} FROM waitlist_entries WHERE id = ?", ENTRY_COLUMNS)
+    )
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse { error: "Waitlist entry not found".to_string() })
+    ))
+}
+
+async fn fetch_hold(
+    conn: &mut SqliteConnection,
+    id: &str,
+) -> Result<WaitlistHold, (StatusCode, Json<ErrorResponse>)> {
+    sqlx::query_as::<_, WaitlistHold>(
+        &format!("SELECT {} FROM waitlist_holds WHERE id = ?", HOLD_COLUMNS)
+    )
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse { error: "Waitlist hold not found".to_string() })
+    ))
+}
+
+// Ponudi slobodan slot prvom odgovarajućem pacijentu sa liste čekanja.
+// Pacijent kome je slot već bio ponuđen se preskače, pa ponuda ide dalje niz listu.
+pub(crate) async fn offer_slot(
+    conn: &mut SqliteConnection,
+    config: &Config,
+    slot: FreedSlot<'_>,
+) -> Result<Option<WaitlistHold>, (StatusCode, Json<ErrorResponse>)> {
+    let FreedSlot { physiotherapist_id, appointment_date, start_time, end_time, .. } = slot;
+    let starts_at = format!("{} {}", appointment_date, start_time);
+    if starts_at <= clinic_now(config).format("%Y-%m-%d %H:%M").to_string() {
+        return Ok(None);
+    }
+
+    let blocked_end = blocked_until(end_time, slot.buffer_minutes);
+    if !slot_is_free(conn, physiotherapist_id, appointment_date, start_time, &blocked_end, None).await? {
+        return Ok(None);
+    }
+
+    let entry = sqlx::query_as::<_, WaitlistEntry>(
+        &format!(
+            r#"
+            SELECT {} FROM waitlist_entries e
+            WHERE e.physiotherapist_id = ?
+            AND e.status = 'waiting'
+            AND e.date_from <= ? AND e.date_to >= ?
+            AND e.preferred_start <= ? AND e.preferred_end >= ?
+            AND NOT EXISTS (
+                SELECT 1 FROM waitlist_holds h
+                WHERE h.entry_id = e.id
+                AND h.appointment_date = ?
+                AND h.start_time = ?
+            )
+            ORDER BY e.created_at ASC, e.rowid ASC
+            LIMIT 1
+            "#,
+            ENTRY_COLUMNS
+        )
+    )
+    .bind(physiotherapist_id)
+    .bind(appointment_date)
+    .bind(appointment_date)
+    .bind(start_time)
+    .bind(end_time)
+    .bind(appointment_date)
+    .bind(start_time)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    let entry = match entry {
+        Some(entry) => entry,
+        None => return Ok(None),
+    };
+
+    let now = Utc::now();
+    let hold_id = uuid::Uuid::new_v4().to_string();
+
+    sqlx::query(
+        r#"
+        INSERT INTO waitlist_holds (
+            id, entry_id, patient_id, physiotherapist_id, appointment_date,
+            start_time, end_time, appointment_type_id, buffer_minutes, status, expires_at, created_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, 'active', ?, ?)
+        "#
+    )
+    .bind(&hold_id)
+    .bind(&entry.id)
+    .bind(&entry.patient_id)
+    .bind(physiotherapist_id)
+    .bind(appointment_date)
+    .bind(start_time)
+    .bind(end_time)
+    .bind(slot.appointment_type_id)
+    .bind(slot.buffer_minutes)
+    .bind(utc_timestamp(now + Duration::minutes(config.waitlist_hold_minutes)))
+    .bind(utc_timestamp(now))
+    .execute(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    set_entry_status(conn, &entry.id, "offered").await?;
+
+    tracing::info!("Slot {} {} held for waitlist entry {}", appointment_date, start_time, entry.id);
+
+    Ok(Some(fetch_hold(conn, &hold_id).await?))
+}
+
+// Ponudi slot otkazanog termina listi čekanja
+pub(crate) async fn offer_freed_slot(
+    conn: &mut SqliteConnection,
+    config: &Config,
+    appointment: &AppointmentResponse,
+) -> Result<Option<WaitlistHold>, (StatusCode, Json<ErrorResponse>)> {
+    offer_slot(conn, config, appointment.into()).await
+}
+
+// Obavesti pacijenta o ponudi; poziva se posle commit-a
+pub(crate) fn notify_hold(config: &Config, hold: &WaitlistHold) {
+    notify(
+        config,
+        &hold.patient_id,
+        "waitlist_offer",
+        "A slot opened up",
+        &format!(
+            "A slot on {} at {} is held for you until {}. Claim it before it passes to the next patient.",
+            hold.appointment_date, hold.start_time, hold.expires_at
+        ),
+        serde_json::json!({
+            "hold_id": hold.id,
+            "physiotherapist_id": hold.physiotherapist_id,
+            "appointment_date": hold.appointment_date,
+            "start_time": hold.start_time,
+            "expires_at": hold.expires_at,
+        }),
+    );
+}
+
+async fn set_entry_status(
+    conn: &mut SqliteConnection,
+    entry_id: &str,
+    status: &str,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    sqlx::query("UPDATE waitlist_entries SET status = ?, updated_at = ? WHERE id = ?")
+        .bind(status)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(entry_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(database_error)?;
+
+    Ok(())
+}
+
+// Zatvori ponudu (declined/expired), vrati pacijenta na listu i ponudi slot sledećem
+async fn release_hold(
+    conn: &mut SqliteConnection,
+    config: &Config,
+    hold: &WaitlistHold,
+    status: &str,
+) -> Result<Option<WaitlistHold>, (StatusCode, Json<ErrorResponse>)> {
+    sqlx::query("UPDATE waitlist_holds SET status = ?, decided_at = ? WHERE id = ? AND status = 'active'")
+        .bind(status)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(&hold.id)
+        .execute(&mut *conn)
+        .await
+        .map_err(database_error)?;
+
+    sqlx::query(
+        "UPDATE waitlist_entries SET status = 'waiting', updated_at = ? WHERE id = ? AND status = 'offered'"
+    )
+    .bind(chrono::Utc::now().to_rfc3339())
+    .bind(&hold.entry_id)
+    .execute(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    offer_slot(conn, config, hold.into()).await
+}
+
+// Istekle ponude prelaze na sledećeg pacijenta. Vraća broj isteklih ponuda.
+pub(crate) async fn expire_waitlist_holds(
+    pool: &SqlitePool,
+    config: &Config,
+) -> Result<usize, (StatusCode, Json<ErrorResponse>)> {
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE")
+        .await
+        .map_err(database_error)?;
+
+    let expired = sqlx::query_as::<_, WaitlistHold>(
+        &format!(
+            "SELECT {} FROM waitlist_holds WHERE status = 'active' AND expires_at <= ? ORDER BY expires_at ASC",
+            HOLD_COLUMNS
+        )
+    )
+    .bind(utc_timestamp(Utc::now()))
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    let mut offered = Vec::new();
+    for hold in &expired {
+        if let Some(next) = release_hold(&mut tx, config, hold, "expired").await? {
+            offered.push(next);
+        }
+    }
+
+    tx.commit().await.map_err(database_error)?;
+
+    for hold in &offered {
+        notify_hold(config, hold);
+    }
+
+    Ok(expired.len())
+}
+
+// Pozadinski posao koji se pokreće iz main-a
+pub async fn run_waitlist_expiry(pool: SqlitePool, config: Config) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(HOLD_EXPIRY_CHECK_SECONDS));
+
+    loop {
+        interval.tick().await;
+        match expire_waitlist_holds(&pool, &config).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Expired {} waitlist holds", count),
+            Err((_, Json(e))) => tracing::error!("Failed to expire waitlist holds: {}", e.error),
+        }
+    }
+}
+
+pub async fn join_waitlist(
+    Extension(pool): Extension<SqlitePool>,
+    Extension(config): Extension<Config>,
+    headers: HeaderMap,
+    Json(req): Json<JoinWaitlistRequest>,
+) -> Result<(StatusCode, Json<WaitlistEntry>), (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+    ensure_can_book_for(&claims, &req.patient_id)?;
+
+    let date_from = NaiveDate::parse_from_str(&req.date_from, "%Y-%m-%d")
+        .map_err(|_| bad_request("Invalid date_from format"))?;
+    let date_to = NaiveDate::parse_from_str(&req.date_to, "%Y-%m-%d")
+        .map_err(|_| bad_request("Invalid date_to format"))?;
+    if date_to < date_from {
+        return Err(bad_request("date_to must not be before date_from"));
+    }
+    if date_to < clinic_now(&config).date() {
+        return Err(bad_request("The date range is in the past"));
+    }
+
+    let preferred_start = match &req.preferred_start {
+        Some(time) => NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| bad_request("Invalid preferred_start format"))?,
+        None => NaiveTime::from_hms_opt(WORK_START_HOUR, 0, 0).unwrap(),
+    };
+    let preferred_end = match &req.preferred_end {
+        Some(time) => NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| bad_request("Invalid preferred_end format"))?,
+        None => NaiveTime::from_hms_opt(WORK_END_HOUR, 0, 0).unwrap(),
+    };
+    if preferred_end <= preferred_start {
+        return Err(bad_request("preferred_end must be after preferred_start"));
+    }
+
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+
+    sqlx::query(
+        r#"
+        INSERT INTO waitlist_entries (
+            id, patient_id, physiotherapist_id, date_from, date_to,
+            preferred_start, preferred_end, status, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, 'waiting', ?, ?)
+        "#
+    )
+    .bind(&id)
+    .bind(&req.patient_id)
+    .bind(&req.physiotherapist_id)
+    .bind(date_from.format("%Y-%m-%d").to_string())
+    .bind(date_to.format("%Y-%m-%d").to_string())
+    .bind(preferred_start.format("%H:%M").to_string())
+    .bind(preferred_end.format("%H:%M").to_string())
+    .bind(&now)
+    .bind(&now)
+    .execute(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    let entry = fetch_entry(&mut conn, &id).await?;
+    Ok((StatusCode::CREATED, Json(entry)))
+}
+
+// Pacijent vidi svoje prijave, fizioterapeut prijave za sebe, admin sve
+pub async fn get_waitlist(
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+) -> Result<Json<WaitlistResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+
+    let entries = sqlx::query_as::<_, WaitlistEntry>(
+        &format!(
+            r#"
+            SELECT {} FROM waitlist_entries
+            WHERE (? = 'admin' OR patient_id = ? OR physiotherapist_id = ?)
+            AND status IN ('waiting', 'offered')
+            ORDER BY created_at ASC
+            "#,
+            ENTRY_COLUMNS
+        )
+    )
+    .bind(&claims.role)
+    .bind(&claims.sub)
+    .bind(&claims.sub)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    let holds = sqlx::query_as::<_, WaitlistHold>(
+        &format!(
+            r#"
+            SELECT {} FROM waitlist_holds
+            WHERE (? = 'admin' OR patient_id = ? OR physiotherapist_id = ?)
+            AND status = 'active' AND expires_at > ?
+            ORDER BY expires_at ASC
+            "#,
+            HOLD_COLUMNS
+        )
+    )
+    .bind(&claims.role)
+    .bind(&claims.sub)
+    .bind(&claims.sub)
+    .bind(utc_timestamp(Utc::now()))
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    Ok(Json(WaitlistResponse { entries, holds }))
+}
+
+pub async fn leave_waitlist(
+    Path(id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(config): Extension<Config>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE")
+        .await
+        .map_err(database_error)?;
+
+    let entry = fetch_entry(&mut tx, &id).await?;
+    if claims.role != "admin" && claims.sub != entry.patient_id {
+        return Err(forbidden());
+    }
+
+    // Ponuda koju pacijent još drži ide sledećem na listi
+    let active = sqlx::query_as::<_, WaitlistHold>(
+        &format!("SELECT {} FROM waitlist_holds WHERE entry_id = ? AND status = 'active'", HOLD_COLUMNS)
+    )
+    .bind(&id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    set_entry_status(&mut tx, &id, "cancelled").await?;
+
+    let mut offered = Vec::new();
+    for hold in &active {
+        if let Some(next) = release_hold(&mut tx, &config, hold, "declined").await? {
+            offered.push(next);
+        }
+    }
+
+    tx.commit().await.map_err(database_error)?;
+
+    for hold in &offered {
+        notify_hold(&config, hold);
+    }
+
+    Ok(StatusCode::OK)
+}
+
+// Pacijent preuzima ponuđeni slot i dobija zakazan termin
+pub async fn claim_waitlist_hold(
+    Path(id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(config): Extension<Config>,
+    headers: HeaderMap,
+) -> Result<(StatusCode, Json<AppointmentResponse>), (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE")
+        .await
+        .map_err(database_error)?;
+
+    let hold = fetch_hold(&mut tx, &id).await?;
+    if claims.role != "admin" && claims.sub != hold.patient_id {
+        return Err(forbidden());
+    }
+    if hold.status != "active" || hold.expires_at <= utc_timestamp(Utc::now()) {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse { error: "This hold is no longer available".to_string() })
+        ));
+    }
+
+    sqlx::query("UPDATE waitlist_holds SET status = 'claimed', decided_at = ? WHERE id = ?")
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(&hold.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(database_error)?;
+
+    // Isto kao pri zakazivanju: slot sa pauzom i resursi vrste termina moraju biti slobodni
+    ensure_slot_free(
+        &mut tx,
+        &hold.physiotherapist_id,
+        &hold.appointment_date,
+        &hold.start_time,
+        &blocked_until(&hold.end_time, hold.buffer_minutes),
+        None,
+    ).await?;
+    ensure_resources_free(
+        &mut tx,
+        hold.appointment_type_id.as_deref(),
+        &hold.appointment_date,
+        &hold.start_time,
+        &hold.end_time,
+        None,
+    ).await?;
+    ensure_booking_allowed(&mut tx, &config, &hold.patient_id, &[]).await?;
+
+    let mut appointment = insert_appointment(
+        &mut tx,
+        &config,
+        &hold.patient_id,
+        &hold.physiotherapist_id,
+        &hold.appointment_date,
+        &hold.start_time,
+        &hold.end_time,
+    ).await?;
+    if let Some(type_id) = &hold.appointment_type_id {
+        assign_appointment_type(&mut tx, &mut appointment, type_id, hold.buffer_minutes).await?;
+    }
+    set_entry_status(&mut tx, &hold.entry_id, "booked").await?;
+    localize_for(&mut tx, &config, &claims.sub, std::slice::from_mut(&mut appointment)).await;
+
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!("Waitlist hold {} claimed as appointment {}", hold.id, appointment.id);
+
+    Ok((StatusCode::CREATED, Json(appointment)))
+}
+
+// Pacijent odbija ponudu, koja odmah ide sledećem na listi
+pub async fn decline_waitlist_hold(
+    Path(id): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(config): Extension<Config>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE")
+        .await
+        .map_err(database_error)?;
+
+    let hold = fetch_hold(&mut tx, &id).await?;
+    if claims.role != "admin" && claims.sub != hold.patient_id {
+        return Err(forbidden());
+    }
+    if hold.status != "active" {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse { error: "This hold is no longer available".to_string() })
+        ));
+    }
+
+    let next = release_hold(&mut tx, &config, &hold, "declined").await?;
+    tx.commit().await.map_err(database_error)?;
+
+    if let Some(next) = next {
+        notify_hold(&config, &next);
+    }
+
+    Ok(StatusCode::OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::{
+        cancel_appointment, create_appointment, create_appointment_type, create_resource,
+        AppointmentTypeRequest, CreateAppointmentRequest, CreateResourceRequest,
+    };
+    use crate::test_support::{auth_headers, insert_patient, insert_user, test_config, test_pool};
+
+    fn booking(patient_id: &str) -> CreateAppointmentRequest {
+        CreateAppointmentRequest {
+            patient_id: patient_id.to_string(),
+            physiotherapist_id: "physio-001".to_string(),
+            appointment_date: "2030-01-15".to_string(),
+            start_time: "10:00".to_string(),
+            appointment_type_id: None,
+        }
+    }
+
+    async fn join(pool: &SqlitePool, patient_id: &str) -> WaitlistEntry {
+        let (_, Json(entry)) = join_waitlist(
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers(patient_id, "patient"),
+            Json(JoinWaitlistRequest {
+                patient_id: patient_id.to_string(),
+                physiotherapist_id: "physio-001".to_string(),
+                date_from: "2030-01-14".to_string(),
+                date_to: "2030-01-18".to_string(),
+                preferred_start: Some("09:00".to_string()),
+                preferred_end: Some("12:00".to_string()),
+            }),
+        )
+        .await
+        .unwrap();
+        entry
+    }
+
+    async fn cancel(pool: &SqlitePool, appointment: &AppointmentResponse) {
+        let Json(cancelled) = cancel_appointment(
+            Path(appointment.id.clone()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("physio-001", "physiotherapist"),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(cancelled.status, "cancelled");
+    }
+
+    async fn active_holds(pool: &SqlitePool, patient_id: &str) -> Vec<WaitlistHold> {
+        let Json(waitlist) = get_waitlist(Extension(pool.clone()), auth_headers(patient_id, "patient"))
+            .await
+            .unwrap();
+        waitlist.holds
+    }
+
+    #[tokio::test]
+    async fn cancellation_holds_slot_for_first_waiting_patient() {
+        let pool = test_pool().await;
+        let booked = create_appointment(Extension(pool.clone()), Extension(test_config()), auth_headers("patient-001", "patient"), Json(booking("patient-001")))
+            .await
+            .unwrap();
+        insert_patient(&pool, "patient-002").await;
+        insert_patient(&pool, "patient-003").await;
+        let first = join(&pool, "patient-002").await;
+        join(&pool, "patient-003").await;
+
+        cancel(&pool, &booked).await;
+
+        let holds = active_holds(&pool, "patient-002").await;
+        assert_eq!(holds.len(), 1);
+        assert_eq!(holds[0].entry_id, first.id);
+        assert_eq!(holds[0].start_time, "10:00");
+
+        // Dok ponuda važi, slot ne može da zauzme niko drugi
+        let err = create_appointment(Extension(pool.clone()), Extension(test_config()), auth_headers("patient-001", "patient"), Json(booking("patient-001")))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+
+        let err = claim_waitlist_hold(
+            Path(holds[0].id.clone()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-001", "patient"),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+
+        let (status, Json(appointment)) = claim_waitlist_hold(
+            Path(holds[0].id.clone()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-002", "patient"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(appointment.patient_id, "patient-002");
+        assert_eq!(appointment.start_time, "10:00");
+
+        let mut conn = pool.acquire().await.unwrap();
+        assert_eq!(fetch_entry(&mut conn, &first.id).await.unwrap().status, "booked");
+    }
+
+    #[tokio::test]
+    async fn expired_or_declined_hold_passes_to_next_patient() {
+        let pool = test_pool().await;
+        let booked = create_appointment(Extension(pool.clone()), Extension(test_config()), auth_headers("patient-001", "patient"), Json(booking("patient-001")))
+            .await
+            .unwrap();
+        insert_patient(&pool, "patient-002").await;
+        insert_patient(&pool, "patient-003").await;
+        join(&pool, "patient-002").await;
+        let second = join(&pool, "patient-003").await;
+
+        cancel(&pool, &booked).await;
+        let first_hold = active_holds(&pool, "patient-002").await.remove(0);
+
+        sqlx::query("UPDATE waitlist_holds SET expires_at = '2000-01-01T00:00:00Z' WHERE id = ?")
+            .bind(&first_hold.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(expire_waitlist_holds(&pool, &test_config()).await.unwrap(), 1);
+
+        assert!(active_holds(&pool, "patient-002").await.is_empty());
+        let second_hold = active_holds(&pool, "patient-003").await.remove(0);
+        assert_eq!(second_hold.entry_id, second.id);
+
+        // Kada i drugi odbije, nema više nikoga i slot se oslobađa
+        decline_waitlist_hold(
+            Path(second_hold.id.clone()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-003", "patient"),
+        )
+        .await
+        .unwrap();
+
+        let rebooked = create_appointment(Extension(pool.clone()), Extension(test_config()), auth_headers("patient-001", "patient"), Json(booking("patient-001")))
+            .await
+            .unwrap();
+        assert_eq!(rebooked.start_time, "10:00");
+    }
+
+    #[tokio::test]
+    async fn claimed_hold_keeps_the_type_and_needs_its_resources() {
+        let pool = test_pool().await;
+        insert_user(&pool, "physio-002", "physiotherapist").await;
+        insert_patient(&pool, "patient-002").await;
+        let admin = || auth_headers("admin-001", "admin");
+        let (_, Json(shockwave)) = create_resource(
+            Extension(pool.clone()),
+            admin(),
+            Json(CreateResourceRequest { name: "Shockwave".to_string(), kind: "equipment".to_string(), quantity: None }),
+        )
+        .await
+        .unwrap();
+        let (_, Json(therapy)) = create_appointment_type(
+            Extension(pool.clone()),
+            admin(),
+            Json(serde_json::from_value::<AppointmentTypeRequest>(serde_json::json!({
+                "name": "Udarni talas",
+                "buffer_minutes": 20,
+                "resource_ids": [shockwave.id],
+            })).unwrap()),
+        )
+        .await
+        .unwrap();
+        let book = |physiotherapist_id: &str| {
+            let request = CreateAppointmentRequest {
+                physiotherapist_id: physiotherapist_id.to_string(),
+                appointment_type_id: Some(therapy.id.clone()),
+                ..booking("patient-001")
+            };
+            create_appointment(Extension(pool.clone()), Extension(test_config()), admin(), Json(request))
+        };
+
+        let Json(booked) = book("physio-001").await.unwrap();
+        join(&pool, "patient-002").await;
+        cancel(&pool, &booked).await;
+        let hold = active_holds(&pool, "patient-002").await.remove(0);
+        assert_eq!(hold.appointment_type_id.as_deref(), Some(therapy.id.as_str()));
+        assert_eq!(hold.buffer_minutes, 20);
+
+        // Aparat je u međuvremenu zauzeo drugi fizioterapeut
+        let Json(elsewhere) = book("physio-002").await.unwrap();
+        let claim = || claim_waitlist_hold(
+            Path(hold.id.clone()),
+            Extension(pool.clone()),
+            Extension(test_config()),
+            auth_headers("patient-002", "patient"),
+        );
+        assert_eq!(claim().await.unwrap_err().0, StatusCode::CONFLICT);
+
+        let Json(freed) = cancel_appointment(Path(elsewhere.id.clone()), Extension(pool.clone()), Extension(test_config()), admin(), None)
+            .await
+            .unwrap();
+        assert_eq!(freed.status, "cancelled");
+        let (status, Json(appointment)) = claim().await.unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(appointment.appointment_type_id.as_deref(), Some(therapy.id.as_str()));
+        assert_eq!(appointment.buffer_minutes, 20);
+    }
+}
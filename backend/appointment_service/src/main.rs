@@ -1,15 +1,20 @@
 use axum::{
-    routing::{get, post, put},
+    routing::{delete, get, post, put},
     Router,
     Extension,
 };
 use std::net::SocketAddr;
 use tower_http::cors::CorsLayer;
-use tracing_subscriber;
 use sqlx::sqlite::SqlitePoolOptions;
 
+mod config;
 mod handlers;
+mod notifications;
+mod utils;
+#[cfg(test)]
+mod test_support;
 
+use config::Config;
 use handlers::*;
 
 #[tokio::main]
@@ -17,20 +22,39 @@ async fn main() {
     // Initialize logging
     tracing_subscriber::fmt::init();
 
-    tracing::info!("Starting Appointment Service");
+    // Load configuration
+    let config = Config::from_env();
+    tracing::info!("Starting Appointment Service with config: {:?}", config);
+    if config.jwt_secret == utils::DEV_JWT_SECRET {
+        tracing::warn!("JWT_SECRET is not set; tokens from {} are verified with the development secret", config.auth_service_url);
+    }
+    if !config.chat_service_url.is_empty() && config.notification_service_token.is_empty() {
+        tracing::warn!("NOTIFICATION_SERVICE_TOKEN is not set; {} will reject notifications", config.chat_service_url);
+    }
 
     // Initialize database connection
-    let database_url = std::env::var("DATABASE_URL")
-        .unwrap_or_else(|_| "../auth_service/data/fisionet.db".to_string());
-    
     let pool = SqlitePoolOptions::new()
         .max_connections(5)
-        .connect(&database_url)
+        .connect(&config.database_url)
         .await
         .expect("Failed to connect to database");
 
     tracing::info!("Connected to database");
 
+    // Convert appointments stored before time zone support to UTC instants
+    let converted = backfill_appointment_instants(&pool, &config)
+        .await
+        .expect("Failed to convert appointments to UTC instants");
+    if converted > 0 {
+        tracing::info!("Converted {} appointments to UTC instants", converted);
+    }
+
+    // Expired waitlist holds pass to the next patient in line
+    tokio::spawn(run_waitlist_expiry(pool.clone(), config.clone()));
+
+    // Reminders before upcoming appointments
+    tokio::spawn(run_reminder_scheduler(pool.clone(), config.clone()));
+
     // Build application routes
     let app = Router::new()
         .route("/", get(health_check))
@@ -40,12 +64,71 @@ async fn main() {
         // Appointment management
         .route("/appointments", post(create_appointment))
         .route("/appointments", get(get_user_appointments))
+        .route("/appointments/:id", get(get_appointment_ics))
         .route("/appointments/:id/status", put(update_appointment_status))
+        .route("/appointments/:id/cancel", put(cancel_appointment))
+        .route("/appointments/:id/reschedule", post(reschedule_appointment))
+        .route("/appointments/:id/reschedule/approve", post(approve_reschedule))
+        .route("/appointments/:id/reschedule/reject", post(reject_reschedule))
+        .route("/appointments/:id/history", get(get_appointment_history))
+        // Confirming attendance from a reminder (the token replaces login)
+        .route("/reminders/:token/confirm", post(confirm_from_reminder))
+        // Clinical session notes (SOAP)
+        .route("/appointments/:id/notes", post(create_session_note))
+        .route("/appointments/:id/notes", get(get_session_note))
+        .route("/appointments/:id/notes/amendments", post(amend_session_note))
+        .route("/appointments/:id/notes/versions/:version", get(get_session_note_version))
+        .route("/appointments/:id/summary", get(get_session_summary))
+        // Recurring series
+        .route("/appointment-series/preview", post(preview_appointment_series))
+        .route("/appointment-series", post(create_appointment_series))
+        .route("/appointment-series/:id", get(get_appointment_series))
+        // Calendar export and subscription feed
+        .route("/calendar/token", get(get_calendar_token))
+        .route("/calendar/token/regenerate", post(regenerate_calendar_token))
+        .route("/calendar/:file", get(get_calendar_feed))
+        // Waitlist
+        .route("/waitlist", post(join_waitlist))
+        .route("/waitlist", get(get_waitlist))
+        .route("/waitlist/:id", delete(leave_waitlist))
+        .route("/waitlist/holds/:id/claim", post(claim_waitlist_hold))
+        .route("/waitlist/holds/:id/decline", post(decline_waitlist_hold))
+        // Therapy plans (prescriptions)
+        .route("/therapy-plans", post(create_therapy_plan))
+        .route("/therapy-plans", get(get_therapy_plans))
+        .route("/therapy-plans/today", get(get_therapy_plan_today))
+        .route("/therapy-plans/:id", get(get_therapy_plan))
+        .route("/therapy-plans/:id", put(update_therapy_plan))
+        .route("/therapy-plans/:id", delete(delete_therapy_plan))
+        .route("/therapy-plans/:id/logs", post(create_exercise_log))
+        .route("/therapy-plans/:id/logs", get(get_exercise_logs))
+        .route("/therapy-plans/:id/adherence", get(get_plan_adherence))
+        // Appointment types and the resources they need
+        .route("/appointment-types", get(get_appointment_types))
+        .route("/appointment-types", post(create_appointment_type))
+        .route("/appointment-types/:id", put(update_appointment_type))
+        .route("/appointment-types/:id", delete(delete_appointment_type))
+        .route("/resources", get(get_resources))
+        .route("/resources", post(create_resource))
+        .route("/resources/:id", put(update_resource))
+        .route("/admin/resources/occupancy", get(get_resource_occupancy))
+        // Invoices and payments
+        .route("/invoices", get(get_invoices))
+        .route("/invoices/export", get(export_invoices_csv))
+        .route("/invoices/:id", get(get_invoice))
+        .route("/invoices/:id/pdf", get(get_invoice_pdf))
+        .route("/invoices/:id/payments", post(record_payment))
+        .route("/admin/balances", get(get_outstanding_balances))
+        // Admin reports
+        .route("/admin/cancellation-report", get(get_cancellation_report))
+        .route("/admin/analytics", get(get_analytics))
+        .route("/admin/analytics/export", get(export_analytics_csv))
         .layer(Extension(pool))
+        .layer(Extension(config.clone()))
         .layer(CorsLayer::permissive());
 
     // Start server
-    let addr = SocketAddr::from(([0, 0, 0, 0], 8002));
+    let addr = SocketAddr::from(([0, 0, 0, 0], config.server_port));
     tracing::info!("Appointment service listening on {}", addr);
     
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
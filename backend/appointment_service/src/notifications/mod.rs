@@ -0,0 +1,82 @@
+use std::sync::OnceLock;
+
+use lettre::message::header::ContentType;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde_json::{json, Value};
+use anyhow::{bail, Result};
+
+use crate::config::Config;
+
+fn http_client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(reqwest::Client::new)
+}
+
+// Pošalji notifikaciju preko chat_notification_service u pozadini.
+// Neuspeh se samo loguje, da ne bi oborio zahtev koji ju je pokrenuo.
+pub fn notify(
+    config: &Config,
+    user_id: &str,
+    notification_type: &str,
+    title: &str,
+    body: &str,
+    data: Value,
+) {
+    if config.chat_service_url.is_empty() {
+        return;
+    }
+
+    let config = config.clone();
+    let (user_id, notification_type, title, body) =
+        (user_id.to_string(), notification_type.to_string(), title.to_string(), body.to_string());
+    tokio::spawn(async move {
+        if let Err(e) = deliver_notification(&config, &user_id, &notification_type, &title, &body, data).await {
+            tracing::warn!("Failed to send notification: {}", e);
+        }
+    });
+}
+
+// Kao notify, ali čeka odgovor servisa, za pozivaoce koji beleže da je notifikacija stigla
+pub async fn deliver_notification(
+    config: &Config,
+    user_id: &str,
+    notification_type: &str,
+    title: &str,
+    body: &str,
+    data: Value,
+) -> Result<()> {
+    let url = format!("{}/notifications", config.chat_service_url.trim_end_matches('/'));
+    let payload = json!({
+        "user_id": user_id,
+        "notification_type": notification_type,
+        "title": title,
+        "body": body,
+        "data": data,
+    });
+
+    let resp = http_client()
+        .post(&url)
+        .header("x-service-token", &config.notification_service_token)
+        .json(&payload)
+        .send()
+        .await?;
+    if !resp.status().is_success() {
+        bail!("notification service responded with {}", resp.status());
+    }
+    Ok(())
+}
+
+// Pošalji email preko SMTP servera iz SMTP_URL i sačekaj odgovor.
+// Pozivalac proverava da je SMTP_URL podešen.
+pub async fn send_email(config: &Config, to: &str, subject: &str, body: &str) -> Result<()> {
+    let message = Message::builder()
+        .from(config.email_from.parse()?)
+        .to(to.parse()?)
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(body.to_string())?;
+    let mailer = AsyncSmtpTransport::<Tokio1Executor>::from_url(&config.smtp_url)?.build();
+
+    mailer.send(message).await?;
+    Ok(())
+}
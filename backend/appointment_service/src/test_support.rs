@@ -0,0 +1,147 @@
+use axum::http::{header::AUTHORIZATION, HeaderMap, HeaderValue};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use sqlx::migrate::Migrator;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::SqlitePool;
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::utils::{jwt_secret, Claims};
+
+// Briše fajlove test baze kada se odbaci
+struct RemoveOnDrop(PathBuf);
+
+impl Drop for RemoveOnDrop {
+    fn drop(&mut self) {
+        for suffix in ["", "-wal", "-shm", "-journal"] {
+            let _ = std::fs::remove_file(format!("{}{}", self.0.display(), suffix));
+        }
+    }
+}
+
+// Svaki test dobija svoju bazu sa šemom iz auth_service migracija
+pub async fn test_pool() -> SqlitePool {
+    let pool = empty_test_pool().await;
+    run_migrations(&pool).await;
+    insert_exercises(&pool).await;
+    pool
+}
+
+// Tabelu exercises u zajedničkoj bazi pravi exercise_service; testovima trebaju samo id-jevi 1-5
+async fn insert_exercises(pool: &SqlitePool) {
+    sqlx::query("CREATE TABLE exercises (id INTEGER PRIMARY KEY AUTOINCREMENT, title TEXT NOT NULL)")
+        .execute(pool)
+        .await
+        .expect("Failed to create exercises table");
+    for id in 1..=5 {
+        sqlx::query("INSERT INTO exercises (id, title) VALUES (?, ?)")
+            .bind(id)
+            .bind(format!("Exercise {}", id))
+            .execute(pool)
+            .await
+            .expect("Failed to insert test exercise");
+    }
+}
+
+// Baza sa migracijama pre zadate verzije, za testove podataka starijih od nje
+pub async fn test_pool_before(version: i64) -> SqlitePool {
+    let pool = empty_test_pool().await;
+    let migrations = sqlx::migrate!("../auth_service/migrations");
+    let earlier: Vec<_> = migrations.iter().filter(|m| m.version < version).cloned().collect();
+    Migrator { migrations: earlier.into(), ..Migrator::DEFAULT }
+        .run(&pool)
+        .await
+        .expect("Failed to run migrations");
+    pool
+}
+
+// Preostale migracije nad bazom iz test_pool_before
+pub async fn run_migrations(pool: &SqlitePool) {
+    sqlx::migrate!("../auth_service/migrations")
+        .run(pool)
+        .await
+        .expect("Failed to run migrations");
+}
+
+async fn empty_test_pool() -> SqlitePool {
+    let path = std::env::temp_dir().join(format!("fisionet-test-{}.db", uuid::Uuid::new_v4()));
+    let options = SqliteConnectOptions::new()
+        .filename(&path)
+        .create_if_missing(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(5)
+        .connect_with(options)
+        .await
+        .expect("Failed to open test database");
+
+    // Runtime testa na kraju odbacuje sve taskove, pa i ovaj, i tada se baza briše
+    let guard = RemoveOnDrop(path);
+    tokio::spawn(async move {
+        let _guard = guard;
+        std::future::pending::<()>().await
+    });
+    pool
+}
+
+// Dodatni korisnik pored onih iz 006_insert_test_users.sql
+pub async fn insert_user(pool: &SqlitePool, id: &str, role: &str) {
+    sqlx::query(
+        r#"
+        INSERT INTO users (id, email, password_hash, first_name, last_name, role, created_at, updated_at)
+        VALUES (?, ?, 'x', 'Test', ?, ?, '2025-01-01T00:00:00Z', '2025-01-01T00:00:00Z')
+        "#
+    )
+    .bind(id)
+    .bind(format!("{}@fisionet.rs", id))
+    .bind(id)
+    .bind(role)
+    .execute(pool)
+    .await
+    .expect("Failed to insert test user");
+}
+
+pub async fn insert_patient(pool: &SqlitePool, id: &str) {
+    insert_user(pool, id, "patient").await;
+}
+
+// Authorization zaglavlje sa tokenom kakav izdaje auth_service
+pub fn auth_headers(user_id: &str, role: &str) -> HeaderMap {
+    let claims = Claims {
+        sub: user_id.to_string(),
+        email: format!("{}@fisionet.rs", user_id),
+        role: role.to_string(),
+        exp: chrono::Utc::now().timestamp() + 3600,
+    };
+    let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(jwt_secret().as_ref()))
+        .expect("Failed to sign test token");
+
+    let mut headers = HeaderMap::new();
+    headers.insert(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap());
+    headers
+}
+
+// Podrazumevana podešavanja kao u Config::from_env bez env promenljivih
+pub fn test_config() -> Config {
+    Config {
+        database_url: String::new(),
+        server_port: 0,
+        auth_service_url: String::new(),
+        jwt_secret: jwt_secret(),
+        reschedule_requires_approval: false,
+        cancellation_notice_hours: 24,
+        late_cancellation_threshold: 3,
+        restricted_max_future_bookings: 1,
+        // Testovi ne šalju notifikacije
+        chat_service_url: String::new(),
+        notification_service_token: String::new(),
+        waitlist_hold_minutes: 30,
+        clinic_location: "FisioNet".to_string(),
+        clinic_timezone: chrono_tz::Europe::Belgrade,
+        invoice_prefix: "FN".to_string(),
+        default_price_cents: 200000,
+        reminder_offsets_minutes: vec![1440, 60],
+        smtp_url: String::new(),
+        email_from: "FisioNet <no-reply@fisionet.rs>".to_string(),
+        reminder_confirm_url: "http://localhost:3000/reminders".to_string(),
+    }
+}
@@ -0,0 +1,91 @@
+-- Guarantee at the schema level that a physiotherapist cannot hold two active
+-- (non-cancelled) appointments whose time ranges overlap on the same day.
+
+-- Bookings that already overlap are written to appointment_overlap_conflicts so the
+-- clinic can contact the patients and resolve them by hand. Each row pairs a booking
+-- with an earlier inserted active booking it overlaps.
+CREATE TABLE IF NOT EXISTS appointment_overlap_conflicts (
+    appointment_id TEXT NOT NULL,
+    conflicts_with TEXT NOT NULL,
+    previous_status TEXT NOT NULL,
+    cancelled_by_migration INTEGER NOT NULL DEFAULT 0,
+    detected_at TEXT NOT NULL,
+    PRIMARY KEY (appointment_id, conflicts_with)
+);
+
+INSERT OR IGNORE INTO appointment_overlap_conflicts (appointment_id, conflicts_with, previous_status, detected_at)
+SELECT later.id, earlier.id, later.status, strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+FROM appointments later
+JOIN appointments earlier
+    ON earlier.physiotherapist_id = later.physiotherapist_id
+    AND earlier.appointment_date = later.appointment_date
+    AND earlier.rowid < later.rowid
+    AND earlier.start_time < later.end_time
+    AND earlier.end_time > later.start_time
+WHERE later.status != 'cancelled'
+AND earlier.status != 'cancelled';
+
+-- Exact duplicates would break the unique index below, so all but the earliest
+-- inserted row of each slot are cancelled; the conflict rows above say which ones
+UPDATE appointment_overlap_conflicts
+SET cancelled_by_migration = 1
+WHERE appointment_id IN (
+    SELECT id FROM appointments
+    WHERE status != 'cancelled'
+    AND rowid NOT IN (
+        SELECT MIN(rowid) FROM appointments
+        WHERE status != 'cancelled'
+        GROUP BY physiotherapist_id, appointment_date, start_time
+    )
+);
+
+UPDATE appointments
+SET status = 'cancelled'
+WHERE id IN (SELECT appointment_id FROM appointment_overlap_conflicts WHERE cancelled_by_migration = 1);
+
+-- Two active bookings can never start at the same time
+CREATE UNIQUE INDEX IF NOT EXISTS idx_appointments_active_slot
+ON appointments(physiotherapist_id, appointment_date, start_time)
+WHERE status != 'cancelled';
+
+-- Overlapping ranges (e.g. 09:00-09:40 and 09:20-09:40) are rejected by triggers,
+-- since SQLite has no exclusion constraints
+CREATE TRIGGER IF NOT EXISTS trg_appointments_no_overlap_insert
+BEFORE INSERT ON appointments
+WHEN NEW.status != 'cancelled'
+BEGIN
+    SELECT RAISE(ABORT, 'appointment_overlap')
+    WHERE EXISTS (
+        SELECT 1 FROM appointments
+        WHERE physiotherapist_id = NEW.physiotherapist_id
+        AND appointment_date = NEW.appointment_date
+        AND status != 'cancelled'
+        AND start_time < NEW.end_time
+        AND end_time > NEW.start_time
+    );
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_appointments_no_overlap_update
+BEFORE UPDATE OF physiotherapist_id, appointment_date, start_time, end_time, status ON appointments
+-- Only when the booking moves or becomes active again; other status changes keep the slot,
+-- so overlapping rows from before this migration can still be confirmed, completed or cancelled
+WHEN NEW.status != 'cancelled'
+AND (
+    OLD.status = 'cancelled'
+    OR NEW.physiotherapist_id != OLD.physiotherapist_id
+    OR NEW.appointment_date != OLD.appointment_date
+    OR NEW.start_time != OLD.start_time
+    OR NEW.end_time != OLD.end_time
+)
+BEGIN
+    SELECT RAISE(ABORT, 'appointment_overlap')
+    WHERE EXISTS (
+        SELECT 1 FROM appointments
+        WHERE id != NEW.id
+        AND physiotherapist_id = NEW.physiotherapist_id
+        AND appointment_date = NEW.appointment_date
+        AND status != 'cancelled'
+        AND start_time < NEW.end_time
+        AND end_time > NEW.start_time
+    );
+END;
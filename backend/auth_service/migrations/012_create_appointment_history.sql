@@ -0,0 +1,39 @@
+-- History of changes to an appointment's time (reschedules keep the original id)
+CREATE TABLE IF NOT EXISTS appointment_history (
+    id TEXT PRIMARY KEY,
+    appointment_id TEXT NOT NULL,
+    action TEXT NOT NULL,             -- rescheduled
+    old_date TEXT,
+    old_start_time TEXT,
+    old_end_time TEXT,
+    new_date TEXT,
+    new_start_time TEXT,
+    new_end_time TEXT,
+    changed_by TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (appointment_id) REFERENCES appointments (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_appointment_history_appointment_id ON appointment_history(appointment_id);
+
+-- Reschedules requested by patients while physiotherapist approval is required.
+-- The appointment keeps its current slot until the request is approved.
+CREATE TABLE IF NOT EXISTS reschedule_requests (
+    id TEXT PRIMARY KEY,
+    appointment_id TEXT NOT NULL,
+    requested_by TEXT NOT NULL,
+    new_date TEXT NOT NULL,
+    new_start_time TEXT NOT NULL,
+    new_end_time TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending', -- pending, approved, rejected
+    created_at TEXT NOT NULL,
+    decided_at TEXT,
+    FOREIGN KEY (appointment_id) REFERENCES appointments (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_reschedule_requests_appointment_id ON reschedule_requests(appointment_id);
+
+-- At most one open request per appointment
+CREATE UNIQUE INDEX IF NOT EXISTS idx_reschedule_requests_pending
+ON reschedule_requests(appointment_id)
+WHERE status = 'pending';
@@ -0,0 +1,55 @@
+-- Cancellation tracking: who cancelled, when and why.
+-- A patient cancelling inside the notice window gets status 'late_cancellation'.
+ALTER TABLE appointments ADD COLUMN cancelled_at TEXT;
+ALTER TABLE appointments ADD COLUMN cancelled_by TEXT;
+ALTER TABLE appointments ADD COLUMN cancellation_reason TEXT;
+
+-- A late cancellation frees the slot just like a regular one, so the
+-- overlap guards from migration 011 are rebuilt to ignore both statuses
+DROP INDEX IF EXISTS idx_appointments_active_slot;
+DROP TRIGGER IF EXISTS trg_appointments_no_overlap_insert;
+DROP TRIGGER IF EXISTS trg_appointments_no_overlap_update;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_appointments_active_slot
+ON appointments(physiotherapist_id, appointment_date, start_time)
+WHERE status NOT IN ('cancelled', 'late_cancellation');
+
+CREATE TRIGGER IF NOT EXISTS trg_appointments_no_overlap_insert
+BEFORE INSERT ON appointments
+WHEN NEW.status NOT IN ('cancelled', 'late_cancellation')
+BEGIN
+    SELECT RAISE(ABORT, 'appointment_overlap')
+    WHERE EXISTS (
+        SELECT 1 FROM appointments
+        WHERE physiotherapist_id = NEW.physiotherapist_id
+        AND appointment_date = NEW.appointment_date
+        AND status NOT IN ('cancelled', 'late_cancellation')
+        AND start_time < NEW.end_time
+        AND end_time > NEW.start_time
+    );
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_appointments_no_overlap_update
+BEFORE UPDATE OF physiotherapist_id, appointment_date, start_time, end_time, status ON appointments
+WHEN NEW.status NOT IN ('cancelled', 'late_cancellation')
+AND (
+    OLD.status IN ('cancelled', 'late_cancellation')
+    OR NEW.physiotherapist_id != OLD.physiotherapist_id
+    OR NEW.appointment_date != OLD.appointment_date
+    OR NEW.start_time != OLD.start_time
+    OR NEW.end_time != OLD.end_time
+)
+BEGIN
+    SELECT RAISE(ABORT, 'appointment_overlap')
+    WHERE EXISTS (
+        SELECT 1 FROM appointments
+        WHERE id != NEW.id
+        AND physiotherapist_id = NEW.physiotherapist_id
+        AND appointment_date = NEW.appointment_date
+        AND status NOT IN ('cancelled', 'late_cancellation')
+        AND start_time < NEW.end_time
+        AND end_time > NEW.start_time
+    );
+END;
+
+CREATE INDEX IF NOT EXISTS idx_appointments_patient_status ON appointments(patient_id, status);
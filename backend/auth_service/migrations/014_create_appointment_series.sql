@@ -0,0 +1,23 @@
+-- Recurring appointment series (e.g. twice a week for six weeks).
+-- Each occurrence is a regular row in appointments pointing back to its series.
+CREATE TABLE IF NOT EXISTS appointment_series (
+    id TEXT PRIMARY KEY,
+    patient_id TEXT NOT NULL,
+    physiotherapist_id TEXT NOT NULL,
+    start_date TEXT NOT NULL,      -- YYYY-MM-DD, first day the rule applies
+    start_time TEXT NOT NULL,      -- HH:MM
+    duration_minutes INTEGER NOT NULL DEFAULT 20,
+    weekdays TEXT NOT NULL,        -- comma separated: mon,thu
+    occurrences INTEGER,           -- either occurrences or end_date is set
+    end_date TEXT,
+    created_by TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (patient_id) REFERENCES users (id) ON DELETE CASCADE,
+    FOREIGN KEY (physiotherapist_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_appointment_series_patient_id ON appointment_series(patient_id);
+
+ALTER TABLE appointments ADD COLUMN series_id TEXT REFERENCES appointment_series (id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_appointments_series_id ON appointments(series_id);
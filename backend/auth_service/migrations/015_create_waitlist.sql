@@ -0,0 +1,38 @@
+-- Waitlist for fully booked days.
+-- A patient waits for a physiotherapist within a date range and a preferred time window.
+CREATE TABLE IF NOT EXISTS waitlist_entries (
+    id TEXT PRIMARY KEY,
+    patient_id TEXT NOT NULL,
+    physiotherapist_id TEXT NOT NULL,
+    date_from TEXT NOT NULL,            -- YYYY-MM-DD, inclusive
+    date_to TEXT NOT NULL,              -- YYYY-MM-DD, inclusive
+    preferred_start TEXT NOT NULL DEFAULT '08:00',
+    preferred_end TEXT NOT NULL DEFAULT '16:00',
+    status TEXT NOT NULL DEFAULT 'waiting', -- waiting, offered, booked, cancelled
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (patient_id) REFERENCES users (id) ON DELETE CASCADE,
+    FOREIGN KEY (physiotherapist_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_waitlist_entries_physio_status ON waitlist_entries(physiotherapist_id, status);
+CREATE INDEX IF NOT EXISTS idx_waitlist_entries_patient_id ON waitlist_entries(patient_id);
+
+-- A freed slot held for one waitlisted patient until expires_at (UTC, RFC 3339).
+CREATE TABLE IF NOT EXISTS waitlist_holds (
+    id TEXT PRIMARY KEY,
+    entry_id TEXT NOT NULL,
+    patient_id TEXT NOT NULL,
+    physiotherapist_id TEXT NOT NULL,
+    appointment_date TEXT NOT NULL,
+    start_time TEXT NOT NULL,
+    end_time TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'active', -- active, claimed, declined, expired
+    expires_at TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    decided_at TEXT,
+    FOREIGN KEY (entry_id) REFERENCES waitlist_entries (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_waitlist_holds_slot ON waitlist_holds(physiotherapist_id, appointment_date, status);
+CREATE INDEX IF NOT EXISTS idx_waitlist_holds_expires_at ON waitlist_holds(status, expires_at);
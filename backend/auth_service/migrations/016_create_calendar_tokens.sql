@@ -0,0 +1,8 @@
+-- Private, read-only calendar subscription tokens (one per user).
+-- Regenerating the token replaces the row, which invalidates the old feed URL.
+CREATE TABLE IF NOT EXISTS calendar_tokens (
+    user_id TEXT PRIMARY KEY,
+    token TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
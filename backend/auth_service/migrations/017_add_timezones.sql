@@ -0,0 +1,12 @@
+-- Time zone support.
+-- users.timezone is an IANA zone name (e.g. Europe/Belgrade); NULL means the clinic zone.
+ALTER TABLE users ADD COLUMN timezone TEXT;
+
+-- Every appointment keeps its clinic wall-clock date/time (appointment_date, start_time,
+-- end_time) and the UTC instants it maps to (RFC 3339, e.g. 2030-01-15T09:00:00Z).
+-- SQLite has no time zone database, so existing rows are converted by appointment_service
+-- on startup using CLINIC_TIMEZONE; new rows are written with both.
+ALTER TABLE appointments ADD COLUMN starts_at TEXT;
+ALTER TABLE appointments ADD COLUMN ends_at TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_appointments_starts_at ON appointments(starts_at);
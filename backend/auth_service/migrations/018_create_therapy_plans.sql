@@ -0,0 +1,35 @@
+-- Therapy plans ("recepti") a physiotherapist prescribes to a patient.
+-- A plan is active between start_date and end_date; its items repeat every week.
+CREATE TABLE IF NOT EXISTS therapy_plans (
+    id TEXT PRIMARY KEY,
+    patient_id TEXT NOT NULL,
+    physiotherapist_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    goals TEXT,
+    start_date TEXT NOT NULL,      -- YYYY-MM-DD, inclusive
+    end_date TEXT NOT NULL,        -- YYYY-MM-DD, inclusive
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (patient_id) REFERENCES users (id) ON DELETE CASCADE,
+    FOREIGN KEY (physiotherapist_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_therapy_plans_patient_id ON therapy_plans(patient_id);
+CREATE INDEX IF NOT EXISTS idx_therapy_plans_physiotherapist_id ON therapy_plans(physiotherapist_id);
+
+-- exercise_id points to exercises owned by exercise_service, so there is no foreign key.
+CREATE TABLE IF NOT EXISTS therapy_plan_items (
+    id TEXT PRIMARY KEY,
+    plan_id TEXT NOT NULL,
+    exercise_id INTEGER NOT NULL,
+    weekdays TEXT NOT NULL,        -- comma separated: mon,wed,fri
+    sets INTEGER NOT NULL,
+    reps INTEGER NOT NULL,
+    hold_seconds INTEGER NOT NULL DEFAULT 0,
+    times_per_day INTEGER NOT NULL DEFAULT 1,
+    notes TEXT,
+    position INTEGER NOT NULL,
+    FOREIGN KEY (plan_id) REFERENCES therapy_plans (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_therapy_plan_items_plan_id ON therapy_plan_items(plan_id, position);
@@ -0,0 +1,22 @@
+-- Home exercise sessions a patient logs against a therapy plan.
+-- Logs point to the plan item they were done for; plan edits keep item ids, so the link
+-- survives them. A removed item leaves plan_item_id NULL and exercise_id records what was done.
+CREATE TABLE IF NOT EXISTS exercise_logs (
+    id TEXT PRIMARY KEY,
+    plan_id TEXT NOT NULL,
+    plan_item_id TEXT,
+    patient_id TEXT NOT NULL,
+    exercise_id INTEGER NOT NULL,
+    performed_on TEXT NOT NULL,    -- YYYY-MM-DD in the patient's calendar
+    sets_done INTEGER NOT NULL,
+    reps_done INTEGER NOT NULL,
+    pain_level INTEGER NOT NULL CHECK (pain_level BETWEEN 0 AND 10),
+    note TEXT,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (plan_id) REFERENCES therapy_plans (id) ON DELETE CASCADE,
+    FOREIGN KEY (plan_item_id) REFERENCES therapy_plan_items (id) ON DELETE SET NULL,
+    FOREIGN KEY (patient_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_exercise_logs_plan_date ON exercise_logs(plan_id, performed_on);
+CREATE INDEX IF NOT EXISTS idx_exercise_logs_plan_item_id ON exercise_logs(plan_item_id);
@@ -0,0 +1,60 @@
+-- Structured SOAP notes for completed appointments.
+-- Every save is a new version; older versions are kept as written and never deleted,
+-- so an appointment (or its users) with notes cannot be deleted either.
+CREATE TABLE IF NOT EXISTS session_notes (
+    id TEXT PRIMARY KEY,
+    appointment_id TEXT NOT NULL,
+    version INTEGER NOT NULL,          -- 1 is the original note, higher versions are amendments
+    author_id TEXT NOT NULL,
+    subjective TEXT,
+    objective TEXT,
+    assessment TEXT,
+    plan TEXT,
+    amendment_reason TEXT,
+    patient_summary TEXT,              -- patient-facing text, visible to the patient once published
+    summary_published INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (appointment_id) REFERENCES appointments (id) ON DELETE RESTRICT,
+    UNIQUE (appointment_id, version)
+);
+
+-- Body regions and measurements (e.g. knee flexion 95 deg) for one note version.
+CREATE TABLE IF NOT EXISTS session_note_entries (
+    id TEXT PRIMARY KEY,
+    note_id TEXT NOT NULL,
+    body_region TEXT NOT NULL,
+    side TEXT,                         -- left, right, bilateral
+    measurement TEXT,                  -- e.g. flexion, abduction, pain_vas
+    value REAL,
+    unit TEXT,                         -- e.g. deg, cm, kg
+    comment TEXT,
+    position INTEGER NOT NULL,
+    FOREIGN KEY (note_id) REFERENCES session_notes (id) ON DELETE RESTRICT
+);
+
+CREATE INDEX IF NOT EXISTS idx_session_note_entries_note_id ON session_note_entries(note_id, position);
+
+-- Versions are append-only
+CREATE TRIGGER IF NOT EXISTS session_notes_no_update
+BEFORE UPDATE ON session_notes
+BEGIN
+    SELECT RAISE(ABORT, 'session notes are append-only; add an amendment instead');
+END;
+
+CREATE TRIGGER IF NOT EXISTS session_note_entries_no_update
+BEFORE UPDATE ON session_note_entries
+BEGIN
+    SELECT RAISE(ABORT, 'session notes are append-only; add an amendment instead');
+END;
+
+CREATE TRIGGER IF NOT EXISTS session_notes_no_delete
+BEFORE DELETE ON session_notes
+BEGIN
+    SELECT RAISE(ABORT, 'session notes are append-only and cannot be deleted');
+END;
+
+CREATE TRIGGER IF NOT EXISTS session_note_entries_no_delete
+BEFORE DELETE ON session_note_entries
+BEGIN
+    SELECT RAISE(ABORT, 'session notes are append-only and cannot be deleted');
+END;
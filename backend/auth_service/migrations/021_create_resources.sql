@@ -0,0 +1,40 @@
+-- Treatment rooms and machines (ultrasound, shockwave, ...) shared by all physiotherapists.
+-- quantity is how many identical units exist, e.g. 3 treatment rooms.
+CREATE TABLE IF NOT EXISTS resources (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    kind TEXT NOT NULL,                -- room, equipment
+    quantity INTEGER NOT NULL DEFAULT 1 CHECK (quantity > 0),
+    active INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+-- Kinds of treatment; each one may need one or more resources
+CREATE TABLE IF NOT EXISTS appointment_types (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS appointment_type_resources (
+    appointment_type_id TEXT NOT NULL,
+    resource_id TEXT NOT NULL,
+    PRIMARY KEY (appointment_type_id, resource_id),
+    FOREIGN KEY (appointment_type_id) REFERENCES appointment_types (id) ON DELETE CASCADE,
+    FOREIGN KEY (resource_id) REFERENCES resources (id) ON DELETE CASCADE
+);
+
+ALTER TABLE appointments ADD COLUMN appointment_type_id TEXT REFERENCES appointment_types (id) ON DELETE SET NULL;
+
+-- Resources held by a booking; times come from the appointment row
+CREATE TABLE IF NOT EXISTS appointment_resources (
+    appointment_id TEXT NOT NULL,
+    resource_id TEXT NOT NULL,
+    PRIMARY KEY (appointment_id, resource_id),
+    FOREIGN KEY (appointment_id) REFERENCES appointments (id) ON DELETE CASCADE,
+    FOREIGN KEY (resource_id) REFERENCES resources (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_appointment_resources_resource_id ON appointment_resources(resource_id);
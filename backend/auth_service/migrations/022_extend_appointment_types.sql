@@ -0,0 +1,30 @@
+-- Appointment types become a catalog with their own length, buffer and price.
+-- buffer_minutes is extra time after the appointment during which the physiotherapist
+-- cannot be booked (cleanup, notes). Prices are stored in minor units (para).
+ALTER TABLE appointment_types ADD COLUMN description TEXT;
+ALTER TABLE appointment_types ADD COLUMN duration_minutes INTEGER NOT NULL DEFAULT 20;
+ALTER TABLE appointment_types ADD COLUMN buffer_minutes INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE appointment_types ADD COLUMN price_cents INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE appointment_types ADD COLUMN currency TEXT NOT NULL DEFAULT 'RSD';
+ALTER TABLE appointment_types ADD COLUMN online_bookable INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE appointment_types ADD COLUMN active INTEGER NOT NULL DEFAULT 1;
+
+-- Physiotherapists who offer a type; a type without rows here is offered by everyone
+CREATE TABLE IF NOT EXISTS appointment_type_physiotherapists (
+    appointment_type_id TEXT NOT NULL,
+    physiotherapist_id TEXT NOT NULL,
+    PRIMARY KEY (appointment_type_id, physiotherapist_id),
+    FOREIGN KEY (appointment_type_id) REFERENCES appointment_types (id) ON DELETE CASCADE,
+    FOREIGN KEY (physiotherapist_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+-- Buffer copied from the type at booking time, so later catalog edits do not move existing bookings
+ALTER TABLE appointments ADD COLUMN buffer_minutes INTEGER NOT NULL DEFAULT 0;
+
+-- Default catalog
+INSERT OR IGNORE INTO appointment_types (
+    id, name, description, duration_minutes, buffer_minutes, price_cents, online_bookable, created_at, updated_at
+) VALUES
+    ('type-initial-assessment', 'Initial assessment', 'First visit with examination and therapy plan', 60, 10, 400000, 0, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+    ('type-follow-up', 'Follow-up', 'Regular therapy session', 20, 0, 200000, 1, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+    ('type-massage', 'Massage', 'Therapeutic massage', 40, 10, 300000, 1, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), strftime('%Y-%m-%dT%H:%M:%SZ', 'now'));
@@ -0,0 +1,55 @@
+-- Billing for completed appointments. Amounts are in minor units (para), like appointment_types.price_cents.
+-- An invoice without payments is voided when its appointment stops being completed, and
+-- reopened under the same number if the appointment is completed again.
+
+-- Last issued number per prefix and year; invoice numbers run without gaps
+CREATE TABLE IF NOT EXISTS invoice_sequences (
+    prefix TEXT NOT NULL,
+    year INTEGER NOT NULL,
+    last_number INTEGER NOT NULL,
+    PRIMARY KEY (prefix, year)
+);
+
+CREATE TABLE IF NOT EXISTS invoices (
+    id TEXT PRIMARY KEY,
+    number TEXT NOT NULL UNIQUE,       -- e.g. FN-2030-00001
+    patient_id TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'paid', 'void')),
+    currency TEXT NOT NULL,
+    total_cents INTEGER NOT NULL CHECK (total_cents >= 0),
+    issued_at TEXT NOT NULL,
+    issue_date TEXT NOT NULL,          -- clinic wall-clock date printed on the invoice
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (patient_id) REFERENCES users (id)
+);
+
+-- One line per billed appointment; price is copied from the type when the appointment is completed
+CREATE TABLE IF NOT EXISTS invoice_lines (
+    id TEXT PRIMARY KEY,
+    invoice_id TEXT NOT NULL,
+    appointment_id TEXT NOT NULL UNIQUE,
+    description TEXT NOT NULL,
+    quantity INTEGER NOT NULL DEFAULT 1 CHECK (quantity > 0),
+    unit_price_cents INTEGER NOT NULL CHECK (unit_price_cents >= 0),
+    amount_cents INTEGER NOT NULL CHECK (amount_cents >= 0),
+    position INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY (invoice_id) REFERENCES invoices (id) ON DELETE CASCADE,
+    FOREIGN KEY (appointment_id) REFERENCES appointments (id)
+);
+
+CREATE TABLE IF NOT EXISTS payments (
+    id TEXT PRIMARY KEY,
+    invoice_id TEXT NOT NULL,
+    amount_cents INTEGER NOT NULL CHECK (amount_cents > 0),
+    method TEXT NOT NULL CHECK (method IN ('cash', 'card', 'insurance')),
+    reference TEXT,                    -- card slip or insurance claim number
+    recorded_by TEXT NOT NULL,
+    paid_at TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (invoice_id) REFERENCES invoices (id) ON DELETE CASCADE,
+    FOREIGN KEY (recorded_by) REFERENCES users (id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_invoices_patient_id ON invoices(patient_id);
+CREATE INDEX IF NOT EXISTS idx_invoice_lines_invoice_id ON invoice_lines(invoice_id);
+CREATE INDEX IF NOT EXISTS idx_payments_invoice_id ON payments(invoice_id);
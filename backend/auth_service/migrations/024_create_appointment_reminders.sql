@@ -0,0 +1,16 @@
+-- Reminders sent before appointments, one row per appointment, offset and start time.
+-- The row is written once the reminder reached at least one channel, so a failed send is retried
+-- on the next pass and a delivered one is not repeated after a restart; a rescheduled appointment
+-- has a new starts_at and gets its reminders again.
+CREATE TABLE IF NOT EXISTS appointment_reminders (
+    id TEXT PRIMARY KEY,
+    appointment_id TEXT NOT NULL,
+    offset_minutes INTEGER NOT NULL,   -- e.g. 1440 for a day before, 60 for an hour before
+    starts_at TEXT NOT NULL,           -- appointment start the reminder was sent for
+    token TEXT NOT NULL UNIQUE,        -- lets the patient confirm from the reminder without logging in
+    channels TEXT NOT NULL,            -- channels it reached: chat, email, chat,email
+    sent_at TEXT NOT NULL,
+    confirmed_at TEXT,
+    FOREIGN KEY (appointment_id) REFERENCES appointments (id) ON DELETE CASCADE,
+    UNIQUE (appointment_id, offset_minutes, starts_at)
+);
@@ -0,0 +1,46 @@
+-- The overlap guards from migration 013 compare start_time/end_time only, while the
+-- handlers also keep each appointment's buffer_minutes (migration 022) free.
+-- They are rebuilt so that the database enforces the buffers too: an appointment
+-- blocks the physiotherapist from start_time until end_time + buffer_minutes.
+DROP TRIGGER IF EXISTS trg_appointments_no_overlap_insert;
+DROP TRIGGER IF EXISTS trg_appointments_no_overlap_update;
+
+CREATE TRIGGER IF NOT EXISTS trg_appointments_no_overlap_insert
+BEFORE INSERT ON appointments
+WHEN NEW.status NOT IN ('cancelled', 'late_cancellation')
+BEGIN
+    SELECT RAISE(ABORT, 'appointment_overlap')
+    WHERE EXISTS (
+        SELECT 1 FROM appointments
+        WHERE physiotherapist_id = NEW.physiotherapist_id
+        AND appointment_date = NEW.appointment_date
+        AND status NOT IN ('cancelled', 'late_cancellation')
+        AND start_time < strftime('%H:%M', NEW.end_time, '+' || NEW.buffer_minutes || ' minutes')
+        AND strftime('%H:%M', end_time, '+' || buffer_minutes || ' minutes') > NEW.start_time
+    );
+END;
+
+-- A longer buffer can also make an appointment collide with the next one
+CREATE TRIGGER IF NOT EXISTS trg_appointments_no_overlap_update
+BEFORE UPDATE OF physiotherapist_id, appointment_date, start_time, end_time, buffer_minutes, status ON appointments
+WHEN NEW.status NOT IN ('cancelled', 'late_cancellation')
+AND (
+    OLD.status IN ('cancelled', 'late_cancellation')
+    OR NEW.physiotherapist_id != OLD.physiotherapist_id
+    OR NEW.appointment_date != OLD.appointment_date
+    OR NEW.start_time != OLD.start_time
+    OR NEW.end_time != OLD.end_time
+    OR NEW.buffer_minutes > OLD.buffer_minutes
+)
+BEGIN
+    SELECT RAISE(ABORT, 'appointment_overlap')
+    WHERE EXISTS (
+        SELECT 1 FROM appointments
+        WHERE id != NEW.id
+        AND physiotherapist_id = NEW.physiotherapist_id
+        AND appointment_date = NEW.appointment_date
+        AND status NOT IN ('cancelled', 'late_cancellation')
+        AND start_time < strftime('%H:%M', NEW.end_time, '+' || NEW.buffer_minutes || ' minutes')
+        AND strftime('%H:%M', end_time, '+' || buffer_minutes || ' minutes') > NEW.start_time
+    );
+END;
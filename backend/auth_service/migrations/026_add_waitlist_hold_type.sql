@@ -0,0 +1,4 @@
+-- A hold offers the freed slot as it was booked: the claimed appointment gets the same
+-- appointment type (and with it the resources it needs) and the same buffer.
+ALTER TABLE waitlist_holds ADD COLUMN appointment_type_id TEXT REFERENCES appointment_types (id);
+ALTER TABLE waitlist_holds ADD COLUMN buffer_minutes INTEGER NOT NULL DEFAULT 0;
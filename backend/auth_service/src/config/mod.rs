@@ -31,11 +31,12 @@ impl std::str::FromStr for Environment {
 
 impl Config {
     pub fn from_env() -> Self {
+        dotenv::dotenv().ok();
+
         let database_url = env::var("DATABASE_URL")
             .unwrap_or_else(|_| "sqlite:./data/fisionet.db".to_string());
         
-        let jwt_secret = env::var("JWT_SECRET")
-            .unwrap_or_else(|_| "fisionet_jwt_secret_key_2024".to_string());
+        let jwt_secret = crate::utils::jwt_secret();
         
         let server_host = env::var("SERVER_HOST")
             .unwrap_or_else(|_| "127.0.0.1".to_string());
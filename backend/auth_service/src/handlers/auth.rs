@@ -343,6 +343,18 @@ pub async fn update_profile(
         )
     })?;
 
+    if let Some(Some(timezone)) = &payload.timezone {
+        if !timezone.is_empty() && timezone.parse::<chrono_tz::Tz>().is_err() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: "invalid_timezone".to_string(),
+                    message: format!("Unknown time zone: {}", timezone),
+                }),
+            ));
+        }
+    }
+
     // Update user profile
     let updated_user = User::update_profile(&pool, &claims.sub, payload)
         .await
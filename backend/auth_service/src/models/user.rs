@@ -70,6 +70,7 @@ pub struct User {
     pub years_of_experience: Option<i32>,
     pub education: Option<String>,
     pub bio: Option<String>,
+    pub timezone: Option<String>, // IANA zone, None = clinic time zone
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -96,6 +97,17 @@ pub struct UpdateUserRequest {
     pub years_of_experience: Option<i32>,
     pub education: Option<String>,
     pub bio: Option<String>,
+    // null or "" clears the zone and falls back to the clinic time zone
+    #[serde(default, deserialize_with = "present")]
+    pub timezone: Option<Option<String>>,
+}
+
+// Tells a field sent as null apart from a missing one
+fn present<'de, D>(deserializer: D) -> Result<Option<Option<String>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer).map(Some)
 }
 
 #[derive(Serialize, Deserialize)]
@@ -146,6 +158,7 @@ pub struct UserProfile {
     pub years_of_experience: Option<i32>,
     pub education: Option<String>,
     pub bio: Option<String>,
+    pub timezone: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -202,6 +215,7 @@ impl User {
             years_of_experience,
             education: education.clone(),
             bio: bio.clone(),
+            timezone: None,
             created_at: now,
             updated_at: now,
         };
@@ -239,7 +253,7 @@ impl User {
 
     pub async fn find_by_email(pool: &SqlitePool, email: &str) -> Result<Option<User>> {
         let user_row = sqlx::query(
-            "SELECT id, email, password_hash, first_name, last_name, phone, birth_date, height, weight, job_type, profile_image, role, specializations, certifications, years_of_experience, education, bio, created_at, updated_at FROM users WHERE email = ?"
+            "SELECT id, email, password_hash, first_name, last_name, phone, birth_date, height, weight, job_type, profile_image, role, specializations, certifications, years_of_experience, education, bio, timezone, created_at, updated_at FROM users WHERE email = ?"
         )
         .bind(email)
         .fetch_optional(pool)
@@ -270,6 +284,7 @@ impl User {
                 years_of_experience: row.get("years_of_experience"),
                 education: row.get("education"),
                 bio: row.get("bio"),
+                timezone: row.get("timezone"),
                 created_at: row.get("created_at"),
                 updated_at: row.get("updated_at"),
             };
@@ -281,7 +296,7 @@ impl User {
 
     pub async fn find_by_id(pool: &SqlitePool, user_id: &str) -> Result<Option<User>> {
         let user_row = sqlx::query(
-            "SELECT id, email, password_hash, first_name, last_name, phone, birth_date, height, weight, job_type, profile_image, role, specializations, certifications, years_of_experience, education, bio, created_at, updated_at FROM users WHERE id = ?"
+            "SELECT id, email, password_hash, first_name, last_name, phone, birth_date, height, weight, job_type, profile_image, role, specializations, certifications, years_of_experience, education, bio, timezone, created_at, updated_at FROM users WHERE id = ?"
         )
         .bind(user_id)
         .fetch_optional(pool)
@@ -312,6 +327,7 @@ impl User {
                 years_of_experience: row.get("years_of_experience"),
                 education: row.get("education"),
                 bio: row.get("bio"),
+                timezone: row.get("timezone"),
                 created_at: row.get("created_at"),
                 updated_at: row.get("updated_at"),
             };
@@ -347,6 +363,7 @@ impl User {
             years_of_experience: self.years_of_experience,
             education: self.education.clone(),
             bio: self.bio.clone(),
+            timezone: self.timezone.clone(),
             created_at: self.created_at,
         }
     }
@@ -380,6 +397,7 @@ impl User {
                 years_of_experience = COALESCE(?, years_of_experience),
                 education = COALESCE(?, education),
                 bio = COALESCE(?, bio),
+                timezone = CASE WHEN ? THEN NULLIF(?, '') ELSE timezone END,
                 updated_at = ?
             WHERE id = ?
             "#
@@ -397,6 +415,8 @@ impl User {
         .bind(&update_data.years_of_experience)
         .bind(&update_data.education)
         .bind(&update_data.bio)
+        .bind(update_data.timezone.is_some())
+        .bind(update_data.timezone.flatten())
         .bind(&now)
         .bind(user_id)
         .execute(pool)
@@ -411,7 +431,7 @@ impl User {
     // Get all users (admin function)
     pub async fn get_all(pool: &SqlitePool) -> Result<Vec<User>> {
         let users = sqlx::query(
-            "SELECT id, email, password_hash, first_name, last_name, phone, birth_date, height, weight, job_type, profile_image, role, specializations, certifications, years_of_experience, education, bio, created_at, updated_at FROM users ORDER BY created_at DESC"
+            "SELECT id, email, password_hash, first_name, last_name, phone, birth_date, height, weight, job_type, profile_image, role, specializations, certifications, years_of_experience, education, bio, timezone, created_at, updated_at FROM users ORDER BY created_at DESC"
         )
         .fetch_all(pool)
         .await?;
@@ -446,6 +466,7 @@ impl User {
                 years_of_experience: row.get("years_of_experience"),
                 education: row.get("education"),
                 bio: row.get("bio"),
+                timezone: row.get("timezone"),
                 created_at: row.get("created_at"),
                 updated_at: row.get("updated_at"),
             };
@@ -458,7 +479,7 @@ impl User {
     // Get users by role
     pub async fn get_by_role(pool: &SqlitePool, role: &UserRole) -> Result<Vec<User>> {
         let users = sqlx::query(
-            "SELECT id, email, password_hash, first_name, last_name, phone, birth_date, height, weight, job_type, profile_image, role, specializations, certifications, years_of_experience, education, bio, created_at, updated_at FROM users WHERE role = ? ORDER BY first_name, last_name"
+            "SELECT id, email, password_hash, first_name, last_name, phone, birth_date, height, weight, job_type, profile_image, role, specializations, certifications, years_of_experience, education, bio, timezone, created_at, updated_at FROM users WHERE role = ? ORDER BY first_name, last_name"
         )
         .bind(role.to_string())
         .fetch_all(pool)
@@ -494,6 +515,7 @@ impl User {
                 years_of_experience: row.get("years_of_experience"),
                 education: row.get("education"),
                 bio: row.get("bio"),
+                timezone: row.get("timezone"),
                 created_at: row.get("created_at"),
                 updated_at: row.get("updated_at"),
             };
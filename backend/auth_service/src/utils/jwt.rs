@@ -5,9 +5,14 @@ use chrono::Utc;
 use crate::models::User;
 
 // JWT Configuration
-const JWT_SECRET: &str = "fisionet_jwt_secret_key_2024";
+const DEV_JWT_SECRET: &str = "fisionet_jwt_secret_key_2024";
 const JWT_EXPIRATION: i64 = 24 * 60 * 60; // 24 hours in seconds
 
+// The other services verify tokens with the same JWT_SECRET
+pub fn jwt_secret() -> String {
+    std::env::var("JWT_SECRET").unwrap_or_else(|_| DEV_JWT_SECRET.to_string())
+}
+
 // JWT Claims
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
@@ -29,7 +34,7 @@ pub fn generate_jwt_token(user: &User) -> Result<String> {
     let token = encode(
         &Header::default(),
         &claims,
-        &EncodingKey::from_secret(JWT_SECRET.as_ref()),
+        &EncodingKey::from_secret(jwt_secret().as_ref()),
     )?;
 
     Ok(token)
@@ -38,7 +43,7 @@ pub fn generate_jwt_token(user: &User) -> Result<String> {
 pub fn verify_jwt_token(token: &str) -> Result<Claims> {
     let token_data = decode::<Claims>(
         token,
-        &DecodingKey::from_secret(JWT_SECRET.as_ref()),
+        &DecodingKey::from_secret(jwt_secret().as_ref()),
         &Validation::default(),
     )?;
 
@@ -0,0 +1,51 @@
+use axum::{
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+
+use crate::models::ErrorResponse;
+
+pub use common::jwt::Claims;
+use common::jwt::verify_jwt_token;
+
+fn unauthorized(error: &str) -> (StatusCode, Json<ErrorResponse>) {
+    (StatusCode::UNAUTHORIZED, Json(ErrorResponse { error: error.to_string() }))
+}
+
+// Claims from the "Authorization: Bearer <token>" header
+pub fn authenticate(headers: &HeaderMap) -> Result<Claims, (StatusCode, Json<ErrorResponse>)> {
+    let token = headers
+        .get("authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .ok_or_else(|| unauthorized("Missing or invalid authorization header"))?;
+
+    verify_jwt_token(token).map_err(|_| unauthorized("Invalid token"))
+}
+
+// Only the user themselves (or an admin) reads their notifications
+pub fn ensure_user(claims: &Claims, user_id: &str) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if claims.sub == user_id || claims.role == "admin" {
+        return Ok(());
+    }
+
+    Err((StatusCode::FORBIDDEN, Json(ErrorResponse { error: "Access denied".to_string() })))
+}
+
+// Calls from other services carry the shared token in "x-service-token"
+pub fn ensure_service(headers: &HeaderMap, expected: &str) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    let provided = headers
+        .get("x-service-token")
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or_default();
+
+    if expected.is_empty() || !constant_time_eq(provided.as_bytes(), expected.as_bytes()) {
+        return Err(unauthorized("Invalid service token"));
+    }
+
+    Ok(())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
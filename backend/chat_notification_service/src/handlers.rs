@@ -1,6 +1,6 @@
 use axum::{
     extract::{ws::{WebSocket, WebSocketUpgrade, Message as WsMessage}, State, Path, Query},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::Response,
     Json,
 };
@@ -12,6 +12,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::mpsc;
 
 use crate::{
+    auth::{authenticate, ensure_service, ensure_user},
     models::*,
     state::AppState,
 };
@@ -367,3 +368,90 @@ pub async fn get_unread_count(
 
     Ok(Json(serde_json::json!({ "unread_count": total_unread })))
 }
+
+// Create a notification for a user (called by other services with the service token)
+pub async fn create_notification(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<CreateNotificationRequest>,
+) -> Result<(StatusCode, Json<Notification>), (StatusCode, Json<ErrorResponse>)> {
+    ensure_service(&headers, &state.service_token)?;
+
+    let notification = Notification {
+        id: Uuid::new_v4().to_string(),
+        user_id: req.user_id,
+        notification_type: req.notification_type,
+        title: req.title,
+        body: req.body,
+        data: req.data,
+        timestamp: get_timestamp(),
+        read: false,
+    };
+
+    let notification_json = serde_json::to_string(&notification)
+        .map_err(|e| (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e.to_string() })
+        ))?;
+
+    // Store notification in Redis
+    state.redis.set(&format!("notification:{}", notification.id), &notification_json)
+        .await
+        .map_err(|e| (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e.to_string() })
+        ))?;
+    state.redis.rpush(&format!("user:{}:notifications", notification.user_id), &notification.id)
+        .await
+        .map_err(|e| (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e.to_string() })
+        ))?;
+
+    // Push to user via WebSocket if online
+    {
+        let connections = state.ws_connections.lock().await;
+        if let Some(tx) = connections.get(&notification.user_id) {
+            let ws_msg = serde_json::json!({
+                "message_type": "notification",
+                "data": &notification
+            });
+            let _ = tx.send(ws_msg.to_string());
+        }
+    }
+
+    Ok((StatusCode::CREATED, Json(notification)))
+}
+
+// Get user's latest notifications, newest first
+pub async fn get_notifications(
+    Path(user_id): Path<String>,
+    Query(query): Query<MessagesQuery>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<NotificationsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = authenticate(&headers)?;
+    ensure_user(&claims, &user_id)?;
+
+    let limit = query.limit.unwrap_or(50) as isize;
+
+    let notification_ids: Vec<String> = state.redis
+        .lrange(&format!("user:{}:notifications", user_id), -limit, -1)
+        .await
+        .map_err(|e| (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e.to_string() })
+        ))?;
+
+    let mut notifications = Vec::new();
+    for notification_id in notification_ids.iter().rev() {
+        let key = format!("notification:{}", notification_id);
+        if let Ok(Some(json)) = state.redis.get::<String>(&key).await {
+            if let Ok(notification) = serde_json::from_str::<Notification>(&json) {
+                notifications.push(notification);
+            }
+        }
+    }
+
+    Ok(Json(NotificationsResponse { notifications }))
+}
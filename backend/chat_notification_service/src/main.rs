@@ -10,6 +10,7 @@ use tower_http::cors::CorsLayer;
 use tracing_subscriber;
 use sqlx::sqlite::SqlitePoolOptions;
 
+mod auth;
 mod handlers;
 mod models;
 mod state;
@@ -49,11 +50,18 @@ async fn main() {
 
     tracing::info!("Connected to database");
 
+    // Token that appointment_service and other services use to create notifications
+    let service_token = std::env::var("NOTIFICATION_SERVICE_TOKEN").unwrap_or_default();
+    if service_token.is_empty() {
+        tracing::warn!("NOTIFICATION_SERVICE_TOKEN is not set; POST /notifications will be rejected");
+    }
+
     // Create app state
     let state = Arc::new(AppState {
         redis,
         db_pool,
         ws_connections: Arc::new(Mutex::new(HashMap::new())),
+        service_token,
     });
 
     // Build application routes
@@ -68,6 +76,9 @@ async fn main() {
         .route("/users/:user_id/conversations/:conversation_id/messages", get(get_messages))
         .route("/users/:user_id/conversations/:conversation_id/read", post(mark_conversation_read))
         .route("/users/:user_id/unread", get(get_unread_count))
+        .route("/users/:user_id/notifications", get(get_notifications))
+        // Notifications from other services
+        .route("/notifications", post(create_notification))
         .with_state(state)
         .layer(CorsLayer::permissive());
 
@@ -59,3 +59,31 @@ pub struct ConversationWithUser {
 pub struct MessagesResponse {
     pub messages: Vec<Message>,
 }
+
+// System notifications sent by other services (waitlist offers, reminders, ...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub id: String,
+    pub user_id: String,
+    pub notification_type: String,
+    pub title: String,
+    pub body: String,
+    pub data: serde_json::Value,
+    pub timestamp: i64, // Unix timestamp
+    pub read: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateNotificationRequest {
+    pub user_id: String,
+    pub notification_type: String,
+    pub title: String,
+    pub body: String,
+    #[serde(default)]
+    pub data: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotificationsResponse {
+    pub notifications: Vec<Notification>,
+}
@@ -10,6 +10,8 @@ pub struct AppState {
     pub redis: RedisClient,
     pub db_pool: SqlitePool,
     pub ws_connections: Arc<Mutex<HashMap<String, WsSender>>>,
+    // Shared secret other services send on POST /notifications (empty rejects all)
+    pub service_token: String,
 }
 
 pub struct RedisClient {
@@ -0,0 +1,32 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use jsonwebtoken::{decode, DecodingKey, Validation, Algorithm};
+
+// Same claims that auth_service puts into its tokens
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,    // user ID
+    pub email: String,
+    pub role: String,
+    pub exp: i64,       // expiration time
+}
+
+// Development fallback, the same one auth_service uses when JWT_SECRET is not set
+pub const DEV_JWT_SECRET: &str = "fisionet_jwt_secret_key_2024";
+
+// Must match the secret auth_service signs tokens with
+pub fn jwt_secret() -> String {
+    std::env::var("JWT_SECRET").unwrap_or_else(|_| DEV_JWT_SECRET.to_string())
+}
+
+pub fn verify_jwt_token(token: &str) -> Result<Claims> {
+    let secret = jwt_secret();
+    
+    let token_data = decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(secret.as_ref()),
+        &Validation::new(Algorithm::HS256),
+    ).map_err(|e| anyhow!("Invalid token: {}", e))?;
+
+    Ok(token_data.claims)
+}
@@ -0,0 +1,2 @@
+// Code shared by the services that accept auth_service tokens
+pub mod jwt;
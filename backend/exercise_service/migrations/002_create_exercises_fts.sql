@@ -0,0 +1,32 @@
+-- Migration: full-text index over exercises for GET /exercises/search
+-- remove_diacritics 2 folds č/ć/š/ž so "cucanj" finds "čučanj". The index reads its
+-- text from the exercises table (external content) and triggers keep it in sync.
+CREATE VIRTUAL TABLE IF NOT EXISTS exercises_fts USING fts5(
+    title,
+    description,
+    instructions,
+    target_muscles,
+    content='exercises',
+    content_rowid='id',
+    tokenize='unicode61 remove_diacritics 2'
+);
+
+CREATE TRIGGER IF NOT EXISTS exercises_fts_after_insert AFTER INSERT ON exercises BEGIN
+    INSERT INTO exercises_fts (rowid, title, description, instructions, target_muscles)
+    VALUES (new.id, new.title, new.description, new.instructions, new.target_muscles);
+END;
+
+CREATE TRIGGER IF NOT EXISTS exercises_fts_after_delete AFTER DELETE ON exercises BEGIN
+    INSERT INTO exercises_fts (exercises_fts, rowid, title, description, instructions, target_muscles)
+    VALUES ('delete', old.id, old.title, old.description, old.instructions, old.target_muscles);
+END;
+
+CREATE TRIGGER IF NOT EXISTS exercises_fts_after_update AFTER UPDATE ON exercises BEGIN
+    INSERT INTO exercises_fts (exercises_fts, rowid, title, description, instructions, target_muscles)
+    VALUES ('delete', old.id, old.title, old.description, old.instructions, old.target_muscles);
+    INSERT INTO exercises_fts (rowid, title, description, instructions, target_muscles)
+    VALUES (new.id, new.title, new.description, new.instructions, new.target_muscles);
+END;
+
+-- Index rows that existed before this migration
+INSERT INTO exercises_fts (exercises_fts) VALUES ('rebuild');
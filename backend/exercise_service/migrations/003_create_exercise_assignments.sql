@@ -0,0 +1,12 @@
+-- Migration: specialized exercises recommended to specific patients
+-- A patient sees a specialized exercise only while an assignment row exists for them.
+CREATE TABLE IF NOT EXISTS exercise_assignments (
+    exercise_id INTEGER NOT NULL,
+    patient_id TEXT NOT NULL,
+    assigned_by TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (exercise_id, patient_id),
+    FOREIGN KEY (exercise_id) REFERENCES exercises(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_exercise_assignments_patient_id ON exercise_assignments(patient_id);
@@ -0,0 +1,227 @@
+-- Migration: managed taxonomy for categories, difficulty levels, muscles and equipment.
+-- Exercises reference entries by id. The old text columns stay as a display copy of the entry names
+-- (they feed full-text search) and are rewritten from the taxonomy whenever it changes.
+
+CREATE TABLE IF NOT EXISTS exercise_categories (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    parent_id INTEGER,
+    name TEXT NOT NULL,
+    slug TEXT NOT NULL UNIQUE,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (parent_id) REFERENCES exercise_categories(id)
+);
+
+CREATE TABLE IF NOT EXISTS difficulty_levels (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    slug TEXT NOT NULL UNIQUE,
+    position INTEGER NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS muscles (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    slug TEXT NOT NULL UNIQUE,
+    created_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS equipment (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    slug TEXT NOT NULL UNIQUE,
+    created_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS exercise_muscles (
+    exercise_id INTEGER NOT NULL,
+    muscle_id INTEGER NOT NULL,
+    position INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (exercise_id, muscle_id),
+    FOREIGN KEY (exercise_id) REFERENCES exercises(id) ON DELETE CASCADE,
+    FOREIGN KEY (muscle_id) REFERENCES muscles(id)
+);
+
+CREATE TABLE IF NOT EXISTS exercise_equipment (
+    exercise_id INTEGER NOT NULL,
+    equipment_id INTEGER NOT NULL,
+    position INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (exercise_id, equipment_id),
+    FOREIGN KEY (exercise_id) REFERENCES exercises(id) ON DELETE CASCADE,
+    FOREIGN KEY (equipment_id) REFERENCES equipment(id)
+);
+
+-- Other spellings that resolve to an entry, e.g. the Serbian name. Aliases are stored lowercase.
+CREATE TABLE IF NOT EXISTS taxonomy_aliases (
+    kind TEXT NOT NULL CHECK (kind IN ('category', 'difficulty', 'muscle', 'equipment')),
+    alias TEXT NOT NULL,
+    target_id INTEGER NOT NULL,
+    PRIMARY KEY (kind, alias)
+);
+
+ALTER TABLE exercises ADD COLUMN category_id INTEGER REFERENCES exercise_categories(id);
+ALTER TABLE exercises ADD COLUMN difficulty_id INTEGER REFERENCES difficulty_levels(id);
+
+CREATE INDEX IF NOT EXISTS idx_exercise_categories_parent_id ON exercise_categories(parent_id);
+CREATE INDEX IF NOT EXISTS idx_exercises_category_id ON exercises(category_id);
+CREATE INDEX IF NOT EXISTS idx_exercises_difficulty_id ON exercises(difficulty_id);
+CREATE INDEX IF NOT EXISTS idx_exercise_muscles_muscle_id ON exercise_muscles(muscle_id);
+CREATE INDEX IF NOT EXISTS idx_exercise_equipment_equipment_id ON exercise_equipment(equipment_id);
+
+-- Starting entries; the values the frontend offers plus the spine hierarchy
+INSERT OR IGNORE INTO difficulty_levels (name, slug, position, created_at) VALUES
+    ('Beginner', 'beginner', 1, strftime('%s', 'now')),
+    ('Intermediate', 'intermediate', 2, strftime('%s', 'now')),
+    ('Advanced', 'advanced', 3, strftime('%s', 'now'));
+
+INSERT OR IGNORE INTO exercise_categories (name, slug, created_at) VALUES
+    ('Strength', 'strength', strftime('%s', 'now')),
+    ('Flexibility', 'flexibility', strftime('%s', 'now')),
+    ('Balance', 'balance', strftime('%s', 'now')),
+    ('Cardio', 'cardio', strftime('%s', 'now')),
+    ('Rehabilitation', 'rehabilitation', strftime('%s', 'now')),
+    ('Back', 'back', strftime('%s', 'now')),
+    ('Spine', 'spine', strftime('%s', 'now'));
+
+INSERT OR IGNORE INTO exercise_categories (parent_id, name, slug, created_at)
+SELECT id, 'Cervical', 'cervical', strftime('%s', 'now') FROM exercise_categories WHERE slug = 'spine';
+INSERT OR IGNORE INTO exercise_categories (parent_id, name, slug, created_at)
+SELECT id, 'Lumbar', 'lumbar', strftime('%s', 'now') FROM exercise_categories WHERE slug = 'spine';
+
+INSERT OR IGNORE INTO taxonomy_aliases (kind, alias, target_id)
+SELECT 'difficulty', alias.value, d.id
+FROM (SELECT 'početnik' AS value, 'beginner' AS slug
+      UNION ALL SELECT 'pocetnik', 'beginner'
+      UNION ALL SELECT 'napredni', 'intermediate'
+      UNION ALL SELECT 'ekspert', 'advanced') alias
+JOIN difficulty_levels d ON d.slug = alias.slug;
+
+INSERT OR IGNORE INTO taxonomy_aliases (kind, alias, target_id)
+SELECT 'category', alias.value, c.id
+FROM (SELECT 'snaga' AS value, 'strength' AS slug
+      UNION ALL SELECT 'istezanje', 'flexibility'
+      UNION ALL SELECT 'fleksibilnost', 'flexibility'
+      UNION ALL SELECT 'ravnoteža', 'balance'
+      UNION ALL SELECT 'ravnoteza', 'balance'
+      UNION ALL SELECT 'kardio', 'cardio'
+      UNION ALL SELECT 'rehabilitacija', 'rehabilitation'
+      UNION ALL SELECT 'leđa', 'back'
+      UNION ALL SELECT 'ledja', 'back'
+      UNION ALL SELECT 'kičma', 'spine'
+      UNION ALL SELECT 'kicma', 'spine'
+      UNION ALL SELECT 'lumbalni deo', 'lumbar'
+      UNION ALL SELECT 'vratni deo', 'cervical') alias
+JOIN exercise_categories c ON c.slug = alias.slug;
+
+-- Map the free-text values of exercises that have no references yet. Values that differ only in
+-- case or surrounding spaces, or that are a known alias, land on the same entry.
+DROP TABLE IF EXISTS temp.unmapped_exercises;
+CREATE TEMP TABLE unmapped_exercises AS
+SELECT id FROM exercises e
+WHERE e.category_id IS NULL
+   OR e.difficulty_id IS NULL
+   OR (json_valid(e.target_muscles) AND json_array_length(e.target_muscles) > 0
+       AND NOT EXISTS (SELECT 1 FROM exercise_muscles em WHERE em.exercise_id = e.id))
+   OR (json_valid(e.equipment_needed) AND json_array_length(e.equipment_needed) > 0
+       AND NOT EXISTS (SELECT 1 FROM exercise_equipment ee WHERE ee.exercise_id = e.id));
+
+INSERT OR IGNORE INTO exercise_categories (name, slug, created_at)
+SELECT trim(e.category), replace(lower(trim(e.category)), ' ', '-'), strftime('%s', 'now')
+FROM exercises e
+WHERE e.category_id IS NULL AND trim(e.category) <> ''
+  AND NOT EXISTS (SELECT 1 FROM exercise_categories c WHERE c.name = trim(e.category) COLLATE NOCASE)
+  AND NOT EXISTS (SELECT 1 FROM taxonomy_aliases a WHERE a.kind = 'category' AND a.alias = lower(trim(e.category)))
+GROUP BY replace(lower(trim(e.category)), ' ', '-');
+
+UPDATE exercises SET category_id = (
+    SELECT c.id FROM exercise_categories c
+    WHERE c.slug = replace(lower(trim(exercises.category)), ' ', '-')
+       OR c.name = trim(exercises.category) COLLATE NOCASE
+       OR c.id IN (SELECT target_id FROM taxonomy_aliases WHERE kind = 'category' AND alias = lower(trim(exercises.category)))
+    ORDER BY c.id
+    LIMIT 1
+)
+WHERE category_id IS NULL;
+
+-- Unknown levels are added after the known ones
+INSERT OR IGNORE INTO difficulty_levels (name, slug, position, created_at)
+SELECT trim(e.difficulty_level), replace(lower(trim(e.difficulty_level)), ' ', '-'),
+       (SELECT COALESCE(MAX(position), 0) FROM difficulty_levels) + ROW_NUMBER() OVER (ORDER BY MIN(e.id)),
+       strftime('%s', 'now')
+FROM exercises e
+WHERE e.difficulty_id IS NULL AND trim(e.difficulty_level) <> ''
+  AND NOT EXISTS (SELECT 1 FROM difficulty_levels d WHERE d.name = trim(e.difficulty_level) COLLATE NOCASE)
+  AND NOT EXISTS (SELECT 1 FROM taxonomy_aliases a WHERE a.kind = 'difficulty' AND a.alias = lower(trim(e.difficulty_level)))
+GROUP BY replace(lower(trim(e.difficulty_level)), ' ', '-');
+
+UPDATE exercises SET difficulty_id = (
+    SELECT d.id FROM difficulty_levels d
+    WHERE d.slug = replace(lower(trim(exercises.difficulty_level)), ' ', '-')
+       OR d.name = trim(exercises.difficulty_level) COLLATE NOCASE
+       OR d.id IN (SELECT target_id FROM taxonomy_aliases WHERE kind = 'difficulty' AND alias = lower(trim(exercises.difficulty_level)))
+    ORDER BY d.id
+    LIMIT 1
+)
+WHERE difficulty_id IS NULL;
+
+INSERT OR IGNORE INTO muscles (name, slug, created_at)
+SELECT trim(j.value), replace(lower(trim(j.value)), ' ', '-'), strftime('%s', 'now')
+FROM unmapped_exercises u
+JOIN exercises e ON e.id = u.id, json_each(CASE WHEN json_valid(e.target_muscles) THEN e.target_muscles ELSE '[]' END) j
+WHERE trim(j.value) <> ''
+  AND NOT EXISTS (SELECT 1 FROM exercise_muscles em WHERE em.exercise_id = e.id)
+  AND NOT EXISTS (SELECT 1 FROM muscles m WHERE m.name = trim(j.value) COLLATE NOCASE)
+  AND NOT EXISTS (SELECT 1 FROM taxonomy_aliases a WHERE a.kind = 'muscle' AND a.alias = lower(trim(j.value)))
+GROUP BY replace(lower(trim(j.value)), ' ', '-');
+
+INSERT OR IGNORE INTO exercise_muscles (exercise_id, muscle_id, position)
+SELECT e.id,
+       (SELECT m.id FROM muscles m
+        WHERE m.slug = replace(lower(trim(j.value)), ' ', '-')
+           OR m.name = trim(j.value) COLLATE NOCASE
+           OR m.id IN (SELECT target_id FROM taxonomy_aliases WHERE kind = 'muscle' AND alias = lower(trim(j.value)))
+        ORDER BY m.id
+        LIMIT 1),
+       j.key
+FROM unmapped_exercises u
+JOIN exercises e ON e.id = u.id, json_each(CASE WHEN json_valid(e.target_muscles) THEN e.target_muscles ELSE '[]' END) j
+WHERE trim(j.value) <> ''
+  AND NOT EXISTS (SELECT 1 FROM exercise_muscles em WHERE em.exercise_id = e.id);
+
+INSERT OR IGNORE INTO equipment (name, slug, created_at)
+SELECT trim(j.value), replace(lower(trim(j.value)), ' ', '-'), strftime('%s', 'now')
+FROM unmapped_exercises u
+JOIN exercises e ON e.id = u.id, json_each(CASE WHEN json_valid(e.equipment_needed) THEN e.equipment_needed ELSE '[]' END) j
+WHERE trim(j.value) <> ''
+  AND NOT EXISTS (SELECT 1 FROM exercise_equipment ee WHERE ee.exercise_id = e.id)
+  AND NOT EXISTS (SELECT 1 FROM equipment q WHERE q.name = trim(j.value) COLLATE NOCASE)
+  AND NOT EXISTS (SELECT 1 FROM taxonomy_aliases a WHERE a.kind = 'equipment' AND a.alias = lower(trim(j.value)))
+GROUP BY replace(lower(trim(j.value)), ' ', '-');
+
+INSERT OR IGNORE INTO exercise_equipment (exercise_id, equipment_id, position)
+SELECT e.id,
+       (SELECT q.id FROM equipment q
+        WHERE q.slug = replace(lower(trim(j.value)), ' ', '-')
+           OR q.name = trim(j.value) COLLATE NOCASE
+           OR q.id IN (SELECT target_id FROM taxonomy_aliases WHERE kind = 'equipment' AND alias = lower(trim(j.value)))
+        ORDER BY q.id
+        LIMIT 1),
+       j.key
+FROM unmapped_exercises u
+JOIN exercises e ON e.id = u.id, json_each(CASE WHEN json_valid(e.equipment_needed) THEN e.equipment_needed ELSE '[]' END) j
+WHERE trim(j.value) <> ''
+  AND NOT EXISTS (SELECT 1 FROM exercise_equipment ee WHERE ee.exercise_id = e.id);
+
+-- Rewrite the display copy of the mapped exercises with the canonical names
+UPDATE exercises SET
+    category = COALESCE((SELECT name FROM exercise_categories WHERE id = exercises.category_id), category),
+    difficulty_level = COALESCE((SELECT name FROM difficulty_levels WHERE id = exercises.difficulty_id), difficulty_level),
+    target_muscles = (SELECT json_group_array(name) FROM (
+        SELECT m.name FROM exercise_muscles em JOIN muscles m ON m.id = em.muscle_id
+        WHERE em.exercise_id = exercises.id ORDER BY em.position, m.id)),
+    equipment_needed = (SELECT json_group_array(name) FROM (
+        SELECT q.name FROM exercise_equipment ee JOIN equipment q ON q.id = ee.equipment_id
+        WHERE ee.exercise_id = exercises.id ORDER BY ee.position, q.id))
+WHERE id IN (SELECT id FROM unmapped_exercises);
+
+DROP TABLE temp.unmapped_exercises;
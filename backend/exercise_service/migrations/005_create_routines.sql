@@ -0,0 +1,30 @@
+-- Migration: routines, ordered sequences of exercises built by physiotherapists
+-- Templates are shared with all staff; other routines are visible only to their owner (and admins).
+CREATE TABLE IF NOT EXISTS routines (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title TEXT NOT NULL,
+    description TEXT,
+    is_template INTEGER NOT NULL DEFAULT 0,
+    owner_id TEXT NOT NULL,
+    cloned_from INTEGER,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    FOREIGN KEY (cloned_from) REFERENCES routines(id) ON DELETE SET NULL
+);
+
+CREATE TABLE IF NOT EXISTS routine_exercises (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    routine_id INTEGER NOT NULL,
+    exercise_id INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    sets INTEGER,
+    reps INTEGER,
+    rest_seconds INTEGER,          -- rest between sets
+    notes TEXT,
+    FOREIGN KEY (routine_id) REFERENCES routines(id) ON DELETE CASCADE,
+    FOREIGN KEY (exercise_id) REFERENCES exercises(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_routines_owner_id ON routines(owner_id);
+CREATE INDEX IF NOT EXISTS idx_routine_exercises_routine_id ON routine_exercises(routine_id, position);
+CREATE INDEX IF NOT EXISTS idx_routine_exercises_exercise_id ON routine_exercises(exercise_id);
@@ -0,0 +1,11 @@
+-- Migration: resized copies of each uploaded image
+-- exercise_images.url keeps pointing at the full variant; images uploaded before variants existed have no rows here.
+CREATE TABLE IF NOT EXISTS exercise_image_variants (
+    image_id INTEGER NOT NULL,
+    variant TEXT NOT NULL CHECK (variant IN ('thumbnail', 'medium', 'full')),
+    url TEXT NOT NULL,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    PRIMARY KEY (image_id, variant),
+    FOREIGN KEY (image_id) REFERENCES exercise_images(id) ON DELETE CASCADE
+);
@@ -0,0 +1,25 @@
+-- Migration: videos uploaded for an exercise and their HLS renditions
+-- One video per exercise; uploading another one replaces it.
+CREATE TABLE IF NOT EXISTS exercise_videos (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    exercise_id INTEGER NOT NULL UNIQUE,
+    status TEXT NOT NULL CHECK (status IN ('pending', 'processing', 'ready', 'failed')),
+    error TEXT,
+    content_type TEXT NOT NULL,
+    size_bytes INTEGER NOT NULL,
+    source_key TEXT NOT NULL,
+    playlist_key TEXT,
+    poster_key TEXT,
+    uploaded_by TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    FOREIGN KEY (exercise_id) REFERENCES exercises(id) ON DELETE CASCADE
+);
+
+-- Every stored object of a video (source, playlist, segments, poster) so that all of them can be removed
+CREATE TABLE IF NOT EXISTS exercise_video_files (
+    video_id INTEGER NOT NULL,
+    storage_key TEXT NOT NULL,
+    PRIMARY KEY (video_id, storage_key),
+    FOREIGN KEY (video_id) REFERENCES exercise_videos(id) ON DELETE CASCADE
+);
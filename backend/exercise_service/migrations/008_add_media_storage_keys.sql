@@ -0,0 +1,4 @@
+-- Migration: media store key of each image file; images from before the media store keep a NULL
+-- key and are still served from ./static
+ALTER TABLE exercise_images ADD COLUMN storage_key TEXT;
+ALTER TABLE exercise_image_variants ADD COLUMN storage_key TEXT;
@@ -0,0 +1,4 @@
+-- Migration: alt text and caption per image, and the image picked as the exercise cover
+ALTER TABLE exercise_images ADD COLUMN alt_text TEXT;
+ALTER TABLE exercise_images ADD COLUMN caption TEXT;
+ALTER TABLE exercises ADD COLUMN cover_image_id INTEGER REFERENCES exercise_images(id) ON DELETE SET NULL;
@@ -1,4 +1,7 @@
+use anyhow::{anyhow, Result};
 use std::env;
+use std::fmt;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -6,6 +9,34 @@ pub struct Config {
     pub server_host: String,
     pub server_port: u16,
     pub environment: Environment,
+    pub media: MediaConfig,
+    pub video: VideoConfig,
+}
+
+// Uploaded exercise videos and the ffmpeg job that turns them into HLS
+#[derive(Debug, Clone)]
+pub struct VideoConfig {
+    pub max_upload_bytes: u64,
+    pub ffmpeg_path: String,
+    // Scratch space for uploads in progress and ffmpeg output
+    pub work_dir: PathBuf,
+}
+
+impl VideoConfig {
+    pub fn from_env() -> Self {
+        let max_upload_mb: u64 = env::var("MAX_VIDEO_UPLOAD_MB")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(500);
+
+        VideoConfig {
+            max_upload_bytes: max_upload_mb * 1024 * 1024,
+            ffmpeg_path: env::var("FFMPEG_PATH").unwrap_or_else(|_| "ffmpeg".to_string()),
+            work_dir: env::var("VIDEO_WORK_DIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| env::temp_dir().join("fisionet-videos")),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -15,6 +46,95 @@ pub enum Environment {
     Test,
 }
 
+// Where uploads are stored; MEDIA_BACKEND selects `local` (default) or `s3`
+#[derive(Clone)]
+pub enum MediaConfig {
+    Local {
+        root: PathBuf,
+        public_url: String,
+        signing_key: String,
+    },
+    S3(S3Config),
+}
+
+#[derive(Clone)]
+pub struct S3Config {
+    // Unset for AWS itself; e.g. http://localhost:9000 for MinIO
+    pub endpoint: Option<String>,
+    pub region: String,
+    pub bucket: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub public_url: String,
+}
+
+// Config is logged at startup, so keys are left out
+impl fmt::Debug for MediaConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MediaConfig::Local { root, public_url, .. } => f
+                .debug_struct("Local")
+                .field("root", root)
+                .field("public_url", public_url)
+                .finish_non_exhaustive(),
+            MediaConfig::S3(s3) => f
+                .debug_struct("S3")
+                .field("endpoint", &s3.endpoint)
+                .field("region", &s3.region)
+                .field("bucket", &s3.bucket)
+                .field("public_url", &s3.public_url)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+impl MediaConfig {
+    pub fn from_env() -> Result<Self> {
+        let backend = env::var("MEDIA_BACKEND").unwrap_or_else(|_| "local".to_string());
+
+        if backend.eq_ignore_ascii_case("s3") {
+            let endpoint = env::var("S3_ENDPOINT").ok().filter(|v| !v.is_empty());
+            let region = env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string());
+            let bucket = env::var("S3_BUCKET").unwrap_or_else(|_| "fisionet-media".to_string());
+            let public_url = env::var("MEDIA_PUBLIC_URL").unwrap_or_else(|_| match &endpoint {
+                Some(endpoint) => format!("{}/{}", endpoint.trim_end_matches('/'), bucket),
+                None => format!("https://{}.s3.{}.amazonaws.com", bucket, region),
+            });
+
+            // Empty credentials would only fail on the first upload
+            let credential = |name: &str| {
+                env::var(name)
+                    .ok()
+                    .filter(|v| !v.is_empty())
+                    .ok_or_else(|| anyhow!("{} must be set when MEDIA_BACKEND is s3", name))
+            };
+
+            Ok(MediaConfig::S3(S3Config {
+                endpoint,
+                region,
+                bucket,
+                access_key_id: credential("S3_ACCESS_KEY_ID")?,
+                secret_access_key: credential("S3_SECRET_ACCESS_KEY")?,
+                public_url,
+            }))
+        } else {
+            // Anyone with the key can sign links to private media, so there is no default
+            let signing_key = env::var("MEDIA_SIGNING_KEY")
+                .ok()
+                .filter(|v| !v.is_empty())
+                .ok_or_else(|| anyhow!("MEDIA_SIGNING_KEY must be set when MEDIA_BACKEND is local"))?;
+
+            Ok(MediaConfig::Local {
+                root: env::var("MEDIA_ROOT")
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|_| PathBuf::from("media")),
+                public_url: env::var("MEDIA_PUBLIC_URL").unwrap_or_else(|_| "/media".to_string()),
+                signing_key,
+            })
+        }
+    }
+}
+
 impl std::str::FromStr for Environment {
     type Err = String;
     
@@ -29,12 +149,12 @@ impl std::str::FromStr for Environment {
 }
 
 impl Config {
-    pub fn from_env() -> Self {
+    pub fn from_env() -> Result<Self> {
         let database_url = env::var("DATABASE_URL")
             .unwrap_or_else(|_| "sqlite:../auth_service/data/fisionet.db".to_string());
         
         let server_host = env::var("SERVER_HOST")
-            .unwrap_or_else(|_| "127.0.0.1".to_string());
+            .unwrap_or_else(|_| "0.0.0.0".to_string());
         
         let server_port = env::var("SERVER_PORT")
             .unwrap_or_else(|_| "8005".to_string())
@@ -46,11 +166,13 @@ impl Config {
             .parse()
             .unwrap_or(Environment::Development);
 
-        Config {
+        Ok(Config {
             database_url,
             server_host,
             server_port,
             environment,
-        }
+            media: MediaConfig::from_env()?,
+            video: VideoConfig::from_env(),
+        })
     }
 }
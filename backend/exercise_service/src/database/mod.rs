@@ -1,5 +1,7 @@
 use sqlx::{SqlitePool, sqlite::SqlitePoolOptions};
 use anyhow::Result;
+use std::fs;
+use std::path::Path;
 use std::time::Duration;
 
 pub async fn create_pool(database_url: &str) -> Result<SqlitePool> {
@@ -11,3 +13,121 @@ pub async fn create_pool(database_url: &str) -> Result<SqlitePool> {
 
     Ok(pool)
 }
+
+// Create the exercises table if needed and run the service's own migrations
+pub async fn prepare_schema(pool: &SqlitePool, migrations_dir: &Path) -> Result<()> {
+    // Check if exercises table exists, create it if not
+    let table_exists = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='exercises'"
+    )
+    .fetch_one(pool)
+    .await?;
+
+    if table_exists == 0 {
+        tracing::info!("Creating exercises table...");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS exercises (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                title TEXT NOT NULL,
+                description TEXT NOT NULL,
+                category TEXT NOT NULL,
+                difficulty_level TEXT NOT NULL,
+                duration_minutes INTEGER,
+                equipment_needed TEXT NOT NULL,
+                instructions TEXT NOT NULL,
+                image_url TEXT,
+                video_url TEXT,
+                youtube_url TEXT,
+                target_muscles TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                is_specialized INTEGER NOT NULL DEFAULT 0
+            )
+            "#
+        )
+        .execute(pool)
+        .await?;
+
+        // Create indexes
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_exercises_category ON exercises(category)")
+            .execute(pool)
+            .await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_exercises_difficulty ON exercises(difficulty_level)")
+            .execute(pool)
+            .await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_exercises_created_at ON exercises(created_at)")
+            .execute(pool)
+            .await?;
+
+        tracing::info!("Exercises table created successfully");
+    } else {
+        tracing::info!("Exercises table already exists");
+    }
+
+    // Run migrations in file name order, each once; applied ones are recorded in exercise_migrations
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS exercise_migrations (name TEXT PRIMARY KEY, applied_at INTEGER NOT NULL)"
+    )
+    .execute(pool)
+    .await?;
+
+    let mut migration_files = fs::read_dir(migrations_dir)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "sql"))
+        .collect::<Vec<_>>();
+    migration_files.sort();
+
+    for migration_path in migration_files {
+        let name = migration_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+            .to_string();
+        let applied = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM exercise_migrations WHERE name = ?")
+            .bind(&name)
+            .fetch_one(pool)
+            .await?;
+        if applied > 0 {
+            continue;
+        }
+
+        let migration_sql = fs::read_to_string(&migration_path)?;
+        tracing::info!("Running migration: {:?}", migration_path);
+        let mut tx = pool.begin().await?;
+        sqlx::raw_sql(&migration_sql).execute(&mut *tx).await?;
+        sqlx::query("INSERT INTO exercise_migrations (name, applied_at) VALUES (?, CAST(strftime('%s', 'now') AS INTEGER))")
+            .bind(&name)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+    }
+
+    tracing::info!("Migrations completed successfully");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_pool;
+
+    #[tokio::test]
+    async fn migrations_run_once() {
+        let pool = test_pool().await;
+        let applied: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM exercise_migrations").fetch_one(&pool).await.unwrap();
+        assert!(applied > 0);
+
+        // A migration that fails when repeated, e.g. ALTER TABLE, only runs on the first start
+        let dir = std::env::temp_dir().join(format!("fisionet-exercise-migrations-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("100_add_column.sql"), "ALTER TABLE exercises ADD COLUMN once INTEGER;").unwrap();
+        prepare_schema(&pool, &dir).await.unwrap();
+        prepare_schema(&pool, &dir).await.unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let applied_again: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM exercise_migrations").fetch_one(&pool).await.unwrap();
+        assert_eq!(applied_again, applied + 1);
+    }
+}
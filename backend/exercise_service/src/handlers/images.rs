@@ -0,0 +1,788 @@
+use axum::{
+    extract::{Extension, Json, Multipart, Path},
+    http::{HeaderMap, StatusCode},
+};
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection, SqlitePool};
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::{db_error, error, now, require_staff, storage_error, ApiError, ErrorResponse};
+use crate::images::{process_upload, ImageError, Variant};
+use crate::models::*;
+use crate::storage::{media_url, remove_keys, MediaStore, Visibility};
+
+// Images of specialized exercises are only reachable through signed URLs this long
+pub(crate) const SIGNED_URL_TTL: Duration = Duration::from_secs(60 * 60);
+
+const MAX_ALT_TEXT_CHARS: usize = 250;
+const MAX_CAPTION_CHARS: usize = 1000;
+
+// Whether the exercise is specialized, or 404
+pub(crate) async fn exercise_visibility(pool: &SqlitePool, exercise_id: i64) -> Result<bool, ApiError> {
+    sqlx::query_scalar::<_, bool>("SELECT is_specialized FROM exercises WHERE id = ?")
+        .bind(exercise_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "Exercise not found"))
+}
+
+// Images uploaded before the media store were written to ./static/images and are still served
+// from there; they have no storage key
+pub(super) fn legacy_images_root() -> Result<PathBuf, ApiError> {
+    let current_dir = env::current_dir().map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, format!("Server error: {}", e)))?;
+    Ok(current_dir.join("static").join("images"))
+}
+
+pub(super) fn legacy_file_for_url(root: &std::path::Path, url: &str) -> Option<PathBuf> {
+    url.strip_prefix("/static/images/").map(|name| root.join(name))
+}
+
+// Best-effort cleanup of ./static files, like remove_keys for the media store
+pub(super) async fn remove_legacy_files(paths: &[PathBuf]) {
+    for path in paths {
+        match tokio::fs::remove_file(path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                tracing::warn!("Failed to remove legacy image {:?}: {}", path, e);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct ImageRow {
+    exercise_id: i64,
+    id: i64,
+    thumbnail_url: String,
+    medium_url: String,
+    full_url: String,
+    thumbnail_key: Option<String>,
+    medium_key: Option<String>,
+    full_key: Option<String>,
+    alt_text: Option<String>,
+    caption: Option<String>,
+    is_cover: bool,
+}
+
+// Stored variants of each image of the given exercises, in gallery order
+async fn exercise_images(pool: &SqlitePool, exercise_ids: &[i64]) -> Result<Vec<ImageRow>, ApiError> {
+    if exercise_ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    // Images uploaded before variants existed, or moved out of ./static, use their single file for every size
+    let mut builder = QueryBuilder::<Sqlite>::new(
+        r#"
+        SELECT i.exercise_id, i.id,
+               COALESCE((SELECT url FROM exercise_image_variants WHERE image_id = i.id AND variant = 'thumbnail'), i.url) AS thumbnail_url,
+               COALESCE((SELECT url FROM exercise_image_variants WHERE image_id = i.id AND variant = 'medium'), i.url) AS medium_url,
+               COALESCE((SELECT url FROM exercise_image_variants WHERE image_id = i.id AND variant = 'full'), i.url) AS full_url,
+               COALESCE((SELECT storage_key FROM exercise_image_variants WHERE image_id = i.id AND variant = 'thumbnail'), i.storage_key) AS thumbnail_key,
+               COALESCE((SELECT storage_key FROM exercise_image_variants WHERE image_id = i.id AND variant = 'medium'), i.storage_key) AS medium_key,
+               COALESCE((SELECT storage_key FROM exercise_image_variants WHERE image_id = i.id AND variant = 'full'), i.storage_key) AS full_key,
+               i.alt_text, i.caption,
+               i.id = COALESCE(e.cover_image_id, (
+                   SELECT id FROM exercise_images WHERE exercise_id = i.exercise_id ORDER BY position, id LIMIT 1
+               )) AS is_cover
+        FROM exercise_images i
+        JOIN exercises e ON e.id = i.exercise_id
+        WHERE i.exercise_id IN (
+        "#
+    );
+    let mut separated = builder.separated(", ");
+    for id in exercise_ids {
+        separated.push_bind(*id);
+    }
+    builder.push(") ORDER BY i.exercise_id, i.position, i.id");
+
+    builder
+        .build_query_as::<ImageRow>()
+        .fetch_all(pool)
+        .await
+        .map_err(db_error)
+}
+
+// URLs are built on every read so that signed ones are fresh and a change of MEDIA_PUBLIC_URL
+// applies to existing images
+async fn image_urls(store: &dyn MediaStore, row: ImageRow, sign: bool) -> Result<ExerciseImage, ApiError> {
+    async fn link(store: &dyn MediaStore, key: Option<String>, stored: String, sign: bool) -> Result<String, ApiError> {
+        match key {
+            Some(key) => media_url(store, &key, sign, SIGNED_URL_TTL).await.map_err(storage_error),
+            None => Ok(stored),
+        }
+    }
+
+    let full_url = link(store, row.full_key, row.full_url, sign).await?;
+    Ok(ExerciseImage {
+        id: row.id,
+        url: full_url.clone(),
+        thumbnail_url: link(store, row.thumbnail_key, row.thumbnail_url, sign).await?,
+        medium_url: link(store, row.medium_key, row.medium_url, sign).await?,
+        full_url,
+        alt_text: row.alt_text,
+        caption: row.caption,
+        is_cover: row.is_cover,
+    })
+}
+
+// The gallery of one exercise in display order
+async fn gallery(pool: &SqlitePool, store: &dyn MediaStore, exercise_id: i64, sign: bool) -> Result<Vec<ExerciseImage>, ApiError> {
+    let mut images = Vec::new();
+    for row in exercise_images(pool, &[exercise_id]).await? {
+        images.push(image_urls(store, row, sign).await?);
+    }
+    Ok(images)
+}
+
+// Fill `images` (full size URLs) and `image_variants` on exercise responses in a single query.
+// Specialized exercises get signed URLs. Exercises with images take `image_url` from the cover.
+pub(crate) async fn attach_images(
+    pool: &SqlitePool,
+    store: &dyn MediaStore,
+    exercises: &mut [ExerciseResponse],
+) -> Result<(), ApiError> {
+    let ids: Vec<i64> = exercises.iter().map(|e| e.id).collect();
+    let mut by_exercise: HashMap<i64, Vec<ImageRow>> = HashMap::new();
+    for row in exercise_images(pool, &ids).await? {
+        by_exercise.entry(row.exercise_id).or_default().push(row);
+    }
+
+    for exercise in exercises.iter_mut() {
+        let mut images = Vec::new();
+        for row in by_exercise.remove(&exercise.id).unwrap_or_default() {
+            images.push(image_urls(store, row, exercise.is_specialized).await?);
+        }
+        if let Some(cover) = images.iter().find(|i| i.is_cover) {
+            exercise.image_url = Some(cover.full_url.clone());
+        }
+        exercise.images = Some(images.iter().map(|i| i.full_url.clone()).collect());
+        exercise.image_variants = Some(images);
+    }
+    Ok(())
+}
+
+// Upload images for an exercise (multipart/form-data). Every file is checked and resized before
+// anything is stored, so one bad file rejects the whole request with 415.
+pub async fn upload_exercise_images(
+    Extension(pool): Extension<SqlitePool>,
+    Extension(store): Extension<Arc<dyn MediaStore>>,
+    headers: HeaderMap,
+    Path(exercise_id): Path<i64>,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<Vec<ExerciseImage>>), ApiError> {
+    require_staff(&headers)?;
+    let is_specialized = exercise_visibility(&pool, exercise_id).await?;
+
+    let mut uploads: Vec<(String, Vec<Variant>)> = Vec::new();
+    while let Some(field) = multipart.next_field().await.map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: format!("Multipart parse error: {}", e) })))? {
+        let Some(file_name) = field.file_name().map(|s| s.to_string()) else {
+            continue;
+        };
+        let data = field.bytes().await.map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: format!("Error reading field bytes: {}", e) })))?;
+
+        // Decoding and resizing is CPU bound
+        let variants = tokio::task::spawn_blocking(move || process_upload(&data))
+            .await
+            .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, format!("Image processing failed: {}", e)))?
+            .map_err(|e| match e {
+                ImageError::UnsupportedFormat | ImageError::Undecodable(_) => (
+                    StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                    Json(ErrorResponse { error: format!("{}: {}", file_name, e) }),
+                ),
+                ImageError::TooLarge => (
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    Json(ErrorResponse { error: format!("{}: {}", file_name, e) }),
+                ),
+                ImageError::Encode(_) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            })?;
+        uploads.push((file_name, variants));
+    }
+
+    // Specialized exercises are only for assigned patients, so their images are private
+    let visibility = if is_specialized { Visibility::Private } else { Visibility::Public };
+
+    // Store the files first; they are removed again if the database insert fails
+    let mut written: Vec<String> = Vec::new();
+    let mut stored: Vec<Vec<(&Variant, String)>> = Vec::new();
+    for (_, variants) in &uploads {
+        let name = uuid::Uuid::new_v4().simple().to_string();
+        let mut keys = Vec::new();
+        for variant in variants {
+            let key = visibility.key(&format!("exercises/{}/images/{}-{}.{}", exercise_id, name, variant.name, variant.extension));
+            let content_type = if variant.extension == "png" { "image/png" } else { "image/jpeg" };
+            if let Err(e) = store.put(&key, variant.bytes.clone(), content_type).await {
+                remove_keys(store.as_ref(), &written).await;
+                return Err(error(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save file: {}", e)));
+            }
+            written.push(key.clone());
+            keys.push((variant, key));
+        }
+        stored.push(keys);
+    }
+
+    match insert_images(&pool, store.as_ref(), exercise_id, &stored).await {
+        Ok(ids) => {
+            let created = gallery(&pool, store.as_ref(), exercise_id, is_specialized)
+                .await?
+                .into_iter()
+                .filter(|image| ids.contains(&image.id))
+                .collect();
+            Ok((StatusCode::CREATED, Json(created)))
+        }
+        Err(e) => {
+            remove_keys(store.as_ref(), &written).await;
+            Err(db_error(e))
+        }
+    }
+}
+
+// `url` keeps the public URL at upload time for other readers of the table; responses are built from the key
+async fn insert_images(
+    pool: &SqlitePool,
+    store: &dyn MediaStore,
+    exercise_id: i64,
+    stored: &[Vec<(&Variant, String)>],
+) -> Result<Vec<i64>, sqlx::Error> {
+    let now = now();
+
+    let mut tx = pool.begin().await?;
+    let mut ids = Vec::new();
+    for keys in stored {
+        let full_key = keys
+            .iter()
+            .find(|(variant, _)| variant.name == "full")
+            .map(|(_, key)| key.clone())
+            .unwrap_or_default();
+
+        // New images go to the end of the gallery
+        let image_id = sqlx::query(
+            "INSERT INTO exercise_images (exercise_id, url, storage_key, position, created_at) \
+             VALUES (?, ?, ?, (SELECT COALESCE(MAX(position), -1) + 1 FROM exercise_images WHERE exercise_id = ?), ?)"
+        )
+        .bind(exercise_id)
+        .bind(store.public_url(&full_key))
+        .bind(&full_key)
+        .bind(exercise_id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+
+        for (variant, key) in keys {
+            sqlx::query("INSERT INTO exercise_image_variants (image_id, variant, url, storage_key, width, height) VALUES (?, ?, ?, ?, ?, ?)")
+                .bind(image_id)
+                .bind(variant.name)
+                .bind(store.public_url(key))
+                .bind(key)
+                .bind(variant.width as i64)
+                .bind(variant.height as i64)
+                .execute(&mut *tx)
+                .await?;
+        }
+        ids.push(image_id);
+    }
+    tx.commit().await?;
+    Ok(ids)
+}
+
+pub async fn delete_exercise_image(
+    Extension(pool): Extension<SqlitePool>,
+    Extension(store): Extension<Arc<dyn MediaStore>>,
+    headers: HeaderMap,
+    Path((exercise_id, image_id)): Path<(i64, i64)>,
+) -> Result<StatusCode, ApiError> {
+    require_staff(&headers)?;
+
+    // the original file and every variant
+    let files = sqlx::query_as::<_, (String, Option<String>)>(
+        "SELECT url, storage_key FROM exercise_images WHERE id = ? AND exercise_id = ? \
+         UNION SELECT v.url, v.storage_key FROM exercise_image_variants v JOIN exercise_images i ON i.id = v.image_id \
+         WHERE i.id = ? AND i.exercise_id = ?"
+    )
+    .bind(image_id)
+    .bind(exercise_id)
+    .bind(image_id)
+    .bind(exercise_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(db_error)?;
+
+    // delete DB row; variants go with it and a cover pointing at it is cleared
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    let res = sqlx::query("DELETE FROM exercise_images WHERE id = ? AND exercise_id = ?")
+        .bind(image_id)
+        .bind(exercise_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+    if res.rows_affected() == 0 {
+        return Err(error(StatusCode::NOT_FOUND, "Image not found"));
+    }
+    renumber(&mut tx, exercise_id).await.map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
+
+    let (stored, legacy): (Vec<_>, Vec<_>) = files.into_iter().partition(|(_, key)| key.is_some());
+    let keys: Vec<String> = stored.into_iter().filter_map(|(_, key)| key).collect();
+    remove_keys(store.as_ref(), &keys).await;
+
+    let root = legacy_images_root()?;
+    let legacy: Vec<PathBuf> = legacy.iter().filter_map(|(url, _)| legacy_file_for_url(&root, url)).collect();
+    remove_legacy_files(&legacy).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Close the gaps left by deleted images: positions become 0..n in the current order
+async fn renumber(conn: &mut SqliteConnection, exercise_id: i64) -> Result<(), sqlx::Error> {
+    let ids = sqlx::query_scalar::<_, i64>("SELECT id FROM exercise_images WHERE exercise_id = ? ORDER BY position, id")
+        .bind(exercise_id)
+        .fetch_all(&mut *conn)
+        .await?;
+    set_positions(conn, &ids).await
+}
+
+async fn set_positions(conn: &mut SqliteConnection, image_ids: &[i64]) -> Result<(), sqlx::Error> {
+    for (position, id) in image_ids.iter().enumerate() {
+        sqlx::query("UPDATE exercise_images SET position = ? WHERE id = ?")
+            .bind(position as i64)
+            .bind(id)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+// Put the gallery in the given order and return it
+pub async fn reorder_exercise_images(
+    Extension(pool): Extension<SqlitePool>,
+    Extension(store): Extension<Arc<dyn MediaStore>>,
+    headers: HeaderMap,
+    Path(exercise_id): Path<i64>,
+    Json(req): Json<ReorderImagesRequest>,
+) -> Result<Json<Vec<ExerciseImage>>, ApiError> {
+    require_staff(&headers)?;
+    let is_specialized = exercise_visibility(&pool, exercise_id).await?;
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    let mut current = sqlx::query_scalar::<_, i64>("SELECT id FROM exercise_images WHERE exercise_id = ?")
+        .bind(exercise_id)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    let mut requested = req.image_ids.clone();
+    current.sort_unstable();
+    requested.sort_unstable();
+    if current != requested {
+        return Err(error(
+            StatusCode::BAD_REQUEST,
+            "image_ids must list every image of the exercise exactly once",
+        ));
+    }
+
+    set_positions(&mut tx, &req.image_ids).await.map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(Json(gallery(&pool, store.as_ref(), exercise_id, is_specialized).await?))
+}
+
+// Trimmed text, None when empty, or 400 when too long
+fn image_text(value: &str, field: &str, max_chars: usize) -> Result<Option<String>, ApiError> {
+    let value = value.trim();
+    if value.chars().count() > max_chars {
+        return Err(error(StatusCode::BAD_REQUEST, format!("{} can be at most {} characters", field, max_chars)));
+    }
+    Ok(Some(value.to_string()).filter(|v| !v.is_empty()))
+}
+
+// Set alt text and caption, or make the image the exercise's cover
+pub async fn update_exercise_image(
+    Extension(pool): Extension<SqlitePool>,
+    Extension(store): Extension<Arc<dyn MediaStore>>,
+    headers: HeaderMap,
+    Path((exercise_id, image_id)): Path<(i64, i64)>,
+    Json(req): Json<UpdateExerciseImageRequest>,
+) -> Result<Json<ExerciseImage>, ApiError> {
+    require_staff(&headers)?;
+    let is_specialized = exercise_visibility(&pool, exercise_id).await?;
+
+    let (alt_text, caption) = sqlx::query_as::<_, (Option<String>, Option<String>)>(
+        "SELECT alt_text, caption FROM exercise_images WHERE id = ? AND exercise_id = ?"
+    )
+    .bind(image_id)
+    .bind(exercise_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(db_error)?
+    .ok_or_else(|| error(StatusCode::NOT_FOUND, "Image not found"))?;
+
+    let alt_text = match &req.alt_text {
+        Some(value) => image_text(value, "alt_text", MAX_ALT_TEXT_CHARS)?,
+        None => alt_text,
+    };
+    let caption = match &req.caption {
+        Some(value) => image_text(value, "caption", MAX_CAPTION_CHARS)?,
+        None => caption,
+    };
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    sqlx::query("UPDATE exercise_images SET alt_text = ?, caption = ? WHERE id = ?")
+        .bind(&alt_text)
+        .bind(&caption)
+        .bind(image_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+    // Unsetting the cover falls back to the first image
+    match req.is_cover {
+        Some(true) => {
+            sqlx::query("UPDATE exercises SET cover_image_id = ? WHERE id = ?")
+                .bind(image_id)
+                .bind(exercise_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+        }
+        Some(false) => {
+            sqlx::query("UPDATE exercises SET cover_image_id = NULL WHERE id = ? AND cover_image_id = ?")
+                .bind(exercise_id)
+                .bind(image_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+        }
+        None => {}
+    }
+    tx.commit().await.map_err(db_error)?;
+
+    let image = gallery(&pool, store.as_ref(), exercise_id, is_specialized)
+        .await?
+        .into_iter()
+        .find(|image| image.id == image_id)
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "Image not found"))?;
+    Ok(Json(image))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::extract::Query;
+    use super::*;
+    use crate::handlers::{delete_exercise, get_exercise, get_private_media, update_exercise, SignedMediaParams};
+    use crate::models::UpdateExerciseRequest;
+    use crate::test_support::*;
+
+    // Upload `count` small JPEGs as physio-001
+    async fn upload_images(pool: &SqlitePool, store: &Arc<dyn MediaStore>, exercise_id: i64, count: usize) -> Vec<ExerciseImage> {
+        use axum::extract::{FromRequest, Multipart, Request};
+        use image::{codecs::jpeg::JpegEncoder, Rgb, RgbImage};
+
+        let mut jpeg = Vec::new();
+        RgbImage::from_pixel(40, 20, Rgb([10, 120, 200])).write_with_encoder(JpegEncoder::new(&mut jpeg)).unwrap();
+        let mut body = Vec::new();
+        for i in 0..count {
+            body.extend_from_slice(format!("--boundary\r\nContent-Disposition: form-data; name=\"images\"; filename=\"{}.jpg\"\r\nContent-Type: image/jpeg\r\n\r\n", i).as_bytes());
+            body.extend_from_slice(&jpeg);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(b"--boundary--\r\n");
+        let request = Request::builder()
+            .header("content-type", "multipart/form-data; boundary=boundary")
+            .body(axum::body::Body::from(body))
+            .unwrap();
+
+        let multipart = Multipart::from_request(request, &()).await.unwrap();
+        let (status, Json(images)) = upload_exercise_images(Extension(pool.clone()), Extension(store.clone()), auth_headers("physio-001", "physiotherapist"), Path(exercise_id), multipart)
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        images
+    }
+
+    #[tokio::test]
+    async fn uploads_go_through_the_media_store_and_private_images_are_signed() {
+        use axum::extract::Request;
+
+        let pool = test_pool().await;
+        seed_all(&pool).await;
+        let root = std::env::temp_dir().join(format!("fisionet-exercise-media-{}", uuid::Uuid::new_v4()));
+        let local = Arc::new(crate::storage::LocalStore::new(&root, "/media", "test-signing-key"));
+        let store: Arc<dyn MediaStore> = local.clone();
+        let squat = exercise_id(&pool, "Squat").await;
+        let specialized = exercise_id(&pool, "Resisted knee extension").await;
+
+        let public = upload_images(&pool, &store, squat, 1).await;
+        let thumbnail = public[0].thumbnail_url.strip_prefix("/media/").unwrap();
+        assert!(thumbnail.starts_with(&format!("public/exercises/{}/images/", squat)), "{}", thumbnail);
+        assert!(root.join(thumbnail).exists());
+
+        let private = upload_images(&pool, &store, specialized, 1).await;
+        let signed = &private[0].full_url;
+        assert!(signed.starts_with(&format!("/media/private/exercises/{}/images/", specialized)), "{}", signed);
+        assert!(signed.contains("signature="));
+        let Json(exercise) = get_exercise(Extension(pool.clone()), Extension(store.clone()), auth_headers("physio-001", "physiotherapist"), Path(specialized))
+            .await
+            .unwrap();
+        assert_eq!(exercise.images.unwrap().len(), 1);
+
+        // The signed URL serves the file; a tampered or missing signature does not
+        let (path, query) = signed.split_once('?').unwrap();
+        let path = path.strip_prefix("/media/private/").unwrap().to_string();
+        let serve = |query: &str| {
+            let uri: axum::http::Uri = format!("/?{}", query).parse().unwrap();
+            let params = Query::<SignedMediaParams>::try_from_uri(&uri).unwrap();
+            get_private_media(Extension(local.clone()), Path(path.clone()), params, Request::new(axum::body::Body::empty()))
+        };
+        let response = serve(query).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "image/jpeg");
+        let tampered = query.replace("expires=", "expires=1");
+        assert_eq!(serve(&tampered).await.unwrap_err().0, StatusCode::FORBIDDEN);
+        assert_eq!(serve("").await.unwrap_err().0, StatusCode::FORBIDDEN);
+
+        let status = delete_exercise_image(Extension(pool.clone()), Extension(store.clone()), auth_headers("physio-001", "physiotherapist"), Path((specialized, private[0].id)))
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert!(!root.join("private").join(format!("exercises/{}/images", specialized)).read_dir().unwrap().any(|_| true));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn images_follow_the_exercise_between_public_and_private() {
+        let pool = test_pool().await;
+        seed_all(&pool).await;
+        let root = std::env::temp_dir().join(format!("fisionet-exercise-media-{}", uuid::Uuid::new_v4()));
+        let store: Arc<dyn MediaStore> = Arc::new(crate::storage::LocalStore::new(&root, "/media", "test-signing-key"));
+        let jobs = test_jobs(&pool, &store);
+        let squat = exercise_id(&pool, "Squat").await;
+        upload_images(&pool, &store, squat, 1).await;
+
+        // An image from before the media store, still served from ./static
+        let legacy_root = legacy_images_root().unwrap();
+        std::fs::create_dir_all(&legacy_root).unwrap();
+        let legacy_name = format!("{}.png", uuid::Uuid::new_v4());
+        std::fs::write(legacy_root.join(&legacy_name), b"legacy").unwrap();
+        sqlx::query("INSERT INTO exercise_images (exercise_id, url, position, created_at) VALUES (?, ?, 1, 0)")
+            .bind(squat)
+            .bind(format!("/static/images/{}", legacy_name))
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let keys = |pool: &SqlitePool| {
+            let pool = pool.clone();
+            async move {
+                sqlx::query_scalar::<_, String>(
+                    "SELECT storage_key FROM exercise_images WHERE exercise_id = ? AND storage_key IS NOT NULL
+                     UNION ALL
+                     SELECT v.storage_key FROM exercise_image_variants v JOIN exercise_images i ON i.id = v.image_id WHERE i.exercise_id = ?"
+                )
+                .bind(squat)
+                .bind(squat)
+                .fetch_all(&pool)
+                .await
+                .unwrap()
+            }
+        };
+        let public_keys = keys(&pool).await;
+        assert!(public_keys.iter().all(|key| key.starts_with("public/")), "{:?}", public_keys);
+
+        let set_specialized = |specialized: bool| {
+            let pool = pool.clone();
+            let store = store.clone();
+            let jobs = jobs.clone();
+            async move {
+                let Json(updated) = update_exercise(
+                    Extension(pool),
+                    Extension(store),
+                    Extension(jobs),
+                    auth_headers("physio-001", "physiotherapist"),
+                    Path(squat),
+                    Json(UpdateExerciseRequest {
+                        title: None,
+                        description: None,
+                        category: None,
+                        difficulty_level: None,
+                        duration_minutes: None,
+                        equipment_needed: None,
+                        instructions: None,
+                        image_url: None,
+                        video_url: None,
+                        youtube_url: None,
+                        target_muscles: None,
+                        is_specialized: Some(specialized),
+                    }),
+                )
+                .await
+                .unwrap();
+                assert_eq!(updated.is_specialized, specialized);
+            }
+        };
+
+        set_specialized(true).await;
+        let private_keys = keys(&pool).await;
+        assert_eq!(private_keys.len(), public_keys.len() + 1);
+        assert!(private_keys.iter().all(|key| key.starts_with("private/")), "{:?}", private_keys);
+        assert!(!legacy_root.join(&legacy_name).exists());
+        assert!(!root.join("public").join(format!("exercises/{}/images", squat)).read_dir().unwrap().any(|_| true));
+        let Json(exercise) = get_exercise(Extension(pool.clone()), Extension(store.clone()), auth_headers("physio-001", "physiotherapist"), Path(squat))
+            .await
+            .unwrap();
+        let images = exercise.image_variants.unwrap();
+        assert_eq!(images.len(), 2);
+        assert!(images.iter().all(|image| image.thumbnail_url.contains("signature=")));
+
+        set_specialized(false).await;
+        let public_again = keys(&pool).await;
+        assert!(public_again.iter().all(|key| key.starts_with("public/")), "{:?}", public_again);
+        assert!(!root.join("private").join(format!("exercises/{}/images", squat)).read_dir().unwrap().any(|_| true));
+        let _ = std::fs::remove_dir(&legacy_root);
+        let _ = std::fs::remove_dir(legacy_root.parent().unwrap());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn images_are_reordered_described_and_chosen_as_cover() {
+        let pool = test_pool().await;
+        seed_all(&pool).await;
+        let store = test_store();
+        let squat = exercise_id(&pool, "Squat").await;
+        let uploaded = upload_images(&pool, &store, squat, 3).await;
+        let ids: Vec<i64> = uploaded.iter().map(|i| i.id).collect();
+        let gallery = |pool: &SqlitePool| {
+            let pool = pool.clone();
+            let store = store.clone();
+            async move {
+                let Json(exercise) = get_exercise(Extension(pool), Extension(store), HeaderMap::new(), Path(squat)).await.unwrap();
+                exercise
+            }
+        };
+
+        // Without a chosen cover the first image is used
+        let exercise = gallery(&pool).await;
+        let covers: Vec<bool> = exercise.image_variants.as_ref().unwrap().iter().map(|i| i.is_cover).collect();
+        assert_eq!(covers, vec![true, false, false]);
+        assert_eq!(exercise.image_url.as_deref(), Some(uploaded[0].full_url.as_str()));
+
+        let Json(reordered) = reorder_exercise_images(
+            Extension(pool.clone()),
+            Extension(store.clone()),
+            auth_headers("physio-001", "physiotherapist"),
+            Path(squat),
+            Json(ReorderImagesRequest { image_ids: vec![ids[2], ids[0], ids[1]] }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(reordered.iter().map(|i| i.id).collect::<Vec<_>>(), vec![ids[2], ids[0], ids[1]]);
+        assert!(reordered[0].is_cover);
+
+        for image_ids in [vec![ids[0], ids[1]], vec![ids[0], ids[0], ids[1]], vec![ids[0], ids[1], ids[2], 9999]] {
+            let err = reorder_exercise_images(
+                Extension(pool.clone()),
+                Extension(store.clone()),
+                auth_headers("physio-001", "physiotherapist"),
+                Path(squat),
+                Json(ReorderImagesRequest { image_ids }),
+            )
+            .await
+            .unwrap_err();
+            assert_eq!(err.0, StatusCode::BAD_REQUEST);
+        }
+        let err = reorder_exercise_images(
+            Extension(pool.clone()),
+            Extension(store.clone()),
+            auth_headers("patient-001", "patient"),
+            Path(squat),
+            Json(ReorderImagesRequest { image_ids: ids.clone() }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+        // The x-user-id header alone is not trusted
+        let err = reorder_exercise_images(
+            Extension(pool.clone()),
+            Extension(store.clone()),
+            user_headers("physio-001"),
+            Path(squat),
+            Json(ReorderImagesRequest { image_ids: ids.clone() }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::UNAUTHORIZED);
+
+        let update = |image_id: i64, req: UpdateExerciseImageRequest| {
+            update_exercise_image(Extension(pool.clone()), Extension(store.clone()), auth_headers("physio-001", "physiotherapist"), Path((squat, image_id)), Json(req))
+        };
+        let Json(described) = update(
+            ids[1],
+            UpdateExerciseImageRequest {
+                alt_text: Some("  Feet shoulder-width apart ".to_string()),
+                caption: Some("Starting position".to_string()),
+                is_cover: Some(true),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(described.alt_text.as_deref(), Some("Feet shoulder-width apart"));
+        assert!(described.is_cover);
+        let Json(cleared) = update(ids[1], UpdateExerciseImageRequest { caption: Some(String::new()), ..Default::default() }).await.unwrap();
+        assert_eq!((cleared.alt_text.as_deref(), cleared.caption), (Some("Feet shoulder-width apart"), None));
+        let err = update(ids[1], UpdateExerciseImageRequest { alt_text: Some("a".repeat(251)), ..Default::default() }).await.unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+
+        let exercise = gallery(&pool).await;
+        assert_eq!(exercise.image_url.as_deref(), Some(described.full_url.as_str()));
+
+        // Deleting the cover falls back to the first image and closes the gap
+        let status = delete_exercise_image(Extension(pool.clone()), Extension(store.clone()), auth_headers("physio-001", "physiotherapist"), Path((squat, ids[1])))
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let positions = sqlx::query_as::<_, (i64, i64)>("SELECT id, position FROM exercise_images WHERE exercise_id = ? ORDER BY position")
+            .bind(squat)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(positions, vec![(ids[2], 0), (ids[0], 1)]);
+        let exercise = gallery(&pool).await;
+        assert_eq!(exercise.image_variants.unwrap()[0].id, ids[2]);
+        assert_eq!(exercise.image_url.as_deref(), Some(reordered[0].full_url.as_str()));
+    }
+
+    #[tokio::test]
+    async fn deleting_an_exercise_removes_its_files() {
+        let pool = test_pool().await;
+        seed_all(&pool).await;
+        let root = std::env::temp_dir().join(format!("fisionet-exercise-media-{}", uuid::Uuid::new_v4()));
+        let store: Arc<dyn MediaStore> = Arc::new(crate::storage::LocalStore::new(&root, "/media", "test-signing-key"));
+        let squat = exercise_id(&pool, "Squat").await;
+        upload_images(&pool, &store, squat, 2).await;
+
+        let legacy_root = legacy_images_root().unwrap();
+        std::fs::create_dir_all(&legacy_root).unwrap();
+        let legacy = legacy_root.join(format!("{}.png", uuid::Uuid::new_v4()));
+        std::fs::write(&legacy, b"legacy").unwrap();
+        sqlx::query("INSERT INTO exercise_images (exercise_id, url, position, created_at) VALUES (?, ?, 2, 0)")
+            .bind(squat)
+            .bind(format!("/static/images/{}", legacy.file_name().unwrap().to_str().unwrap()))
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let images = root.join(format!("public/exercises/{}/images", squat));
+        assert!(images.read_dir().unwrap().any(|_| true));
+
+        let status = delete_exercise(Extension(pool.clone()), Extension(store.clone()), auth_headers("physio-001", "physiotherapist"), Path(squat))
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert!(!images.read_dir().unwrap().any(|_| true));
+        assert!(!legacy.exists());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,248 @@
+use axum::{
+    body::Body,
+    extract::{Extension, Path, Query, Request},
+    http::StatusCode,
+    response::Response,
+};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tower::ServiceExt;
+use tower_http::services::ServeFile;
+
+use super::images::{legacy_file_for_url, legacy_images_root, remove_legacy_files};
+use super::{db_error, error, storage_error, ApiError};
+use crate::storage::{is_private, remove_keys, with_visibility, LocalStore, MediaStore, Visibility};
+
+#[derive(Debug, Deserialize)]
+pub struct SignedMediaParams {
+    pub expires: Option<u64>,
+    pub signature: Option<String>,
+}
+
+// Private files of the local media store, reachable only through URLs from `signed_url`.
+// ServeFile takes care of content types and range requests.
+pub async fn get_private_media(
+    Extension(store): Extension<Arc<LocalStore>>,
+    Path(path): Path<String>,
+    Query(params): Query<SignedMediaParams>,
+    request: Request,
+) -> Result<Response, ApiError> {
+    let forbidden = || error(StatusCode::FORBIDDEN, "Invalid or expired media link");
+
+    let key = Visibility::Private.key(&path);
+    let (Some(expires), Some(signature)) = (params.expires, params.signature) else {
+        return Err(forbidden());
+    };
+    if !store.verify(&key, expires, &signature) {
+        return Err(forbidden());
+    }
+
+    let file = store.path_for(&key).map_err(|_| forbidden())?;
+    let response = ServeFile::new(file)
+        .oneshot(request)
+        .await
+        .unwrap_or_else(|never| match never {});
+    Ok(response.map(Body::new))
+}
+
+// Keys of every image, image variant and video file of an exercise in the media store
+pub(crate) async fn exercise_media_keys(pool: &SqlitePool, exercise_id: i64) -> Result<Vec<String>, ApiError> {
+    sqlx::query_scalar::<_, String>(
+        "SELECT storage_key FROM exercise_images WHERE exercise_id = ? AND storage_key IS NOT NULL \
+         UNION SELECT v.storage_key FROM exercise_image_variants v JOIN exercise_images i ON i.id = v.image_id \
+         WHERE i.exercise_id = ? AND v.storage_key IS NOT NULL \
+         UNION SELECT source_key FROM exercise_videos WHERE exercise_id = ? \
+         UNION SELECT f.storage_key FROM exercise_video_files f JOIN exercise_videos v ON v.id = f.video_id \
+         WHERE v.exercise_id = ?"
+    )
+    .bind(exercise_id)
+    .bind(exercise_id)
+    .bind(exercise_id)
+    .bind(exercise_id)
+    .fetch_all(pool)
+    .await
+    .map_err(db_error)
+}
+
+// Files in ./static of the exercise's images from before the media store
+pub(crate) async fn legacy_image_files(pool: &SqlitePool, exercise_id: i64) -> Result<Vec<PathBuf>, ApiError> {
+    let urls = sqlx::query_scalar::<_, String>(
+        "SELECT url FROM exercise_images WHERE exercise_id = ? AND storage_key IS NULL \
+         UNION SELECT v.url FROM exercise_image_variants v JOIN exercise_images i ON i.id = v.image_id \
+         WHERE i.exercise_id = ? AND v.storage_key IS NULL"
+    )
+    .bind(exercise_id)
+    .bind(exercise_id)
+    .fetch_all(pool)
+    .await
+    .map_err(db_error)?;
+
+    let root = legacy_images_root()?;
+    Ok(urls.iter().filter_map(|url| legacy_file_for_url(&root, url)).collect())
+}
+
+// Move the stored files of an exercise to the prefix that matches is_specialized: public/ is
+// served to anyone, so files of specialized exercises must live under private/. Images from
+// before the media store are taken out of ./static into the store when the exercise becomes
+// specialized. Returns the videos that were still being processed; they are pending again and
+// have to be queued from their new place.
+pub(crate) async fn relocate_exercise_media(
+    pool: &SqlitePool,
+    store: &dyn MediaStore,
+    exercise_id: i64,
+    is_specialized: bool,
+) -> Result<Vec<i64>, ApiError> {
+    let visibility = if is_specialized { Visibility::Private } else { Visibility::Public };
+    if is_specialized {
+        import_legacy_images(pool, store, exercise_id).await?;
+    }
+
+    let keys = exercise_media_keys(pool, exercise_id).await?;
+    let moves: Vec<(String, String)> = keys
+        .into_iter()
+        .filter(|key| is_private(key) != is_specialized)
+        .map(|key| {
+            let moved = with_visibility(&key, visibility);
+            (key, moved)
+        })
+        .collect();
+    if moves.is_empty() {
+        return Ok(vec![]);
+    }
+
+    // Copy first and switch the references over once everything is in place
+    let mut copied: Vec<String> = Vec::new();
+    for (from, to) in &moves {
+        if let Err(e) = store.copy(from, to).await {
+            remove_keys(store, &copied).await;
+            return Err(storage_error(e));
+        }
+        copied.push(to.clone());
+    }
+
+    let requeue = match switch_keys(pool, store, exercise_id, &moves).await {
+        Ok(requeue) => requeue,
+        Err(e) => {
+            remove_keys(store, &copied).await;
+            return Err(db_error(e));
+        }
+    };
+    let old: Vec<String> = moves.into_iter().map(|(from, _)| from).collect();
+    remove_keys(store, &old).await;
+    Ok(requeue)
+}
+
+// Point every reference at the moved keys. Returns the videos that were not processed yet: an
+// ffmpeg job that is already running writes next to the old source and its result is dropped.
+async fn switch_keys(
+    pool: &SqlitePool,
+    store: &dyn MediaStore,
+    exercise_id: i64,
+    moves: &[(String, String)],
+) -> Result<Vec<i64>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    for (from, to) in moves {
+        sqlx::query("UPDATE exercise_images SET storage_key = ?, url = ? WHERE storage_key = ?")
+            .bind(to)
+            .bind(store.public_url(to))
+            .bind(from)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE exercise_image_variants SET storage_key = ?, url = ? WHERE storage_key = ?")
+            .bind(to)
+            .bind(store.public_url(to))
+            .bind(from)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE exercise_video_files SET storage_key = ? WHERE storage_key = ?")
+            .bind(to)
+            .bind(from)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "UPDATE exercise_videos SET \
+             status = CASE WHEN source_key = ?1 AND status IN ('pending', 'processing') THEN 'pending' ELSE status END, \
+             source_key = CASE WHEN source_key = ?1 THEN ?2 ELSE source_key END, \
+             playlist_key = CASE WHEN playlist_key = ?1 THEN ?2 ELSE playlist_key END, \
+             poster_key = CASE WHEN poster_key = ?1 THEN ?2 ELSE poster_key END \
+             WHERE exercise_id = ?3"
+        )
+        .bind(from)
+        .bind(to)
+        .bind(exercise_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+    let requeue = sqlx::query_scalar::<_, i64>("SELECT id FROM exercise_videos WHERE exercise_id = ? AND status = 'pending'")
+        .bind(exercise_id)
+        .fetch_all(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(requeue)
+}
+
+// ./static is served to anyone, so legacy images of a specialized exercise are copied into the
+// store as private files and removed from there
+async fn import_legacy_images(pool: &SqlitePool, store: &dyn MediaStore, exercise_id: i64) -> Result<(), ApiError> {
+    let legacy = sqlx::query_as::<_, (i64, String)>(
+        "SELECT id, url FROM exercise_images WHERE exercise_id = ? AND storage_key IS NULL"
+    )
+    .bind(exercise_id)
+    .fetch_all(pool)
+    .await
+    .map_err(db_error)?;
+
+    let root = legacy_images_root()?;
+    for (image_id, url) in legacy {
+        let Some(path) = legacy_file_for_url(&root, &url) else {
+            continue;
+        };
+        let bytes = match tokio::fs::read(&path).await {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(error(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to read {}: {}", url, e))),
+        };
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_lowercase();
+        let content_type = match extension.as_str() {
+            "png" => "image/png",
+            "webp" => "image/webp",
+            "gif" => "image/gif",
+            _ => "image/jpeg",
+        };
+        let key = Visibility::Private.key(&format!(
+            "exercises/{}/images/{}-legacy.{}",
+            exercise_id,
+            uuid::Uuid::new_v4().simple(),
+            if extension.is_empty() { "jpg" } else { extension.as_str() }
+        ));
+        store.put(&key, bytes, content_type).await.map_err(storage_error)?;
+
+        let updated = sqlx::query("UPDATE exercise_images SET storage_key = ?, url = ? WHERE id = ?")
+            .bind(&key)
+            .bind(store.public_url(&key))
+            .bind(image_id)
+            .execute(pool)
+            .await;
+        if let Err(e) = updated {
+            remove_keys(store, std::slice::from_ref(&key)).await;
+            return Err(db_error(e));
+        }
+        remove_legacy_files(std::slice::from_ref(&path)).await;
+    }
+    Ok(())
+}
+
+// Repair files left under the wrong prefix, e.g. by a restart in the middle of a move. Run before
+// VideoJobs::requeue_unfinished, which queues the videos that are pending again.
+pub async fn relocate_all_media(pool: &SqlitePool, store: &dyn MediaStore) -> Result<(), ApiError> {
+    let exercises = sqlx::query_as::<_, (i64, bool)>("SELECT id, is_specialized FROM exercises")
+        .fetch_all(pool)
+        .await
+        .map_err(db_error)?;
+    for (exercise_id, is_specialized) in exercises {
+        relocate_exercise_media(pool, store, exercise_id, is_specialized).await?;
+    }
+    Ok(())
+}
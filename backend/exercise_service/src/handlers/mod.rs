@@ -3,165 +3,509 @@ use axum::{
     http::StatusCode,
     response::IntoResponse,
 };
-use sqlx::SqlitePool;
+use sqlx::{FromRow, QueryBuilder, Sqlite, SqlitePool};
 use serde::{Deserialize, Serialize};
 use crate::models::*;
-use axum::http::HeaderMap;
-use axum::extract::Multipart;
-use std::fs;
-use std::env;
-use sqlx::Row;
-use sanitize_filename;
+use crate::storage::{remove_keys, MediaStore, StorageError};
+use crate::utils::{verify_jwt_token, Claims};
+use crate::videos::VideoJobs;
+use std::sync::Arc;
+use axum::http::{header::AUTHORIZATION, HeaderMap};
 
-#[derive(Debug, Deserialize)]
-pub struct AuthHeader {
-    pub user_id: String,
+mod images;
+mod media;
+mod routines;
+mod taxonomy;
+mod videos;
+
+pub use images::*;
+pub use media::*;
+pub use routines::*;
+pub use taxonomy::*;
+pub use videos::*;
+
+#[derive(Debug, Serialize)]
+pub struct ErrorResponse {
+    pub error: String,
 }
 
-async fn ensure_admin_or_physio(pool: &SqlitePool, user_id: &str) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
-    // Query user role from users table in auth database
-    let row = sqlx::query_scalar::<_, String>("SELECT role FROM users WHERE id = ?")
-        .bind(user_id)
-        .fetch_optional(pool)
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse { error: format!("Database error: {}", e) }),
-            )
-        })?;
+type ApiError = (StatusCode, Json<ErrorResponse>);
+
+fn db_error(e: sqlx::Error) -> ApiError {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse { error: format!("Database error: {}", e) }),
+    )
+}
+
+fn error(status: StatusCode, message: impl Into<String>) -> ApiError {
+    (status, Json(ErrorResponse { error: message.into() }))
+}
+
+fn storage_error(e: StorageError) -> ApiError {
+    match e {
+        StorageError::NotFound(_) => error(StatusCode::NOT_FOUND, "File not found"),
+        e => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+pub(crate) fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+// Who is reading the catalog, resolved from the bearer token
+#[derive(Debug)]
+pub enum Viewer {
+    Anonymous,
+    // Sees basic exercises plus the specialized ones assigned to them
+    Patient(String),
+    // Physiotherapists and admins see everything
+    Staff(String),
+}
+
+fn unauthorized() -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(ErrorResponse { error: "Invalid or expired token".to_string() }),
+    )
+}
+
+// Verified claims from the Authorization header, if one was sent. A header with a bad
+// token is rejected rather than treated as anonymous.
+fn bearer_claims(headers: &HeaderMap) -> Result<Option<Claims>, (StatusCode, Json<ErrorResponse>)> {
+    let Some(value) = headers.get(AUTHORIZATION) else {
+        return Ok(None);
+    };
+    let token = value
+        .to_str()
+        .ok()
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or_else(unauthorized)?;
+    verify_jwt_token(token).map(Some).map_err(|_| unauthorized())
+}
+
+fn resolve_viewer(headers: &HeaderMap) -> Result<Viewer, (StatusCode, Json<ErrorResponse>)> {
+    Ok(match bearer_claims(headers)? {
+        None => Viewer::Anonymous,
+        Some(claims) if claims.role == "admin" || claims.role == "physiotherapist" => Viewer::Staff(claims.sub),
+        Some(claims) => Viewer::Patient(claims.sub),
+    })
+}
 
-    let role = row.ok_or_else(|| {
+// Verified caller with one of the given roles
+fn require_role(headers: &HeaderMap, roles: &[&str]) -> Result<Claims, (StatusCode, Json<ErrorResponse>)> {
+    let claims = bearer_claims(headers)?.ok_or_else(|| {
         (
             StatusCode::UNAUTHORIZED,
-            Json(ErrorResponse { error: "User not found".to_string() }),
+            Json(ErrorResponse { error: "Missing authorization token".to_string() }),
         )
     })?;
-
-    if role != "admin" && role != "physiotherapist" {
+    if !roles.contains(&claims.role.as_str()) {
         return Err((
             StatusCode::FORBIDDEN,
             Json(ErrorResponse { error: "Insufficient permissions".to_string() }),
         ));
     }
+    Ok(claims)
+}
 
-    Ok(())
+// Verified physiotherapist or admin
+fn require_staff(headers: &HeaderMap) -> Result<Claims, (StatusCode, Json<ErrorResponse>)> {
+    require_role(headers, &["admin", "physiotherapist"])
 }
 
-#[derive(Debug, Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
+// Limit a query on the exercises table to what the viewer may see
+fn push_visibility<'a>(builder: &mut QueryBuilder<'a, Sqlite>, viewer: &'a Viewer) {
+    match viewer {
+        Viewer::Staff(_) => {}
+        Viewer::Anonymous => {
+            builder.push(" AND is_specialized = 0");
+        }
+        Viewer::Patient(patient_id) => {
+            builder
+                .push(" AND (is_specialized = 0 OR EXISTS (SELECT 1 FROM exercise_assignments ea \
+                       WHERE ea.exercise_id = exercises.id AND ea.patient_id = ")
+                .push_bind(patient_id)
+                .push("))");
+        }
+    }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct FilterParams {
+    // Slug, name or alias; a category also matches its subcategories
     pub category: Option<String>,
     pub difficulty: Option<String>,
     pub search: Option<String>,
+    // Comma separated; matches exercises that list any of the values
+    pub target_muscles: Option<String>,
+    pub equipment_needed: Option<String>,
+    pub is_specialized: Option<bool>,
+    pub min_duration_minutes: Option<i64>,
+    pub max_duration_minutes: Option<i64>,
+    // Paging: page starts at 1
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+    // title, difficulty, duration or created_at; order is asc or desc
+    pub sort: Option<String>,
+    pub order: Option<String>,
 }
 
-pub async fn health_check() -> impl IntoResponse {
-    Json(serde_json::json!({ "status": "ok" }))
+#[derive(Debug, Serialize)]
+pub struct ExercisePage {
+    pub exercises: Vec<ExerciseResponse>,
+    pub total: i64,
+    pub page: i64,
+    pub limit: i64,
+    pub has_more: bool,
 }
 
-// Get all exercises with optional filters
-pub async fn get_exercises(
-    Extension(pool): Extension<SqlitePool>,
-    Query(params): Query<FilterParams>,
-) -> Result<Json<Vec<ExerciseResponse>>, (StatusCode, Json<ErrorResponse>)> {
-    let mut query = "SELECT * FROM exercises WHERE 1=1".to_string();
-    
+const DEFAULT_PAGE_LIMIT: i64 = 20;
+const MAX_PAGE_LIMIT: i64 = 100;
+
+// Escape LIKE wildcards so user input only ever matches literally (used with ESCAPE '\')
+fn like_pattern(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|v| v.trim().to_lowercase())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+// Append the WHERE conditions for the list filters. Every value from the request is
+// bound as a parameter; nothing from the request is formatted into the SQL text.
+fn push_filters<'a>(builder: &mut QueryBuilder<'a, Sqlite>, params: &'a FilterParams) {
     if let Some(category) = &params.category {
-        query.push_str(&format!(" AND category = '{}'", category));
+        builder.push(
+            " AND category_id IN (WITH RECURSIVE subtree(id) AS (SELECT id FROM exercise_categories WHERE "
+        );
+        push_entry_match(builder, Taxonomy::Category, category);
+        builder.push(
+            " UNION SELECT c.id FROM exercise_categories c JOIN subtree s ON c.parent_id = s.id) SELECT id FROM subtree)"
+        );
     }
-    
+
     if let Some(difficulty) = &params.difficulty {
-        query.push_str(&format!(" AND difficulty_level = '{}'", difficulty));
+        builder.push(" AND difficulty_id IN (SELECT id FROM difficulty_levels WHERE ");
+        push_entry_match(builder, Taxonomy::Difficulty, difficulty);
+        builder.push(")");
     }
-    
+
     if let Some(search) = &params.search {
-        query.push_str(&format!(
-            " AND (title LIKE '%{}%' OR description LIKE '%{}%')",
-            search, search
+        let pattern = like_pattern(search);
+        builder
+            .push(" AND (title LIKE ")
+            .push_bind(pattern.clone())
+            .push(" ESCAPE '\\' OR description LIKE ")
+            .push_bind(pattern)
+            .push(" ESCAPE '\\')");
+    }
+
+    // Matches exercises linked to any of the listed entries
+    for (kind, link_table, link_column, values) in [
+        (Taxonomy::Muscle, "exercise_muscles", "muscle_id", &params.target_muscles),
+        (Taxonomy::Equipment, "exercise_equipment", "equipment_id", &params.equipment_needed),
+    ] {
+        let Some(values) = values.as_deref().map(split_list).filter(|v| !v.is_empty()) else {
+            continue;
+        };
+        builder.push(format!(
+            " AND EXISTS (SELECT 1 FROM {0} link WHERE link.exercise_id = exercises.id AND link.{1} IN (SELECT id FROM {2} WHERE ",
+            link_table,
+            link_column,
+            kind.table()
         ));
+        for (i, value) in values.iter().enumerate() {
+            if i > 0 {
+                builder.push(" OR ");
+            }
+            push_entry_match(builder, kind, value);
+        }
+        builder.push("))");
     }
-    
-    query.push_str(" ORDER BY created_at DESC");
-    
-    let exercises = sqlx::query_as::<_, Exercise>(&query)
+
+    if let Some(is_specialized) = params.is_specialized {
+        builder.push(" AND is_specialized = ").push_bind(is_specialized as i64);
+    }
+
+    if let Some(min) = params.min_duration_minutes {
+        builder.push(" AND duration_minutes >= ").push_bind(min);
+    }
+
+    if let Some(max) = params.max_duration_minutes {
+        builder.push(" AND duration_minutes <= ").push_bind(max);
+    }
+}
+
+// ORDER BY clause for the requested sort. Only these fixed strings reach the SQL; id breaks
+// ties so pages do not overlap or skip rows.
+fn order_clause(sort: Option<&str>, order: Option<&str>) -> Result<String, (StatusCode, Json<ErrorResponse>)> {
+    let bad_request = |message: &str| {
+        (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: message.to_string() }))
+    };
+
+    let sort = sort.unwrap_or("created_at");
+    let direction = match order {
+        Some("asc") => "ASC",
+        Some("desc") => "DESC",
+        None if sort == "created_at" => "DESC",
+        None => "ASC",
+        Some(_) => return Err(bad_request("order must be asc or desc")),
+    };
+
+    let clause = match sort {
+        "title" => format!("title COLLATE NOCASE {0}, id {0}", direction),
+        // Levels follow their configured position; exercises without a level come last
+        "difficulty" => format!(
+            "(SELECT position FROM difficulty_levels WHERE id = exercises.difficulty_id) IS NULL, \
+             (SELECT position FROM difficulty_levels WHERE id = exercises.difficulty_id) {0}, \
+             title COLLATE NOCASE ASC, id ASC",
+            direction
+        ),
+        // Exercises without a duration come last either way
+        "duration" => format!("duration_minutes IS NULL, duration_minutes {0}, id {0}", direction),
+        "created_at" => format!("created_at {0}, id {0}", direction),
+        _ => return Err(bad_request("sort must be one of title, difficulty, duration, created_at")),
+    };
+
+    Ok(format!(" ORDER BY {}", clause))
+}
+
+pub async fn health_check() -> impl IntoResponse {
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+// Get all exercises with optional filters
+pub async fn get_exercises(
+    Extension(pool): Extension<SqlitePool>,
+    Extension(store): Extension<Arc<dyn MediaStore>>,
+    headers: HeaderMap,
+    Query(params): Query<FilterParams>,
+) -> Result<Json<ExercisePage>, (StatusCode, Json<ErrorResponse>)> {
+    let viewer = resolve_viewer(&headers)?;
+    if let (Some(min), Some(max)) = (params.min_duration_minutes, params.max_duration_minutes) {
+        if min > max {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse { error: "min_duration_minutes cannot be greater than max_duration_minutes".to_string() }),
+            ));
+        }
+    }
+
+    let page = params.page.unwrap_or(1).max(1);
+    let limit = params.limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT);
+    let order = order_clause(params.sort.as_deref(), params.order.as_deref())?;
+
+    let mut count = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM exercises WHERE 1=1");
+    push_filters(&mut count, &params);
+    push_visibility(&mut count, &viewer);
+    let total: i64 = count
+        .build_query_scalar()
+        .fetch_one(&pool)
+        .await
+        .map_err(db_error)?;
+
+    let mut builder = QueryBuilder::<Sqlite>::new("SELECT * FROM exercises WHERE 1=1");
+    push_filters(&mut builder, &params);
+    push_visibility(&mut builder, &viewer);
+    builder.push(order);
+    builder.push(" LIMIT ").push_bind(limit);
+    builder.push(" OFFSET ").push_bind((page - 1).saturating_mul(limit));
+
+    let exercises = builder
+        .build_query_as::<Exercise>()
         .fetch_all(&pool)
         .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse { error: format!("Database error: {}", e) }),
-            )
-        })?;
+        .map_err(db_error)?;
 
     let mut response: Vec<ExerciseResponse> = exercises.into_iter().map(|e| e.into()).collect();
 
-    // Attach images for each exercise in the list efficiently in a single query
-    let ids: Vec<i64> = response.iter().map(|r| r.id).collect();
-    if !ids.is_empty() {
-        // build placeholders for IN clause like ?,?,?
-        let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
-        let images_query = format!(
-            "SELECT exercise_id, url FROM exercise_images WHERE exercise_id IN ({}) ORDER BY exercise_id, position ASC",
-            placeholders
-        );
+    // Images, videos and routines for the exercises on this page, one query each
+    attach_images(&pool, store.as_ref(), &mut response).await?;
+    attach_videos(&pool, store.as_ref(), &mut response).await?;
+    attach_routines(&pool, &viewer, &mut response).await?;
 
-        let mut q = sqlx::query(&images_query);
-        for id in &ids {
-            q = q.bind(id);
-        }
+    let has_more = (page - 1).saturating_mul(limit) + (response.len() as i64) < total;
+    Ok(Json(ExercisePage {
+        exercises: response,
+        total,
+        page,
+        limit,
+        has_more,
+    }))
+}
 
-        let rows = q
-            .fetch_all(&pool)
-            .await
-            .map_err(|e| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse { error: format!("Database error: {}", e) }),
-                )
-            })?;
-
-        use std::collections::HashMap;
-        let mut map: HashMap<i64, Vec<String>> = HashMap::new();
-        for row in rows {
-            let ex_id: i64 = row.get::<i64, _>("exercise_id");
-            let url: String = row.get::<String, _>("url");
-            map.entry(ex_id).or_default().push(url);
+#[derive(Debug, Deserialize)]
+pub struct SearchParams {
+    pub q: String,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchResult {
+    #[serde(flatten)]
+    pub exercise: ExerciseResponse,
+    // Higher is more relevant (negated bm25)
+    pub score: f64,
+    // Title and best matching excerpt, HTML-escaped, with matches wrapped in <mark></mark>
+    pub title_highlight: String,
+    pub snippet: String,
+}
+
+#[derive(Debug, FromRow)]
+struct SearchRow {
+    #[sqlx(flatten)]
+    exercise: Exercise,
+    score: f64,
+    title_highlight: String,
+    snippet: String,
+}
+
+const DEFAULT_SEARCH_LIMIT: i64 = 20;
+const MAX_SEARCH_LIMIT: i64 = 100;
+const MAX_SEARCH_TERMS: usize = 10;
+
+// highlight() and snippet() mark matches with these control characters; the text is
+// HTML-escaped first and only then are they swapped for <mark> tags
+const MATCH_START: char = '\u{2}';
+const MATCH_END: char = '\u{3}';
+
+fn mark_matches(text: &str) -> String {
+    let mut html = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            MATCH_START => html.push_str("<mark>"),
+            MATCH_END => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
         }
+    }
+    html
+}
 
-        for resp in response.iter_mut() {
-            if let Some(imgs) = map.get(&resp.id) {
-                resp.images = Some(imgs.clone());
+// Turn free text into an FTS5 query: every word must match as a prefix. Only letters and
+// digits reach MATCH, so FTS operators and quotes in the input cannot change the query.
+// The tokenizer folds č/ć/š/ž but not đ, so words with đ or dj also try the other spelling.
+fn fts_query(q: &str) -> Option<String> {
+    let terms: Vec<String> = q
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .take(MAX_SEARCH_TERMS)
+        .map(|word| {
+            let word = word.to_lowercase();
+            let mut variants = vec![word.clone()];
+            if word.contains("dj") {
+                variants.push(word.replace("dj", "đ"));
+            }
+            if word.contains('đ') {
+                variants.push(word.replace('đ', "dj"));
+            }
+            let variants: Vec<String> = variants.iter().map(|v| format!("\"{}\"*", v)).collect();
+            if variants.len() == 1 {
+                variants[0].clone()
             } else {
-                resp.images = Some(vec![]);
+                format!("({})", variants.join(" OR "))
             }
-        }
+        })
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
     }
+}
+
+// Full-text search over title, description, instructions and target muscles, best matches first
+pub async fn search_exercises(
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+    Query(params): Query<SearchParams>,
+) -> Result<Json<Vec<SearchResult>>, (StatusCode, Json<ErrorResponse>)> {
+    let viewer = resolve_viewer(&headers)?;
+    let query = fts_query(&params.q).ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "Search query must contain at least one word".to_string() }),
+        )
+    })?;
+    let limit = params.limit.unwrap_or(DEFAULT_SEARCH_LIMIT).clamp(1, MAX_SEARCH_LIMIT);
 
-    Ok(Json(response))
+    // Column weights: title, description, instructions, target muscles
+    let rows = sqlx::query_as::<_, SearchRow>(
+        r#"
+        SELECT e.*,
+               -bm25(exercises_fts, 10.0, 4.0, 1.0, 2.0) AS score,
+               highlight(exercises_fts, 0, char(2), char(3)) AS title_highlight,
+               snippet(exercises_fts, -1, char(2), char(3), '…', 16) AS snippet
+        FROM exercises_fts
+        JOIN exercises e ON e.id = exercises_fts.rowid
+        WHERE exercises_fts MATCH ?
+        AND (e.is_specialized = 0 OR ? OR EXISTS (
+            SELECT 1 FROM exercise_assignments ea WHERE ea.exercise_id = e.id AND ea.patient_id = ?
+        ))
+        ORDER BY score DESC, e.created_at DESC
+        LIMIT ?
+        "#
+    )
+    .bind(&query)
+    .bind(matches!(viewer, Viewer::Staff(_)))
+    .bind(match &viewer {
+        Viewer::Patient(patient_id) => Some(patient_id.as_str()),
+        _ => None,
+    })
+    .bind(limit)
+    .fetch_all(&pool)
+    .await
+    .map_err(db_error)?;
+
+    let results = rows
+        .into_iter()
+        .map(|row| SearchResult {
+            exercise: row.exercise.into(),
+            score: row.score,
+            title_highlight: mark_matches(&row.title_highlight),
+            snippet: mark_matches(&row.snippet),
+        })
+        .collect();
+
+    Ok(Json(results))
 }
 
 // Get single exercise
 pub async fn get_exercise(
     Extension(pool): Extension<SqlitePool>,
+    Extension(store): Extension<Arc<dyn MediaStore>>,
+    headers: HeaderMap,
     Path(exercise_id): Path<i64>,
 ) -> Result<Json<ExerciseResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let exercise = sqlx::query_as::<_, Exercise>(
-        "SELECT * FROM exercises WHERE id = ?"
-    )
-    .bind(exercise_id)
+    let viewer = resolve_viewer(&headers)?;
+
+    // Specialized exercises the viewer may not see are reported as missing
+    let mut builder = QueryBuilder::<Sqlite>::new("SELECT * FROM exercises WHERE id = ");
+    builder.push_bind(exercise_id);
+    push_visibility(&mut builder, &viewer);
+
+    let exercise = builder
+    .build_query_as::<Exercise>()
     .fetch_optional(&pool)
     .await
-    .map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: format!("Database error: {}", e) }),
-        )
-    })?
+    .map_err(db_error)?
     .ok_or_else(|| {
         (
             StatusCode::NOT_FOUND,
@@ -170,56 +514,30 @@ pub async fn get_exercise(
     })?;
 
     let mut resp: ExerciseResponse = exercise.into();
-    // load images
-    match load_images(&pool, exercise_id).await {
-        Ok(imgs) => resp.images = Some(imgs),
-        Err(_) => resp.images = Some(vec![]),
-    }
+    attach_images(&pool, store.as_ref(), std::slice::from_mut(&mut resp)).await?;
+    attach_videos(&pool, store.as_ref(), std::slice::from_mut(&mut resp)).await?;
+    attach_routines(&pool, &viewer, std::slice::from_mut(&mut resp)).await?;
 
     Ok(Json(resp))
 }
 
-// helper to load images for an exercise
-async fn load_images(pool: &SqlitePool, exercise_id: i64) -> Result<Vec<String>, (StatusCode, Json<ErrorResponse>)> {
-    // Instead of reading from DB, scan static/images/{exercise_id}/ for files
-    let mut urls = Vec::new();
-    let folder = format!("static/images/{}", exercise_id);
-    match std::fs::read_dir(&folder) {
-        Ok(entries) => {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_file() {
-                    if let Some(fname) = path.file_name().and_then(|f| f.to_str()) {
-                        let url = format!("/static/images/{}/{}", exercise_id, fname);
-                        urls.push(url);
-                    }
-                }
-            }
-        }
-        Err(_) => {}
-    }
-    Ok(urls)
-}
-
 // Create exercise
 pub async fn create_exercise(
     Extension(pool): Extension<SqlitePool>,
     headers: HeaderMap,
     Json(req): Json<CreateExerciseRequest>,
 ) -> Result<Json<ExerciseResponse>, (StatusCode, Json<ErrorResponse>)> {
-    // Extract user id from header
-    let user_id = match headers.get("x-user-id") {
-        Some(v) => v.to_str().ok().map(|s| s.to_string()),
-        None => None,
-    };
+    require_staff(&headers)?;
+    let now = now();
 
-    let user_id = user_id.ok_or_else(|| (StatusCode::UNAUTHORIZED, Json(ErrorResponse { error: "Missing or invalid x-user-id header".to_string() })))?;
 
-    ensure_admin_or_physio(&pool, &user_id).await?;
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs() as i64;
+    let mut tx = pool.begin().await.map_err(db_error)?;
+
+    // Category, level, muscles and equipment must name existing taxonomy entries
+    let category_id = resolve_entry(&mut tx, Taxonomy::Category, &req.category).await?;
+    let difficulty_id = resolve_entry(&mut tx, Taxonomy::Difficulty, &req.difficulty_level).await?;
+    let muscle_ids = resolve_entries(&mut tx, Taxonomy::Muscle, &req.target_muscles).await?;
+    let equipment_ids = resolve_entries(&mut tx, Taxonomy::Equipment, &req.equipment_needed).await?;
 
     let equipment_json = serde_json::to_string(&req.equipment_needed).unwrap();
     let instructions_json = serde_json::to_string(&req.instructions).unwrap();
@@ -231,8 +549,8 @@ pub async fn create_exercise(
         INSERT INTO exercises (
             title, description, category, difficulty_level, duration_minutes,
             equipment_needed, instructions, image_url, video_url, youtube_url,
-            target_muscles, created_at, is_specialized
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            target_muscles, created_at, is_specialized, category_id, difficulty_id
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#
     )
     .bind(&req.title)
@@ -248,51 +566,38 @@ pub async fn create_exercise(
     .bind(&target_muscles_json)
     .bind(now)
     .bind(is_specialized)
-    .execute(&pool)
+    .bind(category_id)
+    .bind(difficulty_id)
+    .execute(&mut *tx)
     .await
-    .map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: format!("Database error: {}", e) }),
-        )
-    })?;
+    .map_err(db_error)?;
 
-    let exercise = ExerciseResponse {
-        id: result.last_insert_rowid(),
-        title: req.title,
-        description: req.description,
-        category: req.category,
-        difficulty_level: req.difficulty_level,
-        duration_minutes: req.duration_minutes,
-        equipment_needed: req.equipment_needed,
-        instructions: req.instructions,
-        image_url: req.image_url,
-        images: None,
-        video_url: req.video_url,
-        youtube_url: req.youtube_url,
-        target_muscles: req.target_muscles,
-        created_at: now,
-        is_specialized: req.is_specialized.unwrap_or(false),
-    };
+    let exercise_id = result.last_insert_rowid();
+    set_exercise_links(&mut tx, Taxonomy::Muscle, exercise_id, &muscle_ids).await?;
+    set_exercise_links(&mut tx, Taxonomy::Equipment, exercise_id, &equipment_ids).await?;
+    // Store the canonical names, e.g. "Back" for "leđa"
+    refresh_labels(&mut tx, Some(exercise_id)).await?;
+
+    let exercise = sqlx::query_as::<_, Exercise>("SELECT * FROM exercises WHERE id = ?")
+        .bind(exercise_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
 
-    Ok(Json(exercise))
+    Ok(Json(exercise.into()))
 }
 
 // Update exercise
 pub async fn update_exercise(
     Extension(pool): Extension<SqlitePool>,
+    Extension(store): Extension<Arc<dyn MediaStore>>,
+    Extension(jobs): Extension<VideoJobs>,
     headers: HeaderMap,
     Path(exercise_id): Path<i64>,
     Json(req): Json<UpdateExerciseRequest>,
 ) -> Result<Json<ExerciseResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let user_id = match headers.get("x-user-id") {
-        Some(v) => v.to_str().ok().map(|s| s.to_string()),
-        None => None,
-    };
-
-    let user_id = user_id.ok_or_else(|| (StatusCode::UNAUTHORIZED, Json(ErrorResponse { error: "Missing or invalid x-user-id header".to_string() })))?;
-
-    ensure_admin_or_physio(&pool, &user_id).await?;
+    require_staff(&headers)?;
     // Get existing exercise
     let existing = sqlx::query_as::<_, Exercise>(
         "SELECT * FROM exercises WHERE id = ?"
@@ -300,12 +605,7 @@ pub async fn update_exercise(
     .bind(exercise_id)
     .fetch_optional(&pool)
     .await
-    .map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: format!("Database error: {}", e) }),
-        )
-    })?
+    .map_err(db_error)?
     .ok_or_else(|| {
         (
             StatusCode::NOT_FOUND,
@@ -313,6 +613,26 @@ pub async fn update_exercise(
         )
     })?;
 
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+
+    let category_id = match &req.category {
+        Some(category) => Some(resolve_entry(&mut tx, Taxonomy::Category, category).await?),
+        None => existing.category_id,
+    };
+    let difficulty_id = match &req.difficulty_level {
+        Some(difficulty) => Some(resolve_entry(&mut tx, Taxonomy::Difficulty, difficulty).await?),
+        None => existing.difficulty_id,
+    };
+    let muscle_ids = match &req.target_muscles {
+        Some(muscles) => Some(resolve_entries(&mut tx, Taxonomy::Muscle, muscles).await?),
+        None => None,
+    };
+    let equipment_ids = match &req.equipment_needed {
+        Some(equipment) => Some(resolve_entries(&mut tx, Taxonomy::Equipment, equipment).await?),
+        None => None,
+    };
+
     let title = req.title.unwrap_or(existing.title);
     let description = req.description.unwrap_or(existing.description);
     let category = req.category.unwrap_or(existing.category);
@@ -340,13 +660,15 @@ pub async fn update_exercise(
     let image_url = req.image_url.or(existing.image_url);
     let video_url = req.video_url.or(existing.video_url);
     let youtube_url = req.youtube_url.or(existing.youtube_url);
+    let is_specialized = req.is_specialized.map(i64::from).unwrap_or(existing.is_specialized);
 
     sqlx::query(
         r#"
         UPDATE exercises 
         SET title = ?, description = ?, category = ?, difficulty_level = ?,
             duration_minutes = ?, equipment_needed = ?, instructions = ?,
-            image_url = ?, video_url = ?, youtube_url = ?, target_muscles = ?
+            image_url = ?, video_url = ?, youtube_url = ?, target_muscles = ?,
+            is_specialized = ?, category_id = ?, difficulty_id = ?
         WHERE id = ?
         "#
     )
@@ -361,184 +683,476 @@ pub async fn update_exercise(
     .bind(&video_url)
     .bind(&youtube_url)
     .bind(&target_muscles_json)
+    .bind(is_specialized)
+    .bind(category_id)
+    .bind(difficulty_id)
     .bind(exercise_id)
-    .execute(&pool)
+    .execute(&mut *tx)
     .await
-    .map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: format!("Database error: {}", e) }),
-        )
-    })?;
+    .map_err(db_error)?;
+
+    if let Some(muscle_ids) = muscle_ids {
+        set_exercise_links(&mut tx, Taxonomy::Muscle, exercise_id, &muscle_ids).await?;
+    }
+    if let Some(equipment_ids) = equipment_ids {
+        set_exercise_links(&mut tx, Taxonomy::Equipment, exercise_id, &equipment_ids).await?;
+    }
+    refresh_labels(&mut tx, Some(exercise_id)).await?;
 
     // Fetch updated exercise
     let exercise = sqlx::query_as::<_, Exercise>(
         "SELECT * FROM exercises WHERE id = ?"
     )
     .bind(exercise_id)
-    .fetch_one(&pool)
+    .fetch_one(&mut *tx)
     .await
-    .map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: format!("Database error: {}", e) }),
-        )
-    })?;
+    .map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
+
+    // Images and videos follow the exercise between public/ and private/. The update is already
+    // saved, so a failed move is only logged; relocate_all_media repairs it on the next start.
+    if exercise.is_specialized != existing.is_specialized {
+        match relocate_exercise_media(&pool, store.as_ref(), exercise_id, exercise.is_specialized != 0).await {
+            Ok(requeue) => {
+                for video_id in requeue {
+                    jobs.enqueue(video_id);
+                }
+            }
+            Err((_, Json(e))) => tracing::error!("Failed to move media of exercise {}: {}", exercise_id, e.error),
+        }
+    }
 
     Ok(Json(exercise.into()))
 }
 
-// Upload images for an exercise (multipart/form-data)
-pub async fn upload_exercise_images(
+// Delete exercise
+pub async fn delete_exercise(
     Extension(pool): Extension<SqlitePool>,
+    Extension(store): Extension<Arc<dyn MediaStore>>,
     headers: HeaderMap,
     Path(exercise_id): Path<i64>,
-    mut multipart: Multipart,
-) -> Result<Json<Vec<String>>, (StatusCode, Json<ErrorResponse>)> {
-    let user_id = match headers.get("x-user-id") {
-        Some(v) => v.to_str().ok().map(|s| s.to_string()),
-        None => None,
-    };
-
-    let user_id = user_id.ok_or_else(|| (StatusCode::UNAUTHORIZED, Json(ErrorResponse { error: "Missing or invalid x-user-id header".to_string() })))?;
-    ensure_admin_or_physio(&pool, &user_id).await?;
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    require_staff(&headers)?;
+    // The rows go with the exercise, so the files are looked up first
+    let keys = exercise_media_keys(&pool, exercise_id).await?;
+    let legacy = legacy_image_files(&pool, exercise_id).await?;
 
-    // ensure exercise exists
-    let exists = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM exercises WHERE id = ?")
+    let result = sqlx::query("DELETE FROM exercises WHERE id = ?")
         .bind(exercise_id)
-        .fetch_one(&pool)
+        .execute(&pool)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: format!("Database error: {}", e) })))?;
-
-    if exists == 0 {
-        return Err((StatusCode::NOT_FOUND, Json(ErrorResponse { error: "Exercise not found".to_string() })));
-    }
-
-    let current_dir = env::current_dir().map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: format!("Server error: {}", e) })))?;
-    let images_dir = current_dir.join("static").join("images").join(exercise_id.to_string());
-    if !images_dir.exists() {
-        std::fs::create_dir_all(&images_dir).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: format!("Failed to create exercise image directory: {}", e) })))?;
+        .map_err(db_error)?;
+    if result.rows_affected() == 0 {
+        return Err(error(StatusCode::NOT_FOUND, "Exercise not found"));
     }
 
-    let mut saved_urls: Vec<String> = Vec::new();
-
-    while let Some(field) = multipart.next_field().await.map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: format!("Multipart parse error: {}", e) })))? {
-        if let Some(file_name) = field.file_name().map(|s| s.to_string()) {
-            let data = field.bytes().await.map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: format!("Error reading field bytes: {}", e) })))?;
-            let timestamp = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .map(|d| d.as_millis() as i128)
-                .unwrap_or(0) as i64;
-            let safe_name = format!("{}-{}", timestamp, sanitize_filename::sanitize(&file_name));
-            let file_path = images_dir.join(&safe_name);
-            fs::write(&file_path, &data).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: format!("Failed to save file: {}", e) })))?;
-
-            // store URL (serve under /static/images/{exercise_id}/{name})
-            let url = format!("/static/images/{}/{}", exercise_id, safe_name);
-
-            // determine next position
-            let position: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(position), -1) + 1 FROM exercise_images WHERE exercise_id = ?")
-                .bind(exercise_id)
-                .fetch_one(&pool)
-                .await
-                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: format!("Database error: {}", e) })))?;
-
-            let now = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .map(|d| d.as_secs() as i64)
-                .unwrap_or(0);
-            sqlx::query("INSERT INTO exercise_images (exercise_id, url, position, created_at) VALUES (?, ?, ?, ?)")
-                .bind(exercise_id)
-                .bind(&url)
-                .bind(position)
-                .bind(now)
-                .execute(&pool)
-                .await
-                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: format!("Database error: {}", e) })))?;
-
-            saved_urls.push(url);
-        }
-    }
+    remove_keys(store.as_ref(), &keys).await;
+    remove_legacy_files(&legacy).await;
 
-    Ok(Json(saved_urls))
+    Ok(StatusCode::NO_CONTENT)
 }
 
-pub async fn delete_exercise_image(
+// List the patients a specialized exercise is assigned to
+pub async fn get_exercise_assignments(
     Extension(pool): Extension<SqlitePool>,
     headers: HeaderMap,
-    Path((exercise_id, image_id)): Path<(i64, i64)>,
-) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
-    let user_id = match headers.get("x-user-id") {
-        Some(v) => v.to_str().ok().map(|s| s.to_string()),
-        None => None,
-    };
+    Path(exercise_id): Path<i64>,
+) -> Result<Json<Vec<ExerciseAssignment>>, (StatusCode, Json<ErrorResponse>)> {
+    require_staff(&headers)?;
 
-    let user_id = user_id.ok_or_else(|| (StatusCode::UNAUTHORIZED, Json(ErrorResponse { error: "Missing or invalid x-user-id header".to_string() })))?;
-    ensure_admin_or_physio(&pool, &user_id).await?;
+    let assignments = sqlx::query_as::<_, ExerciseAssignment>(
+        "SELECT * FROM exercise_assignments WHERE exercise_id = ? ORDER BY created_at, patient_id"
+    )
+    .bind(exercise_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(db_error)?;
 
-    // fetch url
-    let row = sqlx::query("SELECT url FROM exercise_images WHERE id = ? AND exercise_id = ?")
-        .bind(image_id)
+    Ok(Json(assignments))
+}
+
+// Recommend a specialized exercise to a patient
+pub async fn assign_exercise(
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+    Path(exercise_id): Path<i64>,
+    Json(req): Json<AssignExerciseRequest>,
+) -> Result<(StatusCode, Json<ExerciseAssignment>), (StatusCode, Json<ErrorResponse>)> {
+    let claims = require_staff(&headers)?;
+
+    let is_specialized = sqlx::query_scalar::<_, i64>("SELECT is_specialized FROM exercises WHERE id = ?")
         .bind(exercise_id)
         .fetch_optional(&pool)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: format!("Database error: {}", e) })))?;
+        .map_err(db_error)?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse { error: "Exercise not found".to_string() }),
+            )
+        })?;
 
-    let url = row.ok_or_else(|| (StatusCode::NOT_FOUND, Json(ErrorResponse { error: "Image not found".to_string() })))?.get::<String, _>("url");
+    // Basic exercises are visible to everyone already
+    if is_specialized == 0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "Only specialized exercises can be assigned".to_string() }),
+        ));
+    }
 
-    // delete DB row
-    let res = sqlx::query("DELETE FROM exercise_images WHERE id = ?")
-        .bind(image_id)
-        .execute(&pool)
+    let role = sqlx::query_scalar::<_, String>("SELECT role FROM users WHERE id = ?")
+        .bind(&req.patient_id)
+        .fetch_optional(&pool)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: format!("Database error: {}", e) })))?;
-
-    // attempt remove file on disk if it points to /static/images/
-    if url.starts_with("/static/images/") {
-        let file_name = url.trim_start_matches("/static/images/");
-        let current_dir = env::current_dir().map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: format!("Server error: {}", e) })))?;
-        let file_path = current_dir.join("static").join("images").join(file_name);
-        let _ = fs::remove_file(file_path);
+        .map_err(db_error)?;
+    if role.as_deref() != Some("patient") {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: "Patient not found".to_string() }),
+        ));
     }
 
-    if res.rows_affected() == 0 {
-        return Err((StatusCode::NOT_FOUND, Json(ErrorResponse { error: "Image not found".to_string() })));
-    }
+    let now = now();
 
-    Ok(StatusCode::NO_CONTENT)
+    let result = sqlx::query(
+        r#"
+        INSERT OR IGNORE INTO exercise_assignments (exercise_id, patient_id, assigned_by, created_at)
+        VALUES (?, ?, ?, ?)
+        "#
+    )
+    .bind(exercise_id)
+    .bind(&req.patient_id)
+    .bind(&claims.sub)
+    .bind(now)
+    .execute(&pool)
+    .await
+    .map_err(db_error)?;
+
+    let assignment = sqlx::query_as::<_, ExerciseAssignment>(
+        "SELECT * FROM exercise_assignments WHERE exercise_id = ? AND patient_id = ?"
+    )
+    .bind(exercise_id)
+    .bind(&req.patient_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(db_error)?;
+
+    // Assigning twice keeps the original assignment
+    let status = if result.rows_affected() == 0 { StatusCode::OK } else { StatusCode::CREATED };
+    Ok((status, Json(assignment)))
 }
 
-// Delete exercise
-pub async fn delete_exercise(
+// Withdraw a specialized exercise from a patient
+pub async fn unassign_exercise(
     Extension(pool): Extension<SqlitePool>,
     headers: HeaderMap,
-    Path(exercise_id): Path<i64>,
+    Path((exercise_id, patient_id)): Path<(i64, String)>,
 ) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
-    let user_id = match headers.get("x-user-id") {
-        Some(v) => v.to_str().ok().map(|s| s.to_string()),
-        None => None,
-    };
-
-    let user_id = user_id.ok_or_else(|| (StatusCode::UNAUTHORIZED, Json(ErrorResponse { error: "Missing or invalid x-user-id header".to_string() })))?;
+    require_staff(&headers)?;
 
-    ensure_admin_or_physio(&pool, &user_id).await?;
-    let result = sqlx::query("DELETE FROM exercises WHERE id = ?")
+    let result = sqlx::query("DELETE FROM exercise_assignments WHERE exercise_id = ? AND patient_id = ?")
         .bind(exercise_id)
+        .bind(&patient_id)
         .execute(&pool)
         .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse { error: format!("Database error: {}", e) }),
-            )
-        })?;
+        .map_err(db_error)?;
 
     if result.rows_affected() == 0 {
         return Err((
             StatusCode::NOT_FOUND,
-            Json(ErrorResponse { error: "Exercise not found".to_string() }),
+            Json(ErrorResponse { error: "Assignment not found".to_string() }),
         ));
     }
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{auth_headers, seed, seed_all, test_pool, test_store, titles, user_headers};
+
+    #[tokio::test]
+    async fn exercise_writes_need_a_staff_token() {
+        let pool = test_pool().await;
+        seed_all(&pool).await;
+        let squat: i64 = sqlx::query_scalar("SELECT id FROM exercises WHERE title = 'Squat'").fetch_one(&pool).await.unwrap();
+
+        // x-user-id alone names a user but proves nothing
+        let err = delete_exercise(Extension(pool.clone()), Extension(test_store()), user_headers("admin-001"), Path(squat)).await.unwrap_err();
+        assert_eq!(err.0, StatusCode::UNAUTHORIZED);
+        let err = delete_exercise(Extension(pool.clone()), Extension(test_store()), auth_headers("patient-001", "patient"), Path(squat)).await.unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+
+        let status = delete_exercise(Extension(pool.clone()), Extension(test_store()), auth_headers("admin-001", "admin"), Path(squat)).await.unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn filters_on_json_lists_flags_and_duration() {
+        let pool = test_pool().await;
+        seed_all(&pool).await;
+
+        let quads = titles(&pool, FilterParams { target_muscles: Some("quadriceps".to_string()), ..Default::default() }).await;
+        assert_eq!(quads, vec!["Resisted knee extension", "Squat"]);
+
+        let equipment = titles(&pool, FilterParams { equipment_needed: Some("mat, resistance band".to_string()), ..Default::default() }).await;
+        assert_eq!(equipment, vec!["Resisted knee extension", "Runner's stretch"]);
+
+        let general = titles(&pool, FilterParams { is_specialized: Some(false), min_duration_minutes: Some(6), ..Default::default() }).await;
+        assert_eq!(general, vec!["Squat"]);
+
+        let short = titles(&pool, FilterParams { max_duration_minutes: Some(10), ..Default::default() }).await;
+        assert_eq!(short, vec!["Runner's stretch", "Squat"]);
+
+        // Quotes in the search term are matched literally
+        let stretch = titles(&pool, FilterParams { search: Some("runner's".to_string()), ..Default::default() }).await;
+        assert_eq!(stretch, vec!["Runner's stretch"]);
+
+        let err = get_exercises(
+            Extension(pool.clone()),
+            Extension(test_store()),
+            HeaderMap::new(),
+            Query(FilterParams { min_duration_minutes: Some(20), max_duration_minutes: Some(5), ..Default::default() }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn malicious_filter_values_are_treated_as_data() {
+        let pool = test_pool().await;
+        seed_all(&pool).await;
+
+        let payloads = [
+            "' OR '1'='1",
+            "' OR 1=1 --",
+            "strength' --",
+            "'; DROP TABLE exercises; --",
+            "'; DELETE FROM users; --",
+            "x' UNION SELECT id, role, role, role, role, 0, role, role, role, role, role, role, 0, 0 FROM users --",
+            "') OR ('1'='1",
+            "\" OR \"\"=\"",
+            "%",
+            "_",
+            "\\",
+            "%' OR title LIKE '%",
+        ];
+
+        for payload in payloads {
+            let filters = [
+                FilterParams { category: Some(payload.to_string()), ..Default::default() },
+                FilterParams { difficulty: Some(payload.to_string()), ..Default::default() },
+                FilterParams { search: Some(payload.to_string()), ..Default::default() },
+                FilterParams { target_muscles: Some(payload.to_string()), ..Default::default() },
+                FilterParams { equipment_needed: Some(payload.to_string()), ..Default::default() },
+            ];
+            for params in filters {
+                let debug = format!("{:?}", params);
+                let Json(page) = get_exercises(Extension(pool.clone()), Extension(test_store()), HeaderMap::new(), Query(params))
+                    .await
+                    .unwrap_or_else(|_| panic!("query failed for {}", debug));
+                assert_eq!(page.total, 0, "{} matched {} exercises", debug, page.total);
+            }
+        }
+
+        // Nothing was dropped or deleted along the way
+        let exercises: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM exercises").fetch_one(&pool).await.unwrap();
+        let users: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users").fetch_one(&pool).await.unwrap();
+        assert_eq!((exercises, users), (3, 3));
+    }
+
+    async fn search(pool: &SqlitePool, q: &str) -> Vec<SearchResult> {
+        let Json(results) = search_exercises(
+            Extension(pool.clone()),
+            HeaderMap::new(),
+            Query(SearchParams { q: q.to_string(), limit: None }),
+        )
+        .await
+        .unwrap();
+        results
+    }
+
+    #[tokio::test]
+    async fn full_text_search_ranks_folds_diacritics_and_stays_in_sync() {
+        let pool = test_pool().await;
+        seed(&pool, "Čučanj uz zid", 10, &["Kvadriceps"], &[], false).await;
+        seed(&pool, "Istezanje za leđa", 5, &["Leđni mišići"], &["Prostirka"], false).await;
+        seed(&pool, "Iskorak", 10, &["Kvadriceps", "Gluteus"], &[], false).await;
+        sqlx::query("UPDATE exercises SET description = 'Lagani čučanj na jednoj nozi' WHERE title = 'Iskorak'")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // Title matches rank above description matches
+        let results = search(&pool, "cucanj").await;
+        let titles: Vec<&str> = results.iter().map(|r| r.exercise.title.as_str()).collect();
+        assert_eq!(titles, vec!["Čučanj uz zid", "Iskorak"]);
+        assert!(results[0].score > results[1].score);
+        assert_eq!(results[0].title_highlight, "<mark>Čučanj</mark> uz zid");
+        assert!(results[1].snippet.contains("<mark>čučanj</mark>"));
+
+        // Target muscles are indexed; "dj" also finds "đ"
+        assert_eq!(search(&pool, "ledja").await.len(), 1);
+        assert_eq!(search(&pool, "glut").await[0].exercise.title, "Iskorak");
+
+        // FTS syntax in the input is not interpreted; input without words is a bad request
+        for q in ["\"", "cucanj OR", "NEAR(cucanj", "title:cucanj", "*", "cucanj\" OR \"1"] {
+            let result = search_exercises(
+                Extension(pool.clone()),
+                HeaderMap::new(),
+                Query(SearchParams { q: q.to_string(), limit: None }),
+            )
+            .await;
+            assert!(
+                matches!(result, Ok(_) | Err((StatusCode::BAD_REQUEST, _))),
+                "search failed for {}",
+                q
+            );
+        }
+
+        sqlx::query("DELETE FROM exercises WHERE title = 'Čučanj uz zid'").execute(&pool).await.unwrap();
+        assert_eq!(search(&pool, "cucanj").await.len(), 1);
+
+        // Markup in the exercise text comes back escaped; only the match markers are HTML
+        seed(&pool, "Most <img src=x onerror=alert(1)> & \"plank\"", 5, &[], &[], false).await;
+        let results = search(&pool, "plank").await;
+        assert_eq!(
+            results[0].title_highlight,
+            "Most &lt;img src=x onerror=alert(1)&gt; &amp; &quot;<mark>plank</mark>&quot;"
+        );
+    }
+
+    #[tokio::test]
+    async fn pages_are_sorted_and_counted() {
+        let pool = test_pool().await;
+        seed_all(&pool).await;
+        sqlx::query(
+            "UPDATE exercises SET difficulty_level = 'Advanced', \
+             difficulty_id = (SELECT id FROM difficulty_levels WHERE slug = 'advanced') WHERE title = 'Squat'"
+        )
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let staff = || auth_headers("admin-001", "admin");
+        let page = |page: i64, sort: &str, order: Option<&str>| FilterParams {
+            page: Some(page),
+            limit: Some(2),
+            sort: Some(sort.to_string()),
+            order: order.map(str::to_string),
+            ..Default::default()
+        };
+
+        let Json(first) = get_exercises(Extension(pool.clone()), Extension(test_store()), staff(), Query(page(1, "title", None))).await.unwrap();
+        let titles: Vec<&str> = first.exercises.iter().map(|e| e.title.as_str()).collect();
+        assert_eq!(titles, vec!["Resisted knee extension", "Runner's stretch"]);
+        assert_eq!((first.total, first.has_more), (3, true));
+        // Images are attached to the paged rows
+        assert!(first.exercises.iter().all(|e| e.images.is_some()));
+
+        let Json(second) = get_exercises(Extension(pool.clone()), Extension(test_store()), staff(), Query(page(2, "title", None))).await.unwrap();
+        assert_eq!(second.exercises.len(), 1);
+        assert_eq!((second.exercises[0].title.as_str(), second.has_more), ("Squat", false));
+
+        let Json(by_duration) = get_exercises(Extension(pool.clone()), Extension(test_store()), staff(), Query(page(1, "duration", Some("desc")))).await.unwrap();
+        assert_eq!(by_duration.exercises[0].title, "Resisted knee extension");
+
+        let Json(by_difficulty) = get_exercises(Extension(pool.clone()), Extension(test_store()), staff(), Query(page(1, "difficulty", Some("desc")))).await.unwrap();
+        assert_eq!(by_difficulty.exercises[0].title, "Squat");
+
+        let err = get_exercises(Extension(pool.clone()), Extension(test_store()), staff(), Query(page(1, "title; DROP TABLE exercises", None)))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+    }
+
+    async fn visible_titles(pool: &SqlitePool, headers: HeaderMap) -> Vec<String> {
+        let Json(page) = get_exercises(Extension(pool.clone()), Extension(test_store()), headers, Query(FilterParams::default())).await.unwrap();
+        let mut titles: Vec<String> = page.exercises.into_iter().map(|e| e.title).collect();
+        titles.sort();
+        titles
+    }
+
+    #[tokio::test]
+    async fn specialized_exercises_are_visible_to_staff_and_assigned_patients() {
+        let pool = test_pool().await;
+        seed_all(&pool).await;
+        let specialized: i64 = sqlx::query_scalar("SELECT id FROM exercises WHERE is_specialized = 1")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let patient = || auth_headers("patient-001", "patient");
+        let physio = || auth_headers("physio-001", "physiotherapist");
+        let basic = vec!["Runner's stretch", "Squat"];
+
+        assert_eq!(visible_titles(&pool, HeaderMap::new()).await, basic);
+        assert_eq!(visible_titles(&pool, patient()).await, basic);
+        assert_eq!(visible_titles(&pool, physio()).await.len(), 3);
+        assert_eq!(search(&pool, "knee").await.len(), 0);
+
+        let err = get_exercise(Extension(pool.clone()), Extension(test_store()), patient(), Path(specialized)).await.unwrap_err();
+        assert_eq!(err.0, StatusCode::NOT_FOUND);
+
+        // The role comes from the verified token, not from what the caller claims
+        let mut forged = HeaderMap::new();
+        forged.insert(AUTHORIZATION, "Bearer not-a-token".parse().unwrap());
+        let err = get_exercises(Extension(pool.clone()), Extension(test_store()), forged, Query(FilterParams::default())).await.unwrap_err();
+        assert_eq!(err.0, StatusCode::UNAUTHORIZED);
+
+        let assign = |headers: HeaderMap, patient_id: &str| {
+            assign_exercise(
+                Extension(pool.clone()),
+                headers,
+                Path(specialized),
+                Json(AssignExerciseRequest { patient_id: patient_id.to_string() }),
+            )
+        };
+        assert_eq!(assign(patient(), "patient-001").await.unwrap_err().0, StatusCode::FORBIDDEN);
+        assert_eq!(assign(physio(), "admin-001").await.unwrap_err().0, StatusCode::NOT_FOUND);
+        assert_eq!(assign(physio(), "patient-001").await.unwrap().0, StatusCode::CREATED);
+        assert_eq!(assign(physio(), "patient-001").await.unwrap().0, StatusCode::OK);
+
+        assert_eq!(visible_titles(&pool, patient()).await.len(), 3);
+        assert_eq!(visible_titles(&pool, HeaderMap::new()).await, basic);
+        let Json(exercise) = get_exercise(Extension(pool.clone()), Extension(test_store()), patient(), Path(specialized)).await.unwrap();
+        assert!(exercise.is_specialized);
+        let Json(results) = search_exercises(
+            Extension(pool.clone()),
+            patient(),
+            Query(SearchParams { q: "knee".to_string(), limit: None }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(results.len(), 1);
+
+        let basic_id: i64 = sqlx::query_scalar("SELECT id FROM exercises WHERE title = 'Squat'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let err = assign_exercise(
+            Extension(pool.clone()),
+            physio(),
+            Path(basic_id),
+            Json(AssignExerciseRequest { patient_id: "patient-001".to_string() }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+
+        let Json(assignments) = get_exercise_assignments(Extension(pool.clone()), physio(), Path(specialized)).await.unwrap();
+        assert_eq!(assignments.len(), 1);
+        assert_eq!(assignments[0].assigned_by, "physio-001");
+
+        let status = unassign_exercise(Extension(pool.clone()), physio(), Path((specialized, "patient-001".to_string())))
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert_eq!(visible_titles(&pool, patient()).await, basic);
+        let err = unassign_exercise(Extension(pool.clone()), physio(), Path((specialized, "patient-001".to_string())))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::NOT_FOUND);
+    }
+}
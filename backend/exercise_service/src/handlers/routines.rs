@@ -0,0 +1,548 @@
+use axum::{
+    extract::{Extension, Json, Path, Query},
+    http::{HeaderMap, StatusCode},
+};
+use serde::Deserialize;
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection, SqlitePool};
+use std::collections::HashMap;
+
+use super::{db_error, error, now, require_staff, ApiError, Viewer};
+use crate::models::*;
+use crate::utils::Claims;
+
+// Templates are shared with all staff; private routines only with their owner and admins
+fn can_view(claims: &Claims, routine: &Routine) -> bool {
+    routine.is_template != 0 || can_edit(claims, routine)
+}
+
+fn can_edit(claims: &Claims, routine: &Routine) -> bool {
+    routine.owner_id == claims.sub || claims.role == "admin"
+}
+
+fn estimated_minutes(items: &[RoutineExercise]) -> i64 {
+    let seconds: i64 = items
+        .iter()
+        .map(|item| {
+            let rests = (item.sets.unwrap_or(1) - 1).max(0);
+            item.duration_minutes.unwrap_or(0) * 60 + item.rest_seconds.unwrap_or(0) * rests
+        })
+        .sum();
+    (seconds + 59) / 60
+}
+
+async fn fetch_routine(conn: &mut SqliteConnection, routine_id: i64) -> Result<Routine, ApiError> {
+    sqlx::query_as::<_, Routine>("SELECT * FROM routines WHERE id = ?")
+        .bind(routine_id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "Routine not found"))
+}
+
+// Routines with their exercises in order, loaded in one query
+async fn with_items(conn: &mut SqliteConnection, routines: Vec<Routine>) -> Result<Vec<RoutineResponse>, ApiError> {
+    let mut items: HashMap<i64, Vec<RoutineExercise>> = HashMap::new();
+    if !routines.is_empty() {
+        let mut builder = QueryBuilder::<Sqlite>::new(
+            "SELECT re.routine_id, re.position, re.exercise_id, e.title, e.duration_minutes, \
+             re.sets, re.reps, re.rest_seconds, re.notes \
+             FROM routine_exercises re JOIN exercises e ON e.id = re.exercise_id \
+             WHERE re.routine_id IN ("
+        );
+        let mut separated = builder.separated(", ");
+        for routine in &routines {
+            separated.push_bind(routine.id);
+        }
+        builder.push(") ORDER BY re.routine_id, re.position");
+
+        #[derive(sqlx::FromRow)]
+        struct Row {
+            routine_id: i64,
+            #[sqlx(flatten)]
+            item: RoutineExercise,
+        }
+
+        let rows = builder
+            .build_query_as::<Row>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(db_error)?;
+        for row in rows {
+            items.entry(row.routine_id).or_default().push(row.item);
+        }
+    }
+
+    Ok(routines
+        .into_iter()
+        .map(|routine| {
+            let exercises = items.remove(&routine.id).unwrap_or_default();
+            RoutineResponse {
+                id: routine.id,
+                title: routine.title,
+                description: routine.description,
+                is_template: routine.is_template != 0,
+                owner_id: routine.owner_id,
+                cloned_from: routine.cloned_from,
+                estimated_duration_minutes: estimated_minutes(&exercises),
+                exercises,
+                created_at: routine.created_at,
+                updated_at: routine.updated_at,
+            }
+        })
+        .collect())
+}
+
+async fn load_routine(conn: &mut SqliteConnection, routine_id: i64) -> Result<RoutineResponse, ApiError> {
+    let routine = fetch_routine(conn, routine_id).await?;
+    Ok(with_items(conn, vec![routine]).await?.remove(0))
+}
+
+// Unknown exercises and non-positive counts are rejected before anything is written
+async fn validate_items(conn: &mut SqliteConnection, items: &[RoutineExerciseRequest]) -> Result<(), ApiError> {
+    for item in items {
+        if item.sets.is_some_and(|v| v < 1) || item.reps.is_some_and(|v| v < 1) {
+            return Err(error(StatusCode::BAD_REQUEST, "sets and reps must be at least 1"));
+        }
+        if item.rest_seconds.is_some_and(|v| v < 0) {
+            return Err(error(StatusCode::BAD_REQUEST, "rest_seconds cannot be negative"));
+        }
+    }
+
+    let mut missing = Vec::new();
+    for item in items {
+        let exists = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM exercises WHERE id = ?")
+            .bind(item.exercise_id)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(db_error)?;
+        if exists == 0 && !missing.contains(&item.exercise_id) {
+            missing.push(item.exercise_id);
+        }
+    }
+    if !missing.is_empty() {
+        let ids: Vec<String> = missing.iter().map(i64::to_string).collect();
+        return Err(error(StatusCode::BAD_REQUEST, format!("Unknown exercises: {}", ids.join(", "))));
+    }
+    Ok(())
+}
+
+async fn replace_items(conn: &mut SqliteConnection, routine_id: i64, items: &[RoutineExerciseRequest]) -> Result<(), ApiError> {
+    sqlx::query("DELETE FROM routine_exercises WHERE routine_id = ?")
+        .bind(routine_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(db_error)?;
+
+    for (position, item) in items.iter().enumerate() {
+        sqlx::query(
+            r#"
+            INSERT INTO routine_exercises (routine_id, exercise_id, position, sets, reps, rest_seconds, notes)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(routine_id)
+        .bind(item.exercise_id)
+        .bind(position as i64)
+        .bind(item.sets)
+        .bind(item.reps)
+        .bind(item.rest_seconds)
+        .bind(&item.notes)
+        .execute(&mut *conn)
+        .await
+        .map_err(db_error)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RoutineParams {
+    // true: only templates, false: only the caller's private routines
+    pub templates: Option<bool>,
+}
+
+// List the routines the caller can see
+pub async fn get_routines(
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+    Query(params): Query<RoutineParams>,
+) -> Result<Json<Vec<RoutineResponse>>, ApiError> {
+    let claims = require_staff(&headers)?;
+    let mut conn = pool.acquire().await.map_err(db_error)?;
+
+    let mut builder = QueryBuilder::<Sqlite>::new("SELECT * FROM routines WHERE ");
+    match params.templates {
+        Some(true) => {
+            builder.push("is_template = 1");
+        }
+        Some(false) => {
+            builder.push("is_template = 0 AND owner_id = ").push_bind(&claims.sub);
+        }
+        None if claims.role == "admin" => {
+            builder.push("1=1");
+        }
+        None => {
+            builder.push("(is_template = 1 OR owner_id = ").push_bind(&claims.sub).push(")");
+        }
+    }
+    builder.push(" ORDER BY title COLLATE NOCASE, id");
+
+    let routines = builder
+        .build_query_as::<Routine>()
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(db_error)?;
+
+    Ok(Json(with_items(&mut conn, routines).await?))
+}
+
+pub async fn get_routine(
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+    Path(routine_id): Path<i64>,
+) -> Result<Json<RoutineResponse>, ApiError> {
+    let claims = require_staff(&headers)?;
+    let mut conn = pool.acquire().await.map_err(db_error)?;
+
+    // Private routines of others are reported as missing
+    let routine = fetch_routine(&mut conn, routine_id).await?;
+    if !can_view(&claims, &routine) {
+        return Err(error(StatusCode::NOT_FOUND, "Routine not found"));
+    }
+
+    Ok(Json(with_items(&mut conn, vec![routine]).await?.remove(0)))
+}
+
+pub async fn create_routine(
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+    Json(req): Json<CreateRoutineRequest>,
+) -> Result<(StatusCode, Json<RoutineResponse>), ApiError> {
+    let claims = require_staff(&headers)?;
+    if req.title.trim().is_empty() {
+        return Err(error(StatusCode::BAD_REQUEST, "Title is required"));
+    }
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    validate_items(&mut tx, &req.exercises).await?;
+
+    let now = now();
+    let routine_id = sqlx::query(
+        r#"
+        INSERT INTO routines (title, description, is_template, owner_id, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(req.title.trim())
+    .bind(&req.description)
+    .bind(req.is_template.unwrap_or(false))
+    .bind(&claims.sub)
+    .bind(now)
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error)?
+    .last_insert_rowid();
+
+    replace_items(&mut tx, routine_id, &req.exercises).await?;
+    let routine = load_routine(&mut tx, routine_id).await?;
+    tx.commit().await.map_err(db_error)?;
+
+    Ok((StatusCode::CREATED, Json(routine)))
+}
+
+// Owner or admin
+pub async fn update_routine(
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+    Path(routine_id): Path<i64>,
+    Json(req): Json<UpdateRoutineRequest>,
+) -> Result<Json<RoutineResponse>, ApiError> {
+    let claims = require_staff(&headers)?;
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    let existing = fetch_routine(&mut tx, routine_id).await?;
+    if !can_view(&claims, &existing) {
+        return Err(error(StatusCode::NOT_FOUND, "Routine not found"));
+    }
+    if !can_edit(&claims, &existing) {
+        return Err(error(StatusCode::FORBIDDEN, "Only the owner can change this routine"));
+    }
+
+    let title = req.title.as_deref().map(str::trim).unwrap_or(&existing.title).to_string();
+    if title.is_empty() {
+        return Err(error(StatusCode::BAD_REQUEST, "Title is required"));
+    }
+    let description = req.description.or(existing.description);
+    let is_template = req.is_template.map(i64::from).unwrap_or(existing.is_template);
+
+    if let Some(items) = &req.exercises {
+        validate_items(&mut tx, items).await?;
+        replace_items(&mut tx, routine_id, items).await?;
+    }
+
+    sqlx::query("UPDATE routines SET title = ?, description = ?, is_template = ?, updated_at = ? WHERE id = ?")
+        .bind(&title)
+        .bind(&description)
+        .bind(is_template)
+        .bind(now())
+        .bind(routine_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+    let routine = load_routine(&mut tx, routine_id).await?;
+    tx.commit().await.map_err(db_error)?;
+    Ok(Json(routine))
+}
+
+// Owner or admin
+pub async fn delete_routine(
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+    Path(routine_id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    let claims = require_staff(&headers)?;
+
+    let mut conn = pool.acquire().await.map_err(db_error)?;
+    let existing = fetch_routine(&mut conn, routine_id).await?;
+    if !can_view(&claims, &existing) {
+        return Err(error(StatusCode::NOT_FOUND, "Routine not found"));
+    }
+    if !can_edit(&claims, &existing) {
+        return Err(error(StatusCode::FORBIDDEN, "Only the owner can delete this routine"));
+    }
+
+    sqlx::query("DELETE FROM routines WHERE id = ?")
+        .bind(routine_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(db_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Copy a template (or one of the caller's own routines) into a new private routine of the caller
+pub async fn clone_routine(
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+    Path(routine_id): Path<i64>,
+) -> Result<(StatusCode, Json<RoutineResponse>), ApiError> {
+    let claims = require_staff(&headers)?;
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    let source = fetch_routine(&mut tx, routine_id).await?;
+    if !can_view(&claims, &source) {
+        return Err(error(StatusCode::NOT_FOUND, "Routine not found"));
+    }
+
+    let now = now();
+    let clone_id = sqlx::query(
+        r#"
+        INSERT INTO routines (title, description, is_template, owner_id, cloned_from, created_at, updated_at)
+        VALUES (?, ?, 0, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&source.title)
+    .bind(&source.description)
+    .bind(&claims.sub)
+    .bind(source.id)
+    .bind(now)
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error)?
+    .last_insert_rowid();
+
+    sqlx::query(
+        r#"
+        INSERT INTO routine_exercises (routine_id, exercise_id, position, sets, reps, rest_seconds, notes)
+        SELECT ?, exercise_id, position, sets, reps, rest_seconds, notes
+        FROM routine_exercises WHERE routine_id = ? ORDER BY position
+        "#
+    )
+    .bind(clone_id)
+    .bind(source.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    let routine = load_routine(&mut tx, clone_id).await?;
+    tx.commit().await.map_err(db_error)?;
+    Ok((StatusCode::CREATED, Json(routine)))
+}
+
+// Fill `routines` on exercise responses for staff: templates plus the viewer's own routines.
+// Routines are staff only, so patients and anonymous viewers get none.
+pub(crate) async fn attach_routines(
+    pool: &SqlitePool,
+    viewer: &Viewer,
+    exercises: &mut [ExerciseResponse],
+) -> Result<(), ApiError> {
+    let Viewer::Staff(staff_id) = viewer else {
+        return Ok(());
+    };
+    if exercises.is_empty() {
+        return Ok(());
+    }
+
+    let mut builder = QueryBuilder::<Sqlite>::new(
+        "SELECT DISTINCT re.exercise_id, r.id, r.title, r.is_template \
+         FROM routine_exercises re JOIN routines r ON r.id = re.routine_id \
+         WHERE re.exercise_id IN ("
+    );
+    let mut separated = builder.separated(", ");
+    for exercise in exercises.iter() {
+        separated.push_bind(exercise.id);
+    }
+    builder.push(") AND (r.is_template = 1 OR r.owner_id = ");
+    builder.push_bind(staff_id);
+    builder.push(") ORDER BY r.title COLLATE NOCASE, r.id");
+
+    #[derive(sqlx::FromRow)]
+    struct Row {
+        exercise_id: i64,
+        #[sqlx(flatten)]
+        routine: RoutineSummary,
+    }
+
+    let rows = builder
+        .build_query_as::<Row>()
+        .fetch_all(pool)
+        .await
+        .map_err(db_error)?;
+
+    let mut by_exercise: HashMap<i64, Vec<RoutineSummary>> = HashMap::new();
+    for row in rows {
+        by_exercise.entry(row.exercise_id).or_default().push(row.routine);
+    }
+    for exercise in exercises.iter_mut() {
+        exercise.routines = Some(by_exercise.remove(&exercise.id).unwrap_or_default());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::{get_exercise, get_exercises, FilterParams};
+    use crate::test_support::*;
+
+    #[tokio::test]
+    async fn routines_are_ordered_timed_and_cloned_from_templates() {
+        let pool = test_pool().await;
+        seed_all(&pool).await;
+        let id = |title: &'static str| {
+            let pool = pool.clone();
+            async move {
+                sqlx::query_scalar::<_, i64>("SELECT id FROM exercises WHERE title = ?")
+                    .bind(title)
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap()
+            }
+        };
+        let (squat, stretch) = (id("Squat").await, id("Runner's stretch").await);
+        let physio = || auth_headers("physio-001", "physiotherapist");
+        let other_physio = || auth_headers("physio-002", "physiotherapist");
+
+        let item = |exercise_id: i64, sets: Option<i64>, rest_seconds: Option<i64>| RoutineExerciseRequest {
+            exercise_id,
+            sets,
+            reps: Some(10),
+            rest_seconds,
+            notes: None,
+        };
+        let request = |is_template: bool, exercises: Vec<RoutineExerciseRequest>| CreateRoutineRequest {
+            title: "Lower back morning routine".to_string(),
+            description: None,
+            is_template: Some(is_template),
+            exercises,
+        };
+
+        let err = create_routine(Extension(pool.clone()), auth_headers("patient-001", "patient"), Json(request(true, vec![])))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+        let err = create_routine(Extension(pool.clone()), physio(), Json(request(true, vec![item(9999, None, None)])))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+
+        // Stretch 5 min, then squats 10 min with 3 sets and 90 s rest between them
+        let (status, Json(template)) = create_routine(
+            Extension(pool.clone()),
+            physio(),
+            Json(request(true, vec![item(stretch, None, None), item(squat, Some(3), Some(90))])),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        let order: Vec<i64> = template.exercises.iter().map(|e| e.exercise_id).collect();
+        assert_eq!(order, vec![stretch, squat]);
+        assert_eq!(template.estimated_duration_minutes, 18);
+
+        let (_, Json(private)) = create_routine(Extension(pool.clone()), physio(), Json(request(false, vec![item(squat, None, None)])))
+            .await
+            .unwrap();
+
+        // Other staff see the template but not the private routine
+        let err = get_routine(Extension(pool.clone()), other_physio(), Path(private.id)).await.unwrap_err();
+        assert_eq!(err.0, StatusCode::NOT_FOUND);
+        let err = update_routine(
+            Extension(pool.clone()),
+            other_physio(),
+            Path(template.id),
+            Json(UpdateRoutineRequest { title: Some("Mine now".to_string()), ..Default::default() }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+
+        let (status, Json(copy)) = clone_routine(Extension(pool.clone()), other_physio(), Path(template.id)).await.unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!((copy.owner_id.as_str(), copy.is_template, copy.cloned_from), ("physio-002", false, Some(template.id)));
+        assert_eq!(copy.exercises.len(), 2);
+        assert_eq!(copy.estimated_duration_minutes, 18);
+
+        // The copy is independent of the template
+        let Json(updated) = update_routine(
+            Extension(pool.clone()),
+            other_physio(),
+            Path(copy.id),
+            Json(UpdateRoutineRequest { exercises: Some(vec![item(squat, Some(2), Some(30))]), ..Default::default() }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(updated.estimated_duration_minutes, 11);
+        let Json(template_again) = get_routine(Extension(pool.clone()), physio(), Path(template.id)).await.unwrap();
+        assert_eq!(template_again.exercises.len(), 2);
+
+        let Json(listed) = get_routines(Extension(pool.clone()), other_physio(), Query(RoutineParams::default())).await.unwrap();
+        let mut ids: Vec<i64> = listed.iter().map(|r| r.id).collect();
+        ids.sort();
+        assert_eq!(ids, vec![template.id, copy.id]);
+
+        // Exercises list the routines the caller can see
+        let Json(exercise) = get_exercise(Extension(pool.clone()), Extension(test_store()), physio(), Path(squat)).await.unwrap();
+        let routines: Vec<i64> = exercise.routines.unwrap().iter().map(|r| r.id).collect();
+        assert_eq!(routines.len(), 2);
+        assert!(routines.contains(&template.id) && routines.contains(&private.id));
+        let Json(page) = get_exercises(Extension(pool.clone()), Extension(test_store()), auth_headers("admin-001", "admin"), Query(FilterParams::default())).await.unwrap();
+        let squat_routines = page.exercises.iter().find(|e| e.id == squat).unwrap().routines.clone().unwrap();
+        assert_eq!(squat_routines.len(), 1);
+        assert!(squat_routines[0].is_template);
+
+        // Patients and anonymous viewers do not see routines at all
+        for headers in [HeaderMap::new(), auth_headers("patient-001", "patient")] {
+            let Json(page) = get_exercises(Extension(pool.clone()), Extension(test_store()), headers, Query(FilterParams::default())).await.unwrap();
+            assert!(page.exercises.iter().all(|e| e.routines.is_none()));
+        }
+        let Json(exercise) = get_exercise(Extension(pool.clone()), Extension(test_store()), HeaderMap::new(), Path(squat)).await.unwrap();
+        assert!(exercise.routines.is_none());
+
+        let status = delete_routine(Extension(pool.clone()), physio(), Path(template.id)).await.unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let Json(orphan) = get_routine(Extension(pool.clone()), other_physio(), Path(copy.id)).await.unwrap();
+        assert_eq!(orphan.cloned_from, None);
+    }
+}
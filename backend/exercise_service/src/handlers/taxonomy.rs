@@ -0,0 +1,788 @@
+use axum::{
+    extract::{Extension, Json, Path, Query},
+    http::{HeaderMap, StatusCode},
+};
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection, SqlitePool};
+
+use super::{db_error, error, now, push_filters, push_visibility, require_role, resolve_viewer, ApiError, FilterParams};
+use crate::models::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Taxonomy {
+    Category,
+    Difficulty,
+    Muscle,
+    Equipment,
+}
+
+impl Taxonomy {
+    fn from_path(kind: &str) -> Result<Self, ApiError> {
+        match kind {
+            "categories" => Ok(Taxonomy::Category),
+            "difficulty-levels" => Ok(Taxonomy::Difficulty),
+            "muscles" => Ok(Taxonomy::Muscle),
+            "equipment" => Ok(Taxonomy::Equipment),
+            _ => Err(error(StatusCode::NOT_FOUND, "Unknown taxonomy")),
+        }
+    }
+
+    pub(crate) fn table(self) -> &'static str {
+        match self {
+            Taxonomy::Category => "exercise_categories",
+            Taxonomy::Difficulty => "difficulty_levels",
+            Taxonomy::Muscle => "muscles",
+            Taxonomy::Equipment => "equipment",
+        }
+    }
+
+    // kind column in taxonomy_aliases
+    fn alias_kind(self) -> &'static str {
+        match self {
+            Taxonomy::Category => "category",
+            Taxonomy::Difficulty => "difficulty",
+            Taxonomy::Muscle => "muscle",
+            Taxonomy::Equipment => "equipment",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Taxonomy::Category => "category",
+            Taxonomy::Difficulty => "difficulty level",
+            Taxonomy::Muscle => "muscle",
+            Taxonomy::Equipment => "equipment",
+        }
+    }
+
+    fn select(self) -> &'static str {
+        match self {
+            Taxonomy::Category => "SELECT id, name, slug, parent_id, NULL AS position FROM exercise_categories",
+            Taxonomy::Difficulty => "SELECT id, name, slug, NULL AS parent_id, position FROM difficulty_levels",
+            Taxonomy::Muscle => "SELECT id, name, slug, NULL AS parent_id, NULL AS position FROM muscles",
+            Taxonomy::Equipment => "SELECT id, name, slug, NULL AS parent_id, NULL AS position FROM equipment",
+        }
+    }
+
+    fn order(self) -> &'static str {
+        match self {
+            Taxonomy::Difficulty => " ORDER BY position, id",
+            _ => " ORDER BY name COLLATE NOCASE, id",
+        }
+    }
+
+    // Number of exercises that reference an entry
+    fn usage(self) -> &'static str {
+        match self {
+            Taxonomy::Category => "SELECT COUNT(*) FROM exercises WHERE category_id = ?",
+            Taxonomy::Difficulty => "SELECT COUNT(*) FROM exercises WHERE difficulty_id = ?",
+            Taxonomy::Muscle => "SELECT COUNT(*) FROM exercise_muscles WHERE muscle_id = ?",
+            Taxonomy::Equipment => "SELECT COUNT(*) FROM exercise_equipment WHERE equipment_id = ?",
+        }
+    }
+}
+
+// "Resistance band" -> "resistance-band"; the same rule the taxonomy migration uses for old values
+pub(crate) fn slugify(value: &str) -> String {
+    value.trim().to_lowercase().split_whitespace().collect::<Vec<_>>().join("-")
+}
+
+// Append a condition matching entries of one taxonomy by slug, name (any case) or alias
+pub(crate) fn push_entry_match(builder: &mut QueryBuilder<'_, Sqlite>, kind: Taxonomy, value: &str) {
+    builder
+        .push("(slug = ")
+        .push_bind(slugify(value))
+        .push(" OR name = ")
+        .push_bind(value.trim().to_string())
+        .push(" COLLATE NOCASE OR id IN (SELECT target_id FROM taxonomy_aliases WHERE kind = ")
+        .push_bind(kind.alias_kind())
+        .push(" AND alias = ")
+        .push_bind(value.trim().to_lowercase())
+        .push("))");
+}
+
+async fn find_entry(conn: &mut SqliteConnection, kind: Taxonomy, value: &str) -> Result<Option<i64>, ApiError> {
+    let mut builder = QueryBuilder::<Sqlite>::new(format!("SELECT id FROM {} WHERE ", kind.table()));
+    push_entry_match(&mut builder, kind, value);
+    builder.push(" ORDER BY id LIMIT 1");
+    builder
+        .build_query_scalar::<i64>()
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(db_error)
+}
+
+// Id of the entry a free-text value from an exercise request refers to
+pub(crate) async fn resolve_entry(conn: &mut SqliteConnection, kind: Taxonomy, value: &str) -> Result<i64, ApiError> {
+    find_entry(conn, kind, value)
+        .await?
+        .ok_or_else(|| error(StatusCode::BAD_REQUEST, format!("Unknown {}: {}", kind.label(), value.trim())))
+}
+
+// Ids for a list of values in the given order, without blanks or duplicates. All unknown values
+// are reported at once.
+pub(crate) async fn resolve_entries(conn: &mut SqliteConnection, kind: Taxonomy, values: &[String]) -> Result<Vec<i64>, ApiError> {
+    let mut ids = Vec::new();
+    let mut unknown = Vec::new();
+    for value in values.iter().filter(|v| !v.trim().is_empty()) {
+        match find_entry(conn, kind, value).await? {
+            Some(id) if !ids.contains(&id) => ids.push(id),
+            Some(_) => {}
+            None => unknown.push(value.trim()),
+        }
+    }
+
+    if !unknown.is_empty() {
+        return Err(error(StatusCode::BAD_REQUEST, format!("Unknown {}: {}", kind.label(), unknown.join(", "))));
+    }
+    Ok(ids)
+}
+
+// Replace the muscles or equipment linked to an exercise
+pub(crate) async fn set_exercise_links(
+    conn: &mut SqliteConnection,
+    kind: Taxonomy,
+    exercise_id: i64,
+    ids: &[i64],
+) -> Result<(), ApiError> {
+    let (table, column) = match kind {
+        Taxonomy::Muscle => ("exercise_muscles", "muscle_id"),
+        Taxonomy::Equipment => ("exercise_equipment", "equipment_id"),
+        _ => unreachable!("only muscles and equipment are linked through a table"),
+    };
+
+    sqlx::query(&format!("DELETE FROM {} WHERE exercise_id = ?", table))
+        .bind(exercise_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(db_error)?;
+
+    for (position, id) in ids.iter().enumerate() {
+        sqlx::query(&format!("INSERT INTO {} (exercise_id, {}, position) VALUES (?, ?, ?)", table, column))
+            .bind(exercise_id)
+            .bind(id)
+            .bind(position as i64)
+            .execute(&mut *conn)
+            .await
+            .map_err(db_error)?;
+    }
+    Ok(())
+}
+
+// Rewrite the text columns of exercises from the taxonomy names (all exercises when no id is given).
+// Keep in sync with the last statement of 004_create_taxonomy.sql.
+pub(crate) async fn refresh_labels(conn: &mut SqliteConnection, exercise_id: Option<i64>) -> Result<(), ApiError> {
+    sqlx::query(
+        r#"
+        UPDATE exercises SET
+            category = COALESCE((SELECT name FROM exercise_categories WHERE id = exercises.category_id), category),
+            difficulty_level = COALESCE((SELECT name FROM difficulty_levels WHERE id = exercises.difficulty_id), difficulty_level),
+            target_muscles = (SELECT json_group_array(name) FROM (
+                SELECT m.name FROM exercise_muscles em JOIN muscles m ON m.id = em.muscle_id
+                WHERE em.exercise_id = exercises.id ORDER BY em.position, m.id)),
+            equipment_needed = (SELECT json_group_array(name) FROM (
+                SELECT q.name FROM exercise_equipment ee JOIN equipment q ON q.id = ee.equipment_id
+                WHERE ee.exercise_id = exercises.id ORDER BY ee.position, q.id))
+        WHERE ? IS NULL OR id = ?
+        "#
+    )
+    .bind(exercise_id)
+    .bind(exercise_id)
+    .execute(&mut *conn)
+    .await
+    .map_err(db_error)?;
+    Ok(())
+}
+
+async fn load_entry(conn: &mut SqliteConnection, kind: Taxonomy, id: i64) -> Result<TaxonomyEntry, ApiError> {
+    let mut entry = sqlx::query_as::<_, TaxonomyEntry>(&format!("{} WHERE id = ?", kind.select()))
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, format!("{} not found", capitalize(kind.label()))))?;
+
+    entry.aliases = sqlx::query_scalar::<_, String>(
+        "SELECT alias FROM taxonomy_aliases WHERE kind = ? AND target_id = ? ORDER BY alias"
+    )
+    .bind(kind.alias_kind())
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(db_error)?;
+    Ok(entry)
+}
+
+fn capitalize(value: &str) -> String {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+async fn set_aliases(conn: &mut SqliteConnection, kind: Taxonomy, id: i64, aliases: &[String]) -> Result<(), ApiError> {
+    sqlx::query("DELETE FROM taxonomy_aliases WHERE kind = ? AND target_id = ?")
+        .bind(kind.alias_kind())
+        .bind(id)
+        .execute(&mut *conn)
+        .await
+        .map_err(db_error)?;
+
+    for alias in aliases.iter().map(|a| a.trim().to_lowercase()).filter(|a| !a.is_empty()) {
+        // An alias names exactly one entry
+        let result = sqlx::query("INSERT OR IGNORE INTO taxonomy_aliases (kind, alias, target_id) VALUES (?, ?, ?)")
+            .bind(kind.alias_kind())
+            .bind(&alias)
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .map_err(db_error)?;
+        if result.rows_affected() == 0 {
+            return Err(error(StatusCode::CONFLICT, format!("Alias already in use: {}", alias)));
+        }
+    }
+    Ok(())
+}
+
+// Name, slug or alias may not resolve to a different entry of the same taxonomy
+async fn ensure_unique(conn: &mut SqliteConnection, kind: Taxonomy, id: Option<i64>, values: &[&str]) -> Result<(), ApiError> {
+    for value in values {
+        if let Some(existing) = find_entry(conn, kind, value).await? {
+            if Some(existing) != id {
+                return Err(error(StatusCode::CONFLICT, format!("{} already exists: {}", capitalize(kind.label()), value.trim())));
+            }
+        }
+    }
+    Ok(())
+}
+
+// The parent must exist and must not be the category itself or one of its descendants
+async fn check_parent(conn: &mut SqliteConnection, id: Option<i64>, parent_id: i64) -> Result<(), ApiError> {
+    let exists = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM exercise_categories WHERE id = ?")
+        .bind(parent_id)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(db_error)?;
+    if exists == 0 {
+        return Err(error(StatusCode::BAD_REQUEST, "Parent category not found"));
+    }
+
+    if let Some(id) = id {
+        let cycle = sqlx::query_scalar::<_, i64>(
+            r#"
+            WITH RECURSIVE subtree(id) AS (
+                SELECT ?
+                UNION SELECT c.id FROM exercise_categories c JOIN subtree s ON c.parent_id = s.id
+            )
+            SELECT COUNT(*) FROM subtree WHERE id = ?
+            "#
+        )
+        .bind(id)
+        .bind(parent_id)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(db_error)?;
+        if cycle > 0 {
+            return Err(error(StatusCode::BAD_REQUEST, "A category cannot be moved under itself"));
+        }
+    }
+    Ok(())
+}
+
+// List a taxonomy; categories carry parent_id so clients can build the tree
+pub async fn get_taxonomy(
+    Extension(pool): Extension<SqlitePool>,
+    Path(kind): Path<String>,
+) -> Result<Json<Vec<TaxonomyEntry>>, ApiError> {
+    let kind = Taxonomy::from_path(&kind)?;
+    let mut conn = pool.acquire().await.map_err(db_error)?;
+
+    let mut entries = sqlx::query_as::<_, TaxonomyEntry>(&format!("{}{}", kind.select(), kind.order()))
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(db_error)?;
+
+    let aliases = sqlx::query_as::<_, (i64, String)>(
+        "SELECT target_id, alias FROM taxonomy_aliases WHERE kind = ? ORDER BY alias"
+    )
+    .bind(kind.alias_kind())
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(db_error)?;
+    for (target_id, alias) in aliases {
+        if let Some(entry) = entries.iter_mut().find(|e| e.id == target_id) {
+            entry.aliases.push(alias);
+        }
+    }
+
+    Ok(Json(entries))
+}
+
+// Admin only
+pub async fn create_taxonomy_entry(
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+    Path(kind): Path<String>,
+    Json(req): Json<CreateTaxonomyRequest>,
+) -> Result<(StatusCode, Json<TaxonomyEntry>), ApiError> {
+    require_role(&headers, &["admin"])?;
+    let kind = Taxonomy::from_path(&kind)?;
+
+    let name = req.name.trim().to_string();
+    let slug = slugify(req.slug.as_deref().unwrap_or(&name));
+    if name.is_empty() || slug.is_empty() {
+        return Err(error(StatusCode::BAD_REQUEST, "Name is required"));
+    }
+    if req.parent_id.is_some() && kind != Taxonomy::Category {
+        return Err(error(StatusCode::BAD_REQUEST, "Only categories have a parent"));
+    }
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    ensure_unique(&mut tx, kind, None, &[&name, &slug]).await?;
+    if let Some(parent_id) = req.parent_id {
+        check_parent(&mut tx, None, parent_id).await?;
+    }
+
+    let id = match kind {
+        Taxonomy::Category => sqlx::query("INSERT INTO exercise_categories (parent_id, name, slug, created_at) VALUES (?, ?, ?, ?)")
+            .bind(req.parent_id)
+            .bind(&name)
+            .bind(&slug)
+            .bind(now())
+            .execute(&mut *tx)
+            .await,
+        // New levels go last unless a position is given
+        Taxonomy::Difficulty => sqlx::query(
+            "INSERT INTO difficulty_levels (name, slug, position, created_at) \
+             VALUES (?, ?, COALESCE(?, (SELECT COALESCE(MAX(position), 0) + 1 FROM difficulty_levels)), ?)"
+        )
+            .bind(&name)
+            .bind(&slug)
+            .bind(req.position)
+            .bind(now())
+            .execute(&mut *tx)
+            .await,
+        Taxonomy::Muscle | Taxonomy::Equipment => sqlx::query(&format!("INSERT INTO {} (name, slug, created_at) VALUES (?, ?, ?)", kind.table()))
+            .bind(&name)
+            .bind(&slug)
+            .bind(now())
+            .execute(&mut *tx)
+            .await,
+    }
+    .map_err(db_error)?
+    .last_insert_rowid();
+
+    if let Some(aliases) = &req.aliases {
+        ensure_unique(&mut tx, kind, Some(id), &aliases.iter().map(String::as_str).collect::<Vec<_>>()).await?;
+        set_aliases(&mut tx, kind, id, aliases).await?;
+    }
+
+    let entry = load_entry(&mut tx, kind, id).await?;
+    tx.commit().await.map_err(db_error)?;
+    Ok((StatusCode::CREATED, Json(entry)))
+}
+
+// Admin only. Renaming rewrites the names shown on exercises.
+pub async fn update_taxonomy_entry(
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+    Path((kind, id)): Path<(String, i64)>,
+    Json(req): Json<UpdateTaxonomyRequest>,
+) -> Result<Json<TaxonomyEntry>, ApiError> {
+    require_role(&headers, &["admin"])?;
+    let kind = Taxonomy::from_path(&kind)?;
+    if req.parent_id.is_some() && kind != Taxonomy::Category {
+        return Err(error(StatusCode::BAD_REQUEST, "Only categories have a parent"));
+    }
+    if req.position.is_some() && kind != Taxonomy::Difficulty {
+        return Err(error(StatusCode::BAD_REQUEST, "Only difficulty levels have a position"));
+    }
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    let existing = load_entry(&mut tx, kind, id).await?;
+
+    let name = req.name.as_deref().map(str::trim).unwrap_or(&existing.name).to_string();
+    let slug = req.slug.as_deref().map(slugify).unwrap_or(existing.slug);
+    if name.is_empty() || slug.is_empty() {
+        return Err(error(StatusCode::BAD_REQUEST, "Name is required"));
+    }
+    ensure_unique(&mut tx, kind, Some(id), &[&name, &slug]).await?;
+    if let Some(Some(parent_id)) = req.parent_id {
+        check_parent(&mut tx, Some(id), parent_id).await?;
+    }
+
+    sqlx::query(&format!("UPDATE {} SET name = ?, slug = ? WHERE id = ?", kind.table()))
+        .bind(&name)
+        .bind(&slug)
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    if let Some(parent_id) = req.parent_id {
+        sqlx::query("UPDATE exercise_categories SET parent_id = ? WHERE id = ?")
+            .bind(parent_id)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+    }
+    if let Some(position) = req.position {
+        sqlx::query("UPDATE difficulty_levels SET position = ? WHERE id = ?")
+            .bind(position)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+    }
+    if let Some(aliases) = &req.aliases {
+        ensure_unique(&mut tx, kind, Some(id), &aliases.iter().map(String::as_str).collect::<Vec<_>>()).await?;
+        set_aliases(&mut tx, kind, id, aliases).await?;
+    }
+
+    if name != existing.name {
+        refresh_labels(&mut tx, None).await?;
+    }
+
+    let entry = load_entry(&mut tx, kind, id).await?;
+    tx.commit().await.map_err(db_error)?;
+    Ok(Json(entry))
+}
+
+// Admin only. Entries still used by exercises, and categories with subcategories, are kept.
+pub async fn delete_taxonomy_entry(
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+    Path((kind, id)): Path<(String, i64)>,
+) -> Result<StatusCode, ApiError> {
+    require_role(&headers, &["admin"])?;
+    let kind = Taxonomy::from_path(&kind)?;
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    load_entry(&mut tx, kind, id).await?;
+
+    let used = sqlx::query_scalar::<_, i64>(kind.usage())
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    if used > 0 {
+        return Err(error(StatusCode::CONFLICT, format!("{} is used by {} exercises", capitalize(kind.label()), used)));
+    }
+
+    if kind == Taxonomy::Category {
+        let children = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM exercise_categories WHERE parent_id = ?")
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        if children > 0 {
+            return Err(error(StatusCode::CONFLICT, "Category has subcategories"));
+        }
+    }
+
+    set_aliases(&mut tx, kind, id, &[]).await?;
+    sqlx::query(&format!("DELETE FROM {} WHERE id = ?", kind.table()))
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Count facet values over the exercises matching `params` that the viewer may see
+async fn facet_counts(
+    pool: &SqlitePool,
+    params: &FilterParams,
+    viewer: &super::Viewer,
+    counts: &str,
+) -> Result<Vec<FacetCount>, ApiError> {
+    let mut builder = QueryBuilder::<Sqlite>::new(
+        "WITH RECURSIVE filtered AS (SELECT id, category_id, difficulty_id FROM exercises WHERE 1=1"
+    );
+    push_filters(&mut builder, params);
+    push_visibility(&mut builder, viewer);
+    builder.push(") ");
+    builder.push(counts);
+
+    builder
+        .build_query_as::<FacetCount>()
+        .fetch_all(pool)
+        .await
+        .map_err(db_error)
+}
+
+// Category counts include exercises in subcategories, matching the category filter
+const CATEGORY_COUNTS: &str = r#"
+    , tree(root_id, id) AS (
+        SELECT id, id FROM exercise_categories
+        UNION SELECT tree.root_id, c.id FROM exercise_categories c JOIN tree ON c.parent_id = tree.id
+    )
+    SELECT c.id, c.name, c.slug, c.parent_id, COUNT(DISTINCT f.id) AS count
+    FROM exercise_categories c
+    LEFT JOIN tree t ON t.root_id = c.id
+    LEFT JOIN filtered f ON f.category_id = t.id
+    GROUP BY c.id
+    ORDER BY c.name COLLATE NOCASE, c.id
+"#;
+
+const DIFFICULTY_COUNTS: &str = r#"
+    SELECT d.id, d.name, d.slug, NULL AS parent_id, COUNT(f.id) AS count
+    FROM difficulty_levels d
+    LEFT JOIN filtered f ON f.difficulty_id = d.id
+    GROUP BY d.id
+    ORDER BY d.position, d.id
+"#;
+
+const MUSCLE_COUNTS: &str = r#"
+    SELECT m.id, m.name, m.slug, NULL AS parent_id, COUNT(f.id) AS count
+    FROM muscles m
+    LEFT JOIN exercise_muscles em ON em.muscle_id = m.id
+    LEFT JOIN filtered f ON f.id = em.exercise_id
+    GROUP BY m.id
+    ORDER BY m.name COLLATE NOCASE, m.id
+"#;
+
+const EQUIPMENT_COUNTS: &str = r#"
+    SELECT q.id, q.name, q.slug, NULL AS parent_id, COUNT(f.id) AS count
+    FROM equipment q
+    LEFT JOIN exercise_equipment ee ON ee.equipment_id = q.id
+    LEFT JOIN filtered f ON f.id = ee.exercise_id
+    GROUP BY q.id
+    ORDER BY q.name COLLATE NOCASE, q.id
+"#;
+
+// Facet counts under the same filters as GET /exercises (paging and sorting are ignored)
+pub async fn get_exercise_facets(
+    Extension(pool): Extension<SqlitePool>,
+    headers: HeaderMap,
+    Query(params): Query<FilterParams>,
+) -> Result<Json<ExerciseFacets>, ApiError> {
+    let viewer = resolve_viewer(&headers)?;
+
+    let mut count = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM exercises WHERE 1=1");
+    push_filters(&mut count, &params);
+    push_visibility(&mut count, &viewer);
+    let total: i64 = count.build_query_scalar().fetch_one(&pool).await.map_err(db_error)?;
+
+    let without_category = FilterParams { category: None, ..params.clone() };
+    let without_difficulty = FilterParams { difficulty: None, ..params.clone() };
+    let without_muscles = FilterParams { target_muscles: None, ..params.clone() };
+    let without_equipment = FilterParams { equipment_needed: None, ..params.clone() };
+
+    Ok(Json(ExerciseFacets {
+        total,
+        categories: facet_counts(&pool, &without_category, &viewer, CATEGORY_COUNTS).await?,
+        difficulty_levels: facet_counts(&pool, &without_difficulty, &viewer, DIFFICULTY_COUNTS).await?,
+        muscles: facet_counts(&pool, &without_muscles, &viewer, MUSCLE_COUNTS).await?,
+        equipment: facet_counts(&pool, &without_equipment, &viewer, EQUIPMENT_COUNTS).await?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::{create_exercise, get_exercise, update_exercise};
+    use crate::test_support::*;
+
+    #[tokio::test]
+    async fn migration_maps_free_text_values_onto_one_entry() {
+        let pool = test_pool_before("004_create_taxonomy.sql").await;
+        for (title, category, difficulty, muscles) in [
+            ("Bridge", "Back", "Beginner", r#"["Glutes", "Lower back"]"#),
+            ("Cat-cow", " back ", "Početnik", r#"["lower back"]"#),
+            ("Bird dog", "Leđa", "Expert", r#"["Glutes", "glutes"]"#),
+        ] {
+            sqlx::query(
+                "INSERT INTO exercises (title, description, category, difficulty_level, equipment_needed, \
+                 instructions, target_muscles, created_at) VALUES (?, '', ?, ?, '[]', '[]', ?, 0)"
+            )
+            .bind(title)
+            .bind(category)
+            .bind(difficulty)
+            .bind(muscles)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        crate::database::prepare_schema(&pool, &migrations_dir()).await.unwrap();
+
+        let rows: Vec<(String, i64, String, String)> = sqlx::query_as(
+            "SELECT e.category, e.category_id, e.difficulty_level, e.target_muscles FROM exercises e ORDER BY e.id"
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert!(rows.iter().all(|r| r.0 == "Back" && r.1 == rows[0].1));
+        assert_eq!((rows[0].2.as_str(), rows[1].2.as_str(), rows[2].2.as_str()), ("Beginner", "Beginner", "Expert"));
+        assert_eq!(rows[1].3, r#"["Lower back"]"#);
+        assert_eq!(rows[2].3, r#"["Glutes"]"#);
+
+        // Unknown levels are added after the seeded ones
+        let position: i64 = sqlx::query_scalar("SELECT position FROM difficulty_levels WHERE slug = 'expert'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(position, 4);
+        let muscles: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM muscles").fetch_one(&pool).await.unwrap();
+        assert_eq!(muscles, 2);
+    }
+
+    #[tokio::test]
+    async fn taxonomy_is_managed_by_admins_and_drives_filters_and_facets() {
+        let pool = test_pool().await;
+        seed_all(&pool).await;
+        let admin = || auth_headers("admin-001", "admin");
+
+        let request = |name: &str, parent_id: Option<i64>| CreateTaxonomyRequest {
+            name: name.to_string(),
+            slug: None,
+            parent_id,
+            position: None,
+            aliases: Some(vec!["Donji deo leđa".to_string()]),
+        };
+        let err = create_taxonomy_entry(
+            Extension(pool.clone()),
+            auth_headers("physio-001", "physiotherapist"),
+            Path("categories".to_string()),
+            Json(request("Thoracic", None)),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+
+        let Json(categories) = get_taxonomy(Extension(pool.clone()), Path("categories".to_string())).await.unwrap();
+        let lumbar = categories.iter().find(|c| c.slug == "lumbar").unwrap().clone();
+        let spine = categories.iter().find(|c| c.slug == "spine").unwrap().id;
+        assert_eq!(lumbar.parent_id, Some(spine));
+
+        // Names are unique per taxonomy regardless of case
+        let err = create_taxonomy_entry(Extension(pool.clone()), admin(), Path("categories".to_string()), Json(request("BALANCE", None)))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+
+        let (status, Json(lower_back)) = create_taxonomy_entry(
+            Extension(pool.clone()),
+            admin(),
+            Path("categories".to_string()),
+            Json(request("Lower back", Some(lumbar.id))),
+        )
+        .await
+        .unwrap();
+        assert_eq!((status, lower_back.slug.as_str()), (StatusCode::CREATED, "lower-back"));
+        assert_eq!(lower_back.aliases, vec!["donji deo leđa"]);
+
+        // A category cannot become its own descendant
+        let err = update_taxonomy_entry(
+            Extension(pool.clone()),
+            admin(),
+            Path(("categories".to_string(), spine)),
+            Json(UpdateTaxonomyRequest { parent_id: Some(Some(lower_back.id)), ..Default::default() }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+
+        // Exercises name their category by alias; filtering on a parent includes subcategories
+        let Json(exercise) = create_exercise(
+            Extension(pool.clone()),
+            auth_headers("physio-001", "physiotherapist"),
+            Json(CreateExerciseRequest {
+                title: "Pelvic tilt".to_string(),
+                description: String::new(),
+                category: "donji deo leđa".to_string(),
+                difficulty_level: "Početnik".to_string(),
+                duration_minutes: Some(5),
+                equipment_needed: vec!["mat".to_string()],
+                instructions: vec![],
+                image_url: None,
+                video_url: None,
+                youtube_url: None,
+                target_muscles: vec![],
+                is_specialized: None,
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!((exercise.category.as_str(), exercise.difficulty_level.as_str()), ("Lower back", "Beginner"));
+        assert_eq!(exercise.equipment_needed, vec!["Mat"]);
+        assert_eq!(exercise.category_id, Some(lower_back.id));
+
+        let spine_titles = titles(&pool, FilterParams { category: Some("Spine".to_string()), ..Default::default() }).await;
+        assert_eq!(spine_titles, vec!["Pelvic tilt"]);
+
+        let store = test_store();
+        let err = update_exercise(
+            Extension(pool.clone()),
+            Extension(store.clone()),
+            Extension(test_jobs(&pool, &store)),
+            auth_headers("physio-001", "physiotherapist"),
+            Path(exercise.id),
+            Json(UpdateExerciseRequest {
+                title: None,
+                description: None,
+                category: Some("Astrology".to_string()),
+                difficulty_level: None,
+                duration_minutes: None,
+                equipment_needed: None,
+                instructions: None,
+                image_url: None,
+                video_url: None,
+                youtube_url: None,
+                target_muscles: None,
+                is_specialized: None,
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+
+        // Facets count the visible exercises; a facet ignores its own filter
+        let facets = |params: FilterParams, headers: HeaderMap| {
+            get_exercise_facets(Extension(pool.clone()), headers, Query(params))
+        };
+        let count = |entries: &[FacetCount], slug: &str| entries.iter().find(|e| e.slug == slug).unwrap().count;
+
+        let Json(all) = facets(FilterParams::default(), HeaderMap::new()).await.unwrap();
+        assert_eq!(all.total, 3);
+        assert_eq!((count(&all.categories, "strength"), count(&all.categories, "spine")), (2, 1));
+        assert_eq!(count(&all.muscles, "quadriceps"), 1);
+        assert_eq!(count(&all.difficulty_levels, "beginner"), 3);
+
+        let Json(filtered) = facets(
+            FilterParams { category: Some("strength".to_string()), ..Default::default() },
+            admin(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(filtered.total, 3);
+        assert_eq!(count(&filtered.categories, "spine"), 1);
+        assert_eq!(count(&filtered.muscles, "quadriceps"), 2);
+        assert_eq!(count(&filtered.equipment, "mat"), 1);
+
+        // Renaming rewrites the names on exercises; entries in use cannot be deleted
+        let mat: i64 = sqlx::query_scalar("SELECT id FROM equipment WHERE slug = 'mat'").fetch_one(&pool).await.unwrap();
+        let Json(_) = update_taxonomy_entry(
+            Extension(pool.clone()),
+            admin(),
+            Path(("equipment".to_string(), mat)),
+            Json(UpdateTaxonomyRequest { name: Some("Exercise mat".to_string()), ..Default::default() }),
+        )
+        .await
+        .unwrap();
+        let Json(renamed) = get_exercise(Extension(pool.clone()), Extension(test_store()), admin(), Path(exercise.id)).await.unwrap();
+        assert_eq!(renamed.equipment_needed, vec!["Exercise mat"]);
+
+        let err = delete_taxonomy_entry(Extension(pool.clone()), admin(), Path(("equipment".to_string(), mat)))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+        let err = delete_taxonomy_entry(Extension(pool.clone()), admin(), Path(("categories".to_string(), 9999)))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::NOT_FOUND);
+    }
+}
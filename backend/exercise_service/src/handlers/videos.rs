@@ -0,0 +1,569 @@
+use axum::{
+    body::Body,
+    extract::{Extension, Json, Multipart, Path},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use http_range_header::parse_range_header;
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_util::io::ReaderStream;
+
+use super::images::{exercise_visibility, SIGNED_URL_TTL};
+use super::{db_error, error, now, push_visibility, require_staff, resolve_viewer, storage_error, ApiError};
+use crate::models::*;
+use crate::storage::{media_url, remove_keys, MediaStore, Visibility};
+use crate::videos::{sniff_container, VideoJobs};
+
+const VIDEO_COLUMNS: &str =
+    "id, exercise_id, status, error, content_type, size_bytes, source_key, playlist_key, poster_key, created_at, updated_at";
+
+#[derive(sqlx::FromRow)]
+struct VideoRow {
+    id: i64,
+    exercise_id: i64,
+    status: String,
+    error: Option<String>,
+    content_type: String,
+    size_bytes: i64,
+    source_key: String,
+    playlist_key: Option<String>,
+    poster_key: Option<String>,
+    created_at: i64,
+    updated_at: i64,
+}
+
+// Playlist and source go through this service so visibility is checked on every request; the
+// poster and HLS segments are media store URLs, signed for specialized exercises
+async fn video_response(store: &dyn MediaStore, row: VideoRow, sign: bool) -> Result<ExerciseVideo, ApiError> {
+    let poster_url = match &row.poster_key {
+        Some(key) => Some(
+            media_url(store, key, sign, SIGNED_URL_TTL)
+                .await
+                .map_err(storage_error)?,
+        ),
+        None => None,
+    };
+    let ready = row.status == "ready" && row.playlist_key.is_some();
+
+    Ok(ExerciseVideo {
+        id: row.id,
+        exercise_id: row.exercise_id,
+        playlist_url: ready.then(|| format!("/exercises/{}/video/playlist.m3u8", row.exercise_id)),
+        poster_url,
+        source_url: format!("/exercises/{}/video/source", row.exercise_id),
+        status: row.status,
+        error: row.error,
+        content_type: row.content_type,
+        size_bytes: row.size_bytes,
+        created_at: row.created_at,
+        updated_at: row.updated_at,
+    })
+}
+
+// Fill `video` on exercise responses in a single query
+pub(crate) async fn attach_videos(
+    pool: &SqlitePool,
+    store: &dyn MediaStore,
+    exercises: &mut [ExerciseResponse],
+) -> Result<(), ApiError> {
+    if exercises.is_empty() {
+        return Ok(());
+    }
+
+    let mut builder = QueryBuilder::<Sqlite>::new(format!("SELECT {} FROM exercise_videos WHERE exercise_id IN (", VIDEO_COLUMNS));
+    let mut separated = builder.separated(", ");
+    for exercise in exercises.iter() {
+        separated.push_bind(exercise.id);
+    }
+    builder.push(")");
+
+    let rows = builder
+        .build_query_as::<VideoRow>()
+        .fetch_all(pool)
+        .await
+        .map_err(db_error)?;
+    let mut by_exercise: HashMap<i64, VideoRow> = rows.into_iter().map(|row| (row.exercise_id, row)).collect();
+
+    for exercise in exercises.iter_mut() {
+        if let Some(row) = by_exercise.remove(&exercise.id) {
+            exercise.video = Some(video_response(store, row, exercise.is_specialized).await?);
+        }
+    }
+    Ok(())
+}
+
+// Whether the exercise is specialized, or 404 when it is missing or the caller may not see it
+async fn visible_exercise(pool: &SqlitePool, headers: &HeaderMap, exercise_id: i64) -> Result<bool, ApiError> {
+    let viewer = resolve_viewer(headers)?;
+    let mut builder = QueryBuilder::<Sqlite>::new("SELECT is_specialized FROM exercises WHERE id = ");
+    builder.push_bind(exercise_id);
+    push_visibility(&mut builder, &viewer);
+
+    builder
+        .build_query_scalar::<bool>()
+        .fetch_optional(pool)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "Exercise not found"))
+}
+
+async fn load_video(pool: &SqlitePool, exercise_id: i64) -> Result<VideoRow, ApiError> {
+    sqlx::query_as::<_, VideoRow>(&format!("SELECT {} FROM exercise_videos WHERE exercise_id = ?", VIDEO_COLUMNS))
+        .bind(exercise_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "No video uploaded for this exercise"))
+}
+
+// Write the first file of the request to `path` without holding it in memory.
+// Returns the file extension, content type and size.
+async fn receive_video(
+    multipart: &mut Multipart,
+    path: &std::path::Path,
+    max_bytes: u64,
+) -> Result<(&'static str, &'static str, u64), ApiError> {
+    let io = |e: std::io::Error| error(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save file: {}", e));
+    let multipart_error = |e: axum::extract::multipart::MultipartError| error(e.status(), format!("Multipart parse error: {}", e));
+
+    while let Some(mut field) = multipart.next_field().await.map_err(multipart_error)? {
+        if field.file_name().is_none() {
+            continue;
+        }
+
+        let mut file = tokio::fs::File::create(path).await.map_err(io)?;
+        let mut size: u64 = 0;
+        let mut head: Vec<u8> = Vec::new();
+        while let Some(chunk) = field.chunk().await.map_err(multipart_error)? {
+            size += chunk.len() as u64;
+            if size > max_bytes {
+                return Err(error(
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    format!("Videos can be at most {} MB", max_bytes / (1024 * 1024)),
+                ));
+            }
+            if head.len() < 16 {
+                let take = (16 - head.len()).min(chunk.len());
+                head.extend_from_slice(&chunk[..take]);
+            }
+            file.write_all(&chunk).await.map_err(io)?;
+        }
+        file.flush().await.map_err(io)?;
+
+        let (extension, content_type) = sniff_container(&head)
+            .ok_or_else(|| error(StatusCode::UNSUPPORTED_MEDIA_TYPE, "Only MP4, MOV and WebM videos are supported"))?;
+        return Ok((extension, content_type, size));
+    }
+    Err(error(StatusCode::BAD_REQUEST, "No video file in the request"))
+}
+
+// Upload a video for an exercise (multipart/form-data, one file). It replaces any previous video
+// and is processed in the background; the response has status `pending`.
+pub async fn upload_exercise_video(
+    Extension(pool): Extension<SqlitePool>,
+    Extension(store): Extension<Arc<dyn MediaStore>>,
+    Extension(jobs): Extension<VideoJobs>,
+    headers: HeaderMap,
+    Path(exercise_id): Path<i64>,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<ExerciseVideo>), ApiError> {
+    let user_id = require_staff(&headers)?.sub;
+    let is_specialized = exercise_visibility(&pool, exercise_id).await?;
+
+    let upload_dir = jobs.config.work_dir.join("uploads");
+    tokio::fs::create_dir_all(&upload_dir)
+        .await
+        .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create upload directory: {}", e)))?;
+    let temp = upload_dir.join(uuid::Uuid::new_v4().simple().to_string());
+
+    let received = receive_video(&mut multipart, &temp, jobs.config.max_upload_bytes).await;
+    let (extension, content_type, size) = match received {
+        Ok(received) => received,
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&temp).await;
+            return Err(e);
+        }
+    };
+
+    // Specialized exercises are only for assigned patients, so their videos are private
+    let visibility = if is_specialized { Visibility::Private } else { Visibility::Public };
+    let name = uuid::Uuid::new_v4().simple().to_string();
+    let source_key = visibility.key(&format!("exercises/{}/videos/{}/source.{}", exercise_id, name, extension));
+    let stored = store.put_file(&source_key, &temp, content_type).await;
+    let _ = tokio::fs::remove_file(&temp).await;
+    stored.map_err(storage_error)?;
+
+    let video_id = match replace_video(&pool, exercise_id, &source_key, content_type, size, &user_id).await {
+        Ok((video_id, old_keys)) => {
+            remove_keys(store.as_ref(), &old_keys).await;
+            video_id
+        }
+        Err(e) => {
+            remove_keys(store.as_ref(), std::slice::from_ref(&source_key)).await;
+            return Err(db_error(e));
+        }
+    };
+    let row = load_video(&pool, exercise_id).await?;
+    jobs.enqueue(video_id);
+    Ok((StatusCode::ACCEPTED, Json(video_response(store.as_ref(), row, is_specialized).await?)))
+}
+
+// Swap in the new upload; returns its id and the stored files of the video it replaced
+async fn replace_video(
+    pool: &SqlitePool,
+    exercise_id: i64,
+    source_key: &str,
+    content_type: &str,
+    size: u64,
+    user_id: &str,
+) -> Result<(i64, Vec<String>), sqlx::Error> {
+    let now = now();
+
+    let mut tx = pool.begin().await?;
+    let old_keys = sqlx::query_scalar::<_, String>(
+        "SELECT f.storage_key FROM exercise_video_files f JOIN exercise_videos v ON v.id = f.video_id WHERE v.exercise_id = ?"
+    )
+    .bind(exercise_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    sqlx::query("DELETE FROM exercise_videos WHERE exercise_id = ?")
+        .bind(exercise_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let video_id = sqlx::query(
+        "INSERT INTO exercise_videos (exercise_id, status, content_type, size_bytes, source_key, uploaded_by, created_at, updated_at) \
+         VALUES (?, 'pending', ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(exercise_id)
+    .bind(content_type)
+    .bind(size as i64)
+    .bind(source_key)
+    .bind(user_id)
+    .bind(now)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?
+    .last_insert_rowid();
+    sqlx::query("INSERT INTO exercise_video_files (video_id, storage_key) VALUES (?, ?)")
+        .bind(video_id)
+        .bind(source_key)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok((video_id, old_keys))
+}
+
+// Processing status and URLs of the exercise's video
+pub async fn get_exercise_video(
+    Extension(pool): Extension<SqlitePool>,
+    Extension(store): Extension<Arc<dyn MediaStore>>,
+    headers: HeaderMap,
+    Path(exercise_id): Path<i64>,
+) -> Result<Json<ExerciseVideo>, ApiError> {
+    let is_specialized = visible_exercise(&pool, &headers, exercise_id).await?;
+    let row = load_video(&pool, exercise_id).await?;
+    Ok(Json(video_response(store.as_ref(), row, is_specialized).await?))
+}
+
+pub async fn delete_exercise_video(
+    Extension(pool): Extension<SqlitePool>,
+    Extension(store): Extension<Arc<dyn MediaStore>>,
+    headers: HeaderMap,
+    Path(exercise_id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    require_staff(&headers)?;
+    exercise_visibility(&pool, exercise_id).await?;
+
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    let keys = sqlx::query_scalar::<_, String>(
+        "SELECT f.storage_key FROM exercise_video_files f JOIN exercise_videos v ON v.id = f.video_id WHERE v.exercise_id = ?"
+    )
+    .bind(exercise_id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(db_error)?;
+    let res = sqlx::query("DELETE FROM exercise_videos WHERE exercise_id = ?")
+        .bind(exercise_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    if res.rows_affected() == 0 {
+        return Err(error(StatusCode::NOT_FOUND, "No video uploaded for this exercise"));
+    }
+    tx.commit().await.map_err(db_error)?;
+
+    // A job still working on it notices the row is gone and removes its own output
+    remove_keys(store.as_ref(), &keys).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// The HLS playlist with every segment pointing at the media store
+pub async fn get_exercise_video_playlist(
+    Extension(pool): Extension<SqlitePool>,
+    Extension(store): Extension<Arc<dyn MediaStore>>,
+    headers: HeaderMap,
+    Path(exercise_id): Path<i64>,
+) -> Result<Response, ApiError> {
+    let is_specialized = visible_exercise(&pool, &headers, exercise_id).await?;
+    let video = load_video(&pool, exercise_id).await?;
+    let Some(playlist_key) = video.playlist_key.filter(|_| video.status == "ready") else {
+        return Err(error(StatusCode::NOT_FOUND, "The video is still being processed"));
+    };
+
+    let size = store.size(&playlist_key).await.map_err(storage_error)?;
+    let mut playlist = String::new();
+    store
+        .read(&playlist_key, 0..size)
+        .await
+        .map_err(storage_error)?
+        .read_to_string(&mut playlist)
+        .await
+        .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to read playlist: {}", e)))?;
+
+    let dir = playlist_key.rsplit_once('/').map(|(dir, _)| dir).unwrap_or_default();
+    let mut body = String::new();
+    for line in playlist.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            body.push_str(line);
+        } else {
+            // Segment file names as written by ffmpeg, next to the playlist
+            let name = line.rsplit('/').next().unwrap_or(line);
+            let url = media_url(store.as_ref(), &format!("{}/{}", dir, name), is_specialized, SIGNED_URL_TTL)
+                .await
+                .map_err(storage_error)?;
+            body.push_str(&url);
+        }
+        body.push('\n');
+    }
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/vnd.apple.mpegurl"),
+            // Signed segment URLs expire
+            (header::CACHE_CONTROL, "no-cache"),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+// The uploaded file with support for HTTP range requests, for players without HLS
+pub async fn stream_exercise_video(
+    Extension(pool): Extension<SqlitePool>,
+    Extension(store): Extension<Arc<dyn MediaStore>>,
+    headers: HeaderMap,
+    Path(exercise_id): Path<i64>,
+) -> Result<Response, ApiError> {
+    visible_exercise(&pool, &headers, exercise_id).await?;
+    let video = load_video(&pool, exercise_id).await?;
+    let size = store.size(&video.source_key).await.map_err(storage_error)?;
+
+    let range = match headers.get(header::RANGE).and_then(|v| v.to_str().ok()) {
+        None => None,
+        Some(value) => match parse_range_header(value).and_then(|ranges| ranges.validate(size)) {
+            Ok(ranges) if ranges.len() == 1 => Some(ranges[0].clone()),
+            // Several ranges at once are answered with the whole file
+            Ok(_) => None,
+            Err(_) => {
+                return Ok((
+                    StatusCode::RANGE_NOT_SATISFIABLE,
+                    [(header::CONTENT_RANGE, format!("bytes */{}", size))],
+                )
+                    .into_response());
+            }
+        },
+    };
+
+    let (status, start, end) = match &range {
+        Some(range) => (StatusCode::PARTIAL_CONTENT, *range.start(), *range.end() + 1),
+        None => (StatusCode::OK, 0, size),
+    };
+    let reader = store.read(&video.source_key, start..end).await.map_err(storage_error)?;
+
+    let mut response = Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, &video.content_type)
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::CONTENT_LENGTH, end - start);
+    if range.is_some() {
+        response = response.header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end - 1, size));
+    }
+    response
+        .body(Body::from_stream(ReaderStream::new(reader)))
+        .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::{get_exercise, ErrorResponse};
+    use crate::test_support::*;
+
+    // Stands in for ffmpeg: writes a one-segment playlist or a poster, depending on the output path
+    fn fake_ffmpeg() -> String {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("fisionet-fake-ffmpeg-{}", uuid::Uuid::new_v4()));
+        let script = concat!(
+            "#!/bin/sh\n",
+            "for out; do :; done\n",
+            "case \"$out\" in\n",
+            "  *.m3u8) printf '#EXTM3U\\n#EXT-X-TARGETDURATION:6\\n#EXTINF:6.0,\\nsegment_000.ts\\n#EXT-X-ENDLIST\\n' > \"$out\"\n",
+            "          printf 'ts' > \"$(dirname \"$out\")/segment_000.ts\" ;;\n",
+            "  *) printf 'jpg' > \"$out\" ;;\n",
+            "esac\n",
+        );
+        std::fs::write(&path, script).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    async fn upload_video(
+        pool: &SqlitePool,
+        store: &Arc<dyn MediaStore>,
+        jobs: &crate::videos::VideoJobs,
+        exercise_id: i64,
+        data: &[u8],
+    ) -> Result<(StatusCode, Json<ExerciseVideo>), (StatusCode, Json<ErrorResponse>)> {
+        use axum::extract::{FromRequest, Multipart, Request};
+
+        let mut body = b"--boundary\r\nContent-Disposition: form-data; name=\"video\"; filename=\"exercise.mp4\"\r\nContent-Type: video/mp4\r\n\r\n".to_vec();
+        body.extend_from_slice(data);
+        body.extend_from_slice(b"\r\n--boundary--\r\n");
+        let request = Request::builder()
+            .header("content-type", "multipart/form-data; boundary=boundary")
+            .body(axum::body::Body::from(body))
+            .unwrap();
+        let multipart = Multipart::from_request(request, &()).await.unwrap();
+        upload_exercise_video(Extension(pool.clone()), Extension(store.clone()), Extension(jobs.clone()), auth_headers("physio-001", "physiotherapist"), Path(exercise_id), multipart)
+            .await
+    }
+
+    // Poll until the background job is done with the video
+    async fn processed_video(pool: &SqlitePool, store: &Arc<dyn MediaStore>, exercise_id: i64) -> ExerciseVideo {
+        for _ in 0..200 {
+            let Json(video) = get_exercise_video(Extension(pool.clone()), Extension(store.clone()), auth_headers("physio-001", "physiotherapist"), Path(exercise_id))
+                .await
+                .unwrap();
+            if video.status != "pending" && video.status != "processing" {
+                return video;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        panic!("video {} was not processed", exercise_id);
+    }
+
+    #[tokio::test]
+    async fn videos_are_transcoded_in_the_background_and_served_in_ranges() {
+        use crate::config::VideoConfig;
+        use crate::videos::VideoJobs;
+
+        let pool = test_pool().await;
+        seed_all(&pool).await;
+        let root = std::env::temp_dir().join(format!("fisionet-exercise-media-{}", uuid::Uuid::new_v4()));
+        let store: Arc<dyn MediaStore> = Arc::new(crate::storage::LocalStore::new(&root, "/media", "test-signing-key"));
+        let config = VideoConfig {
+            max_upload_bytes: 1024,
+            ffmpeg_path: fake_ffmpeg(),
+            work_dir: root.join("work"),
+        };
+        let jobs = VideoJobs::start(pool.clone(), store.clone(), config.clone());
+        let squat = exercise_id(&pool, "Squat").await;
+        let specialized = exercise_id(&pool, "Resisted knee extension").await;
+
+        let mut mp4 = b"\x00\x00\x00\x18ftypisom\x00\x00\x02\x00isomiso2".to_vec();
+        mp4.extend([7u8; 100]);
+
+        let (status, Json(pending)) = upload_video(&pool, &store, &jobs, squat, &mp4).await.unwrap();
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!((pending.status.as_str(), pending.size_bytes, pending.content_type.as_str()), ("pending", mp4.len() as i64, "video/mp4"));
+        assert_eq!(pending.playlist_url, None);
+
+        let video = processed_video(&pool, &store, squat).await;
+        assert_eq!(video.status, "ready", "{:?}", video.error);
+        assert_eq!(video.playlist_url, Some(format!("/exercises/{}/video/playlist.m3u8", squat)));
+        assert!(video.poster_url.as_deref().unwrap().starts_with(&format!("/media/public/exercises/{}/videos/", squat)));
+        let Json(exercise) = get_exercise(Extension(pool.clone()), Extension(store.clone()), HeaderMap::new(), Path(squat)).await.unwrap();
+        assert_eq!(exercise.video.unwrap().status, "ready");
+
+        let response = get_exercise_video_playlist(Extension(pool.clone()), Extension(store.clone()), HeaderMap::new(), Path(squat)).await.unwrap();
+        assert_eq!(response.headers()["content-type"], "application/vnd.apple.mpegurl");
+        let playlist = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let playlist = String::from_utf8(playlist.to_vec()).unwrap();
+        let segment = playlist.lines().find(|line| !line.starts_with('#')).unwrap();
+        assert!(segment.starts_with(&format!("/media/public/exercises/{}/videos/", squat)) && segment.ends_with("/hls/segment_000.ts"), "{}", playlist);
+        assert!(root.join(segment.strip_prefix("/media/").unwrap()).exists());
+
+        // Range requests on the uploaded file
+        let stream = |range: Option<&str>| {
+            let mut headers = HeaderMap::new();
+            if let Some(range) = range {
+                headers.insert("range", range.parse().unwrap());
+            }
+            stream_exercise_video(Extension(pool.clone()), Extension(store.clone()), headers, Path(squat))
+        };
+        let response = stream(Some("bytes=4-7")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.headers()["content-range"], format!("bytes 4-7/{}", mp4.len()));
+        assert_eq!(&axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap()[..], b"ftyp");
+        let response = stream(Some("bytes=-3")).await.unwrap();
+        assert_eq!(&axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap()[..], &[7, 7, 7]);
+        let response = stream(None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["accept-ranges"], "bytes");
+        assert_eq!(axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap().len(), mp4.len());
+        let response = stream(Some("bytes=5000-")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(response.headers()["content-range"], format!("bytes */{}", mp4.len()));
+
+        // Size and format are checked while the upload streams in
+        let err = upload_video(&pool, &store, &jobs, squat, &[0u8; 2048]).await.unwrap_err();
+        assert_eq!(err.0, StatusCode::PAYLOAD_TOO_LARGE);
+        let err = upload_video(&pool, &store, &jobs, squat, b"%PDF-1.7 not a video").await.unwrap_err();
+        assert_eq!(err.0, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(processed_video(&pool, &store, squat).await.id, video.id);
+
+        // Specialized videos are hidden from anonymous callers and their segments are signed
+        let _ = upload_video(&pool, &store, &jobs, specialized, &mp4).await.unwrap();
+        assert_eq!(processed_video(&pool, &store, specialized).await.status, "ready");
+        let err = get_exercise_video(Extension(pool.clone()), Extension(store.clone()), HeaderMap::new(), Path(specialized)).await.unwrap_err();
+        assert_eq!(err.0, StatusCode::NOT_FOUND);
+        let response = get_exercise_video_playlist(Extension(pool.clone()), Extension(store.clone()), auth_headers("physio-001", "physiotherapist"), Path(specialized))
+            .await
+            .unwrap();
+        let playlist = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&playlist).contains("/hls/segment_000.ts?expires="));
+
+        // A new upload replaces the old video and its files; deleting removes the rest
+        let videos_dir = root.join(format!("public/exercises/{}/videos", squat));
+        let _ = upload_video(&pool, &store, &jobs, squat, &mp4).await.unwrap();
+        let replaced = processed_video(&pool, &store, squat).await;
+        assert_ne!(replaced.id, video.id);
+        fn files_of(dir: &std::path::Path) -> usize {
+            std::fs::read_dir(dir)
+                .map(|entries| entries.filter_map(Result::ok).map(|e| if e.path().is_dir() { files_of(&e.path()) } else { 1 }).sum())
+                .unwrap_or(0)
+        }
+        // source, playlist, one segment and the poster
+        assert_eq!(files_of(&videos_dir), 4);
+        let status = delete_exercise_video(Extension(pool.clone()), Extension(store.clone()), auth_headers("physio-001", "physiotherapist"), Path(squat)).await.unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert_eq!(files_of(&videos_dir), 0);
+        let err = get_exercise_video(Extension(pool.clone()), Extension(store.clone()), HeaderMap::new(), Path(squat)).await.unwrap_err();
+        assert_eq!(err.0, StatusCode::NOT_FOUND);
+
+        // ffmpeg failures are reported on the video
+        let broken = VideoJobs::start(pool.clone(), store.clone(), VideoConfig { ffmpeg_path: "/nonexistent/ffmpeg".to_string(), ..config });
+        let _ = upload_video(&pool, &store, &broken, squat, &mp4).await.unwrap();
+        let failed = processed_video(&pool, &store, squat).await;
+        assert_eq!(failed.status, "failed");
+        assert!(failed.error.unwrap().contains("Could not start ffmpeg"));
+        assert_eq!(failed.playlist_url, None);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,219 @@
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader, Limits};
+use std::io::Cursor;
+
+// Longest side of each variant in pixels; images are never upscaled
+pub const VARIANTS: [(&str, u32); 3] = [("thumbnail", 320), ("medium", 1024), ("full", 2560)];
+
+// Larger images are refused from their header, before any pixels are decoded
+const MAX_DIMENSION: u32 = 12_000;
+const MAX_PIXELS: u64 = 40_000_000;
+// Upper bound on what the decoder may allocate, whatever the header claims
+const MAX_DECODE_BYTES: u64 = 512 * 1024 * 1024;
+const JPEG_QUALITY: u8 = 85;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ImageError {
+    #[error("Only JPEG, PNG and WebP images are supported")]
+    UnsupportedFormat,
+    #[error("The file could not be decoded as an image: {0}")]
+    Undecodable(String),
+    #[error("Images larger than 40 megapixels are not supported")]
+    TooLarge,
+    #[error("Failed to encode image: {0}")]
+    Encode(String),
+}
+
+pub struct Variant {
+    pub name: &'static str,
+    pub extension: &'static str,
+    pub width: u32,
+    pub height: u32,
+    pub bytes: Vec<u8>,
+}
+
+// Detect the real format from the leading bytes; the file name and content type are not trusted
+pub fn sniff_format(data: &[u8]) -> Option<ImageFormat> {
+    if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some(ImageFormat::Jpeg)
+    } else if data.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+        Some(ImageFormat::Png)
+    } else if data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        Some(ImageFormat::WebP)
+    } else {
+        None
+    }
+}
+
+// Decode an upload and re-encode it as thumbnail, medium and full variants. Only pixels are
+// written back, so EXIF (camera, GPS) and other metadata are dropped; the EXIF orientation is
+// applied first so photos keep the way they were taken. Images with transparency become PNG,
+// everything else JPEG.
+pub fn process_upload(data: &[u8]) -> Result<Vec<Variant>, ImageError> {
+    let format = sniff_format(data).ok_or(ImageError::UnsupportedFormat)?;
+
+    let mut reader = ImageReader::with_format(Cursor::new(data), format);
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    limits.max_alloc = Some(MAX_DECODE_BYTES);
+    reader.limits(limits);
+
+    let mut decoder = reader.into_decoder().map_err(|e| ImageError::Undecodable(e.to_string()))?;
+    let (width, height) = decoder.dimensions();
+    if u64::from(width) * u64::from(height) > MAX_PIXELS {
+        return Err(ImageError::TooLarge);
+    }
+    let orientation = decoder.orientation().map_err(|e| ImageError::Undecodable(e.to_string()))?;
+    let mut image = DynamicImage::from_decoder(decoder).map_err(|e| ImageError::Undecodable(e.to_string()))?;
+    image.apply_orientation(orientation);
+
+    let keep_alpha = image.color().has_alpha();
+    VARIANTS
+        .iter()
+        .map(|&(name, max_side)| {
+            let resized = if image.width().max(image.height()) > max_side {
+                image.resize(max_side, max_side, FilterType::Lanczos3)
+            } else {
+                image.clone()
+            };
+            encode(name, &resized, keep_alpha)
+        })
+        .collect()
+}
+
+fn encode(name: &'static str, image: &DynamicImage, keep_alpha: bool) -> Result<Variant, ImageError> {
+    let mut bytes = Vec::new();
+    let extension = if keep_alpha {
+        image
+            .to_rgba8()
+            .write_with_encoder(PngEncoder::new(&mut bytes))
+            .map_err(|e| ImageError::Encode(e.to_string()))?;
+        "png"
+    } else {
+        image
+            .to_rgb8()
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, JPEG_QUALITY))
+            .map_err(|e| ImageError::Encode(e.to_string()))?;
+        "jpg"
+    };
+
+    Ok(Variant {
+        name,
+        extension,
+        width: image.width(),
+        height: image.height(),
+        bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage, Rgba, RgbaImage};
+
+    fn jpeg(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        RgbImage::from_pixel(width, height, Rgb([200, 80, 40]))
+            .write_with_encoder(JpegEncoder::new(&mut bytes))
+            .unwrap();
+        bytes
+    }
+
+    // Insert an APP1 Exif segment right after the SOI marker
+    fn with_exif(jpeg: &[u8], orientation: u8) -> Vec<u8> {
+        let mut tiff = b"MM\x00\x2a\x00\x00\x00\x08".to_vec();
+        tiff.extend_from_slice(&[0x00, 0x02]);
+        // Orientation (0x0112), SHORT, count 1
+        tiff.extend_from_slice(&[0x01, 0x12, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01, 0x00, orientation, 0x00, 0x00]);
+        // Make (0x010F), ASCII, count 4, "GPS" inline
+        tiff.extend_from_slice(&[0x01, 0x0F, 0x00, 0x02, 0x00, 0x00, 0x00, 0x04, b'G', b'P', b'S', 0x00]);
+        tiff.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+
+        let mut payload = b"Exif\x00\x00".to_vec();
+        payload.extend_from_slice(&tiff);
+        let length = (payload.len() + 2) as u16;
+
+        let mut out = jpeg[..2].to_vec();
+        out.extend_from_slice(&[0xFF, 0xE1]);
+        out.extend_from_slice(&length.to_be_bytes());
+        out.extend_from_slice(&payload);
+        out.extend_from_slice(&jpeg[2..]);
+        out
+    }
+
+    #[test]
+    fn produces_downscaled_variants_without_metadata() {
+        // Orientation 6: stored landscape, displayed rotated 90° clockwise
+        let upload = with_exif(&jpeg(1600, 800), 6);
+        assert!(upload.windows(4).any(|w| w == b"Exif"));
+
+        let variants = process_upload(&upload).unwrap();
+        let sizes: Vec<(&str, u32, u32)> = variants.iter().map(|v| (v.name, v.width, v.height)).collect();
+        assert_eq!(sizes, vec![("thumbnail", 160, 320), ("medium", 512, 1024), ("full", 800, 1600)]);
+        for variant in &variants {
+            assert_eq!(variant.extension, "jpg");
+            assert_eq!(sniff_format(&variant.bytes), Some(ImageFormat::Jpeg));
+            assert!(!variant.bytes.windows(4).any(|w| w == b"Exif"));
+        }
+    }
+
+    #[test]
+    fn small_images_are_not_upscaled_and_transparency_is_kept() {
+        let mut bytes = Vec::new();
+        RgbaImage::from_pixel(64, 32, Rgba([0, 0, 0, 0]))
+            .write_with_encoder(PngEncoder::new(&mut bytes))
+            .unwrap();
+
+        let variants = process_upload(&bytes).unwrap();
+        assert!(variants.iter().all(|v| (v.width, v.height, v.extension) == (64, 32, "png")));
+    }
+
+    // PNG whose header claims the given size; the pixel data is never reached
+    fn png_header(width: u32, height: u32) -> Vec<u8> {
+        fn crc32(bytes: &[u8]) -> u32 {
+            let mut crc = 0xFFFF_FFFFu32;
+            for &byte in bytes {
+                crc ^= u32::from(byte);
+                for _ in 0..8 {
+                    crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+                }
+            }
+            !crc
+        }
+
+        let mut ihdr = b"IHDR".to_vec();
+        ihdr.extend_from_slice(&width.to_be_bytes());
+        ihdr.extend_from_slice(&height.to_be_bytes());
+        // 8-bit RGB, default compression, filter and no interlacing
+        ihdr.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+        let mut png = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+        for chunk in [ihdr, b"IDAT".to_vec()] {
+            png.extend_from_slice(&(chunk.len() as u32 - 4).to_be_bytes());
+            png.extend_from_slice(&chunk);
+            png.extend_from_slice(&crc32(&chunk).to_be_bytes());
+        }
+        png
+    }
+
+    #[test]
+    fn refuses_oversized_images_from_the_header() {
+        // Each side is under MAX_DIMENSION but together they are 100 megapixels
+        assert!(matches!(process_upload(&png_header(10_000, 10_000)), Err(ImageError::TooLarge)));
+        assert!(matches!(process_upload(&png_header(20_000, 10)), Err(ImageError::Undecodable(_))));
+    }
+
+    #[test]
+    fn rejects_files_that_are_not_images() {
+        assert!(matches!(process_upload(b"%PDF-1.7 not an image"), Err(ImageError::UnsupportedFormat)));
+        assert!(matches!(process_upload(b"GIF89a\x01\x00\x01\x00"), Err(ImageError::UnsupportedFormat)));
+        // Right magic bytes, broken body
+        let truncated = &jpeg(100, 100)[..40];
+        assert!(matches!(process_upload(truncated), Err(ImageError::Undecodable(_))));
+        let riff = b"RIFF\x10\x00\x00\x00WEBPVP8 garbage".to_vec();
+        assert!(matches!(process_upload(&riff), Err(ImageError::Undecodable(_))));
+    }
+}
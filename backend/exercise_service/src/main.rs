@@ -1,25 +1,32 @@
 use axum::{
     routing::{get, post, put, delete},
-    Router, Extension,
+    Router, Extension, Json,
     extract::DefaultBodyLimit,
 };
-use std::net::SocketAddr;
 use tower_http::cors::CorsLayer;
 use tower_http::limit::RequestBodyLimitLayer;
 use tower_http::services::ServeDir;
-use tracing_subscriber;
 use anyhow::Result;
 use std::fs;
 use std::env;
+use std::sync::Arc;
 
 mod models;
 mod handlers;
 mod config;
 mod database;
+mod images;
+mod storage;
+mod utils;
+mod videos;
+#[cfg(test)]
+mod test_support;
 
-use config::Config;
-use database::create_pool;
+use config::{Config, MediaConfig};
+use database::{create_pool, prepare_schema};
 use handlers::*;
+use storage::{LocalStore, MediaStore, S3Store};
+use videos::VideoJobs;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -27,111 +34,98 @@ async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
 
     // Load configuration
-    let config = Config::from_env();
+    let config = Config::from_env()?;
     tracing::info!("Starting Exercise Service with config: {:?}", config);
 
     // Initialize database (using auth service database)
     let pool = create_pool(&config.database_url).await?;
     tracing::info!("Database pool created (using auth service database)");
 
-    // Check if exercises table exists, create it if not
-    let table_exists = sqlx::query_scalar::<_, i64>(
-        "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='exercises'"
-    )
-    .fetch_one(&pool)
-    .await?;
-
-    if table_exists == 0 {
-        tracing::info!("Creating exercises table...");
-        
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS exercises (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                title TEXT NOT NULL,
-                description TEXT NOT NULL,
-                category TEXT NOT NULL,
-                difficulty_level TEXT NOT NULL,
-                duration_minutes INTEGER,
-                equipment_needed TEXT NOT NULL,
-                instructions TEXT NOT NULL,
-                image_url TEXT,
-                video_url TEXT,
-                youtube_url TEXT,
-                target_muscles TEXT NOT NULL,
-                created_at INTEGER NOT NULL,
-                is_specialized INTEGER NOT NULL DEFAULT 0
-            )
-            "#
-        )
-        .execute(&pool)
-        .await?;
-
-        // Create indexes
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_exercises_category ON exercises(category)")
-            .execute(&pool)
-            .await?;
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_exercises_difficulty ON exercises(difficulty_level)")
-            .execute(&pool)
-            .await?;
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_exercises_created_at ON exercises(created_at)")
-            .execute(&pool)
-            .await?;
-
-        tracing::info!("Exercises table created successfully");
-    } else {
-        tracing::info!("Exercises table already exists");
-    }
-
-    // Run migrations
+    // Create the exercises table and run migrations
     let current_dir = env::current_dir()?;
-    let migrations_dir = current_dir.join("migrations");
-    let migration_files = fs::read_dir(&migrations_dir)?
-        .filter_map(Result::ok)
-        .filter(|entry| entry.path().extension().map_or(false, |ext| ext == "sql"))
-        .collect::<Vec<_>>();
-
-    for migration in migration_files {
-        let migration_path = migration.path();
-        let migration_sql = fs::read_to_string(&migration_path)?;
-        tracing::info!("Running migration: {:?}", migration_path);
-        sqlx::query(&migration_sql).execute(&pool).await?;
-    }
-
-    tracing::info!("Migrations completed successfully");
+    prepare_schema(&pool, &current_dir.join("migrations")).await?;
 
     // Build application routes
-    // ensure static images directory exists
-    let static_dir = current_dir.join("static").join("images");
-    if !static_dir.exists() {
-        fs::create_dir_all(&static_dir)?;
-        tracing::info!("Created static images directory: {:?}", static_dir);
-    }
-
-    let app = Router::new()
+    let mut app = Router::new()
         .route("/health", get(health_check))
         .route("/exercises", get(get_exercises))
         .route("/exercises", post(create_exercise))
+        .route("/exercises/search", get(search_exercises))
+        .route("/exercises/facets", get(get_exercise_facets))
         .route("/exercises/:exercise_id", get(get_exercise))
         .route("/exercises/:exercise_id", put(update_exercise))
         .route("/exercises/:exercise_id", delete(delete_exercise))
         // upload and manage images (set 20MB limit for this route only)
         .route("/exercises/:exercise_id/images", post(upload_exercise_images).layer(DefaultBodyLimit::max(20 * 1024 * 1024)))
+        .route("/exercises/:exercise_id/images/order", put(reorder_exercise_images))
+        .route("/exercises/:exercise_id/images/:image_id", put(update_exercise_image))
         .route("/exercises/:exercise_id/images/:image_id", delete(delete_exercise_image))
-        // serve static images from ./static
+        // recommend specialized exercises to patients
+        .route("/exercises/:exercise_id/assignments", get(get_exercise_assignments))
+        .route("/exercises/:exercise_id/assignments", post(assign_exercise))
+        .route("/exercises/:exercise_id/assignments/:patient_id", delete(unassign_exercise))
+        // routines: ordered exercises with sets, reps and rest
+        .route("/routines", get(get_routines))
+        .route("/routines", post(create_routine))
+        .route("/routines/:routine_id", get(get_routine))
+        .route("/routines/:routine_id", put(update_routine))
+        .route("/routines/:routine_id", delete(delete_routine))
+        .route("/routines/:routine_id/clone", post(clone_routine))
+        // categories, difficulty levels, muscles and equipment (writes are admin only)
+        .route("/taxonomy/:kind", get(get_taxonomy))
+        .route("/taxonomy/:kind", post(create_taxonomy_entry))
+        .route("/taxonomy/:kind/:entry_id", put(update_taxonomy_entry))
+        .route("/taxonomy/:kind/:entry_id", delete(delete_taxonomy_entry))
+        // images uploaded before the media store, served from ./static
         .nest_service(
             "/static",
             ServeDir::new(current_dir.join("static"))
-        )
+        );
+
+    // Uploads go through the media store; the local one is served by this service under /media
+    let store: Arc<dyn MediaStore> = match &config.media {
+        MediaConfig::Local { root, public_url, signing_key } => {
+            let local = Arc::new(LocalStore::new(root, public_url, signing_key));
+            let public_dir = local.root().join("public");
+            if !public_dir.exists() {
+                fs::create_dir_all(&public_dir)?;
+                tracing::info!("Created media directory: {:?}", public_dir);
+            }
+            app = app
+                .nest_service("/media/public", ServeDir::new(public_dir))
+                .route("/media/private/*path", get(get_private_media).layer(Extension(local.clone())));
+            local
+        }
+        MediaConfig::S3(s3) => Arc::new(S3Store::new(s3)),
+    };
+
+    // Uploaded videos are turned into HLS by a background job; unfinished ones from the last run start over
+    fs::create_dir_all(&config.video.work_dir)?;
+    let video_body_limit = (config.video.max_upload_bytes + 1024 * 1024) as usize;
+    let jobs = VideoJobs::start(pool.clone(), store.clone(), config.video.clone());
+    // Files left under the wrong prefix by an interrupted visibility change are moved now
+    relocate_all_media(&pool, store.as_ref())
+        .await
+        .map_err(|(_, Json(e))| anyhow::anyhow!("Failed to relocate exercise media: {}", e.error))?;
+    jobs.requeue_unfinished(&pool).await?;
+
+    let app = app
+        .layer(RequestBodyLimitLayer::new(20 * 1024 * 1024)) // fallback global limit
+        // video routes come after the fallback limit; uploads are capped by MAX_VIDEO_UPLOAD_MB instead
+        .route("/exercises/:exercise_id/video", post(upload_exercise_video).layer(DefaultBodyLimit::max(video_body_limit)))
+        .route("/exercises/:exercise_id/video", get(get_exercise_video))
+        .route("/exercises/:exercise_id/video", delete(delete_exercise_video))
+        .route("/exercises/:exercise_id/video/playlist.m3u8", get(get_exercise_video_playlist))
+        .route("/exercises/:exercise_id/video/source", get(stream_exercise_video))
         .layer(Extension(pool))
-        .layer(CorsLayer::permissive())
-        .layer(RequestBodyLimitLayer::new(20 * 1024 * 1024)); // fallback global limit
+        .layer(Extension(store))
+        .layer(Extension(jobs))
+        .layer(CorsLayer::permissive());
 
     // Start server
-    let addr = SocketAddr::from(([0, 0, 0, 0], config.server_port));
-    tracing::info!("Exercise service listening on {}", addr);
+    let listener = tokio::net::TcpListener::bind((config.server_host.as_str(), config.server_port)).await?;
+    tracing::info!("Exercise service listening on {} ({:?})", listener.local_addr()?, config.environment);
     
-    let listener = tokio::net::TcpListener::bind(addr).await?;
     axum::serve(listener, app).await?;
     
     Ok(())
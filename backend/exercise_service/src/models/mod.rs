@@ -17,6 +17,8 @@ pub struct Exercise {
     pub target_muscles: String,   // JSON array as string
     pub created_at: i64,
     pub is_specialized: i64,      // 0 or 1 (SQLite boolean)
+    pub category_id: Option<i64>,
+    pub difficulty_id: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +50,7 @@ pub struct UpdateExerciseRequest {
     pub video_url: Option<String>,
     pub youtube_url: Option<String>,
     pub target_muscles: Option<Vec<String>>,
+    pub is_specialized: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,11 +65,19 @@ pub struct ExerciseResponse {
     pub instructions: Vec<String>,
     pub image_url: Option<String>,
     pub images: Option<Vec<String>>,
+    // Thumbnail, medium and full size URLs for each image, in the same order as `images`
+    pub image_variants: Option<Vec<ExerciseImage>>,
     pub video_url: Option<String>,
     pub youtube_url: Option<String>,
+    // Uploaded video and its processing status
+    pub video: Option<ExerciseVideo>,
     pub target_muscles: Vec<String>,
     pub created_at: i64,
     pub is_specialized: bool,
+    pub category_id: Option<i64>,
+    pub difficulty_id: Option<i64>,
+    // Routines that use the exercise and the caller can see; staff only
+    pub routines: Option<Vec<RoutineSummary>>,
 }
 
 impl From<Exercise> for ExerciseResponse {
@@ -82,11 +93,209 @@ impl From<Exercise> for ExerciseResponse {
             instructions: serde_json::from_str(&exercise.instructions).unwrap_or_default(),
             image_url: exercise.image_url,
             images: None,
+            image_variants: None,
             video_url: exercise.video_url,
             youtube_url: exercise.youtube_url,
+            video: None,
             target_muscles: serde_json::from_str(&exercise.target_muscles).unwrap_or_default(),
             created_at: exercise.created_at,
             is_specialized: exercise.is_specialized != 0,
+            category_id: exercise.category_id,
+            difficulty_id: exercise.difficulty_id,
+            routines: None,
         }
     }
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssignExerciseRequest {
+    pub patient_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ExerciseAssignment {
+    pub exercise_id: i64,
+    pub patient_id: String,
+    pub assigned_by: String,
+    pub created_at: i64,
+}
+
+// Category, difficulty level, muscle or equipment entry
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TaxonomyEntry {
+    pub id: i64,
+    pub name: String,
+    pub slug: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<i64>,   // categories only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<i64>,    // difficulty levels only
+    #[sqlx(skip)]
+    pub aliases: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateTaxonomyRequest {
+    pub name: String,
+    pub slug: Option<String>,
+    pub parent_id: Option<i64>,
+    pub position: Option<i64>,
+    pub aliases: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateTaxonomyRequest {
+    pub name: Option<String>,
+    pub slug: Option<String>,
+    // null moves the category to the top level
+    #[serde(default, deserialize_with = "present")]
+    pub parent_id: Option<Option<i64>>,
+    pub position: Option<i64>,
+    pub aliases: Option<Vec<String>>,
+}
+
+// Tells a field sent as null apart from a missing one
+fn present<'de, D>(deserializer: D) -> Result<Option<Option<i64>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::<i64>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct FacetCount {
+    pub id: i64,
+    pub name: String,
+    pub slug: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<i64>,
+    pub count: i64,
+}
+
+// Counts for each facet value. A facet ignores its own filter so the other values stay selectable.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExerciseFacets {
+    pub total: i64,
+    pub categories: Vec<FacetCount>,
+    pub difficulty_levels: Vec<FacetCount>,
+    pub muscles: Vec<FacetCount>,
+    pub equipment: Vec<FacetCount>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Routine {
+    pub id: i64,
+    pub title: String,
+    pub description: Option<String>,
+    pub is_template: i64,          // 0 or 1 (SQLite boolean)
+    pub owner_id: String,
+    pub cloned_from: Option<i64>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutineExerciseRequest {
+    pub exercise_id: i64,
+    pub sets: Option<i64>,
+    pub reps: Option<i64>,
+    pub rest_seconds: Option<i64>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateRoutineRequest {
+    pub title: String,
+    pub description: Option<String>,
+    pub is_template: Option<bool>,
+    // In the order they are performed
+    pub exercises: Vec<RoutineExerciseRequest>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateRoutineRequest {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub is_template: Option<bool>,
+    // Replaces the whole list when present
+    pub exercises: Option<Vec<RoutineExerciseRequest>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RoutineExercise {
+    pub position: i64,
+    pub exercise_id: i64,
+    pub title: String,
+    pub duration_minutes: Option<i64>,
+    pub sets: Option<i64>,
+    pub reps: Option<i64>,
+    pub rest_seconds: Option<i64>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutineResponse {
+    pub id: i64,
+    pub title: String,
+    pub description: Option<String>,
+    pub is_template: bool,
+    pub owner_id: String,
+    pub cloned_from: Option<i64>,
+    // Exercise durations plus rest between sets, rounded up to whole minutes
+    pub estimated_duration_minutes: i64,
+    pub exercises: Vec<RoutineExercise>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RoutineSummary {
+    pub id: i64,
+    pub title: String,
+    pub is_template: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ExerciseImage {
+    pub id: i64,
+    pub url: String,
+    pub thumbnail_url: String,
+    pub medium_url: String,
+    pub full_url: String,
+    pub alt_text: Option<String>,
+    pub caption: Option<String>,
+    // The chosen cover, or the first image when none was chosen
+    pub is_cover: bool,
+}
+
+// New gallery order; every image of the exercise has to be listed exactly once
+#[derive(Debug, Deserialize)]
+pub struct ReorderImagesRequest {
+    pub image_ids: Vec<i64>,
+}
+
+// Fields left out are unchanged; an empty string clears alt text or caption
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateExerciseImageRequest {
+    pub alt_text: Option<String>,
+    pub caption: Option<String>,
+    pub is_cover: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExerciseVideo {
+    pub id: i64,
+    pub exercise_id: i64,
+    // pending, processing, ready or failed
+    pub status: String,
+    pub error: Option<String>,
+    pub content_type: String,
+    pub size_bytes: i64,
+    // HLS playlist and poster frame, once processing has finished
+    pub playlist_url: Option<String>,
+    pub poster_url: Option<String>,
+    // The uploaded file as is; served with range support
+    pub source_url: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
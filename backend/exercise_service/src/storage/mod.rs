@@ -0,0 +1,480 @@
+use async_trait::async_trait;
+use aws_sdk_s3::config::{BehaviorVersion, Credentials, Region};
+use aws_sdk_s3::error::DisplayErrorContext;
+use aws_sdk_s3::presigning::PresigningConfig;
+use aws_sdk_s3::primitives::ByteStream;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::io::SeekFrom;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt};
+
+use crate::config::S3Config;
+
+// Objects under this prefix are never served without a signed URL: the local store only exposes
+// public/ as static files, and S3 buckets should only grant anonymous reads on public/*.
+const PRIVATE_PREFIX: &str = "private/";
+
+#[derive(Debug, thiserror::Error)]
+pub enum StorageError {
+    #[error("Invalid media key: {0}")]
+    InvalidKey(String),
+    #[error("Media not found: {0}")]
+    NotFound(String),
+    #[error("Media storage I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Media storage error: {0}")]
+    Backend(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    Public,
+    Private,
+}
+
+impl Visibility {
+    // Build a key such as public/exercises/7/images/<name>
+    pub fn key(self, path: &str) -> String {
+        match self {
+            Visibility::Public => format!("public/{}", path),
+            Visibility::Private => format!("{}{}", PRIVATE_PREFIX, path),
+        }
+    }
+}
+
+pub fn is_private(key: &str) -> bool {
+    key.starts_with(PRIVATE_PREFIX)
+}
+
+// The same path under the prefix of another visibility
+pub fn with_visibility(key: &str, visibility: Visibility) -> String {
+    let path = key
+        .strip_prefix(PRIVATE_PREFIX)
+        .or_else(|| key.strip_prefix("public/"))
+        .unwrap_or(key);
+    visibility.key(path)
+}
+
+pub type MediaReader = Box<dyn AsyncRead + Send + Unpin>;
+
+// Where uploaded media lives. Keys are relative paths using `/`; the store decides how they map to
+// files or objects and how clients reach them.
+#[async_trait]
+pub trait MediaStore: Send + Sync {
+    async fn put(&self, key: &str, bytes: Vec<u8>, content_type: &str) -> Result<(), StorageError>;
+
+    // Large files such as videos are streamed from disk instead of being held in memory
+    async fn put_file(&self, key: &str, path: &Path, content_type: &str) -> Result<(), StorageError>;
+
+    async fn size(&self, key: &str) -> Result<u64, StorageError>;
+
+    // Bytes `range.start..range.end` of an object
+    async fn read(&self, key: &str, range: Range<u64>) -> Result<MediaReader, StorageError>;
+
+    // Used to move media when an exercise changes visibility
+    async fn copy(&self, from: &str, to: &str) -> Result<(), StorageError>;
+
+    // Deleting a key that does not exist is not an error
+    async fn delete(&self, key: &str) -> Result<(), StorageError>;
+
+    // Permanent URL for public media
+    fn public_url(&self, key: &str) -> String;
+
+    // URL that stops working after `expires_in`, for media that must not be shared
+    async fn signed_url(&self, key: &str, expires_in: Duration) -> Result<String, StorageError>;
+}
+
+// Private keys, and anything the caller asks to protect, get a signed URL
+pub async fn media_url(store: &dyn MediaStore, key: &str, sign: bool, expires_in: Duration) -> Result<String, StorageError> {
+    if sign || is_private(key) {
+        store.signed_url(key, expires_in).await
+    } else {
+        Ok(store.public_url(key))
+    }
+}
+
+// Best-effort cleanup; failures are only logged
+pub async fn remove_keys(store: &dyn MediaStore, keys: &[String]) {
+    for key in keys {
+        if let Err(e) = store.delete(key).await {
+            tracing::warn!("Failed to remove {}: {}", key, e);
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// Files under a configured root directory. public/ is served as static files under
+// `public_url`; everything else needs a URL signed with `signing_key`.
+pub struct LocalStore {
+    root: PathBuf,
+    public_url: String,
+    signing_key: String,
+}
+
+impl LocalStore {
+    pub fn new(root: impl Into<PathBuf>, public_url: &str, signing_key: &str) -> Self {
+        LocalStore {
+            root: root.into(),
+            public_url: public_url.trim_end_matches('/').to_string(),
+            signing_key: signing_key.to_string(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    // Keys must stay inside the root: no absolute paths, `.`/`..` or empty segments
+    pub fn path_for(&self, key: &str) -> Result<PathBuf, StorageError> {
+        let valid = key
+            .split('/')
+            .all(|segment| !segment.is_empty() && segment != "." && segment != ".." && !segment.contains('\\'));
+        if !valid {
+            return Err(StorageError::InvalidKey(key.to_string()));
+        }
+        Ok(self.root.join(key))
+    }
+
+    fn mac(&self, key: &str, expires: u64) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.signing_key.as_bytes()).expect("HMAC accepts any key length");
+        mac.update(format!("{}:{}", key, expires).as_bytes());
+        mac
+    }
+
+    fn signature(&self, key: &str, expires: u64) -> String {
+        hex::encode(self.mac(key, expires).finalize().into_bytes())
+    }
+
+    // Check the query parameters of a URL produced by `signed_url`
+    pub fn verify(&self, key: &str, expires: u64, signature: &str) -> bool {
+        if expires < unix_now() {
+            return false;
+        }
+        match hex::decode(signature) {
+            Ok(bytes) => self.mac(key, expires).verify_slice(&bytes).is_ok(),
+            Err(_) => false,
+        }
+    }
+}
+
+#[async_trait]
+impl MediaStore for LocalStore {
+    async fn put(&self, key: &str, bytes: Vec<u8>, _content_type: &str) -> Result<(), StorageError> {
+        let path = self.path_for(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&path, bytes).await?;
+        Ok(())
+    }
+
+    async fn put_file(&self, key: &str, source: &Path, _content_type: &str) -> Result<(), StorageError> {
+        let path = self.path_for(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::copy(source, &path).await?;
+        Ok(())
+    }
+
+    async fn size(&self, key: &str) -> Result<u64, StorageError> {
+        match tokio::fs::metadata(self.path_for(key)?).await {
+            Ok(metadata) => Ok(metadata.len()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(StorageError::NotFound(key.to_string())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn read(&self, key: &str, range: Range<u64>) -> Result<MediaReader, StorageError> {
+        let mut file = match tokio::fs::File::open(self.path_for(key)?).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(StorageError::NotFound(key.to_string())),
+            Err(e) => return Err(e.into()),
+        };
+        file.seek(SeekFrom::Start(range.start)).await?;
+        Ok(Box::new(file.take(range.end.saturating_sub(range.start))))
+    }
+
+    async fn copy(&self, from: &str, to: &str) -> Result<(), StorageError> {
+        let target = self.path_for(to)?;
+        if let Some(parent) = target.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        match tokio::fs::copy(self.path_for(from)?, &target).await {
+            Ok(_) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(StorageError::NotFound(from.to_string())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        match tokio::fs::remove_file(self.path_for(key)?).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn public_url(&self, key: &str) -> String {
+        format!("{}/{}", self.public_url, key)
+    }
+
+    async fn signed_url(&self, key: &str, expires_in: Duration) -> Result<String, StorageError> {
+        self.path_for(key)?;
+        let expires = unix_now() + expires_in.as_secs();
+        Ok(format!(
+            "{}/{}?expires={}&signature={}",
+            self.public_url,
+            key,
+            expires,
+            self.signature(key, expires)
+        ))
+    }
+}
+
+// Any S3-compatible service (AWS, MinIO). Signed URLs are S3 presigned GETs.
+pub struct S3Store {
+    client: aws_sdk_s3::Client,
+    bucket: String,
+    public_url: String,
+}
+
+impl S3Store {
+    pub fn new(config: &S3Config) -> Self {
+        let mut builder = aws_sdk_s3::config::Builder::new()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new(config.region.clone()))
+            .credentials_provider(Credentials::new(
+                config.access_key_id.clone(),
+                config.secret_access_key.clone(),
+                None,
+                None,
+                "exercise_service",
+            ))
+            // MinIO and most self-hosted services do not support bucket subdomains
+            .force_path_style(true);
+        if let Some(endpoint) = &config.endpoint {
+            builder = builder.endpoint_url(endpoint.clone());
+        }
+
+        S3Store {
+            client: aws_sdk_s3::Client::from_conf(builder.build()),
+            bucket: config.bucket.clone(),
+            public_url: config.public_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+fn backend_error<E: std::error::Error>(e: E) -> StorageError {
+    StorageError::Backend(DisplayErrorContext(e).to_string())
+}
+
+#[async_trait]
+impl MediaStore for S3Store {
+    async fn put(&self, key: &str, bytes: Vec<u8>, content_type: &str) -> Result<(), StorageError> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type(content_type)
+            .body(ByteStream::from(bytes))
+            .send()
+            .await
+            .map_err(backend_error)?;
+        Ok(())
+    }
+
+    async fn put_file(&self, key: &str, path: &Path, content_type: &str) -> Result<(), StorageError> {
+        let body = ByteStream::from_path(path).await.map_err(backend_error)?;
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type(content_type)
+            .body(body)
+            .send()
+            .await
+            .map_err(backend_error)?;
+        Ok(())
+    }
+
+    async fn size(&self, key: &str) -> Result<u64, StorageError> {
+        let output = self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| match e.as_service_error() {
+                Some(service) if service.is_not_found() => StorageError::NotFound(key.to_string()),
+                _ => backend_error(e),
+            })?;
+        Ok(output.content_length().unwrap_or(0).max(0) as u64)
+    }
+
+    async fn read(&self, key: &str, range: Range<u64>) -> Result<MediaReader, StorageError> {
+        if range.is_empty() {
+            return Ok(Box::new(tokio::io::empty()));
+        }
+        let output = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .range(format!("bytes={}-{}", range.start, range.end - 1))
+            .send()
+            .await
+            .map_err(|e| match e.as_service_error() {
+                Some(service) if service.is_no_such_key() => StorageError::NotFound(key.to_string()),
+                _ => backend_error(e),
+            })?;
+        Ok(Box::new(output.body.into_async_read()))
+    }
+
+    // Keys are built from ids and generated names, so they need no escaping in the copy source
+    async fn copy(&self, from: &str, to: &str) -> Result<(), StorageError> {
+        self.client
+            .copy_object()
+            .bucket(&self.bucket)
+            .copy_source(format!("{}/{}", self.bucket, from))
+            .key(to)
+            .send()
+            .await
+            .map_err(backend_error)?;
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(backend_error)?;
+        Ok(())
+    }
+
+    fn public_url(&self, key: &str) -> String {
+        format!("{}/{}", self.public_url, key)
+    }
+
+    async fn signed_url(&self, key: &str, expires_in: Duration) -> Result<String, StorageError> {
+        let presigning = PresigningConfig::expires_in(expires_in).map_err(backend_error)?;
+        let request = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .presigned(presigning)
+            .await
+            .map_err(backend_error)?;
+        Ok(request.uri().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> LocalStore {
+        let root = std::env::temp_dir().join(format!("fisionet-media-test-{}", uuid::Uuid::new_v4()));
+        LocalStore::new(root, "/media/", "test-signing-key")
+    }
+
+    #[tokio::test]
+    async fn local_store_round_trips_files_under_its_root() {
+        let store = store();
+        let key = Visibility::Public.key("exercises/1/images/a.jpg");
+        store.put(&key, b"jpeg".to_vec(), "image/jpeg").await.unwrap();
+
+        let path = store.root().join("public/exercises/1/images/a.jpg");
+        assert_eq!(std::fs::read(&path).unwrap(), b"jpeg");
+        assert_eq!(store.public_url(&key), "/media/public/exercises/1/images/a.jpg");
+
+        assert_eq!(store.size(&key).await.unwrap(), 4);
+        let mut middle = String::new();
+        store.read(&key, 1..3).await.unwrap().read_to_string(&mut middle).await.unwrap();
+        assert_eq!(middle, "pe");
+
+        store.delete(&key).await.unwrap();
+        assert!(!path.exists());
+        assert!(matches!(store.size(&key).await, Err(StorageError::NotFound(_))));
+        // Already gone
+        store.delete(&key).await.unwrap();
+
+        for key in ["../outside.jpg", "/etc/passwd", "public//a.jpg", "public/./a.jpg", ""] {
+            assert!(matches!(store.put(key, vec![], "image/jpeg").await, Err(StorageError::InvalidKey(_))), "{}", key);
+        }
+        let _ = std::fs::remove_dir_all(store.root());
+    }
+
+    #[tokio::test]
+    async fn local_signed_urls_expire_and_cannot_be_reused_for_other_keys() {
+        let store = store();
+        let key = Visibility::Private.key("exercises/1/images/a.jpg");
+        assert!(is_private(&key));
+
+        let url = media_url(&store, &key, false, Duration::from_secs(60)).await.unwrap();
+        let (path, query) = url.split_once('?').unwrap();
+        assert_eq!(path, "/media/private/exercises/1/images/a.jpg");
+        let params: std::collections::HashMap<&str, &str> = query.split('&').filter_map(|p| p.split_once('=')).collect();
+        let expires: u64 = params["expires"].parse().unwrap();
+        let signature = params["signature"];
+
+        assert!(store.verify(&key, expires, signature));
+        assert!(!store.verify("private/exercises/2/images/a.jpg", expires, signature));
+        assert!(!store.verify(&key, expires + 1, signature));
+        assert!(!store.verify(&key, expires, "not-hex"));
+
+        let stale = unix_now() - 1;
+        assert!(!store.verify(&key, stale, &store.signature(&key, stale)));
+
+        // Public keys only get a signature when asked for one
+        let public = Visibility::Public.key("exercises/1/images/a.jpg");
+        assert_eq!(media_url(&store, &public, false, Duration::from_secs(60)).await.unwrap(), store.public_url(&public));
+        assert!(media_url(&store, &public, true, Duration::from_secs(60)).await.unwrap().contains("signature="));
+    }
+
+    // Needs the MinIO from docker-compose.dev.yml: cargo test -p exercise_service -- --ignored
+    #[tokio::test]
+    #[ignore]
+    async fn s3_store_round_trips_files_in_minio() {
+        let env_or = |name: &str, default: &str| std::env::var(name).unwrap_or_else(|_| default.to_string());
+        let endpoint = env_or("S3_ENDPOINT", "http://localhost:9000");
+        let bucket = env_or("S3_BUCKET", "fisionet-media");
+        let store = S3Store::new(&S3Config {
+            public_url: format!("{}/{}", endpoint, bucket),
+            endpoint: Some(endpoint),
+            region: env_or("S3_REGION", "us-east-1"),
+            bucket,
+            access_key_id: env_or("S3_ACCESS_KEY_ID", "fisionet"),
+            secret_access_key: env_or("S3_SECRET_ACCESS_KEY", "fisionet_minio_pass"),
+        });
+
+        let key = Visibility::Public.key(&format!("tests/{}/a.jpg", uuid::Uuid::new_v4().simple()));
+        store.put(&key, b"jpeg".to_vec(), "image/jpeg").await.unwrap();
+        assert_eq!(store.size(&key).await.unwrap(), 4);
+        let mut middle = String::new();
+        store.read(&key, 1..3).await.unwrap().read_to_string(&mut middle).await.unwrap();
+        assert_eq!(middle, "pe");
+
+        let moved = with_visibility(&key, Visibility::Private);
+        store.copy(&key, &moved).await.unwrap();
+        assert_eq!(store.size(&moved).await.unwrap(), 4);
+        assert!(store.signed_url(&moved, Duration::from_secs(60)).await.unwrap().contains("X-Amz-Signature="));
+
+        remove_keys(&store, &[key.clone(), moved.clone()]).await;
+        assert!(matches!(store.size(&key).await, Err(StorageError::NotFound(_))));
+        assert!(matches!(store.size(&moved).await, Err(StorageError::NotFound(_))));
+    }
+}
@@ -0,0 +1,183 @@
+use axum::extract::{Extension, Json, Query};
+use axum::http::{HeaderMap, HeaderValue};
+use common::jwt::jwt_secret;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::config::VideoConfig;
+use crate::database::prepare_schema;
+use crate::handlers::{create_exercise, get_exercises, slugify, FilterParams};
+use crate::models::CreateExerciseRequest;
+use crate::storage::{LocalStore, MediaStore};
+use crate::utils::Claims;
+use crate::videos::VideoJobs;
+
+// Each test gets its own database with the exercise schema and the users the handlers check roles against
+pub async fn test_pool() -> SqlitePool {
+    let pool = users_pool().await;
+    prepare_schema(&pool, &migrations_dir())
+        .await
+        .expect("Failed to prepare schema");
+    pool
+}
+
+// Database as it was before `migration` (a file name in migrations/); prepare_schema with
+// migrations_dir() then applies the rest
+pub async fn test_pool_before(migration: &str) -> SqlitePool {
+    let pool = users_pool().await;
+    let dir = std::env::temp_dir().join(format!("fisionet-exercise-migrations-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for entry in std::fs::read_dir(migrations_dir()).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_str().unwrap().to_string();
+        if name.as_str() < migration {
+            std::fs::copy(&path, dir.join(name)).unwrap();
+        }
+    }
+    prepare_schema(&pool, &dir)
+        .await
+        .expect("Failed to prepare schema");
+    std::fs::remove_dir_all(&dir).unwrap();
+    pool
+}
+
+pub fn migrations_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("migrations")
+}
+
+async fn users_pool() -> SqlitePool {
+    let path = std::env::temp_dir().join(format!("fisionet-exercise-test-{}.db", uuid::Uuid::new_v4()));
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(5)
+        .connect_with(options)
+        .await
+        .expect("Failed to open test database");
+
+    // Only the columns exercise_service reads from the auth_service users table
+    sqlx::raw_sql(
+        r#"
+        CREATE TABLE users (id TEXT PRIMARY KEY, role TEXT NOT NULL);
+        INSERT INTO users (id, role) VALUES
+            ('admin-001', 'admin'),
+            ('physio-001', 'physiotherapist'),
+            ('patient-001', 'patient');
+        "#
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create users table");
+    pool
+}
+
+// Media store in a fresh temporary directory
+pub fn test_store() -> Arc<dyn MediaStore> {
+    let root = std::env::temp_dir().join(format!("fisionet-exercise-media-{}", uuid::Uuid::new_v4()));
+    Arc::new(LocalStore::new(root, "/media", "test-signing-key"))
+}
+
+// Video queue whose ffmpeg never starts, for handlers that only enqueue
+pub fn test_jobs(pool: &SqlitePool, store: &Arc<dyn MediaStore>) -> VideoJobs {
+    let config = VideoConfig {
+        max_upload_bytes: 1024,
+        ffmpeg_path: "/nonexistent/ffmpeg".to_string(),
+        work_dir: std::env::temp_dir().join(format!("fisionet-exercise-work-{}", uuid::Uuid::new_v4())),
+    };
+    VideoJobs::start(pool.clone(), store.clone(), config)
+}
+
+// x-user-id header the frontend sends
+pub fn user_headers(user_id: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert("x-user-id", HeaderValue::from_str(user_id).unwrap());
+    headers
+}
+
+// Bearer token as issued by auth_service
+pub fn auth_headers(user_id: &str, role: &str) -> HeaderMap {
+    let exp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+        + 3600;
+    let claims = Claims {
+        sub: user_id.to_string(),
+        email: format!("{}@fisionet.test", user_id),
+        role: role.to_string(),
+        exp,
+    };
+    let token = jsonwebtoken::encode(
+        &jsonwebtoken::Header::default(),
+        &claims,
+        &jsonwebtoken::EncodingKey::from_secret(jwt_secret().as_bytes()),
+    )
+    .unwrap();
+    let mut headers = HeaderMap::new();
+    headers.insert("authorization", HeaderValue::from_str(&format!("Bearer {}", token)).unwrap());
+    headers
+}
+
+// Exercise created by physio-001 through the API
+pub async fn seed(pool: &SqlitePool, title: &str, duration: i64, muscles: &[&str], equipment: &[&str], specialized: bool) {
+    // Muscles and equipment have to be in the taxonomy before an exercise can use them
+    for (table, names) in [("muscles", muscles), ("equipment", equipment)] {
+        for name in names {
+            sqlx::query(&format!("INSERT OR IGNORE INTO {} (name, slug, created_at) VALUES (?, ?, 0)", table))
+                .bind(name)
+                .bind(slugify(name))
+                .execute(pool)
+                .await
+                .unwrap();
+        }
+    }
+
+    let Json(_) = create_exercise(
+        Extension(pool.clone()),
+        auth_headers("physio-001", "physiotherapist"),
+        Json(CreateExerciseRequest {
+            title: title.to_string(),
+            description: format!("{} description", title),
+            category: "strength".to_string(),
+            difficulty_level: "beginner".to_string(),
+            duration_minutes: Some(duration),
+            equipment_needed: equipment.iter().map(|e| e.to_string()).collect(),
+            instructions: vec!["Repeat 10 times".to_string()],
+            image_url: None,
+            video_url: None,
+            youtube_url: None,
+            target_muscles: muscles.iter().map(|m| m.to_string()).collect(),
+            is_specialized: Some(specialized),
+        }),
+    )
+    .await
+    .unwrap();
+}
+
+// Two basic exercises and a specialized one
+pub async fn seed_all(pool: &SqlitePool) {
+    seed(pool, "Squat", 10, &["Quadriceps", "Glutes"], &[], false).await;
+    seed(pool, "Runner's stretch", 5, &["Hamstrings"], &["Mat"], false).await;
+    seed(pool, "Resisted knee extension", 20, &["Quadriceps"], &["Resistance band"], true).await;
+}
+
+// Sorted titles a physiotherapist gets for the filters
+pub async fn titles(pool: &SqlitePool, params: FilterParams) -> Vec<String> {
+    let Json(page) = get_exercises(Extension(pool.clone()), Extension(test_store()), auth_headers("physio-001", "physiotherapist"), Query(params))
+        .await
+        .unwrap();
+    let mut titles: Vec<String> = page.exercises.into_iter().map(|e| e.title).collect();
+    titles.sort();
+    titles
+}
+
+pub async fn exercise_id(pool: &SqlitePool, title: &str) -> i64 {
+    sqlx::query_scalar::<_, i64>("SELECT id FROM exercises WHERE title = ?")
+        .bind(title)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
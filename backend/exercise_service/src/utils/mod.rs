@@ -0,0 +1,2 @@
+// Token handling shared with the other services
+pub use common::jwt::{verify_jwt_token, Claims};
@@ -0,0 +1,275 @@
+use sqlx::SqlitePool;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::handlers::now;
+use crate::config::VideoConfig;
+use crate::storage::{remove_keys, MediaStore};
+
+// A stuck ffmpeg is killed after this long and the video marked as failed
+const FFMPEG_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+// Height of the HLS rendition and poster; smaller videos are not upscaled
+const SCALE: &str = "scale=-2:'min(720,ih)'";
+const SEGMENT_SECONDS: &str = "6";
+
+// Detect the container from the leading bytes: MP4/MOV (ftyp box) and WebM/Matroska (EBML header).
+// Returns the file extension and content type.
+pub fn sniff_container(data: &[u8]) -> Option<(&'static str, &'static str)> {
+    if data.len() >= 12 && &data[4..8] == b"ftyp" {
+        if &data[8..12] == b"qt  " {
+            Some(("mov", "video/quicktime"))
+        } else {
+            Some(("mp4", "video/mp4"))
+        }
+    } else if data.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+        Some(("webm", "video/webm"))
+    } else {
+        None
+    }
+}
+
+// Queue for the background job that turns uploaded videos into HLS. Videos are processed one at a
+// time in this process; ffmpeg already uses every core.
+#[derive(Clone)]
+pub struct VideoJobs {
+    sender: mpsc::UnboundedSender<i64>,
+    pub config: Arc<VideoConfig>,
+}
+
+impl VideoJobs {
+    pub fn start(pool: SqlitePool, store: Arc<dyn MediaStore>, config: VideoConfig) -> Self {
+        let config = Arc::new(config);
+        let (sender, mut receiver) = mpsc::unbounded_channel::<i64>();
+
+        let worker_config = config.clone();
+        tokio::spawn(async move {
+            while let Some(video_id) = receiver.recv().await {
+                process(&pool, store.as_ref(), &worker_config, video_id).await;
+            }
+        });
+
+        VideoJobs { sender, config }
+    }
+
+    pub fn enqueue(&self, video_id: i64) {
+        if self.sender.send(video_id).is_err() {
+            tracing::error!("Video worker has stopped; video {} was not queued", video_id);
+        }
+    }
+
+    // Videos a previous run did not finish; their source is in the media store, so they start over
+    pub async fn requeue_unfinished(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        let ids = sqlx::query_scalar::<_, i64>(
+            "SELECT id FROM exercise_videos WHERE status IN ('pending', 'processing') ORDER BY id"
+        )
+        .fetch_all(pool)
+        .await?;
+        for id in ids {
+            self.enqueue(id);
+        }
+        Ok(())
+    }
+}
+
+async fn process(pool: &SqlitePool, store: &dyn MediaStore, config: &VideoConfig, video_id: i64) {
+    if let Err(message) = transcode(pool, store, config, video_id).await {
+        tracing::warn!("Processing video {} failed: {}", video_id, message);
+        let result = sqlx::query("UPDATE exercise_videos SET status = 'failed', error = ?, updated_at = ? WHERE id = ?")
+            .bind(&message)
+            .bind(now())
+            .bind(video_id)
+            .execute(pool)
+            .await;
+        if let Err(e) = result {
+            tracing::error!("Failed to record the error for video {}: {}", video_id, e);
+        }
+    }
+}
+
+async fn transcode(pool: &SqlitePool, store: &dyn MediaStore, config: &VideoConfig, video_id: i64) -> Result<(), String> {
+    let db = |e: sqlx::Error| format!("Database error: {}", e);
+
+    // Deleted or replaced before its turn came
+    let Some(source_key) = sqlx::query_scalar::<_, String>("SELECT source_key FROM exercise_videos WHERE id = ?")
+        .bind(video_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(db)?
+    else {
+        return Ok(());
+    };
+
+    sqlx::query("UPDATE exercise_videos SET status = 'processing', error = NULL, updated_at = ? WHERE id = ?")
+        .bind(now())
+        .bind(video_id)
+        .execute(pool)
+        .await
+        .map_err(db)?;
+
+    let work = config.work_dir.join(format!("job-{}-{}", video_id, uuid::Uuid::new_v4().simple()));
+    let mut stored: Vec<String> = Vec::new();
+    let result = render_and_store(pool, store, config, video_id, &source_key, &work, &mut stored).await;
+    let _ = tokio::fs::remove_dir_all(&work).await;
+
+    match result {
+        Ok(true) => Ok(()),
+        // The upload was replaced, deleted or moved to another prefix meanwhile, so the output belongs to nothing
+        Ok(false) => {
+            remove_keys(store, &stored).await;
+            Ok(())
+        }
+        Err(e) => {
+            remove_keys(store, &stored).await;
+            Err(e)
+        }
+    }
+}
+
+// Everything is written next to the source: <dir>/hls/index.m3u8, <dir>/hls/segment_NNN.ts and
+// <dir>/poster.jpg, so the output has the same visibility as the upload
+async fn render_and_store(
+    pool: &SqlitePool,
+    store: &dyn MediaStore,
+    config: &VideoConfig,
+    video_id: i64,
+    source_key: &str,
+    work: &Path,
+    stored: &mut Vec<String>,
+) -> Result<bool, String> {
+    let io = |e: std::io::Error| format!("Video work directory: {}", e);
+    let storage = |e: crate::storage::StorageError| e.to_string();
+
+    let hls_dir = work.join("hls");
+    tokio::fs::create_dir_all(&hls_dir).await.map_err(io)?;
+
+    // ffmpeg needs a local file
+    let source = work.join("source");
+    let size = store.size(source_key).await.map_err(storage)?;
+    let mut reader = store.read(source_key, 0..size).await.map_err(storage)?;
+    let mut file = tokio::fs::File::create(&source).await.map_err(io)?;
+    tokio::io::copy(&mut reader, &mut file).await.map_err(io)?;
+    drop(file);
+
+    let source_arg = source.to_string_lossy().to_string();
+    let playlist = hls_dir.join("index.m3u8");
+    let segments = hls_dir.join("segment_%03d.ts");
+    // Inputs may only open local files, so an uploaded playlist cannot make ffmpeg fetch URLs
+    ffmpeg(config, &[
+        "-protocol_whitelist", "file",
+        "-i", &source_arg,
+        "-map", "0:v:0", "-map", "0:a:0?",
+        "-vf", SCALE,
+        "-c:v", "libx264", "-preset", "veryfast", "-crf", "23", "-pix_fmt", "yuv420p",
+        "-c:a", "aac", "-b:a", "128k",
+        "-f", "hls", "-hls_time", SEGMENT_SECONDS, "-hls_playlist_type", "vod",
+        "-hls_segment_filename", &segments.to_string_lossy(),
+        &playlist.to_string_lossy(),
+    ])
+    .await?;
+
+    // The thumbnail filter picks a representative frame rather than a black first one
+    let poster = work.join("poster.jpg");
+    ffmpeg(config, &[
+        "-protocol_whitelist", "file",
+        "-i", &source_arg,
+        "-vf", &format!("thumbnail,{}", SCALE),
+        "-frames:v", "1",
+        &poster.to_string_lossy(),
+    ])
+    .await?;
+
+    let dir = source_key.rsplit_once('/').map(|(dir, _)| dir).unwrap_or_default();
+    let mut names = Vec::new();
+    let mut entries = tokio::fs::read_dir(&hls_dir).await.map_err(io)?;
+    while let Some(entry) = entries.next_entry().await.map_err(io)? {
+        names.push(entry.file_name().to_string_lossy().to_string());
+    }
+    names.sort();
+
+    for name in &names {
+        let content_type = if name.ends_with(".m3u8") { "application/vnd.apple.mpegurl" } else { "video/mp2t" };
+        let key = format!("{}/hls/{}", dir, name);
+        store.put_file(&key, &hls_dir.join(name), content_type).await.map_err(storage)?;
+        stored.push(key);
+    }
+    let playlist_key = format!("{}/hls/index.m3u8", dir);
+    if !stored.contains(&playlist_key) {
+        return Err("ffmpeg did not write a playlist".to_string());
+    }
+    let poster_key = format!("{}/poster.jpg", dir);
+    store.put_file(&poster_key, &poster, "image/jpeg").await.map_err(storage)?;
+    stored.push(poster_key.clone());
+
+    let db = |e: sqlx::Error| format!("Database error: {}", e);
+    let mut tx = pool.begin().await.map_err(db)?;
+    let updated = sqlx::query(
+        "UPDATE exercise_videos SET status = 'ready', error = NULL, playlist_key = ?, poster_key = ?, updated_at = ? \
+         WHERE id = ? AND source_key = ?"
+    )
+    .bind(&playlist_key)
+    .bind(&poster_key)
+    .bind(now())
+    .bind(video_id)
+    .bind(source_key)
+    .execute(&mut *tx)
+    .await
+    .map_err(db)?
+    .rows_affected();
+    if updated == 0 {
+        return Ok(false);
+    }
+
+    for key in stored.iter() {
+        sqlx::query("INSERT OR IGNORE INTO exercise_video_files (video_id, storage_key) VALUES (?, ?)")
+            .bind(video_id)
+            .bind(key)
+            .execute(&mut *tx)
+            .await
+            .map_err(db)?;
+    }
+    tx.commit().await.map_err(db)?;
+    Ok(true)
+}
+
+async fn ffmpeg(config: &VideoConfig, args: &[&str]) -> Result<(), String> {
+    let child = tokio::process::Command::new(&config.ffmpeg_path)
+        .args(["-hide_banner", "-loglevel", "error", "-y"])
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Could not start ffmpeg ({}): {}", config.ffmpeg_path, e))?;
+
+    let output = tokio::time::timeout(FFMPEG_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| "ffmpeg timed out".to_string())?
+        .map_err(|e| format!("ffmpeg failed: {}", e))?;
+    if output.status.success() {
+        return Ok(());
+    }
+
+    // The last lines say what went wrong
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let lines: Vec<&str> = stderr.trim().lines().collect();
+    let tail = lines[lines.len().saturating_sub(5)..].join("\n");
+    Err(format!("ffmpeg exited with {}: {}", output.status, tail))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_video_containers() {
+        assert_eq!(sniff_container(b"\x00\x00\x00\x18ftypisom\x00\x00\x02\x00"), Some(("mp4", "video/mp4")));
+        assert_eq!(sniff_container(b"\x00\x00\x00\x14ftypqt  \x00\x00\x00\x00"), Some(("mov", "video/quicktime")));
+        assert_eq!(sniff_container(b"\x1A\x45\xDF\xA3\x9F\x42\x86\x81"), Some(("webm", "video/webm")));
+        assert_eq!(sniff_container(b"\xFF\xD8\xFF\xE0 a jpeg"), None);
+        assert_eq!(sniff_container(b"ftyp"), None);
+    }
+}
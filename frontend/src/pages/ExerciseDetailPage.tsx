@@ -196,7 +196,7 @@ export const ExerciseDetailPage: React.FC = () => {
   const EXERCISE_API_URL = process.env.REACT_APP_EXERCISE_API_URL || 'http://localhost:8005';
   const getImageUrls = (images?: string[]) => {
     if (!images) return [];
-    return images.map(url => url.startsWith('/static/') ? `${EXERCISE_API_URL}${url}` : url);
+    return images.map(url => url.startsWith('/static/') || url.startsWith('/media/') ? `${EXERCISE_API_URL}${url}` : url);
   };
 
   return (
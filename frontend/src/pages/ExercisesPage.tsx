@@ -18,6 +18,8 @@ import {
   Paper,
   Skeleton,
   Alert,
+  Pagination,
+  Autocomplete,
 } from '@mui/material';
 import {
   Search,
@@ -32,7 +34,7 @@ import { Dialog, DialogTitle, DialogContent, DialogActions } from '@mui/material
 import { useAuth } from '../context/AuthContext';
 import { CreateExerciseRequest, UpdateExerciseRequest } from '../types';
 import { useNavigate } from 'react-router-dom';
-import { Exercise, ExerciseFilter } from '../types';
+import { Exercise, ExerciseFilter, TaxonomyEntry } from '../types';
 import { exerciseService } from '../services/exerciseService';
 import ImageCarousel from '../components/ImageCarousel';
 
@@ -41,11 +43,17 @@ export const ExercisesPage: React.FC = () => {
   const EXERCISE_API_URL = process.env.REACT_APP_EXERCISE_API_URL || 'http://localhost:8005';
   const getImageUrls = (images?: string[]) => {
     if (!images) return [];
-    return images.map(url => url.startsWith('/static/') ? `${EXERCISE_API_URL}${url}` : url);
+    return images.map(url => url.startsWith('/static/') || url.startsWith('/media/') ? `${EXERCISE_API_URL}${url}` : url);
   };
   const navigate = useNavigate();
   const [exercises, setExercises] = useState<Exercise[]>([]);
+  const [page, setPage] = useState(1);
+  const [pageCount, setPageCount] = useState(1);
+  const [total, setTotal] = useState(0);
   const [categories, setCategories] = useState<string[]>([]);
+  // The exercise form picks its values from the taxonomy, since the API rejects unknown ones
+  const [difficultyLevels, setDifficultyLevels] = useState<TaxonomyEntry[]>([]);
+  const [muscles, setMuscles] = useState<TaxonomyEntry[]>([]);
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
   const [favorites, setFavorites] = useState<Set<number>>(new Set());
@@ -143,10 +151,16 @@ export const ExercisesPage: React.FC = () => {
   const mockCategories = ['Strength', 'Flexibility', 'Balance', 'Cardio', 'Rehabilitation'];
 
   useEffect(() => {
-    loadExercises();
     loadCategories();
+    loadFormOptions();
   }, []);
 
+  // The API filters and pages the catalog, so each change fetches just the page shown
+  useEffect(() => {
+    loadExercises();
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, [page, filters]);
+
   const { isAuthenticated, user } = useAuth();
 
   // Create / Edit modal state
@@ -162,13 +176,22 @@ export const ExercisesPage: React.FC = () => {
     setLoading(true);
     setError(null);
     try {
-      const data = await exerciseService.getAllExercises(filters);
-      setExercises(data);
+      const data = await exerciseService.getExercisePage(page, filters);
+      // Deleting the last exercise of the last page moves back a page
+      if (data.exercises.length === 0 && page > 1) {
+        setPage(page - 1);
+        return;
+      }
+      setExercises(data.exercises);
+      setTotal(data.total);
+      setPageCount(Math.max(1, Math.ceil(data.total / data.limit)));
     } catch (err: any) {
       console.error('Failed to load exercises:', err);
       setError(err.response?.data?.error || 'Failed to load exercises');
       // Fallback to mock data in case of error
       setExercises(mockExercises);
+      setTotal(mockExercises.length);
+      setPageCount(1);
     } finally {
       setLoading(false);
     }
@@ -176,10 +199,33 @@ export const ExercisesPage: React.FC = () => {
 
   const loadCategories = async () => {
     try {
-      // Use mock categories for now
-      setCategories(mockCategories);
+      const entries = await exerciseService.getTaxonomy('categories');
+      setCategories(entries.map((entry) => entry.name));
     } catch (err) {
       console.error('Failed to load categories');
+      setCategories(mockCategories);
+    }
+  };
+
+  const loadFormOptions = async () => {
+    try {
+      const [levels, muscleEntries] = await Promise.all([
+        exerciseService.getTaxonomy('difficulty-levels'),
+        exerciseService.getTaxonomy('muscles'),
+      ]);
+      setDifficultyLevels(levels);
+      setMuscles(muscleEntries);
+    } catch (err) {
+      console.error('Failed to load exercise form options');
+    }
+  };
+
+  const difficultyLabel = (level: string) => {
+    switch (level) {
+      case 'Beginner': return 'Početnik';
+      case 'Intermediate': return 'Napredni';
+      case 'Advanced': return 'Ekspert';
+      default: return level;
     }
   };
 
@@ -188,6 +234,7 @@ export const ExercisesPage: React.FC = () => {
       ...prev,
       [key]: value,
     }));
+    setPage(1);
   };
 
   const toggleFavorite = (exerciseId: number) => {
@@ -327,19 +374,6 @@ export const ExercisesPage: React.FC = () => {
     }
   };
 
-  const filteredExercises = exercises.filter(exercise => {
-    const matchesSearch = !filters.search || 
-      exercise.title.toLowerCase().includes(filters.search.toLowerCase()) ||
-      exercise.description.toLowerCase().includes(filters.search.toLowerCase());
-    
-    const matchesCategory = !filters.category || exercise.category === filters.category;
-    const matchesDifficulty = !filters.difficultyLevel || exercise.difficulty_level === filters.difficultyLevel;
-    const matchesTargetMuscle = !filters.targetMuscle || 
-      exercise.target_muscles.some((muscle: string) => muscle.toLowerCase().includes(filters.targetMuscle!.toLowerCase()));
-
-    return matchesSearch && matchesCategory && matchesDifficulty && matchesTargetMuscle;
-  });
-
   return (
     <>
     <Container maxWidth="lg" sx={{ py: 4 }}>
@@ -425,7 +459,7 @@ export const ExercisesPage: React.FC = () => {
       {/* Results Info */}
       <Box sx={{ mb: 3, display: 'flex', justifyContent: 'space-between', alignItems: 'center' }}>
         <Typography variant="body2" color="text.secondary">
-          {loading ? 'Učitavanje...' : `Pronađeno ${filteredExercises.length} vežbi`}
+          {loading ? 'Učitavanje...' : `Pronađeno ${total} vežbi`}
         </Typography>
         <Button
           startIcon={<FilterList />}
@@ -457,7 +491,7 @@ export const ExercisesPage: React.FC = () => {
           ))
         ) : (
           // Exercise cards
-          filteredExercises.map((exercise) => (
+          exercises.map((exercise) => (
             <Card key={exercise.id} sx={{ display: 'flex', height: { xs: 220, sm: 250 }, alignItems: 'stretch', transition: 'transform 0.2s', overflow: 'hidden', position: 'relative', '&:hover': { transform: 'translateY(-2px)', boxShadow: 4 } }}>
               {/* Image */}
               <Box sx={{ width: { xs: 150, sm: 200, md: 300 }, height: { xs: 220, sm: 250 }, maxWidth: { xs: 150, sm: 200, md: 300 }, flexBasis: { xs: '150px', sm: '200px', md: '300px' }, flex: '0 0 auto' }}>
@@ -550,8 +584,15 @@ export const ExercisesPage: React.FC = () => {
         )}
       </Box>
 
+      {/* Pager */}
+      {!loading && pageCount > 1 && (
+        <Box sx={{ display: 'flex', justifyContent: 'center', mt: 4 }}>
+          <Pagination count={pageCount} page={page} onChange={(_, value) => setPage(value)} color="primary" />
+        </Box>
+      )}
+
       {/* Empty State */}
-      {!loading && filteredExercises.length === 0 && (
+      {!loading && exercises.length === 0 && (
         <Box sx={{ textAlign: 'center', py: 8 }}>
           <TrendingUp sx={{ fontSize: 64, color: 'text.secondary', mb: 2 }} />
           <Typography variant="h6" gutterBottom>
@@ -571,17 +612,31 @@ export const ExercisesPage: React.FC = () => {
         <Box sx={{ display: 'flex', flexDirection: 'column', gap: 2, mt: 1 }}>
           <TextField label="Naslov" value={currentExercise?.title || ''} onChange={(e) => setCurrentExercise((prev: Partial<Exercise> | null) => prev ? ({ ...prev, title: e.target.value }) : prev)} fullWidth />
           <TextField label="Opis" value={currentExercise?.description || ''} onChange={(e) => setCurrentExercise((prev: Partial<Exercise> | null) => prev ? ({ ...prev, description: e.target.value }) : prev)} fullWidth multiline rows={3} />
-          <TextField label="Kategorija" value={currentExercise?.category || ''} onChange={(e) => setCurrentExercise((prev: Partial<Exercise> | null) => prev ? ({ ...prev, category: e.target.value }) : prev)} fullWidth />
+          <FormControl fullWidth>
+            <InputLabel>Kategorija</InputLabel>
+            <Select value={currentExercise?.category || ''} label="Kategorija" onChange={(e) => setCurrentExercise((prev: Partial<Exercise> | null) => prev ? ({ ...prev, category: e.target.value as string }) : prev)}>
+              {categories.map((category) => (
+                <MenuItem key={category} value={category}>{category}</MenuItem>
+              ))}
+            </Select>
+          </FormControl>
           <FormControl>
             <InputLabel>Nivo</InputLabel>
             <Select value={currentExercise?.difficulty_level || 'Beginner'} label="Nivo" onChange={(e) => setCurrentExercise((prev: Partial<Exercise> | null) => prev ? ({ ...prev, difficulty_level: e.target.value as string }) : prev)}>
-              <MenuItem value="Beginner">Početnik</MenuItem>
-              <MenuItem value="Intermediate">Napredni</MenuItem>
-              <MenuItem value="Advanced">Ekspert</MenuItem>
+              {difficultyLevels.map((level) => (
+                <MenuItem key={level.id} value={level.name}>{difficultyLabel(level.name)}</MenuItem>
+              ))}
             </Select>
           </FormControl>
           <TextField label="Trajanje (min)" type="number" value={currentExercise?.duration_minutes || 0} onChange={(e) => setCurrentExercise((prev: Partial<Exercise> | null) => prev ? ({ ...prev, duration_minutes: parseInt(e.target.value || '0') }) : prev)} />
-          <TextField label="Ciljna grupa (comma separated)" value={(currentExercise?.target_muscles || []).join(', ')} onChange={(e) => setCurrentExercise((prev: Partial<Exercise> | null) => prev ? ({ ...prev, target_muscles: e.target.value.split(',').map((s:string)=>s.trim()) }) : prev)} fullWidth />
+          <Autocomplete
+            multiple
+            options={muscles.map((muscle) => muscle.name)}
+            value={currentExercise?.target_muscles || []}
+            onChange={(_, value) => setCurrentExercise((prev: Partial<Exercise> | null) => prev ? ({ ...prev, target_muscles: value }) : prev)}
+            renderInput={(params) => <TextField {...params} label="Ciljna grupa" />}
+            fullWidth
+          />
           {/* File upload for images */}
           <Box>
             <Typography variant="subtitle2">Upload images</Typography>
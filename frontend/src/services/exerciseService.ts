@@ -1,8 +1,11 @@
 import axios from 'axios';
-import { Exercise, ExerciseFilter, CreateExerciseRequest, UpdateExerciseRequest } from '../types';
+import { Exercise, ExerciseFacets, ExerciseImage, ExerciseFilter, ExercisePage, ExerciseVideo, CreateExerciseRequest, Routine, RoutineRequest, TaxonomyEntry, UpdateExerciseImageRequest, UpdateExerciseRequest } from '../types';
 
 const API_BASE_URL = process.env.REACT_APP_EXERCISE_API_URL || 'http://localhost:8005';
 
+// Exercises per page of the catalog
+const PAGE_LIMIT = 20;
+
 // Create axios instance for exercise service
 const exerciseClient = axios.create({
   baseURL: API_BASE_URL,
@@ -53,38 +56,76 @@ exerciseClient.interceptors.request.use((config) => {
   return config;
 }, (error) => Promise.reject(error));
 
+// Query parameters of GET /exercises and /exercises/facets for the given filters
+function filterParams(filters?: ExerciseFilter): URLSearchParams {
+  const params = new URLSearchParams();
+  if (filters) {
+    if (filters.category) params.append('category', filters.category);
+    if (filters.difficultyLevel) params.append('difficulty', filters.difficultyLevel);
+    if (filters.search) params.append('search', filters.search);
+    if (filters.targetMuscle) params.append('target_muscles', filters.targetMuscle);
+  }
+  return params;
+}
+
 export const exerciseService = {
-  async getAllExercises(filters?: ExerciseFilter): Promise<Exercise[]> {
-    const params = new URLSearchParams();
-    
-    if (filters) {
-      if (filters.category) params.append('category', filters.category);
-      if (filters.difficultyLevel) params.append('difficulty', filters.difficultyLevel);
-      if (filters.search) params.append('search', filters.search);
-    }
+  // One page of the catalog; page starts at 1
+  async getExercisePage(page: number, filters?: ExerciseFilter): Promise<ExercisePage> {
+    const params = filterParams(filters);
+    params.set('page', page.toString());
+    params.set('limit', PAGE_LIMIT.toString());
 
-    const response = await exerciseClient.get<Exercise[]>(`/exercises?${params.toString()}`);
+    const response = await exerciseClient.get<ExercisePage>(`/exercises?${params.toString()}`);
     return response.data;
   },
 
-  async getExerciseById(id: number): Promise<Exercise> {
-    const response = await exerciseClient.get<Exercise>(`/exercises/${id}`);
+  // kind: categories, difficulty-levels, muscles or equipment
+  async getTaxonomy(kind: string): Promise<TaxonomyEntry[]> {
+    const response = await exerciseClient.get<TaxonomyEntry[]>(`/taxonomy/${kind}`);
     return response.data;
   },
 
-  async searchExercises(query: string, filters?: ExerciseFilter): Promise<Exercise[]> {
-    const params = new URLSearchParams();
-    params.append('search', query);
-    
-    if (filters) {
-      if (filters.category) params.append('category', filters.category);
-      if (filters.difficultyLevel) params.append('difficulty', filters.difficultyLevel);
-    }
+  async getFacets(filters?: ExerciseFilter): Promise<ExerciseFacets> {
+    const params = filterParams(filters);
+    const response = await exerciseClient.get<ExerciseFacets>(`/exercises/facets?${params.toString()}`);
+    return response.data;
+  },
 
-    const response = await exerciseClient.get<Exercise[]>(`/exercises?${params.toString()}`);
+  async getRoutines(templatesOnly?: boolean): Promise<Routine[]> {
+    const query = templatesOnly === undefined ? '' : `?templates=${templatesOnly}`;
+    const response = await exerciseClient.get<Routine[]>(`/routines${query}`);
     return response.data;
   },
 
+  async createRoutine(routine: RoutineRequest): Promise<Routine> {
+    const response = await exerciseClient.post<Routine>('/routines', routine);
+    return response.data;
+  },
+
+  async updateRoutine(id: number, routine: Partial<RoutineRequest>): Promise<Routine> {
+    const response = await exerciseClient.put<Routine>(`/routines/${id}`, routine);
+    return response.data;
+  },
+
+  async deleteRoutine(id: number): Promise<void> {
+    await exerciseClient.delete(`/routines/${id}`);
+  },
+
+  // Private copy of a template for the current user
+  async cloneRoutine(id: number): Promise<Routine> {
+    const response = await exerciseClient.post<Routine>(`/routines/${id}/clone`);
+    return response.data;
+  },
+
+  async getExerciseById(id: number): Promise<Exercise> {
+    const response = await exerciseClient.get<Exercise>(`/exercises/${id}`);
+    return response.data;
+  },
+
+  async searchExercises(query: string, filters?: ExerciseFilter, page = 1): Promise<ExercisePage> {
+    return exerciseService.getExercisePage(page, { ...filters, search: query });
+  },
+
   async createExercise(exerciseData: CreateExerciseRequest): Promise<Exercise> {
     const getUserId = () => {
       const uid = localStorage.getItem('user_id');
@@ -154,7 +195,36 @@ export const exerciseService = {
     files.forEach((f) => form.append('file', f));
 
     // Pass headers explicitly, but do not set Content-Type
-    const response = await exerciseClient.post<string[]>(`/exercises/${exerciseId}/images`, form, { headers });
+    // One entry per stored image with its thumbnail, medium and full size URLs
+    const response = await exerciseClient.post<ExerciseImage[]>(`/exercises/${exerciseId}/images`, form, { headers });
+    return response.data.map((image) => image.full_url);
+  },
+
+  // Every image id of the exercise, in the new gallery order
+  async reorderExerciseImages(exerciseId: number, imageIds: number[]): Promise<ExerciseImage[]> {
+    const response = await exerciseClient.put<ExerciseImage[]>(`/exercises/${exerciseId}/images/order`, { image_ids: imageIds });
+    return response.data;
+  },
+
+  async updateExerciseImage(exerciseId: number, imageId: number, update: UpdateExerciseImageRequest): Promise<ExerciseImage> {
+    const response = await exerciseClient.put<ExerciseImage>(`/exercises/${exerciseId}/images/${imageId}`, update);
+    return response.data;
+  },
+
+  // Replaces any previous video; it is transcoded in the background, poll getExerciseVideo for the status
+  async uploadExerciseVideo(exerciseId: number, file: File): Promise<ExerciseVideo> {
+    const form = new FormData();
+    form.append('video', file);
+    const response = await exerciseClient.post<ExerciseVideo>(`/exercises/${exerciseId}/video`, form);
     return response.data;
   },
+
+  async getExerciseVideo(exerciseId: number): Promise<ExerciseVideo> {
+    const response = await exerciseClient.get<ExerciseVideo>(`/exercises/${exerciseId}/video`);
+    return response.data;
+  },
+
+  async deleteExerciseVideo(exerciseId: number): Promise<void> {
+    await exerciseClient.delete(`/exercises/${exerciseId}/video`);
+  },
 };
\ No newline at end of file
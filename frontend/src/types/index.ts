@@ -106,11 +106,16 @@ export interface Exercise {
   instructions: string[];
   image_url?: string;
   images?: string[];
+  image_variants?: ExerciseImage[];
   video_url?: string;
   youtube_url?: string;
   target_muscles: string[];
   created_at: number;
   is_specialized: boolean;
+  category_id?: number;
+  difficulty_id?: number;
+  routines?: RoutineSummary[];
+  video?: ExerciseVideo | null;
 }
 
 export interface CreateExerciseRequest {
@@ -143,6 +148,109 @@ export interface UpdateExerciseRequest {
   target_muscles?: string[];
 }
 
+export interface ExercisePage {
+  exercises: Exercise[];
+  total: number;
+  page: number;
+  limit: number;
+  has_more: boolean;
+}
+
+export interface ExerciseImage {
+  id: number;
+  url: string;
+  thumbnail_url: string;
+  medium_url: string;
+  full_url: string;
+  alt_text?: string | null;
+  caption?: string | null;
+  is_cover: boolean;
+}
+
+// Uploaded video; playlist_url is set once the HLS rendition is ready
+export interface ExerciseVideo {
+  id: number;
+  exercise_id: number;
+  status: 'pending' | 'processing' | 'ready' | 'failed';
+  error?: string | null;
+  content_type: string;
+  size_bytes: number;
+  playlist_url?: string | null;
+  poster_url?: string | null;
+  source_url: string;
+  created_at: number;
+  updated_at: number;
+}
+
+export interface UpdateExerciseImageRequest {
+  alt_text?: string;
+  caption?: string;
+  is_cover?: boolean;
+}
+
+export interface RoutineSummary {
+  id: number;
+  title: string;
+  is_template: boolean;
+}
+
+export interface RoutineExercise {
+  position: number;
+  exercise_id: number;
+  title: string;
+  duration_minutes?: number;
+  sets?: number;
+  reps?: number;
+  rest_seconds?: number;
+  notes?: string;
+}
+
+export interface Routine {
+  id: number;
+  title: string;
+  description?: string;
+  is_template: boolean;
+  owner_id: string;
+  cloned_from?: number;
+  estimated_duration_minutes: number;
+  exercises: RoutineExercise[];
+  created_at: number;
+  updated_at: number;
+}
+
+export interface RoutineRequest {
+  title: string;
+  description?: string;
+  is_template?: boolean;
+  exercises: Omit<RoutineExercise, 'position' | 'title' | 'duration_minutes'>[];
+}
+
+// Category, difficulty level, muscle or equipment entry managed by admins
+export interface TaxonomyEntry {
+  id: number;
+  name: string;
+  slug: string;
+  parent_id?: number;
+  position?: number;
+  aliases: string[];
+}
+
+export interface FacetCount {
+  id: number;
+  name: string;
+  slug: string;
+  parent_id?: number;
+  count: number;
+}
+
+export interface ExerciseFacets {
+  total: number;
+  categories: FacetCount[];
+  difficulty_levels: FacetCount[];
+  muscles: FacetCount[];
+  equipment: FacetCount[];
+}
+
 export interface ExerciseFilter {
   category?: string;
   difficultyLevel?: string;